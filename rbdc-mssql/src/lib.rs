@@ -13,7 +13,9 @@ use crate::decode::Decode;
 use crate::encode::Encode;
 use futures_core::future::BoxFuture;
 use futures_core::Stream;
-use rbdc::db::{ConnectOptions, Connection, ExecResult, MetaData, Placeholder, Row};
+use rbdc::db::{
+    ConnectOptions, Connection, Describe, DescribeColumn, ExecResult, MetaData, Placeholder, Row,
+};
 use rbdc::Error;
 use rbs::Value;
 use std::sync::Arc;
@@ -348,6 +350,46 @@ impl Connection for MssqlConnection {
             Ok(())
         })
     }
+
+    fn describe(&mut self, sql: &str) -> BoxFuture<Result<Describe, Error>> {
+        let sql = MssqlDriver {}.exchange(sql);
+        Box::pin(async move {
+            let mut describe = Describe::default();
+            // the parameters must be declared for sp_describe_first_result_set
+            let params = self
+                .get_values(
+                    "EXEC sp_describe_undeclared_parameters @tsql = ?",
+                    vec![Value::String(sql.clone())],
+                )
+                .await?;
+            let mut declared = Vec::with_capacity(params.len());
+            for param in &params {
+                let name = param["name"].as_str().unwrap_or_default();
+                let param_type = param["suggested_system_type_name"]
+                    .as_str()
+                    .unwrap_or_default();
+                declared.push(format!("{} {}", name, param_type));
+                describe.params.push(param_type.to_string());
+            }
+            let columns = self
+                .get_values(
+                    "EXEC sp_describe_first_result_set @tsql = ?, @params = ?",
+                    vec![Value::String(sql), Value::String(declared.join(", "))],
+                )
+                .await?;
+            for column in &columns {
+                describe.columns.push(DescribeColumn {
+                    name: column["name"].as_str().unwrap_or_default().to_string(),
+                    column_type: column["system_type_name"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .to_string(),
+                });
+                describe.nullable.push(column["is_nullable"].as_bool());
+            }
+            Ok(describe)
+        })
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,646 @@
+use crate::binlog::gtid::format_sid;
+use crate::binlog::reader::Reader;
+use crate::binlog::value::{self, decode_value};
+use rbdc::{err_protocol, Error};
+use rbs::Value;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+// https://dev.mysql.com/doc/dev/mysql-server/latest/page_protocol_replication_binlog_event.html
+
+pub const QUERY_EVENT: u8 = 2;
+pub const ROTATE_EVENT: u8 = 4;
+pub const FORMAT_DESCRIPTION_EVENT: u8 = 15;
+pub const XID_EVENT: u8 = 16;
+pub const TABLE_MAP_EVENT: u8 = 19;
+pub const WRITE_ROWS_EVENT_V1: u8 = 23;
+pub const UPDATE_ROWS_EVENT_V1: u8 = 24;
+pub const DELETE_ROWS_EVENT_V1: u8 = 25;
+pub const HEARTBEAT_EVENT: u8 = 27;
+pub const WRITE_ROWS_EVENT: u8 = 30;
+pub const UPDATE_ROWS_EVENT: u8 = 31;
+pub const DELETE_ROWS_EVENT: u8 = 32;
+pub const GTID_EVENT: u8 = 33;
+pub const HEARTBEAT_EVENT_V2: u8 = 41;
+
+/// the length of the event header since binlog version 4
+const HEADER_LEN: usize = 19;
+
+/// the magic number at the start of a binlog file
+const BINLOG_MAGIC: &[u8] = b"\xfebin";
+
+/// The header common to all binlog events.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MySqlBinlogEventHeader {
+    /// seconds since the unix epoch
+    pub timestamp: u32,
+    pub event_type: u8,
+    /// the server the event originates from
+    pub server_id: u32,
+    /// the length of the event, header and checksum included
+    pub event_size: u32,
+    /// the position of the next event in the binlog file, `0` for artificial events
+    pub log_pos: u32,
+    pub flags: u16,
+}
+
+/// A decoded binlog event.
+#[derive(Debug, Clone)]
+pub struct MySqlBinlogEvent {
+    pub header: MySqlBinlogEventHeader,
+    pub data: MySqlBinlogEventData,
+}
+
+#[derive(Debug, Clone)]
+pub enum MySqlBinlogEventData {
+    /// the first event of each binlog file, describes the format of the other events
+    FormatDescription {
+        binlog_version: u16,
+        server_version: String,
+        /// whether the events end with a CRC32 checksum
+        checksum: bool,
+    },
+    /// the events continue in another binlog file
+    Rotate {
+        position: u64,
+        filename: String,
+    },
+    /// a statement, e.g. `BEGIN`, a DDL statement or a statement of statement based
+    /// replication
+    Query {
+        thread_id: u32,
+        exec_time: u32,
+        schema: String,
+        error_code: u16,
+        query: String,
+    },
+    /// the commit of a transaction
+    Xid {
+        xid: u64,
+    },
+    /// the GTID of the following transaction
+    Gtid(MySqlGtid),
+    /// the columns of a table, sent before the rows events of the table
+    TableMap(Arc<MySqlTableMap>),
+    WriteRows(MySqlBinlogRows),
+    UpdateRows(MySqlBinlogUpdateRows),
+    DeleteRows(MySqlBinlogRows),
+    /// sent by the server while there are no new events
+    Heartbeat,
+    /// an event that is not decoded, without the header and checksum
+    Other {
+        event_type: u8,
+        body: Vec<u8>,
+    },
+}
+
+/// The global transaction identifier of a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MySqlGtid {
+    pub sid: [u8; 16],
+    pub gno: u64,
+}
+
+impl Display for MySqlGtid {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", format_sid(&self.sid), self.gno)
+    }
+}
+
+/// The columns of a table, as described by a `TABLE_MAP` event.
+///
+/// The names, signedness, character sets and enum/set labels are only sent by servers with
+/// `binlog_row_metadata=FULL` (MySQL 8.0.1+), otherwise integers are read as signed,
+/// `ENUM` and `SET` values as numbers and `TEXT` values as bytes.
+#[derive(Debug, Clone)]
+pub struct MySqlTableMap {
+    pub table_id: u64,
+    pub schema: String,
+    pub table: String,
+    pub columns: Vec<MySqlBinlogColumn>,
+    /// the indexes of the primary key columns
+    pub primary_key: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MySqlBinlogColumn {
+    pub name: Option<String>,
+    /// the type of the column in the binlog, e.g. `0x12` for `DATETIME(fsp)`
+    pub column_type: u8,
+    /// the type specific metadata, e.g. the length or precision
+    pub meta: u16,
+    pub nullable: bool,
+    pub unsigned: bool,
+    /// the collation id of a character column
+    pub charset: Option<u16>,
+    /// the labels of an `ENUM` or `SET` column
+    pub values: Vec<String>,
+}
+
+/// The rows of a `WRITE_ROWS` or `DELETE_ROWS` event.
+#[derive(Debug, Clone)]
+pub struct MySqlBinlogRows {
+    pub table: Arc<MySqlTableMap>,
+    pub flags: u16,
+    /// the columns of the table that are in the rows, see `binlog_row_image`
+    pub present: Vec<bool>,
+    /// the values of each row by column of the table, `Null` for the columns that are not
+    /// present
+    pub rows: Vec<Vec<Value>>,
+}
+
+/// The rows of an `UPDATE_ROWS` event, before and after the update.
+#[derive(Debug, Clone)]
+pub struct MySqlBinlogUpdateRows {
+    pub table: Arc<MySqlTableMap>,
+    pub flags: u16,
+    pub before_present: Vec<bool>,
+    pub after_present: Vec<bool>,
+    pub rows: Vec<(Vec<Value>, Vec<Value>)>,
+}
+
+/// Decodes binlog events. It keeps the state needed by later events: the format of the
+/// format description event and the columns of the `TABLE_MAP` events.
+///
+/// ```rust,no_run
+/// # use rbdc_mysql::binlog::MySqlBinlogDecoder;
+/// # fn run() -> Result<(), rbdc::Error> {
+/// let file = std::fs::read("binlog.000001").map_err(|e| rbdc::Error::from(e.to_string()))?;
+/// for event in MySqlBinlogDecoder::new().decode_file(&file)? {
+///     println!("{:?}", event);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct MySqlBinlogDecoder {
+    tables: HashMap<u64, Arc<MySqlTableMap>>,
+    checksum: bool,
+    verify_checksum: bool,
+    // by event type - 1, from the format description event
+    post_header_len: Vec<u8>,
+    offset_sec: i32,
+}
+
+impl Default for MySqlBinlogDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MySqlBinlogDecoder {
+    pub fn new() -> Self {
+        Self {
+            tables: HashMap::new(),
+            checksum: false,
+            verify_checksum: true,
+            post_header_len: Vec::new(),
+            offset_sec: fastdate::offset_sec(),
+        }
+    }
+
+    /// Sets whether the CRC32 checksums of the events are verified. Defaults to `true`.
+    pub fn verify_checksum(mut self, verify: bool) -> Self {
+        self.verify_checksum = verify;
+        self
+    }
+
+    /// Sets the time zone offset of `DATETIME` values, like
+    /// [`MySqlConnectOptions::offset_sec`](crate::options::MySqlConnectOptions::offset_sec).
+    pub fn offset_sec(mut self, offset_sec: i32) -> Self {
+        self.offset_sec = offset_sec;
+        self
+    }
+
+    /// Sets whether events end with a checksum before a format description event says so,
+    /// as the artificial `ROTATE` event the server starts a dump with.
+    pub(crate) fn checksum(mut self, checksum: bool) -> Self {
+        self.checksum = checksum;
+        self
+    }
+
+    /// The columns of a table by the table id of the rows events.
+    pub fn table(&self, table_id: u64) -> Option<&Arc<MySqlTableMap>> {
+        self.tables.get(&table_id)
+    }
+
+    /// Decodes the events of a binlog file, e.g. one recorded with `mysqlbinlog --raw`.
+    pub fn decode_file(&mut self, file: &[u8]) -> Result<Vec<MySqlBinlogEvent>, Error> {
+        let mut r = Reader::new(file);
+        if r.take(BINLOG_MAGIC.len()).ok() != Some(BINLOG_MAGIC) {
+            return Err(Error::from("not a binlog file"));
+        }
+        let mut events = Vec::new();
+        while !r.is_empty() {
+            let size = Reader::new(&r.peek(HEADER_LEN)?[9..13]).u32()? as usize;
+            events.push(self.decode(r.take(size)?)?);
+        }
+        Ok(events)
+    }
+
+    /// Decodes one event, header included.
+    pub fn decode(&mut self, event: &[u8]) -> Result<MySqlBinlogEvent, Error> {
+        let mut r = Reader::new(event);
+        let header = MySqlBinlogEventHeader {
+            timestamp: r.u32()?,
+            event_type: r.u8()?,
+            server_id: r.u32()?,
+            event_size: r.u32()?,
+            log_pos: r.u32()?,
+            flags: r.u16()?,
+        };
+        if header.event_size as usize != event.len() {
+            return Err(err_protocol!(
+                "binlog event of {} bytes, its header says {}",
+                event.len(),
+                header.event_size
+            ));
+        }
+
+        if header.event_type == FORMAT_DESCRIPTION_EVENT {
+            let data = self.decode_format_description(event)?;
+            return Ok(MySqlBinlogEvent { header, data });
+        }
+
+        let body = self.strip_checksum(event)?;
+        let body = &body[HEADER_LEN..];
+        let data = match header.event_type {
+            ROTATE_EVENT => {
+                let mut r = Reader::new(body);
+                MySqlBinlogEventData::Rotate {
+                    position: r.u64()?,
+                    filename: String::from_utf8_lossy(r.rest()).into_owned(),
+                }
+            }
+            QUERY_EVENT => self.decode_query(body)?,
+            XID_EVENT => MySqlBinlogEventData::Xid {
+                xid: Reader::new(body).u64()?,
+            },
+            GTID_EVENT => {
+                let mut r = Reader::new(body);
+                let _commit_flag = r.u8()?;
+                let sid = r.take(16)?.try_into().unwrap_or_default();
+                MySqlBinlogEventData::Gtid(MySqlGtid { sid, gno: r.u64()? })
+            }
+            TABLE_MAP_EVENT => {
+                let table = Arc::new(self.decode_table_map(body)?);
+                self.tables.insert(table.table_id, table.clone());
+                MySqlBinlogEventData::TableMap(table)
+            }
+            WRITE_ROWS_EVENT_V1 | UPDATE_ROWS_EVENT_V1 | DELETE_ROWS_EVENT_V1
+            | WRITE_ROWS_EVENT | UPDATE_ROWS_EVENT | DELETE_ROWS_EVENT => {
+                self.decode_rows(header.event_type, body)?
+            }
+            HEARTBEAT_EVENT | HEARTBEAT_EVENT_V2 => MySqlBinlogEventData::Heartbeat,
+            event_type => MySqlBinlogEventData::Other {
+                event_type,
+                body: body.to_vec(),
+            },
+        };
+        Ok(MySqlBinlogEvent { header, data })
+    }
+
+    /// the event without its checksum, verified if asked to
+    fn strip_checksum<'a>(&self, event: &'a [u8]) -> Result<&'a [u8], Error> {
+        if !self.checksum {
+            return Ok(event);
+        }
+        if event.len() < HEADER_LEN + 4 {
+            return Err(err_protocol!("binlog event too short for its checksum"));
+        }
+        let (event, checksum) = event.split_at(event.len() - 4);
+        if self.verify_checksum {
+            let expected = Reader::new(checksum).u32()?;
+            let mut crc = flate2::Crc::new();
+            crc.update(event);
+            if crc.sum() != expected {
+                return Err(err_protocol!(
+                    "binlog event checksum mismatch: 0x{:08x}, expected 0x{:08x}",
+                    crc.sum(),
+                    expected
+                ));
+            }
+        }
+        Ok(event)
+    }
+
+    fn decode_format_description(&mut self, event: &[u8]) -> Result<MySqlBinlogEventData, Error> {
+        let mut r = Reader::new(&event[HEADER_LEN..]);
+        let binlog_version = r.u16()?;
+        let server_version = r.take(50)?;
+        let server_version = String::from_utf8_lossy(server_version)
+            .trim_end_matches('\0')
+            .to_string();
+        let _create_timestamp = r.u32()?;
+        let _header_len = r.u8()?;
+
+        // servers since 5.6.1 end the event with the checksum algorithm and the checksum,
+        // the checksum of this event is there even when the algorithm is off
+        let checksum = if version_at_least(&server_version, (5, 6, 1)) {
+            let alg = *event
+                .get(event.len().wrapping_sub(5))
+                .ok_or_else(|| err_protocol!("format description event too short"))?;
+            self.checksum = alg == 1;
+            let len = r.len().saturating_sub(5);
+            self.post_header_len = r.take(len)?.to_vec();
+            if self.checksum {
+                self.strip_checksum(event)?;
+            }
+            alg == 1
+        } else {
+            self.checksum = false;
+            self.post_header_len = r.rest().to_vec();
+            false
+        };
+
+        Ok(MySqlBinlogEventData::FormatDescription {
+            binlog_version,
+            server_version,
+            checksum,
+        })
+    }
+
+    fn post_header_len(&self, event_type: u8, default: u8) -> u8 {
+        self.post_header_len
+            .get(usize::from(event_type) - 1)
+            .copied()
+            .unwrap_or(default)
+    }
+
+    fn decode_query(&self, body: &[u8]) -> Result<MySqlBinlogEventData, Error> {
+        let mut r = Reader::new(body);
+        let thread_id = r.u32()?;
+        let exec_time = r.u32()?;
+        let schema_len = r.u8()? as usize;
+        let error_code = r.u16()?;
+        let status_vars_len = r.u16()? as usize;
+        r.skip(usize::from(self.post_header_len(QUERY_EVENT, 13)).saturating_sub(13))?;
+        r.skip(status_vars_len)?;
+        let schema = String::from_utf8_lossy(r.take(schema_len)?).into_owned();
+        r.skip(1)?;
+        Ok(MySqlBinlogEventData::Query {
+            thread_id,
+            exec_time,
+            schema,
+            error_code,
+            query: String::from_utf8_lossy(r.rest()).into_owned(),
+        })
+    }
+
+    fn table_id(&self, r: &mut Reader, event_type: u8, default: u8) -> Result<u64, Error> {
+        // the table id has 4 bytes with servers before 5.1.4
+        if self.post_header_len(event_type, default) == 6 {
+            r.uint(4)
+        } else {
+            r.uint(6)
+        }
+    }
+
+    fn decode_table_map(&self, body: &[u8]) -> Result<MySqlTableMap, Error> {
+        let mut r = Reader::new(body);
+        let table_id = self.table_id(&mut r, TABLE_MAP_EVENT, 8)?;
+        let _flags = r.u16()?;
+        let len = r.u8()? as usize;
+        let schema = String::from_utf8_lossy(r.take(len)?).into_owned();
+        r.skip(1)?;
+        let len = r.u8()? as usize;
+        let table = String::from_utf8_lossy(r.take(len)?).into_owned();
+        r.skip(1)?;
+
+        let count = r.lenenc()? as usize;
+        let types = r.take(count)?;
+        let mut metadata = Reader::new(r.lenenc_bytes()?);
+        let nullable = r.bitmap(count)?;
+
+        let mut columns = Vec::with_capacity(count);
+        for (column_type, nullable) in types.iter().copied().zip(nullable) {
+            let meta = match (value::metadata_len(column_type), column_type) {
+                (0, _) => 0,
+                (1, _) => u16::from(metadata.u8()?),
+                (_, value::VARCHAR | value::BIT) => metadata.u16()?,
+                _ => metadata.uint_be(2)? as u16,
+            };
+            // ENUM and SET columns have the type STRING with their real type in the metadata
+            let real_type = ((meta >> 8) as u8) | 0x30;
+            let column_type = match column_type {
+                value::STRING if real_type == value::ENUM || real_type == value::SET => real_type,
+                t => t,
+            };
+            columns.push(MySqlBinlogColumn {
+                name: None,
+                column_type,
+                meta,
+                nullable,
+                unsigned: false,
+                charset: None,
+                values: Vec::new(),
+            });
+        }
+
+        let mut primary_key = Vec::new();
+        while !r.is_empty() {
+            let field_type = r.u8()?;
+            let mut field = Reader::new(r.lenenc_bytes()?);
+            decode_optional_metadata(field_type, &mut field, &mut columns, &mut primary_key)?;
+        }
+
+        Ok(MySqlTableMap {
+            table_id,
+            schema,
+            table,
+            columns,
+            primary_key,
+        })
+    }
+
+    fn decode_rows(&self, event_type: u8, body: &[u8]) -> Result<MySqlBinlogEventData, Error> {
+        let v2 = event_type >= WRITE_ROWS_EVENT;
+        let mut r = Reader::new(body);
+        let table_id = self.table_id(&mut r, event_type, if v2 { 10 } else { 8 })?;
+        let flags = r.u16()?;
+        if v2 {
+            let extra_len = r.u16()? as usize;
+            r.skip(extra_len.saturating_sub(2))?;
+        }
+        let table = self
+            .tables
+            .get(&table_id)
+            .cloned()
+            .ok_or_else(|| err_protocol!("rows event of the unknown table id {}", table_id))?;
+
+        let count = r.lenenc()? as usize;
+        if count != table.columns.len() {
+            return Err(err_protocol!(
+                "rows event of {} columns for the table {}.{} of {} columns",
+                count,
+                table.schema,
+                table.table,
+                table.columns.len()
+            ));
+        }
+        let present = r.bitmap(count)?;
+
+        let update = matches!(event_type, UPDATE_ROWS_EVENT_V1 | UPDATE_ROWS_EVENT);
+        if update {
+            let after_present = r.bitmap(count)?;
+            let mut rows = Vec::new();
+            while !r.is_empty() {
+                let before = self.decode_row(&mut r, &table, &present)?;
+                let after = self.decode_row(&mut r, &table, &after_present)?;
+                rows.push((before, after));
+            }
+            return Ok(MySqlBinlogEventData::UpdateRows(MySqlBinlogUpdateRows {
+                table,
+                flags,
+                before_present: present,
+                after_present,
+                rows,
+            }));
+        }
+
+        let mut rows = Vec::new();
+        while !r.is_empty() {
+            rows.push(self.decode_row(&mut r, &table, &present)?);
+        }
+        let rows = MySqlBinlogRows {
+            table,
+            flags,
+            present,
+            rows,
+        };
+        Ok(match event_type {
+            WRITE_ROWS_EVENT_V1 | WRITE_ROWS_EVENT => MySqlBinlogEventData::WriteRows(rows),
+            _ => MySqlBinlogEventData::DeleteRows(rows),
+        })
+    }
+
+    fn decode_row(
+        &self,
+        r: &mut Reader,
+        table: &MySqlTableMap,
+        present: &[bool],
+    ) -> Result<Vec<Value>, Error> {
+        let mut nulls = r
+            .bitmap(present.iter().filter(|p| **p).count())?
+            .into_iter();
+        let mut row = Vec::with_capacity(table.columns.len());
+        for (column, present) in table.columns.iter().zip(present) {
+            if !*present || nulls.next().unwrap_or(true) {
+                row.push(Value::Null);
+            } else {
+                row.push(decode_value(r, column, self.offset_sec)?);
+            }
+        }
+        Ok(row)
+    }
+}
+
+/// a field of the optional metadata of a `TABLE_MAP` event
+fn decode_optional_metadata(
+    field_type: u8,
+    field: &mut Reader,
+    columns: &mut [MySqlBinlogColumn],
+    primary_key: &mut Vec<usize>,
+) -> Result<(), Error> {
+    match field_type {
+        // SIGNEDNESS, a bit per numeric column, the highest bit first
+        1 => {
+            let mut numeric = columns
+                .iter_mut()
+                .filter(|c| value::is_numeric(c.column_type));
+            while !field.is_empty() {
+                let byte = field.u8()?;
+                for bit in 0..8 {
+                    if let Some(column) = numeric.next() {
+                        column.unsigned = byte & (0x80 >> bit) != 0;
+                    }
+                }
+            }
+        }
+        // DEFAULT_CHARSET, the default collation then the character columns with another
+        2 => {
+            let default = field.lenenc()? as u16;
+            let mut character: Vec<&mut MySqlBinlogColumn> = columns
+                .iter_mut()
+                .filter(|c| value::is_character(c.column_type))
+                .collect();
+            for column in character.iter_mut() {
+                column.charset = Some(default);
+            }
+            while !field.is_empty() {
+                let index = field.lenenc()? as usize;
+                let collation = field.lenenc()? as u16;
+                if let Some(column) = character.get_mut(index) {
+                    column.charset = Some(collation);
+                }
+            }
+        }
+        // COLUMN_CHARSET, the collation of each character column
+        3 => {
+            for column in columns
+                .iter_mut()
+                .filter(|c| value::is_character(c.column_type))
+            {
+                if field.is_empty() {
+                    break;
+                }
+                column.charset = Some(field.lenenc()? as u16);
+            }
+        }
+        // COLUMN_NAME
+        4 => {
+            for column in columns.iter_mut() {
+                if field.is_empty() {
+                    break;
+                }
+                column.name = Some(field.lenenc_str()?);
+            }
+        }
+        // SET_STR_VALUE and ENUM_STR_VALUE, the labels of each SET or ENUM column
+        5 | 6 => {
+            let column_type = if field_type == 5 {
+                value::SET
+            } else {
+                value::ENUM
+            };
+            for column in columns.iter_mut().filter(|c| c.column_type == column_type) {
+                if field.is_empty() {
+                    break;
+                }
+                let count = field.lenenc()?;
+                column.values = (0..count)
+                    .map(|_| field.lenenc_str())
+                    .collect::<Result<_, _>>()?;
+            }
+        }
+        // SIMPLE_PRIMARY_KEY
+        8 => {
+            while !field.is_empty() {
+                primary_key.push(field.lenenc()? as usize);
+            }
+        }
+        // PRIMARY_KEY_WITH_PREFIX, the column indexes with their prefix length
+        9 => {
+            while !field.is_empty() {
+                primary_key.push(field.lenenc()? as usize);
+                let _prefix = field.lenenc()?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// whether a version such as `8.0.36-log` is at least `min`
+fn version_at_least(version: &str, min: (u16, u16, u16)) -> bool {
+    let mut parts = version
+        .split(|c: char| !c.is_ascii_digit())
+        .map(|p| p.parse().unwrap_or(0));
+    let version = (
+        parts.next().unwrap_or(0),
+        parts.next().unwrap_or(0),
+        parts.next().unwrap_or(0),
+    );
+    version >= min
+}
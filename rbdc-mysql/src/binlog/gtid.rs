@@ -0,0 +1,182 @@
+use rbdc::Error;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// A set of MySQL global transaction identifiers, e.g. the value of `@@gtid_executed`:
+/// `3e11fa47-71ca-11e1-9e33-c80aa9429562:1-5:11,2174b383-5441-11e8-b90a-c80aa9429562:1-27`.
+///
+/// Each source server, identified by a UUID, numbers its transactions from 1.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct MySqlGtidSet {
+    // the intervals of each source, sorted, merged and with an exclusive end
+    sets: BTreeMap<[u8; 16], Vec<(u64, u64)>>,
+}
+
+impl MySqlGtidSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the transaction `gno` of the source `sid`.
+    pub fn add(&mut self, sid: [u8; 16], gno: u64) {
+        self.add_interval(sid, gno, gno + 1);
+    }
+
+    /// Whether the set holds the transaction `gno` of the source `sid`.
+    pub fn contains(&self, sid: &[u8; 16], gno: u64) -> bool {
+        self.sets
+            .get(sid)
+            .map(|intervals| {
+                intervals
+                    .iter()
+                    .any(|(start, end)| *start <= gno && gno < *end)
+            })
+            .unwrap_or(false)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sets.is_empty()
+    }
+
+    fn add_interval(&mut self, sid: [u8; 16], start: u64, end: u64) {
+        let intervals = self.sets.entry(sid).or_default();
+        intervals.push((start, end));
+        intervals.sort_unstable();
+        let mut merged: Vec<(u64, u64)> = Vec::with_capacity(intervals.len());
+        for (start, end) in intervals.drain(..) {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        *intervals = merged;
+    }
+
+    /// The set in the binary format of `COM_BINLOG_DUMP_GTID`.
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend(&(self.sets.len() as u64).to_le_bytes());
+        for (sid, intervals) in &self.sets {
+            buf.extend(sid);
+            buf.extend(&(intervals.len() as u64).to_le_bytes());
+            for (start, end) in intervals {
+                buf.extend(&start.to_le_bytes());
+                buf.extend(&end.to_le_bytes());
+            }
+        }
+        buf
+    }
+}
+
+/// `sid` as a UUID in its usual text form
+pub(crate) fn format_sid(sid: &[u8; 16]) -> String {
+    let hex = hex::encode(sid);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+fn parse_sid(s: &str) -> Result<[u8; 16], Error> {
+    let hex = s.replace('-', "");
+    let mut sid = [0u8; 16];
+    hex::decode_to_slice(&hex, &mut sid)
+        .map_err(|_| Error::from(format!("invalid GTID source id: {:?}", s)))?;
+    Ok(sid)
+}
+
+impl FromStr for MySqlGtidSet {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let mut set = MySqlGtidSet::new();
+        for part in s.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+            let mut fields = part.split(':');
+            let sid = parse_sid(fields.next().unwrap_or_default())?;
+            for interval in fields {
+                let invalid = || Error::from(format!("invalid GTID interval: {:?}", interval));
+                let (start, end) = match interval.split_once('-') {
+                    Some((start, end)) => (start, end),
+                    None => (interval, interval),
+                };
+                let start: u64 = start.trim().parse().map_err(|_| invalid())?;
+                let end: u64 = end.trim().parse().map_err(|_| invalid())?;
+                if start == 0 || end < start {
+                    return Err(invalid());
+                }
+                set.add_interval(sid, start, end + 1);
+            }
+        }
+        Ok(set)
+    }
+}
+
+impl Display for MySqlGtidSet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, (sid, intervals)) in self.sets.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            f.write_str(&format_sid(sid))?;
+            for (start, end) in intervals {
+                if end - start == 1 {
+                    write!(f, ":{}", start)?;
+                } else {
+                    write!(f, ":{}-{}", start, end - 1)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::MySqlGtidSet;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_gtid_set() {
+        let mut set = MySqlGtidSet::from_str(
+            "3E11FA47-71CA-11E1-9E33-C80AA9429562:1-5:11, 2174b383-5441-11e8-b90a-c80aa9429562:7",
+        )
+        .unwrap();
+        assert_eq!(
+            set.to_string(),
+            "2174b383-5441-11e8-b90a-c80aa9429562:7,3e11fa47-71ca-11e1-9e33-c80aa9429562:1-5:11"
+        );
+
+        let sid = hex::decode("3e11fa4771ca11e19e33c80aa9429562").unwrap();
+        let sid: [u8; 16] = sid.try_into().unwrap();
+        set.add(sid, 6);
+        assert!(set.contains(&sid, 6));
+        assert!(!set.contains(&sid, 7));
+        set.add(sid, 8);
+        set.add(sid, 7);
+        set.add(sid, 9);
+        set.add(sid, 10);
+        assert_eq!(
+            set.to_string(),
+            "2174b383-5441-11e8-b90a-c80aa9429562:7,3e11fa47-71ca-11e1-9e33-c80aa9429562:1-11"
+        );
+
+        let encoded = MySqlGtidSet::from_str("3e11fa47-71ca-11e1-9e33-c80aa9429562:1-5")
+            .unwrap()
+            .encode();
+        let mut expected = 1_u64.to_le_bytes().to_vec();
+        expected.extend(&sid);
+        expected.extend(&1_u64.to_le_bytes());
+        expected.extend(&1_u64.to_le_bytes());
+        expected.extend(&6_u64.to_le_bytes());
+        assert_eq!(encoded, expected);
+
+        assert!(MySqlGtidSet::from_str("").unwrap().is_empty());
+        assert!(MySqlGtidSet::from_str("nope:1").is_err());
+        assert!(MySqlGtidSet::from_str("3e11fa47-71ca-11e1-9e33-c80aa9429562:5-1").is_err());
+    }
+}
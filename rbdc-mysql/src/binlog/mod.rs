@@ -0,0 +1,660 @@
+//! A binlog replication client: the connection registers as a replica and streams the row
+//! changes of the server, decoded from the binary log.
+//!
+//! The server needs `log_bin` and `binlog_format=ROW`, and the user the `REPLICATION SLAVE`
+//! privilege. With `binlog_row_metadata=FULL` the table maps carry the column names and
+//! the signedness, character set and labels needed to decode all values.
+//!
+//! ```rust,no_run
+//! # use rbdc_mysql::binlog::{MySqlBinlogEventData, MySqlBinlogOptions};
+//! # use rbdc_mysql::connection::MySqlConnection;
+//! # async fn run(conn: MySqlConnection) -> Result<(), rbdc::Error> {
+//! let options = MySqlBinlogOptions::new(1001).start_position("binlog.000001", 4);
+//! let mut stream = conn.start_binlog(options).await?;
+//! while let Some(event) = stream.recv().await? {
+//!     if let MySqlBinlogEventData::WriteRows(rows) = event.data {
+//!         println!("{}.{}: {:?}", rows.table.schema, rows.table.table, rows.rows);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! See <https://dev.mysql.com/doc/dev/mysql-server/latest/page_protocol_replication.html>
+
+use crate::connection::MySqlConnection;
+use crate::protocol::replication::{
+    BinlogDump, BinlogDumpGtid, RegisterReplica, BINLOG_DUMP_NON_BLOCK,
+};
+use rbdc::db::Connection;
+use rbdc::{err_protocol, Error};
+use std::time::Duration;
+
+pub mod event;
+mod gtid;
+mod reader;
+mod value;
+
+pub use event::{
+    MySqlBinlogColumn, MySqlBinlogDecoder, MySqlBinlogEvent, MySqlBinlogEventData,
+    MySqlBinlogEventHeader, MySqlBinlogRows, MySqlBinlogUpdateRows, MySqlGtid, MySqlTableMap,
+};
+pub use gtid::MySqlGtidSet;
+
+#[derive(Debug, Clone)]
+enum BinlogStart {
+    Position { filename: String, position: u32 },
+    Gtid(MySqlGtidSet),
+}
+
+/// Options for [`MySqlConnection::start_binlog`].
+#[derive(Debug, Clone)]
+pub struct MySqlBinlogOptions {
+    server_id: u32,
+    start: BinlogStart,
+    heartbeat_interval: Duration,
+    verify_checksum: bool,
+    non_blocking: bool,
+}
+
+impl MySqlBinlogOptions {
+    /// Streams the binlog from its first file as the replica `server_id`, which must be
+    /// unique among the servers and replicas of the topology.
+    pub fn new(server_id: u32) -> Self {
+        Self {
+            server_id,
+            start: BinlogStart::Position {
+                filename: String::new(),
+                position: 4,
+            },
+            heartbeat_interval: Duration::from_secs(30),
+            verify_checksum: true,
+            non_blocking: false,
+        }
+    }
+
+    /// Starts at a position of a binlog file, as listed by `SHOW BINARY LOG STATUS`.
+    pub fn start_position(mut self, filename: &str, position: u32) -> Self {
+        self.start = BinlogStart::Position {
+            filename: filename.to_string(),
+            position,
+        };
+        self
+    }
+
+    /// Starts at the first transaction that is not in `executed`, e.g. the
+    /// [`gtid_set`](MySqlBinlogStream::gtid_set) of a previous stream. Needs `gtid_mode=ON`.
+    pub fn start_gtid(mut self, executed: MySqlGtidSet) -> Self {
+        self.start = BinlogStart::Gtid(executed);
+        self
+    }
+
+    /// Sets how often the server sends a heartbeat while there are no new events. The
+    /// stream fails when nothing is received for twice as long, `0` disables both.
+    /// Defaults to 30 seconds.
+    pub fn heartbeat_interval(mut self, interval: Duration) -> Self {
+        self.heartbeat_interval = interval;
+        self
+    }
+
+    /// Sets whether the CRC32 checksums of the events are verified. Defaults to `true`.
+    pub fn verify_checksum(mut self, verify: bool) -> Self {
+        self.verify_checksum = verify;
+        self
+    }
+
+    /// Ends the stream at the end of the binlog instead of waiting for new events.
+    pub fn non_blocking(mut self, non_blocking: bool) -> Self {
+        self.non_blocking = non_blocking;
+        self
+    }
+}
+
+impl MySqlConnection {
+    /// Registers the connection as a replica and starts streaming the binlog. The
+    /// connection is used by the stream until it is closed.
+    pub async fn start_binlog(
+        mut self,
+        options: MySqlBinlogOptions,
+    ) -> Result<MySqlBinlogStream, Error> {
+        if options.server_id == 0 {
+            return Err(Error::from("the server id of a replica must not be 0"));
+        }
+
+        let rows = self
+            .get_values("SELECT @@global.binlog_checksum AS checksum", vec![])
+            .await?;
+        let checksum = rows
+            .first()
+            .and_then(|row| {
+                row["checksum"]
+                    .as_str()
+                    .map(|s| s.eq_ignore_ascii_case("CRC32"))
+            })
+            .unwrap_or(false);
+        // tells the server the replica handles checksums, it refuses to stream otherwise
+        self.exec(
+            "SET @master_binlog_checksum = @@global.binlog_checksum",
+            vec![],
+        )
+        .await?;
+        self.exec(
+            &format!(
+                "SET @master_heartbeat_period = {}",
+                options.heartbeat_interval.as_nanos()
+            ),
+            vec![],
+        )
+        .await?;
+
+        let port = self.option.port;
+        self.stream
+            .send_packet(RegisterReplica {
+                server_id: options.server_id,
+                hostname: "",
+                user: "",
+                password: "",
+                port,
+            })
+            .await?;
+        self.stream.recv_ok().await?;
+
+        let flags = if options.non_blocking {
+            BINLOG_DUMP_NON_BLOCK
+        } else {
+            0
+        };
+        let progress = match &options.start {
+            BinlogStart::Position { filename, position } => {
+                self.stream
+                    .send_packet(BinlogDump {
+                        position: *position,
+                        flags,
+                        server_id: options.server_id,
+                        filename,
+                    })
+                    .await?;
+                Progress::new(filename, u64::from(*position), MySqlGtidSet::new())
+            }
+            BinlogStart::Gtid(executed) => {
+                self.stream
+                    .send_packet(BinlogDumpGtid {
+                        flags,
+                        server_id: options.server_id,
+                        gtid_set: &executed.encode(),
+                    })
+                    .await?;
+                Progress::new("", 4, executed.clone())
+            }
+        };
+
+        let decoder = MySqlBinlogDecoder::new()
+            .checksum(checksum)
+            .verify_checksum(options.verify_checksum)
+            .offset_sec(self.option.offset_sec);
+        let timeout = Some(options.heartbeat_interval * 2).filter(|t| !t.is_zero());
+        Ok(MySqlBinlogStream {
+            conn: self,
+            decoder,
+            progress,
+            timeout,
+        })
+    }
+}
+
+/// A stream of binlog events, see [`MySqlConnection::start_binlog`].
+///
+/// It tracks the position and executed GTIDs of what was received, to resume from with
+/// [`start_position`](MySqlBinlogOptions::start_position) or
+/// [`start_gtid`](MySqlBinlogOptions::start_gtid).
+pub struct MySqlBinlogStream {
+    conn: MySqlConnection,
+    decoder: MySqlBinlogDecoder,
+    progress: Progress,
+    timeout: Option<Duration>,
+}
+
+impl MySqlBinlogStream {
+    /// Receives the next event. Heartbeats are handled internally. Returns `None` when the
+    /// server ended the stream, at the end of the binlog of a non-blocking stream.
+    pub async fn recv(&mut self) -> Result<Option<MySqlBinlogEvent>, Error> {
+        loop {
+            let packet = match self.timeout {
+                Some(timeout) => rbdc::rt::timeout(timeout, self.conn.stream.recv_packet())
+                    .await
+                    .map_err(|_| {
+                        Error::from(format!(
+                            "no binlog event or heartbeat received in {:?}",
+                            timeout
+                        ))
+                    })??,
+                None => self.conn.stream.recv_packet().await?,
+            };
+            match packet.first() {
+                Some(0x00) => {}
+                Some(0xfe) if packet.len() < 9 => return Ok(None),
+                _ => {
+                    return Err(err_protocol!(
+                        "unexpected packet in the binlog stream: {:?}",
+                        packet.first()
+                    ))
+                }
+            }
+
+            let event = self.decoder.decode(&packet[1..])?;
+            self.progress.update(&event);
+            if let MySqlBinlogEventData::Heartbeat = event.data {
+                continue;
+            }
+            return Ok(Some(event));
+        }
+    }
+
+    /// The binlog file and the position after the last event received.
+    pub fn position(&self) -> (&str, u64) {
+        (&self.progress.filename, self.progress.position)
+    }
+
+    /// The GTIDs of the transactions received completely, and those the stream started
+    /// after.
+    pub fn gtid_set(&self) -> &MySqlGtidSet {
+        &self.progress.gtid_set
+    }
+
+    /// The columns of a table by the table id of the rows events.
+    pub fn table(&self, table_id: u64) -> Option<&std::sync::Arc<MySqlTableMap>> {
+        self.decoder.table(table_id)
+    }
+
+    /// Ends the stream and closes the connection.
+    pub async fn close(mut self) -> Result<(), Error> {
+        self.conn.close().await
+    }
+}
+
+/// what was received, to resume from
+#[derive(Debug)]
+struct Progress {
+    filename: String,
+    position: u64,
+    gtid_set: MySqlGtidSet,
+    // the GTID of the transaction being received
+    pending: Option<MySqlGtid>,
+    // inside a `BEGIN` block, which only a `COMMIT` or Xid ends
+    in_transaction: bool,
+}
+
+impl Progress {
+    fn new(filename: &str, position: u64, gtid_set: MySqlGtidSet) -> Self {
+        Self {
+            filename: filename.to_string(),
+            position,
+            gtid_set,
+            pending: None,
+            in_transaction: false,
+        }
+    }
+
+    fn update(&mut self, event: &MySqlBinlogEvent) {
+        match &event.data {
+            MySqlBinlogEventData::Rotate { position, filename } => {
+                self.filename = filename.clone();
+                self.position = *position;
+                return;
+            }
+            MySqlBinlogEventData::Gtid(gtid) => self.pending = Some(*gtid),
+            // a transaction ends with its commit, a DDL statement is one by itself
+            MySqlBinlogEventData::Xid { .. } => self.commit(),
+            MySqlBinlogEventData::Query { query, .. } => {
+                if query.eq_ignore_ascii_case("BEGIN") {
+                    self.in_transaction = true;
+                } else if query.eq_ignore_ascii_case("COMMIT") || !self.in_transaction {
+                    self.commit();
+                }
+            }
+            _ => {}
+        }
+        if event.header.log_pos != 0 {
+            self.position = u64::from(event.header.log_pos);
+        }
+    }
+
+    fn commit(&mut self) {
+        self.in_transaction = false;
+        if let Some(gtid) = self.pending.take() {
+            self.gtid_set.add(gtid.sid, gtid.gno);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::event::*;
+    use super::value::{DATETIME2, LONG, NEWDECIMAL, VARCHAR};
+    use super::{MySqlBinlogDecoder, MySqlBinlogEventData, MySqlGtidSet, Progress};
+    use rbs::Value;
+
+    const SID: [u8; 16] = [
+        0x3e, 0x11, 0xfa, 0x47, 0x71, 0xca, 0x11, 0xe1, 0x9e, 0x33, 0xc8, 0x0a, 0xa9, 0x42, 0x95,
+        0x62,
+    ];
+
+    /// appends an event with its header and checksum at the position `file.len()`
+    fn push_event(file: &mut Vec<u8>, event_type: u8, body: &[u8]) {
+        let size = 19 + body.len() + 4;
+        let mut event = Vec::new();
+        event.extend(&1_700_000_000_u32.to_le_bytes());
+        event.push(event_type);
+        event.extend(&1_u32.to_le_bytes());
+        event.extend(&(size as u32).to_le_bytes());
+        event.extend(&((file.len() + size) as u32).to_le_bytes());
+        event.extend(&0_u16.to_le_bytes());
+        event.extend(body);
+        let mut crc = flate2::Crc::new();
+        crc.update(&event);
+        event.extend(&crc.sum().to_le_bytes());
+        file.extend(event);
+    }
+
+    fn format_description() -> Vec<u8> {
+        let mut body = 4_u16.to_le_bytes().to_vec();
+        let mut version = b"8.0.36".to_vec();
+        version.resize(50, 0);
+        body.extend(version);
+        body.extend(&0_u32.to_le_bytes());
+        body.push(19);
+        let mut post_header_len = vec![0_u8; 41];
+        post_header_len[usize::from(QUERY_EVENT) - 1] = 13;
+        post_header_len[usize::from(ROTATE_EVENT) - 1] = 8;
+        post_header_len[usize::from(TABLE_MAP_EVENT) - 1] = 8;
+        for t in [WRITE_ROWS_EVENT, UPDATE_ROWS_EVENT, DELETE_ROWS_EVENT] {
+            post_header_len[usize::from(t) - 1] = 10;
+        }
+        body.extend(post_header_len);
+        // CRC32
+        body.push(1);
+        body
+    }
+
+    fn table_map() -> Vec<u8> {
+        let mut body = vec![0x6d, 0, 0, 0, 0, 0, 1, 0];
+        body.extend(b"\x02db\x00\x01t\x00");
+        body.push(4);
+        body.extend([LONG, VARCHAR, DATETIME2, NEWDECIMAL]);
+        // VARCHAR(100), DATETIME(0) and DECIMAL(5, 2)
+        body.extend([5, 100, 0, 0, 5, 2]);
+        body.push(0b1110);
+        // SIGNEDNESS, the id is unsigned
+        body.extend([1, 1, 0x80]);
+        // COLUMN_NAME
+        body.extend([
+            4, 17, 2, b'i', b'd', 4, b'n', b'a', b'm', b'e', 2, b'a', b't',
+        ]);
+        body.extend([5, b'p', b'r', b'i', b'c', b'e']);
+        // SIMPLE_PRIMARY_KEY
+        body.extend([8, 1, 0]);
+        body
+    }
+
+    fn rows_header(present: &[u8]) -> Vec<u8> {
+        let mut body = vec![0x6d, 0, 0, 0, 0, 0, 1, 0, 2, 0, 4];
+        body.extend(present);
+        body
+    }
+
+    fn push_gtid(file: &mut Vec<u8>, gno: u64) {
+        let mut gtid = vec![1];
+        gtid.extend(SID);
+        gtid.extend(&gno.to_le_bytes());
+        gtid.extend([2, 0, 0, 0, 0, 0, 0, 0, 0]);
+        push_event(file, GTID_EVENT, &gtid);
+    }
+
+    fn push_query(file: &mut Vec<u8>, sql: &str) {
+        let mut query = 9_u32.to_le_bytes().to_vec();
+        query.extend([0, 0, 0, 0, 2, 0, 0, 0, 0]);
+        query.extend(b"db\x00");
+        query.extend(sql.as_bytes());
+        push_event(file, QUERY_EVENT, &query);
+    }
+
+    fn binlog_file() -> Vec<u8> {
+        let mut file = b"\xfebin".to_vec();
+        push_event(&mut file, FORMAT_DESCRIPTION_EVENT, &format_description());
+
+        push_gtid(&mut file, 7);
+        push_query(&mut file, "BEGIN");
+
+        push_event(&mut file, TABLE_MAP_EVENT, &table_map());
+
+        let mut write = rows_header(&[0x0f]);
+        // 1, 'alice', '2024-01-02 03:04:05', 12.34
+        write.push(0);
+        write.extend(&1_u32.to_le_bytes());
+        write.extend(b"\x05alice");
+        let ymd = ((2024 * 13 + 1) << 5) | 2;
+        let hms = (3 << 12) | (4 << 6) | 5;
+        let datetime: u64 = ((ymd << 17) | hms) + 0x80_0000_0000;
+        write.extend(&datetime.to_be_bytes()[3..]);
+        write.extend([0x80, 0x0c, 0x22]);
+        // 4294967295, NULL, NULL, 0.05
+        write.push(0b0110);
+        write.extend(&u32::MAX.to_le_bytes());
+        write.extend([0x80, 0x00, 0x05]);
+        push_event(&mut file, WRITE_ROWS_EVENT, &write);
+
+        // SET name = 'bob' WHERE id = 1, with binlog_row_image=MINIMAL
+        let mut update = rows_header(&[0x01, 0x02]);
+        update.push(0);
+        update.extend(&1_u32.to_le_bytes());
+        update.push(0);
+        update.extend(b"\x03bob");
+        push_event(&mut file, UPDATE_ROWS_EVENT, &update);
+
+        push_event(&mut file, XID_EVENT, &42_u64.to_le_bytes());
+
+        let mut rotate = 4_u64.to_le_bytes().to_vec();
+        rotate.extend(b"binlog.000002");
+        push_event(&mut file, ROTATE_EVENT, &rotate);
+        file
+    }
+
+    #[test]
+    fn test_decode_binlog_file() {
+        let file = binlog_file();
+        let events = MySqlBinlogDecoder::new()
+            .offset_sec(0)
+            .decode_file(&file)
+            .unwrap();
+        assert_eq!(events.len(), 8);
+
+        match &events[0].data {
+            MySqlBinlogEventData::FormatDescription {
+                binlog_version,
+                server_version,
+                checksum,
+            } => {
+                assert_eq!(*binlog_version, 4);
+                assert_eq!(server_version, "8.0.36");
+                assert!(checksum);
+            }
+            data => panic!("unexpected {:?}", data),
+        }
+
+        match &events[1].data {
+            MySqlBinlogEventData::Gtid(gtid) => {
+                assert_eq!(gtid.to_string(), "3e11fa47-71ca-11e1-9e33-c80aa9429562:7")
+            }
+            data => panic!("unexpected {:?}", data),
+        }
+
+        match &events[2].data {
+            MySqlBinlogEventData::Query {
+                thread_id,
+                schema,
+                query,
+                ..
+            } => {
+                assert_eq!(*thread_id, 9);
+                assert_eq!(schema, "db");
+                assert_eq!(query, "BEGIN");
+            }
+            data => panic!("unexpected {:?}", data),
+        }
+
+        match &events[3].data {
+            MySqlBinlogEventData::TableMap(table) => {
+                assert_eq!(table.table_id, 0x6d);
+                assert_eq!((table.schema.as_str(), table.table.as_str()), ("db", "t"));
+                let names: Vec<_> = table.columns.iter().map(|c| c.name.clone()).collect();
+                assert_eq!(
+                    names,
+                    ["id", "name", "at", "price"].map(|n| Some(n.to_string()))
+                );
+                assert!(table.columns[0].unsigned && !table.columns[0].nullable);
+                assert!(!table.columns[3].unsigned && table.columns[3].nullable);
+                assert_eq!(table.primary_key, vec![0]);
+            }
+            data => panic!("unexpected {:?}", data),
+        }
+
+        match &events[4].data {
+            MySqlBinlogEventData::WriteRows(rows) => {
+                assert_eq!(rows.table.table, "t");
+                assert_eq!(rows.rows.len(), 2);
+                let row = &rows.rows[0];
+                assert_eq!(row[0], Value::I64(1));
+                assert_eq!(row[1], Value::String("alice".to_string()));
+                match &row[2] {
+                    Value::Ext("DateTime", v) => {
+                        let v = v.as_str().unwrap();
+                        assert!(
+                            v.starts_with("2024-01-02") && v.contains("03:04:05"),
+                            "{}",
+                            v
+                        );
+                    }
+                    v => panic!("unexpected {:?}", v),
+                }
+                assert_eq!(
+                    row[3],
+                    Value::Ext("Decimal", Box::new(Value::String("12.34".to_string())))
+                );
+                assert_eq!(
+                    rows.rows[1],
+                    vec![
+                        Value::I64(u32::MAX as i64),
+                        Value::Null,
+                        Value::Null,
+                        Value::Ext("Decimal", Box::new(Value::String("0.05".to_string()))),
+                    ]
+                );
+            }
+            data => panic!("unexpected {:?}", data),
+        }
+
+        match &events[5].data {
+            MySqlBinlogEventData::UpdateRows(rows) => {
+                assert_eq!(rows.before_present, vec![true, false, false, false]);
+                assert_eq!(
+                    rows.rows,
+                    vec![(
+                        vec![Value::I64(1), Value::Null, Value::Null, Value::Null],
+                        vec![
+                            Value::Null,
+                            Value::String("bob".to_string()),
+                            Value::Null,
+                            Value::Null
+                        ],
+                    )]
+                );
+            }
+            data => panic!("unexpected {:?}", data),
+        }
+
+        assert!(matches!(
+            events[6].data,
+            MySqlBinlogEventData::Xid { xid: 42 }
+        ));
+        match &events[7].data {
+            MySqlBinlogEventData::Rotate { position, filename } => {
+                assert_eq!(*position, 4);
+                assert_eq!(filename, "binlog.000002");
+            }
+            data => panic!("unexpected {:?}", data),
+        }
+    }
+
+    #[test]
+    fn test_decode_binlog_errors() {
+        let file = binlog_file();
+
+        // a flipped bit of the WRITE_ROWS event
+        let mut corrupted = file.clone();
+        let at = file.windows(5).position(|w| w == b"alice").unwrap();
+        corrupted[at] ^= 1;
+        assert!(MySqlBinlogDecoder::new().decode_file(&corrupted).is_err());
+        assert!(MySqlBinlogDecoder::new()
+            .verify_checksum(false)
+            .decode_file(&corrupted)
+            .is_ok());
+
+        // truncated events fail instead of panicking
+        for len in 4..file.len() {
+            let mut decoder = MySqlBinlogDecoder::new().verify_checksum(false);
+            let _ = decoder.decode_file(&file[..len]);
+        }
+        assert!(MySqlBinlogDecoder::new().decode_file(b"nope").is_err());
+    }
+
+    #[test]
+    fn test_binlog_progress() {
+        let file = binlog_file();
+        let events = MySqlBinlogDecoder::new().decode_file(&file).unwrap();
+        let mut progress = Progress::new("binlog.000001", 4, MySqlGtidSet::new());
+        for event in &events[..6] {
+            progress.update(event);
+        }
+        // the transaction is not committed yet
+        assert!(progress.gtid_set.is_empty());
+        progress.update(&events[6]);
+        assert_eq!(
+            progress.gtid_set.to_string(),
+            "3e11fa47-71ca-11e1-9e33-c80aa9429562:7"
+        );
+        assert_eq!(progress.position, u64::from(events[6].header.log_pos));
+        progress.update(&events[7]);
+        assert_eq!(
+            (progress.filename.as_str(), progress.position),
+            ("binlog.000002", 4)
+        );
+
+        // statement based, the statements inside BEGIN do not end the transaction
+        let mut file = b"\xfebin".to_vec();
+        push_event(&mut file, FORMAT_DESCRIPTION_EVENT, &format_description());
+        push_gtid(&mut file, 8);
+        push_query(&mut file, "BEGIN");
+        push_query(&mut file, "INSERT INTO t VALUES (1)");
+        push_query(&mut file, "COMMIT");
+        push_gtid(&mut file, 9);
+        push_query(&mut file, "CREATE TABLE u (id INT)");
+        let events = MySqlBinlogDecoder::new().decode_file(&file).unwrap();
+        let mut progress = Progress::new("binlog.000002", 4, MySqlGtidSet::new());
+        for event in &events[..4] {
+            progress.update(event);
+        }
+        assert!(progress.gtid_set.is_empty());
+        progress.update(&events[4]);
+        assert_eq!(
+            progress.gtid_set.to_string(),
+            "3e11fa47-71ca-11e1-9e33-c80aa9429562:8"
+        );
+        for event in &events[5..] {
+            progress.update(event);
+        }
+        assert_eq!(
+            progress.gtid_set.to_string(),
+            "3e11fa47-71ca-11e1-9e33-c80aa9429562:8-9"
+        );
+    }
+}
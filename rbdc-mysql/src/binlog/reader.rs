@@ -0,0 +1,110 @@
+use rbdc::{err_protocol, Error};
+
+/// Reads the fields of a binlog event, an error instead of a panic when the event is
+/// shorter than its fields say.
+pub(crate) struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    pub(crate) fn take(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        if n > self.buf.len() {
+            return Err(err_protocol!(
+                "binlog event truncated: {} bytes needed, {} left",
+                n,
+                self.buf.len()
+            ));
+        }
+        let (taken, rest) = self.buf.split_at(n);
+        self.buf = rest;
+        Ok(taken)
+    }
+
+    /// the next `n` bytes, without reading them
+    pub(crate) fn peek(&self, n: usize) -> Result<&'a [u8], Error> {
+        Reader::new(self.buf).take(n)
+    }
+
+    pub(crate) fn skip(&mut self, n: usize) -> Result<(), Error> {
+        self.take(n).map(|_| ())
+    }
+
+    /// the bytes left
+    pub(crate) fn rest(&mut self) -> &'a [u8] {
+        std::mem::take(&mut self.buf)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn u16(&mut self) -> Result<u16, Error> {
+        Ok(self.uint(2)? as u16)
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, Error> {
+        Ok(self.uint(4)? as u32)
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, Error> {
+        self.uint(8)
+    }
+
+    /// a little endian integer of `n` (up to 8) bytes
+    pub(crate) fn uint(&mut self, n: usize) -> Result<u64, Error> {
+        Ok(self
+            .take(n)?
+            .iter()
+            .rev()
+            .fold(0, |v, b| (v << 8) | u64::from(*b)))
+    }
+
+    /// a big endian integer of `n` (up to 8) bytes
+    pub(crate) fn uint_be(&mut self, n: usize) -> Result<u64, Error> {
+        Ok(self
+            .take(n)?
+            .iter()
+            .fold(0, |v, b| (v << 8) | u64::from(*b)))
+    }
+
+    /// a length-encoded integer
+    pub(crate) fn lenenc(&mut self) -> Result<u64, Error> {
+        match self.u8()? {
+            0xfc => self.uint(2),
+            0xfd => self.uint(3),
+            0xfe => self.uint(8),
+            v => Ok(u64::from(v)),
+        }
+    }
+
+    /// a length-encoded byte sequence
+    pub(crate) fn lenenc_bytes(&mut self) -> Result<&'a [u8], Error> {
+        let len = self.lenenc()?;
+        self.take(len as usize)
+    }
+
+    /// a length-encoded string
+    pub(crate) fn lenenc_str(&mut self) -> Result<String, Error> {
+        Ok(String::from_utf8_lossy(self.lenenc_bytes()?).into_owned())
+    }
+
+    /// a bitmap of `bits` bits
+    pub(crate) fn bitmap(&mut self, bits: usize) -> Result<Vec<bool>, Error> {
+        let bytes = self.take(bits.div_ceil(8))?;
+        Ok((0..bits)
+            .map(|i| bytes[i / 8] & (1 << (i % 8)) != 0)
+            .collect())
+    }
+}
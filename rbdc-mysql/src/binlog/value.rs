@@ -0,0 +1,626 @@
+use crate::binlog::event::MySqlBinlogColumn;
+use crate::binlog::reader::Reader;
+use rbdc::{err_protocol, Error};
+use rbs::Value;
+use serde_json::Value as JsonValue;
+
+// https://dev.mysql.com/doc/dev/mysql-server/latest/classbinary__log_1_1Table__map__event.html
+// the column types of binlog events, including the internal ones of the storage format
+
+pub(crate) const DECIMAL: u8 = 0x00;
+pub(crate) const TINY: u8 = 0x01;
+pub(crate) const SHORT: u8 = 0x02;
+pub(crate) const LONG: u8 = 0x03;
+pub(crate) const FLOAT: u8 = 0x04;
+pub(crate) const DOUBLE: u8 = 0x05;
+pub(crate) const NULL: u8 = 0x06;
+pub(crate) const TIMESTAMP: u8 = 0x07;
+pub(crate) const LONGLONG: u8 = 0x08;
+pub(crate) const INT24: u8 = 0x09;
+pub(crate) const DATE: u8 = 0x0a;
+pub(crate) const TIME: u8 = 0x0b;
+pub(crate) const DATETIME: u8 = 0x0c;
+pub(crate) const YEAR: u8 = 0x0d;
+pub(crate) const NEWDATE: u8 = 0x0e;
+pub(crate) const VARCHAR: u8 = 0x0f;
+pub(crate) const BIT: u8 = 0x10;
+pub(crate) const TIMESTAMP2: u8 = 0x11;
+pub(crate) const DATETIME2: u8 = 0x12;
+pub(crate) const TIME2: u8 = 0x13;
+pub(crate) const JSON: u8 = 0xf5;
+pub(crate) const NEWDECIMAL: u8 = 0xf6;
+pub(crate) const ENUM: u8 = 0xf7;
+pub(crate) const SET: u8 = 0xf8;
+pub(crate) const TINY_BLOB: u8 = 0xf9;
+pub(crate) const MEDIUM_BLOB: u8 = 0xfa;
+pub(crate) const LONG_BLOB: u8 = 0xfb;
+pub(crate) const BLOB: u8 = 0xfc;
+pub(crate) const VAR_STRING: u8 = 0xfd;
+pub(crate) const STRING: u8 = 0xfe;
+pub(crate) const GEOMETRY: u8 = 0xff;
+
+/// the collation of binary strings
+const BINARY_COLLATION: u16 = 63;
+
+/// the number of bytes of the table map metadata of a column type
+pub(crate) fn metadata_len(column_type: u8) -> usize {
+    match column_type {
+        FLOAT | DOUBLE | BLOB | TINY_BLOB | MEDIUM_BLOB | LONG_BLOB | GEOMETRY | JSON | TIME2
+        | DATETIME2 | TIMESTAMP2 => 1,
+        VARCHAR | VAR_STRING | STRING | BIT | NEWDECIMAL | ENUM | SET => 2,
+        _ => 0,
+    }
+}
+
+pub(crate) fn is_numeric(column_type: u8) -> bool {
+    matches!(
+        column_type,
+        TINY | SHORT | INT24 | LONG | LONGLONG | FLOAT | DOUBLE | NEWDECIMAL
+    )
+}
+
+pub(crate) fn is_character(column_type: u8) -> bool {
+    matches!(
+        column_type,
+        VARCHAR | VAR_STRING | STRING | BLOB | TINY_BLOB | MEDIUM_BLOB | LONG_BLOB
+    )
+}
+
+/// Reads the value of `column` in a row of a rows event.
+pub(crate) fn decode_value(
+    r: &mut Reader,
+    column: &MySqlBinlogColumn,
+    offset_sec: i32,
+) -> Result<Value, Error> {
+    let meta = column.meta;
+    Ok(match column.column_type {
+        TINY | SHORT | INT24 | LONG | LONGLONG => {
+            let size = match column.column_type {
+                TINY => 1,
+                SHORT => 2,
+                INT24 => 3,
+                LONG => 4,
+                _ => 8,
+            };
+            let v = r.uint(size)?;
+            if column.unsigned {
+                match column.column_type {
+                    LONGLONG => Value::U64(v),
+                    LONG => Value::I64(v as i64),
+                    _ => Value::I32(v as i32),
+                }
+            } else {
+                // sign extend
+                let shift = 64 - size * 8;
+                let v = ((v << shift) as i64) >> shift;
+                match column.column_type {
+                    LONG | LONGLONG => Value::I64(v),
+                    _ => Value::I32(v as i32),
+                }
+            }
+        }
+        FLOAT => Value::F32(f32::from_bits(r.u32()?)),
+        DOUBLE => Value::F64(f64::from_bits(r.u64()?)),
+        NULL => Value::Null,
+        YEAR => {
+            let v = r.u8()?;
+            let year = if v == 0 { 0 } else { 1900 + u16::from(v) };
+            Value::Ext("Year", Box::new(Value::String(year.to_string())))
+        }
+        DATE | NEWDATE => {
+            let v = r.uint(3)?;
+            let (year, month, day) = (v >> 9, (v >> 5) & 15, v & 31);
+            Value::Ext(
+                "Date",
+                Box::new(Value::String(format!(
+                    "{:04}-{:02}-{:02}",
+                    year, month, day
+                ))),
+            )
+        }
+        TIME => {
+            // HHMMSS as a signed integer
+            let v = ((r.uint(3)? << 40) as i64) >> 40;
+            let sign = if v < 0 { "-" } else { "" };
+            let v = v.unsigned_abs();
+            Value::Ext(
+                "Time",
+                Box::new(Value::String(format!(
+                    "{}{:02}:{:02}:{:02}",
+                    sign,
+                    v / 10000,
+                    v / 100 % 100,
+                    v % 100
+                ))),
+            )
+        }
+        TIME2 => {
+            let fsp = meta as u8;
+            let packed = read_time2(r, fsp)?;
+            Value::Ext("Time", Box::new(Value::String(format_time(packed, fsp))))
+        }
+        DATETIME => {
+            // YYYYMMDDhhmmss as an integer
+            let v = r.u64()?;
+            let (date, time) = (v / 1_000_000, v % 1_000_000);
+            datetime_value(
+                (date / 10000) as u16,
+                (date / 100 % 100) as u8,
+                (date % 100) as u8,
+                (time / 10000) as u8,
+                (time / 100 % 100) as u8,
+                (time % 100) as u8,
+                0,
+                offset_sec,
+            )
+        }
+        DATETIME2 => {
+            let fsp = meta as u8;
+            let ymdhms = r.uint_be(5)?.wrapping_sub(0x80_0000_0000);
+            let micros = read_fraction(r, fsp)?;
+            let (year, month, day, hour, minute, second) = datetime_parts(ymdhms);
+            datetime_value(year, month, day, hour, minute, second, micros, offset_sec)
+        }
+        TIMESTAMP => Value::Ext(
+            "Timestamp",
+            Box::new(Value::U64(u64::from(r.u32()?) * 1000)),
+        ),
+        TIMESTAMP2 => {
+            let seconds = r.uint_be(4)?;
+            let micros = read_fraction(r, meta as u8)?;
+            Value::Ext(
+                "Timestamp",
+                Box::new(Value::U64(seconds * 1000 + u64::from(micros) / 1000)),
+            )
+        }
+        NEWDECIMAL => {
+            let (precision, scale) = ((meta >> 8) as usize, (meta & 0xff) as usize);
+            Value::Ext(
+                "Decimal",
+                Box::new(Value::String(read_decimal(r, precision, scale)?)),
+            )
+        }
+        VARCHAR | VAR_STRING | STRING => {
+            let max_len = if column.column_type == VARCHAR {
+                usize::from(meta)
+            } else {
+                string_max_len(meta)
+            };
+            let len = r.uint(if max_len > 255 { 2 } else { 1 })? as usize;
+            text_value(r.take(len)?, column.charset)
+        }
+        ENUM => {
+            let index = r.uint(usize::from(meta & 0xff))?;
+            let label = match index {
+                0 => Some(String::new()),
+                i => column.values.get(i as usize - 1).cloned(),
+            };
+            Value::Ext(
+                "Enum",
+                Box::new(match label {
+                    Some(label) if !column.values.is_empty() => Value::String(label),
+                    _ => Value::U64(index),
+                }),
+            )
+        }
+        SET => {
+            let bits = r.uint(usize::from(meta & 0xff))?;
+            Value::Ext(
+                "Set",
+                Box::new(if column.values.is_empty() {
+                    Value::U64(bits)
+                } else {
+                    Value::String(
+                        column
+                            .values
+                            .iter()
+                            .enumerate()
+                            .filter(|(i, _)| *i < 64 && bits & (1 << i) != 0)
+                            .map(|(_, label)| label.as_str())
+                            .collect::<Vec<_>>()
+                            .join(","),
+                    )
+                }),
+            )
+        }
+        BIT => {
+            let len = (meta >> 8) as usize + usize::from(meta & 0xff > 0);
+            Value::U64(r.uint_be(len.min(8))?)
+        }
+        BLOB | TINY_BLOB | MEDIUM_BLOB | LONG_BLOB | GEOMETRY | JSON => {
+            let len = r.uint(usize::from(meta))? as usize;
+            let data = r.take(len)?;
+            match column.column_type {
+                GEOMETRY => Value::Ext("Geometry", Box::new(Value::Binary(data.to_vec()))),
+                JSON => serde_json::from_value(decode_json(data)?)
+                    .map_err(|e| Error::from(e.to_string()))?,
+                // a TEXT column when its character set is known
+                _ if column.charset.is_some() => text_value(data, column.charset),
+                _ => Value::Binary(data.to_vec()),
+            }
+        }
+        t => return Err(err_protocol!("unsupported binlog column type 0x{:02x}", t)),
+    })
+}
+
+/// the length of a `STRING` column from its metadata, the real type and the length with
+/// its two high bits stored in the type byte
+pub(crate) fn string_max_len(meta: u16) -> usize {
+    let (byte0, byte1) = ((meta >> 8) as usize, (meta & 0xff) as usize);
+    if byte0 & 0x30 != 0x30 {
+        byte1 | (((byte0 & 0x30) ^ 0x30) << 4)
+    } else {
+        byte1
+    }
+}
+
+fn text_value(data: &[u8], charset: Option<u16>) -> Value {
+    if charset == Some(BINARY_COLLATION) {
+        return Value::Binary(data.to_vec());
+    }
+    match std::str::from_utf8(data) {
+        Ok(s) => Value::String(s.to_string()),
+        Err(_) => Value::Binary(data.to_vec()),
+    }
+}
+
+/// the microseconds of a `DATETIME2`, `TIMESTAMP2` or `TIME2` with `fsp` fractional digits
+fn read_fraction(r: &mut Reader, fsp: u8) -> Result<u32, Error> {
+    let len = usize::from(fsp.min(6)).div_ceil(2);
+    let v = r.uint_be(len)? as u32;
+    Ok(v * 100_u32.pow(3 - len as u32))
+}
+
+/// a `TIME2` as `hms << 24 | microseconds`, negative for a negative time
+fn read_time2(r: &mut Reader, fsp: u8) -> Result<i64, Error> {
+    const INT_OFS: i64 = 0x80_0000;
+    Ok(match fsp.min(6) {
+        0 => (r.uint_be(3)? as i64 - INT_OFS) << 24,
+        fsp @ (1..=4) => {
+            let mut int = r.uint_be(3)? as i64 - INT_OFS;
+            let (len, scale) = if fsp <= 2 { (1, 10_000) } else { (2, 100) };
+            let mut frac = r.uint_be(len)? as i64;
+            if int < 0 && frac != 0 {
+                // the fraction is stored as a positive offset to the next lower second
+                int += 1;
+                frac -= 1 << (len * 8);
+            }
+            (int << 24) + frac * scale
+        }
+        _ => r.uint_be(6)? as i64 - 0x8000_0000_0000,
+    })
+}
+
+/// a packed time, `hms << 24 | microseconds`, as `[-]HH:MM:SS[.ffffff]`
+fn format_time(packed: i64, fsp: u8) -> String {
+    let sign = if packed < 0 { "-" } else { "" };
+    let packed = packed.unsigned_abs();
+    let hms = packed >> 24;
+    format!(
+        "{}{:02}:{:02}:{:02}{}",
+        sign,
+        (hms >> 12) % (1 << 10),
+        (hms >> 6) % (1 << 6),
+        hms % (1 << 6),
+        format_fraction((packed % (1 << 24)) as u32, fsp)
+    )
+}
+
+fn format_fraction(micros: u32, fsp: u8) -> String {
+    match fsp.min(6) {
+        0 => String::new(),
+        fsp => format!(".{:06}", micros)[..usize::from(fsp) + 1].to_string(),
+    }
+}
+
+/// the fields of a packed datetime, `year * 13 + month` then day, hour, minute and second
+/// in 5, 5, 6 and 6 bits
+fn datetime_parts(ymdhms: u64) -> (u16, u8, u8, u8, u8, u8) {
+    let (ymd, hms) = (ymdhms >> 17, ymdhms % (1 << 17));
+    let ym = ymd >> 5;
+    (
+        (ym / 13) as u16,
+        (ym % 13) as u8,
+        (ymd % (1 << 5)) as u8,
+        (hms >> 12) as u8,
+        ((hms >> 6) % (1 << 6)) as u8,
+        (hms % (1 << 6)) as u8,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn datetime_value(
+    year: u16,
+    mon: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    sec: u8,
+    micros: u32,
+    offset_sec: i32,
+) -> Value {
+    let value = if mon == 0 || day == 0 {
+        // a zero date, that is no valid date
+        format!(
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            year, mon, day, hour, minute, sec
+        )
+    } else {
+        rbdc::datetime::DateTime(fastdate::DateTime::from((
+            fastdate::Date {
+                day,
+                mon,
+                year: i32::from(year),
+            },
+            fastdate::Time {
+                nano: micros * 1000,
+                sec,
+                minute,
+                hour,
+            },
+            offset_sec,
+        )))
+        .to_string()
+    };
+    Value::Ext("DateTime", Box::new(Value::String(value)))
+}
+
+/// the number of bytes of the decimal digits in a group of 0 to 9 digits
+const DIG2BYTES: [usize; 10] = [0, 1, 1, 2, 2, 3, 3, 4, 4, 4];
+
+/// Reads a `DECIMAL(precision, scale)` in its binary format: groups of 9 digits in 4 big
+/// endian bytes, with the first bit flipped and all bits inverted when negative.
+fn read_decimal(r: &mut Reader, precision: usize, scale: usize) -> Result<String, Error> {
+    let intg = precision.saturating_sub(scale);
+    let (intg0, intg0x) = (intg / 9, intg % 9);
+    let (frac0, frac0x) = (scale / 9, scale % 9);
+    let size = intg0 * 4 + DIG2BYTES[intg0x] + frac0 * 4 + DIG2BYTES[frac0x];
+
+    let mut bytes = r.take(size)?.to_vec();
+    if bytes.is_empty() {
+        return Ok("0".to_string());
+    }
+    let negative = bytes[0] & 0x80 == 0;
+    bytes[0] ^= 0x80;
+    if negative {
+        bytes.iter_mut().for_each(|b| *b ^= 0xff);
+    }
+
+    let mut d = Reader::new(&bytes);
+    let mut int = String::new();
+    if intg0x > 0 {
+        int.push_str(&format!(
+            "{:0w$}",
+            d.uint_be(DIG2BYTES[intg0x])?,
+            w = intg0x
+        ));
+    }
+    for _ in 0..intg0 {
+        int.push_str(&format!("{:09}", d.uint_be(4)?));
+    }
+    let int = match int.trim_start_matches('0') {
+        "" => "0",
+        int => int,
+    };
+
+    let mut decimal = format!("{}{}", if negative { "-" } else { "" }, int);
+    if scale > 0 {
+        decimal.push('.');
+        for _ in 0..frac0 {
+            decimal.push_str(&format!("{:09}", d.uint_be(4)?));
+        }
+        if frac0x > 0 {
+            decimal.push_str(&format!(
+                "{:0w$}",
+                d.uint_be(DIG2BYTES[frac0x])?,
+                w = frac0x
+            ));
+        }
+    }
+    Ok(decimal)
+}
+
+// https://dev.mysql.com/doc/dev/mysql-server/latest/json__binary_8h.html
+
+/// Decodes a `JSON` column in the binary format of MySQL.
+pub(crate) fn decode_json(data: &[u8]) -> Result<JsonValue, Error> {
+    match data.split_first() {
+        None => Ok(JsonValue::Null),
+        Some((t, data)) => json_value(*t, data),
+    }
+}
+
+fn json_value(t: u8, data: &[u8]) -> Result<JsonValue, Error> {
+    let mut r = Reader::new(data);
+    Ok(match t {
+        0x00 => json_container(data, false, true)?,
+        0x01 => json_container(data, true, true)?,
+        0x02 => json_container(data, false, false)?,
+        0x03 => json_container(data, true, false)?,
+        0x04 => match r.u8()? {
+            1 => JsonValue::Bool(true),
+            2 => JsonValue::Bool(false),
+            _ => JsonValue::Null,
+        },
+        0x05 => JsonValue::from(r.u16()? as i16),
+        0x06 => JsonValue::from(r.u16()?),
+        0x07 => JsonValue::from(r.u32()? as i32),
+        0x08 => JsonValue::from(r.u32()?),
+        0x09 => JsonValue::from(r.u64()? as i64),
+        0x0a => JsonValue::from(r.u64()?),
+        0x0b => JsonValue::from(f64::from_bits(r.u64()?)),
+        0x0c => {
+            let len = json_var_len(&mut r)?;
+            JsonValue::String(String::from_utf8_lossy(r.take(len)?).into_owned())
+        }
+        0x0f => {
+            let column_type = r.u8()?;
+            let len = json_var_len(&mut r)?;
+            json_opaque(column_type, r.take(len)?)?
+        }
+        t => return Err(err_protocol!("unknown JSON value type 0x{:02x}", t)),
+    })
+}
+
+/// an object or array: the element count and size, the key entries of an object, the
+/// value entries and then the keys and values the entries point to
+fn json_container(data: &[u8], large: bool, object: bool) -> Result<JsonValue, Error> {
+    let offset_size = if large { 4 } else { 2 };
+    let mut r = Reader::new(data);
+    let count = r.uint(offset_size)? as usize;
+    let _size = r.uint(offset_size)?;
+
+    let mut keys = Vec::with_capacity(if object { count.min(r.len()) } else { 0 });
+    if object {
+        for _ in 0..count {
+            let offset = r.uint(offset_size)? as usize;
+            let len = r.u16()? as usize;
+            let key = data
+                .get(offset..offset + len)
+                .ok_or_else(|| err_protocol!("JSON key out of bounds"))?;
+            keys.push(String::from_utf8_lossy(key).into_owned());
+        }
+    }
+
+    let mut values = Vec::with_capacity(count.min(r.len()));
+    for _ in 0..count {
+        let t = r.u8()?;
+        let entry = r.take(offset_size)?;
+        let inlined = match t {
+            0x04..=0x06 => true,
+            0x07 | 0x08 => large,
+            _ => false,
+        };
+        values.push(if inlined {
+            json_value(t, entry)?
+        } else {
+            let offset = Reader::new(entry).uint(offset_size)? as usize;
+            let value = data
+                .get(offset..)
+                .ok_or_else(|| err_protocol!("JSON value out of bounds"))?;
+            json_value(t, value)?
+        });
+    }
+
+    Ok(if object {
+        JsonValue::Object(keys.into_iter().zip(values).collect())
+    } else {
+        JsonValue::Array(values)
+    })
+}
+
+/// a length of 7 bits per byte, the high bit set when another byte follows
+fn json_var_len(r: &mut Reader) -> Result<usize, Error> {
+    let mut len = 0;
+    for i in 0..5 {
+        let b = r.u8()?;
+        len |= usize::from(b & 0x7f) << (7 * i);
+        if b & 0x80 == 0 {
+            return Ok(len);
+        }
+    }
+    Err(err_protocol!("invalid JSON length"))
+}
+
+/// a MySQL value stored in a JSON document, e.g. a decimal or a datetime
+fn json_opaque(column_type: u8, data: &[u8]) -> Result<JsonValue, Error> {
+    let mut r = Reader::new(data);
+    Ok(match column_type {
+        NEWDECIMAL => {
+            let precision = r.u8()?;
+            let scale = r.u8()?;
+            let decimal = read_decimal(&mut r, usize::from(precision), usize::from(scale))?;
+            serde_json::from_str(&decimal).unwrap_or(JsonValue::String(decimal))
+        }
+        DATE | DATETIME | DATETIME2 | TIMESTAMP | TIMESTAMP2 => {
+            let packed = r.u64()? as i64;
+            let micros = (packed.unsigned_abs() % (1 << 24)) as u32;
+            let (year, month, day, hour, minute, second) =
+                datetime_parts(packed.unsigned_abs() >> 24);
+            JsonValue::String(if column_type == DATE {
+                format!("{:04}-{:02}-{:02}", year, month, day)
+            } else {
+                format!(
+                    "{:04}-{:02}-{:02} {:02}:{:02}:{:02}{}",
+                    year,
+                    month,
+                    day,
+                    hour,
+                    minute,
+                    second,
+                    format_fraction(micros, if micros == 0 { 0 } else { 6 })
+                )
+            })
+        }
+        TIME | TIME2 => {
+            let packed = r.u64()? as i64;
+            let fsp = if packed % (1 << 24) == 0 { 0 } else { 6 };
+            JsonValue::String(format_time(packed, fsp))
+        }
+        _ => JsonValue::String(String::from_utf8_lossy(data).into_owned()),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{decode_json, format_time, read_decimal, read_time2};
+    use crate::binlog::reader::Reader;
+    use serde_json::json;
+
+    #[test]
+    fn test_decode_decimal() {
+        // DECIMAL(14, 4) 1234567890.1234 and -1234567890.1234
+        let positive = [0x81, 0x0d, 0xfb, 0x38, 0xd2, 0x04, 0xd2];
+        let negative = positive.map(|b| b ^ 0xff);
+        assert_eq!(
+            read_decimal(&mut Reader::new(&positive), 14, 4).unwrap(),
+            "1234567890.1234"
+        );
+        assert_eq!(
+            read_decimal(&mut Reader::new(&negative), 14, 4).unwrap(),
+            "-1234567890.1234"
+        );
+        // DECIMAL(5, 2) 0.05
+        assert_eq!(
+            read_decimal(&mut Reader::new(&[0x80, 0x00, 0x05]), 5, 2).unwrap(),
+            "0.05"
+        );
+        assert!(read_decimal(&mut Reader::new(&[0x80]), 14, 4).is_err());
+    }
+
+    #[test]
+    fn test_decode_time2() {
+        // TIME(3) 12:34:56.789 and -00:00:01.5
+        let hms: u64 = (12 << 12) | (34 << 6) | 56;
+        let mut data = (hms + 0x80_0000).to_be_bytes()[5..].to_vec();
+        data.extend(&7890_u16.to_be_bytes());
+        let packed = read_time2(&mut Reader::new(&data), 3).unwrap();
+        assert_eq!(format_time(packed, 3), "12:34:56.789");
+
+        let mut data = (0x80_0000_u64 - 2).to_be_bytes()[5..].to_vec();
+        data.extend(&(0x10000_u32 - 5000).to_be_bytes()[2..]);
+        let packed = read_time2(&mut Reader::new(&data), 4).unwrap();
+        assert_eq!(format_time(packed, 1), "-00:00:01.5");
+    }
+
+    #[test]
+    fn test_decode_json() {
+        // {"a": [1, true, "x"]}, a small object with a small array
+        let mut array = vec![0x03, 0x00, 0x00, 0x00];
+        array.extend([0x05, 0x01, 0x00, 0x04, 0x01, 0x00, 0x0c, 0x0d, 0x00]);
+        array.extend([0x01, b'x']);
+        let size = array.len() as u16;
+        array[2..4].copy_from_slice(&size.to_le_bytes());
+
+        let mut object = vec![0x00, 0x01, 0x00, 0x00, 0x00];
+        // key at 11 of length 1, the array at 12
+        object.extend([0x0b, 0x00, 0x01, 0x00, 0x02, 0x0c, 0x00, b'a']);
+        object.extend(&array);
+        let size = (object.len() - 1) as u16;
+        object[3..5].copy_from_slice(&size.to_le_bytes());
+
+        assert_eq!(decode_json(&object).unwrap(), json!({"a": [1, true, "x"]}));
+        assert_eq!(decode_json(&[]).unwrap(), json!(null));
+        assert!(decode_json(&object[..8]).is_err());
+    }
+}
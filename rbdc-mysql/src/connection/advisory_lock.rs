@@ -0,0 +1,182 @@
+use crate::connection::MySqlConnection;
+use rbdc::db::Connection;
+use rbdc::Error;
+use rbs::Value;
+use std::ops::{Deref, DerefMut};
+use std::time::Duration;
+
+/// A MySQL named lock (`GET_LOCK` / `RELEASE_LOCK`), for example to elect a leader or to
+/// make sure a job runs once.
+///
+/// ```rust
+/// # use rbdc_mysql::connection::MySqlAdvisoryLock;
+/// let lock = MySqlAdvisoryLock::new("nightly-report");
+/// ```
+///
+/// The lock is held by the session until it is released or the session ends. Acquiring it
+/// returns a [`MySqlAdvisoryLockGuard`] that gives access to the connection and releases
+/// the lock when dropped.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MySqlAdvisoryLock {
+    name: String,
+}
+
+impl MySqlAdvisoryLock {
+    /// A lock named `name`, at most 64 characters long.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into() }
+    }
+
+    /// The name of the lock.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Waits until the lock is acquired.
+    pub async fn acquire<'c>(
+        &self,
+        conn: &'c mut MySqlConnection,
+    ) -> Result<MySqlAdvisoryLockGuard<'c>, Error> {
+        match self.get_lock(conn, -1).await? {
+            true => Ok(self.guard(conn)),
+            false => Err(Error::from(format!(
+                "could not acquire lock {:?}",
+                self.name
+            ))),
+        }
+    }
+
+    /// Acquires the lock if it is free, `None` if another session holds it.
+    pub async fn try_acquire<'c>(
+        &self,
+        conn: &'c mut MySqlConnection,
+    ) -> Result<Option<MySqlAdvisoryLockGuard<'c>>, Error> {
+        self.acquire_timeout(conn, Duration::ZERO).await
+    }
+
+    /// Waits up to `timeout` (in whole seconds) for the lock, `None` if it is still held by
+    /// another session then.
+    pub async fn acquire_timeout<'c>(
+        &self,
+        conn: &'c mut MySqlConnection,
+        timeout: Duration,
+    ) -> Result<Option<MySqlAdvisoryLockGuard<'c>>, Error> {
+        let seconds = timeout.as_secs().min(i64::MAX as u64) as i64;
+        Ok(match self.get_lock(conn, seconds).await? {
+            true => Some(self.guard(conn)),
+            false => None,
+        })
+    }
+
+    async fn get_lock(&self, conn: &mut MySqlConnection, seconds: i64) -> Result<bool, Error> {
+        let sql = format!("SELECT GET_LOCK({}, {})", self.name_literal()?, seconds);
+        match first_column(conn.get_values(&sql, vec![]).await?) {
+            Value::Null => Err(Error::from(format!(
+                "error while acquiring lock {:?}",
+                self.name
+            ))),
+            v => Ok(v.as_i64() == Some(1)),
+        }
+    }
+
+    fn guard<'c>(&self, conn: &'c mut MySqlConnection) -> MySqlAdvisoryLockGuard<'c> {
+        MySqlAdvisoryLockGuard {
+            lock: self.clone(),
+            conn: Some(conn),
+        }
+    }
+
+    // a hexadecimal literal needs no escaping
+    fn name_literal(&self) -> Result<String, Error> {
+        if self.name.is_empty() || self.name.chars().count() > 64 {
+            return Err(Error::from(format!(
+                "lock name must be 1 to 64 characters long: {:?}",
+                self.name
+            )));
+        }
+        Ok(format!("X'{}'", hex::encode(&self.name)))
+    }
+}
+
+/// A held named lock, derefs to the connection that holds it.
+///
+/// Dropping the guard queues the release on the connection, it is sent (without needing a
+/// runtime here) before the next query.
+pub struct MySqlAdvisoryLockGuard<'c> {
+    lock: MySqlAdvisoryLock,
+    conn: Option<&'c mut MySqlConnection>,
+}
+
+impl<'c> MySqlAdvisoryLockGuard<'c> {
+    /// The lock that is held.
+    pub fn lock(&self) -> &MySqlAdvisoryLock {
+        &self.lock
+    }
+
+    /// Releases the lock now and returns the connection. Returns an error if the server
+    /// reports the lock was not held.
+    pub async fn release_now(mut self) -> Result<&'c mut MySqlConnection, Error> {
+        let conn = self.conn.take().expect("conn is taken");
+        let sql = format!("SELECT RELEASE_LOCK({})", self.lock.name_literal()?);
+        let released = first_column(conn.get_values(&sql, vec![]).await?);
+        if released.as_i64() != Some(1) {
+            return Err(Error::from(format!(
+                "lock {:?} was not held",
+                self.lock.name
+            )));
+        }
+        Ok(conn)
+    }
+
+    /// Returns the connection without releasing the lock, which then stays held until the
+    /// session ends.
+    pub fn leak(mut self) -> &'c mut MySqlConnection {
+        self.conn.take().expect("conn is taken")
+    }
+}
+
+impl Deref for MySqlAdvisoryLockGuard<'_> {
+    type Target = MySqlConnection;
+
+    fn deref(&self) -> &Self::Target {
+        self.conn.as_deref().expect("conn is taken")
+    }
+}
+
+impl DerefMut for MySqlAdvisoryLockGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.conn.as_deref_mut().expect("conn is taken")
+    }
+}
+
+impl Drop for MySqlAdvisoryLockGuard<'_> {
+    fn drop(&mut self) {
+        if let (Some(conn), Ok(name)) = (self.conn.take(), self.lock.name_literal()) {
+            conn.queue_simple_query(&format!("DO RELEASE_LOCK({})", name));
+        }
+    }
+}
+
+fn first_column(rows: Vec<Value>) -> Value {
+    rows.into_iter()
+        .next()
+        .and_then(|row| row.into_iter().next().map(|(_, v)| v))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use super::MySqlAdvisoryLock;
+
+    #[test]
+    fn test_name_literal() {
+        assert_eq!(
+            MySqlAdvisoryLock::new("job'1").name_literal().unwrap(),
+            "X'6a6f622731'"
+        );
+        assert!(MySqlAdvisoryLock::new("").name_literal().is_err());
+        assert!(MySqlAdvisoryLock::new("x".repeat(65))
+            .name_literal()
+            .is_err());
+    }
+}
@@ -0,0 +1,199 @@
+use std::io::{Read, Write};
+
+use bytes::{Buf, Bytes, BytesMut};
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+
+use crate::options::{MySqlCompression, MySqlConnectOptions};
+use crate::protocol::Capabilities;
+use rbdc::{err_protocol, Error};
+
+// https://dev.mysql.com/doc/dev/mysql-server/latest/page_protocol_basic_compression.html
+
+// the largest payload of a compressed packet
+const MAX_PAYLOAD: usize = 0xFF_FF_FF;
+
+/// Wraps the packets of the client/server protocol in compressed packets once the
+/// server accepted compression during the handshake.
+///
+/// A compressed packet has a 7 byte header: the length of the (compressed) payload, its
+/// own sequence id and the length of the payload before compression, `0` when the
+/// payload is sent uncompressed because it is smaller than the threshold.
+pub(crate) struct Compression {
+    algorithm: MySqlCompression,
+    zstd_level: u8,
+    threshold: usize,
+    // reset with the sequence id of the packets at each command
+    pub(crate) sequence_id: u8,
+}
+
+impl Compression {
+    /// the algorithm agreed on with the server, if any
+    pub(crate) fn negotiated(
+        options: &MySqlConnectOptions,
+        capabilities: Capabilities,
+    ) -> Option<Self> {
+        let algorithm = match options.compression {
+            MySqlCompression::Zlib if capabilities.contains(Capabilities::COMPRESS) => {
+                MySqlCompression::Zlib
+            }
+            MySqlCompression::Zstd
+                if capabilities.contains(Capabilities::ZSTD_COMPRESSION_ALGORITHM) =>
+            {
+                MySqlCompression::Zstd
+            }
+            _ => return None,
+        };
+
+        Some(Self {
+            algorithm,
+            zstd_level: options.zstd_level,
+            threshold: options.compression_threshold,
+            sequence_id: 0,
+        })
+    }
+
+    /// appends `packets` to `buf` as compressed packets
+    pub(crate) fn write(&mut self, packets: &[u8], buf: &mut Vec<u8>) {
+        for chunk in packets.chunks(MAX_PAYLOAD) {
+            let compressed = if chunk.len() < self.threshold {
+                None
+            } else {
+                self.compress(chunk)
+                    // sent as is when it does not get smaller
+                    .filter(|compressed| compressed.len() < chunk.len())
+            };
+
+            let (payload, uncompressed_len) = match &compressed {
+                Some(compressed) => (&compressed[..], chunk.len()),
+                None => (chunk, 0),
+            };
+
+            buf.extend_from_slice(&(payload.len() as u32).to_le_bytes()[..3]);
+            buf.push(self.sequence_id);
+            buf.extend_from_slice(&(uncompressed_len as u32).to_le_bytes()[..3]);
+            buf.extend_from_slice(payload);
+
+            self.sequence_id = self.sequence_id.wrapping_add(1);
+        }
+    }
+
+    fn compress(&self, data: &[u8]) -> Option<Vec<u8>> {
+        match self.algorithm {
+            MySqlCompression::Zlib => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data).ok()?;
+                encoder.finish().ok()
+            }
+            MySqlCompression::Zstd => zstd::bulk::compress(data, self.zstd_level as i32).ok(),
+            MySqlCompression::Off => None,
+        }
+    }
+
+    /// reads the header of a compressed packet, returns the length of its payload and the
+    /// length after decompression (`0` when it is not compressed)
+    pub(crate) fn read_header(&mut self, mut header: Bytes) -> (usize, usize) {
+        let payload_len = header.get_uint_le(3) as usize;
+        self.sequence_id = header.get_u8().wrapping_add(1);
+        let uncompressed_len = header.get_uint_le(3) as usize;
+
+        (payload_len, uncompressed_len)
+    }
+
+    /// appends the decompressed `payload` of a compressed packet to `buf`
+    pub(crate) fn read(
+        &self,
+        payload: Bytes,
+        uncompressed_len: usize,
+        buf: &mut BytesMut,
+    ) -> Result<(), Error> {
+        if uncompressed_len == 0 {
+            buf.extend_from_slice(&payload);
+            return Ok(());
+        }
+
+        let decompressed = match self.algorithm {
+            MySqlCompression::Zlib => {
+                let mut decompressed = Vec::with_capacity(uncompressed_len);
+                ZlibDecoder::new(&payload[..])
+                    .read_to_end(&mut decompressed)
+                    .map(|_| decompressed)
+            }
+            MySqlCompression::Zstd => zstd::bulk::decompress(&payload, uncompressed_len),
+            MySqlCompression::Off => return Err(err_protocol!("compression is not enabled")),
+        }
+        .map_err(|e| err_protocol!("failed to decompress packet: {}", e))?;
+
+        if decompressed.len() != uncompressed_len {
+            return Err(err_protocol!(
+                "decompressed packet is {} bytes, expected {}",
+                decompressed.len(),
+                uncompressed_len
+            ));
+        }
+
+        buf.extend_from_slice(&decompressed);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Compression;
+    use crate::options::{MySqlCompression, MySqlConnectOptions};
+    use crate::protocol::Capabilities;
+    use bytes::{Bytes, BytesMut};
+
+    fn round_trip(compression: &mut Compression, packets: &[u8]) -> (Vec<u8>, BytesMut) {
+        let mut buf = Vec::new();
+        compression.sequence_id = 0;
+        compression.write(packets, &mut buf);
+
+        let mut frames = Bytes::from(buf.clone());
+        let mut read = BytesMut::new();
+        while !frames.is_empty() {
+            let (payload_len, uncompressed_len) = compression.read_header(frames.split_to(7));
+            let payload = frames.split_to(payload_len);
+            compression
+                .read(payload, uncompressed_len, &mut read)
+                .unwrap();
+        }
+        (buf, read)
+    }
+
+    #[test]
+    fn test_compression_round_trip() {
+        let packets = b"\x0b\x00\x00\x00\x03SELECT 1".repeat(100);
+
+        for algorithm in [MySqlCompression::Zlib, MySqlCompression::Zstd] {
+            let options = MySqlConnectOptions::new().compression(algorithm);
+            let mut compression = Compression::negotiated(
+                &options,
+                Capabilities::COMPRESS | Capabilities::ZSTD_COMPRESSION_ALGORITHM,
+            )
+            .unwrap();
+
+            let (frames, read) = round_trip(&mut compression, &packets);
+            assert_eq!(&read[..], &packets[..]);
+            assert!(frames.len() < packets.len());
+            // the uncompressed length is set
+            assert_eq!(frames[3], 0);
+            assert_eq!(&frames[4..7], &(packets.len() as u32).to_le_bytes()[..3]);
+            assert_eq!(compression.sequence_id, 1);
+
+            // small packets are sent as is
+            let (frames, read) = round_trip(&mut compression, b"\x01\x00\x00\x00\x0e");
+            assert_eq!(frames, b"\x05\x00\x00\x00\x00\x00\x00\x01\x00\x00\x00\x0e");
+            assert_eq!(&read[..], b"\x01\x00\x00\x00\x0e");
+        }
+    }
+
+    #[test]
+    fn test_compression_negotiated() {
+        let options = MySqlConnectOptions::new().compression(MySqlCompression::Zstd);
+        assert!(Compression::negotiated(&options, Capabilities::COMPRESS).is_none());
+        assert!(
+            Compression::negotiated(&MySqlConnectOptions::new(), Capabilities::all()).is_none()
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use std::sync::Arc;
+use crate::connection::compression::Compression;
 use crate::connection::{tls, DropBox, MySqlConnection, MySqlStream, MAX_PACKET_SIZE};
 use crate::options::{MySqlConnectOptions, MySqlSslMode};
 use crate::protocol::auth::AuthPlugin;
@@ -88,6 +89,7 @@ impl MySqlConnection {
             database: options.database.as_deref(),
             auth_plugin: plugin,
             auth_response: auth_response.as_deref(),
+            zstd_level: options.zstd_level,
         });
 
         stream.flush().await?;
@@ -137,6 +139,10 @@ impl MySqlConnection {
                 }
             }
         }
+
+        // both sides compress the packets after authentication
+        stream.compression = Compression::negotiated(options, stream.capabilities);
+
         Ok(Self {
             stream: DropBox {
                 inner: Some(stream),
@@ -6,9 +6,9 @@ use crate::protocol::response::Status;
 use crate::protocol::statement::{
     BinaryRow, Execute as StatementExecute, Prepare, PrepareOk, StmtClose,
 };
-use crate::protocol::text::{ColumnDefinition, Query, TextRow};
+use crate::protocol::text::{ColumnDefinition, ColumnFlags, ColumnType, Query, TextRow};
 use crate::query::MysqlQuery;
-use crate::query_result::MySqlQueryResult;
+use crate::query_result::{MySqlQueryResult, MySqlResultSet};
 use crate::result_set::{MySqlColumn, MySqlTypeInfo};
 use crate::row::MySqlRow;
 use crate::stmt::{MySqlArguments, MySqlStatement, MySqlStatementMetadata};
@@ -18,8 +18,10 @@ use futures_core::future::BoxFuture;
 use futures_core::stream::BoxStream;
 use futures_core::Stream;
 use futures_util::{pin_mut, TryStreamExt};
+use rbdc::db::{Describe, DescribeColumn};
 use rbdc::ext::ustr::UStr;
 use rbdc::{try_stream, Error};
+use rbs::Value;
 use std::collections::HashMap;
 use std::sync::Arc;
 
@@ -129,16 +131,32 @@ impl MySqlConnection {
                 (Arc::default(), MySqlValueFormat::Text, true)
             };
 
+            // the error serving a LOCAL INFILE, reported after the server replied
+            let mut local_infile_error = None;
+
             loop {
                 // query response is a meta-packet which may be one of:
-                //  Ok, Err, ResultSet, or (unhandled) LocalInfileRequest
+                //  Ok, Err, ResultSet, or LocalInfileRequest
                 let mut packet = self.stream.recv_packet().await?;
 
+                if packet[0] == 0xfb {
+                    // the server asks for the data of LOAD DATA LOCAL INFILE
+                    local_infile_error = self.send_local_infile(packet).await.err();
+                    continue;
+                }
+
                 if packet[0] == 0x00 || packet[0] == 0xff {
                     // first packet in a query response is OK or ERR
                     // this indicates either a successful query with no rows at all or a failed query
                     let ok = packet.ok()?;
 
+                    if let Some(e) = local_infile_error.take() {
+                        if !ok.status.contains(Status::SERVER_MORE_RESULTS_EXISTS) {
+                            self.stream.waiting.pop_front();
+                        }
+                        return Err(e);
+                    }
+
                     let rows_affected = ok.affected_rows;
                     let done = MySqlQueryResult {
                         rows_affected,
@@ -260,6 +278,40 @@ impl MySqlConnection {
         })
     }
 
+    /// Runs `sql`, which may hold several statements (see
+    /// [`multi_statements`](crate::options::MySqlConnectOptions::multi_statements)) or call a
+    /// procedure returning several `SELECT`s, and returns the result of each statement.
+    pub async fn fetch_result_sets(
+        &mut self,
+        sql: &str,
+        params: Vec<Value>,
+    ) -> Result<Vec<MySqlResultSet>, Error> {
+        let query = if params.is_empty() {
+            MysqlQuery {
+                statement: Either::Left(sql.to_string()),
+                arguments: params,
+                persistent: false,
+            }
+        } else {
+            MysqlQuery {
+                statement: Either::Right(self.prepare_with(sql, &[]).await?),
+                arguments: params,
+                persistent: true,
+            }
+        };
+        let steps: Vec<Either<MySqlQueryResult, MySqlRow>> =
+            self.fetch_many(query).try_collect().await?;
+
+        Ok(MySqlResultSet::split(steps)
+            .into_iter()
+            .map(|(rows, result)| MySqlResultSet {
+                rows,
+                rows_affected: result.rows_affected,
+                last_insert_id: result.last_insert_id,
+            })
+            .collect())
+    }
+
     pub fn prepare_with<'e>(
         &'e mut self,
         sql: &'e str,
@@ -279,30 +331,56 @@ impl MySqlConnection {
         })
     }
 
-    // #[doc(hidden)]
-    // pub fn describe<'e, 'q: 'e>(mut self, sql: &'q str) -> BoxFuture<'e, Result<Describe, Error>> {
-    //     Box::pin(async move {
-    //         self.stream.wait_until_ready().await?;
-    //
-    //         let (_, metadata) = self.get_or_prepare(sql, false).await?;
-    //
-    //         let columns = (&*metadata.columns).clone();
-    //
-    //         let nullable = columns
-    //             .iter()
-    //             // .map(|col| {
-    //             //     col.flags
-    //             //         .map(|flags| !flags.contains(ColumnFlags::NOT_NULL))
-    //             // })
-    //             .collect();
-    //
-    //         Ok(Describe {
-    //             parameters: Some(Either::Right(metadata.parameters)),
-    //             columns,
-    //             nullable,
-    //         })
-    //     })
-    // }
+    /// The parameters and columns of `sql` from `COM_STMT_PREPARE`. The statement is closed
+    /// again instead of being cached.
+    pub(crate) async fn describe_statement(&mut self, sql: &str) -> Result<Describe, Error> {
+        self.stream.wait_until_ready().await?;
+        self.stream.send_packet(Prepare { query: sql }).await?;
+        let ok: PrepareOk = self.stream.recv().await?;
+
+        let mut params = Vec::with_capacity(ok.params as usize);
+        if ok.params > 0 {
+            for _ in 0..ok.params {
+                let def: ColumnDefinition = self.stream.recv().await?;
+                params.push(match def.r#type {
+                    // the server does not know the type of most parameters
+                    ColumnType::Null => String::new(),
+                    ty => ty.name(def.char_set, def.flags, Some(def.max_size)).to_string(),
+                });
+            }
+            self.stream.maybe_recv_eof().await?;
+        }
+
+        let mut columns = Vec::with_capacity(ok.columns as usize);
+        let mut nullable = Vec::with_capacity(ok.columns as usize);
+        if ok.columns > 0 {
+            for ordinal in 0..ok.columns as usize {
+                let def: ColumnDefinition = self.stream.recv().await?;
+                let column = recv_next_result_column(&def, ordinal)?;
+                columns.push(DescribeColumn {
+                    name: column.name.to_string(),
+                    column_type: def
+                        .r#type
+                        .name(def.char_set, def.flags, Some(def.max_size))
+                        .to_string(),
+                });
+                nullable.push(Some(!def.flags.contains(ColumnFlags::NOT_NULL)));
+            }
+            self.stream.maybe_recv_eof().await?;
+        }
+
+        self.stream
+            .send_packet(StmtClose {
+                statement: ok.statement_id,
+            })
+            .await?;
+
+        Ok(Describe {
+            params,
+            columns,
+            nullable,
+        })
+    }
 }
 
 async fn recv_result_columns(
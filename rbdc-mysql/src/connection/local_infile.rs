@@ -0,0 +1,169 @@
+use crate::connection::MySqlConnection;
+use crate::protocol::Packet;
+use bytes::Bytes;
+use rbdc::rt::AsyncReadExt;
+use rbdc::Error;
+
+// https://dev.mysql.com/doc/dev/mysql-server/latest/page_protocol_com_query_response_local_infile_request.html
+
+// the size of the packets the data is sent in
+const CHUNK_SIZE: usize = 64 * 1024;
+
+impl MySqlConnection {
+    /// Answers a `LOCAL INFILE` request (`0xFB` and the file name) of the server with the
+    /// data of the file, then an empty packet. The server replies with an OK or ERR packet.
+    ///
+    /// The empty packet is sent even when the file is not allowed or reading it failed, to
+    /// keep the connection usable, the returned error is then to be reported once the
+    /// server replied.
+    pub(super) async fn send_local_infile(&mut self, request: Packet<Bytes>) -> Result<(), Error> {
+        let name = String::from_utf8_lossy(&request[1..]).into_owned();
+
+        let result = match self.option.local_infile_handler.clone() {
+            Some(handler) => match handler.open(&name).await {
+                Ok(mut reader) => {
+                    let mut chunk = vec![0; CHUNK_SIZE];
+                    loop {
+                        let n = match reader.read(&mut chunk).await {
+                            Ok(0) => break Ok(()),
+                            Ok(n) => n,
+                            Err(e) => {
+                                break Err(Error::from(format!(
+                                    "failed to read LOCAL INFILE {:?}: {}",
+                                    name, e
+                                )))
+                            }
+                        };
+                        self.stream.write_packet(&chunk[..n]);
+                        self.stream.flush().await?;
+                    }
+                }
+                Err(e) => Err(e),
+            },
+            None => Err(Error::from(format!(
+                "LOAD DATA LOCAL INFILE of {:?} needs a local_infile_handler",
+                name
+            ))),
+        };
+
+        self.stream.write_packet(&[][..]);
+        self.stream.flush().await?;
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::CHUNK_SIZE;
+    use crate::connection::MySqlConnection;
+    use crate::options::{MySqlConnectOptions, MySqlLocalInfileHandler, MySqlSslMode};
+    use rbdc::db::Connection;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    const HANDSHAKE: &[u8] = b"\n8.0.18\x00\x19\x00\x00\x00\x114aB0c\x06g\x00\xff\xff\xff\x02\x00\xff\xc7\x15\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00tL\x03s\x0f[4\rl4. \x00caching_sha2_password\x00";
+    const OK: &[u8] = &[0x00, 0x01, 0x00, 0x02, 0x00, 0x00, 0x00];
+
+    async fn read_packet(socket: &mut TcpStream) -> Option<Vec<u8>> {
+        let mut header = [0; 4];
+        socket.read_exact(&mut header).await.ok()?;
+        let len = u32::from_le_bytes([header[0], header[1], header[2], 0]) as usize;
+        let mut payload = vec![0; len];
+        socket.read_exact(&mut payload).await.ok()?;
+        Some(payload)
+    }
+
+    async fn write_packet(socket: &mut TcpStream, sequence_id: u8, payload: &[u8]) {
+        let mut packet = (payload.len() as u32).to_le_bytes()[..3].to_vec();
+        packet.push(sequence_id);
+        packet.extend(payload);
+        socket.write_all(&packet).await.unwrap();
+    }
+
+    /// A server that asks for the file quoted in each query, returning the packets each
+    /// request was answered with.
+    async fn serve(listener: TcpListener) -> Vec<(String, Vec<Vec<u8>>)> {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        write_packet(&mut socket, 0, HANDSHAKE).await;
+        read_packet(&mut socket).await.unwrap();
+        write_packet(&mut socket, 2, OK).await;
+
+        let mut requests = Vec::new();
+        // COM_QUERY
+        while let Some(query) = read_packet(&mut socket).await {
+            if query[0] != 0x03 {
+                break;
+            }
+            let sql = String::from_utf8_lossy(&query[1..]).to_string();
+            let Some(name) = sql.split('\'').nth(1) else {
+                write_packet(&mut socket, 1, OK).await;
+                continue;
+            };
+            let mut request = vec![0xfb];
+            request.extend(name.as_bytes());
+            write_packet(&mut socket, 1, &request).await;
+            let mut packets = Vec::new();
+            loop {
+                let packet = read_packet(&mut socket).await.unwrap();
+                let end = packet.is_empty();
+                packets.push(packet);
+                if end {
+                    break;
+                }
+            }
+            write_packet(&mut socket, packets.len() as u8 + 2, OK).await;
+            requests.push((name.to_string(), packets));
+        }
+        requests
+    }
+
+    #[tokio::test]
+    async fn test_send_local_infile() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(serve(listener));
+
+        let data = vec![b'1'; CHUNK_SIZE + 10];
+        let handler = MySqlLocalInfileHandler::new({
+            let data = data.clone();
+            move |_| {
+                let data = data.clone();
+                async move { Ok(std::io::Cursor::new(data)) }
+            }
+        })
+        .allow("a.csv");
+        let options = MySqlConnectOptions::new()
+            .host("127.0.0.1")
+            .port(port)
+            .ssl_mode(MySqlSslMode::Disabled)
+            .local_infile_handler(handler);
+        let mut conn = MySqlConnection::establish(&options).await.unwrap();
+
+        let done = conn
+            .exec("LOAD DATA LOCAL INFILE 'a.csv' INTO TABLE t", vec![])
+            .await
+            .unwrap();
+        assert_eq!(done.rows_affected, 1);
+        let err = conn
+            .exec("LOAD DATA LOCAL INFILE '/etc/passwd' INTO TABLE t", vec![])
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not allowed"), "{}", err);
+        // the connection is usable again
+        conn.exec("DO 1", vec![]).await.unwrap();
+        drop(conn);
+
+        let requests = server.await.unwrap();
+        assert_eq!(requests.len(), 2);
+        let (name, packets) = &requests[0];
+        assert_eq!(name, "a.csv");
+        assert_eq!(
+            packets.iter().map(Vec::len).collect::<Vec<_>>(),
+            [CHUNK_SIZE, 10, 0]
+        );
+        assert_eq!(packets.concat(), data);
+        // a refused file is answered with the empty packet alone
+        assert_eq!(requests[1], ("/etc/passwd".to_string(), vec![vec![]]));
+    }
+}
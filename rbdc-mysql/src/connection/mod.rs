@@ -1,12 +1,12 @@
 use crate::protocol::statement::StmtClose;
-use crate::protocol::text::{Ping, Quit};
+use crate::protocol::text::{Ping, Query, Quit};
 use crate::stmt::MySqlStatementMetadata;
 use either::Either;
 use futures_core::future::BoxFuture;
 use futures_core::stream::BoxStream;
 use futures_util::{FutureExt, StreamExt, TryStreamExt};
 use rbdc::common::StatementCache;
-use rbdc::db::{Connection, ExecResult, Row};
+use rbdc::db::{Connection, Describe, ExecResult, Row, TwoPhase};
 use rbdc::Error;
 use rbs::Value;
 use std::fmt::{self, Debug, Formatter};
@@ -14,16 +14,22 @@ use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
 
 
+mod advisory_lock;
 mod auth;
+mod compression;
 mod establish;
 mod executor;
+mod local_infile;
 mod stream;
 mod tls;
+mod two_phase;
 
 use crate::query::MysqlQuery;
 use crate::query_result::MySqlQueryResult;
 use crate::row::MySqlRow;
+pub use advisory_lock::{MySqlAdvisoryLock, MySqlAdvisoryLockGuard};
 pub(crate) use stream::MySqlStream;
+use stream::Waiting;
 use crate::options::MySqlConnectOptions;
 
 const MAX_PACKET_SIZE: u32 = 1024;
@@ -82,6 +88,18 @@ impl MySqlConnection {
         })
     }
 
+    /// Queue a text query whose result is discarded, sent the next time this connection
+    /// is used.
+    ///
+    /// Used for releasing named locks.
+    pub(crate) fn queue_simple_query(&mut self, sql: &str) {
+        if let Some(stream) = self.stream.inner.as_mut() {
+            stream.reset_sequence_id();
+            stream.write_packet(Query(sql));
+            stream.waiting.push_back(Waiting::Result);
+        }
+    }
+
     #[doc(hidden)]
     fn flush(&mut self) -> BoxFuture<'_, Result<(), Error>> {
         self.stream.wait_until_ready().boxed()
@@ -197,4 +215,13 @@ impl Connection for MySqlConnection {
         let c = self.do_ping();
         Box::pin(async move { c.await })
     }
+
+    fn two_phase(&mut self) -> Option<&mut dyn TwoPhase> {
+        Some(self)
+    }
+
+    fn describe(&mut self, sql: &str) -> BoxFuture<'_, Result<Describe, Error>> {
+        let sql = sql.to_owned();
+        Box::pin(async move { self.describe_statement(&sql).await })
+    }
 }
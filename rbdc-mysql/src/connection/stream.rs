@@ -1,18 +1,22 @@
 use std::collections::VecDeque;
 use std::ops::{Deref, DerefMut};
 
-use bytes::{Buf, Bytes};
+use bytes::{Buf, Bytes, BytesMut};
 
 use crate::collation::{CharSet, Collation};
+use crate::connection::compression::Compression;
 use crate::error::MySqlDatabaseError;
 use crate::io::MySqlBufExt;
-use crate::options::MySqlConnectOptions;
+use crate::options::{MySqlCompression, MySqlConnectOptions};
 use crate::protocol::response::{EofPacket, ErrPacket, OkPacket, Status};
 use crate::protocol::{Capabilities, Packet};
 use rbdc::io::{BufStream, Decode, Encode};
 use rbdc::net::{MaybeTlsStream, Socket};
 use rbdc::{err_protocol, Error};
 
+// the largest payload of a packet, larger ones are split
+const MAX_PACKET_SIZE: usize = 0xFF_FF_FF;
+
 pub struct MySqlStream {
     stream: BufStream<MaybeTlsStream<Socket>>,
     pub(crate) server_version: (u16, u16, u16),
@@ -21,6 +25,10 @@ pub struct MySqlStream {
     pub(crate) waiting: VecDeque<Waiting>,
     pub(crate) charset: CharSet,
     pub(crate) collation: Collation,
+    // set once the server accepted compression, after authentication
+    pub(crate) compression: Option<Compression>,
+    // decompressed bytes not read yet
+    decompressed: BytesMut,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -54,7 +62,6 @@ impl MySqlStream {
             | Capabilities::TRANSACTIONS
             | Capabilities::SECURE_CONNECTION
             | Capabilities::PLUGIN_AUTH_LENENC_DATA
-            | Capabilities::MULTI_STATEMENTS
             | Capabilities::MULTI_RESULTS
             | Capabilities::PLUGIN_AUTH
             | Capabilities::PS_MULTI_RESULTS
@@ -64,6 +71,20 @@ impl MySqlStream {
             capabilities |= Capabilities::CONNECT_WITH_DB;
         }
 
+        if options.multi_statements {
+            capabilities |= Capabilities::MULTI_STATEMENTS;
+        }
+
+        if options.local_infile_handler.is_some() {
+            capabilities |= Capabilities::LOCAL_FILES;
+        }
+
+        match options.compression {
+            MySqlCompression::Zlib => capabilities |= Capabilities::COMPRESS,
+            MySqlCompression::Zstd => capabilities |= Capabilities::ZSTD_COMPRESSION_ALGORITHM,
+            MySqlCompression::Off => {}
+        }
+
         Ok(Self {
             waiting: VecDeque::new(),
             capabilities,
@@ -72,6 +93,8 @@ impl MySqlStream {
             collation,
             charset,
             stream: BufStream::new(MaybeTlsStream::Raw(socket)),
+            compression: None,
+            decompressed: BytesMut::new(),
         })
     }
 
@@ -118,17 +141,51 @@ impl MySqlStream {
     where
         T: Encode<'en, Capabilities>,
     {
-        self.sequence_id = 0;
+        self.reset_sequence_id();
         self.write_packet(payload);
         self.flush().await
     }
 
+    /// starts the packets of a new command
+    pub(crate) fn reset_sequence_id(&mut self) {
+        self.sequence_id = 0;
+        if let Some(compression) = &mut self.compression {
+            compression.sequence_id = 0;
+        }
+    }
+
     pub(crate) fn write_packet<'en, T>(&mut self, payload: T)
     where
         T: Encode<'en, Capabilities>,
     {
-        self.stream
-            .write_with(Packet(payload), (self.capabilities, &mut self.sequence_id));
+        match &mut self.compression {
+            Some(compression) => {
+                let mut packet = Vec::new();
+                Packet(payload)
+                    .encode_with(&mut packet, (self.capabilities, &mut self.sequence_id));
+                compression.write(&packet, &mut self.stream.wbuf);
+            }
+            None => self
+                .stream
+                .write_with(Packet(payload), (self.capabilities, &mut self.sequence_id)),
+        }
+    }
+
+    // read `cnt` bytes of packets, from inside compressed packets once compression is on
+    async fn read_bytes(&mut self, cnt: usize) -> Result<Bytes, Error> {
+        let compression = match &mut self.compression {
+            Some(compression) => compression,
+            None => return self.stream.read(cnt).await,
+        };
+
+        while self.decompressed.len() < cnt {
+            let header: Bytes = self.stream.read(7).await?;
+            let (payload_len, uncompressed_len) = compression.read_header(header);
+            let payload: Bytes = self.stream.read(payload_len).await?;
+            compression.read(payload, uncompressed_len, &mut self.decompressed)?;
+        }
+
+        Ok(self.decompressed.split_to(cnt).freeze())
     }
 
     // receive the next packet from the database server
@@ -137,17 +194,29 @@ impl MySqlStream {
         // https://dev.mysql.com/doc/dev/mysql-server/8.0.12/page_protocol_basic_packets.html
         // https://mariadb.com/kb/en/library/0-packet/#standard-packet
 
-        let mut header: Bytes = self.stream.read(4).await?;
+        let mut header: Bytes = self.read_bytes(4).await?;
 
         let packet_size = header.get_uint_le(3) as usize;
         let sequence_id = header.get_u8();
 
         self.sequence_id = sequence_id.wrapping_add(1);
 
-        let payload: Bytes = self.stream.read(packet_size).await?;
-
-        // TODO: packet compression
-        // TODO: packet joining
+        let mut payload: Bytes = self.read_bytes(packet_size).await?;
+
+        // a payload of the maximum size continues in the next packet
+        if packet_size == MAX_PACKET_SIZE {
+            let mut joined = BytesMut::from(&payload[..]);
+            loop {
+                let mut header: Bytes = self.read_bytes(4).await?;
+                let packet_size = header.get_uint_le(3) as usize;
+                self.sequence_id = header.get_u8().wrapping_add(1);
+                joined.extend_from_slice(&self.read_bytes(packet_size).await?);
+                if packet_size < MAX_PACKET_SIZE {
+                    break;
+                }
+            }
+            payload = joined.freeze();
+        }
 
         if payload
             .get(0)
@@ -0,0 +1,91 @@
+use crate::connection::MySqlConnection;
+use futures_core::future::BoxFuture;
+use rbdc::db::{Connection, TwoPhase};
+use rbdc::Error;
+use rbs::Value;
+
+// https://dev.mysql.com/doc/refman/8.0/en/xa-statements.html
+// the gid is the gtrid of the xid, with an empty bqual and the default formatID 1
+
+impl TwoPhase for MySqlConnection {
+    fn begin_two_phase(&mut self, gid: &str) -> BoxFuture<'_, Result<(), Error>> {
+        let sql = xid_literal(gid).map(|xid| format!("XA START {}", xid));
+        Box::pin(async move {
+            self.exec(&sql?, vec![]).await?;
+            Ok(())
+        })
+    }
+
+    fn prepare(&mut self, gid: &str) -> BoxFuture<'_, Result<(), Error>> {
+        let xid = xid_literal(gid);
+        Box::pin(async move {
+            let xid = xid?;
+            self.exec(&format!("XA END {}", xid), vec![]).await?;
+            self.exec(&format!("XA PREPARE {}", xid), vec![]).await?;
+            Ok(())
+        })
+    }
+
+    fn commit_prepared(&mut self, gid: &str) -> BoxFuture<'_, Result<(), Error>> {
+        let sql = xid_literal(gid).map(|xid| format!("XA COMMIT {}", xid));
+        Box::pin(async move {
+            self.exec(&sql?, vec![]).await?;
+            Ok(())
+        })
+    }
+
+    fn rollback_prepared(&mut self, gid: &str) -> BoxFuture<'_, Result<(), Error>> {
+        let xid = xid_literal(gid);
+        Box::pin(async move {
+            let xid = xid?;
+            // ends the transaction if it is still active on this connection, fails when it
+            // is already prepared or belongs to another connection
+            let _ = self.exec(&format!("XA END {}", xid), vec![]).await;
+            self.exec(&format!("XA ROLLBACK {}", xid), vec![]).await?;
+            Ok(())
+        })
+    }
+
+    fn recover(&mut self) -> BoxFuture<'_, Result<Vec<String>, Error>> {
+        Box::pin(async move {
+            let rows = self.get_values("XA RECOVER", vec![]).await?;
+            let mut gids = Vec::with_capacity(rows.len());
+            for row in rows {
+                // only the xids this API creates
+                if row["formatID"].as_i64() != Some(1) || row["bqual_length"].as_i64() != Some(0) {
+                    continue;
+                }
+                let gid = match &row["data"] {
+                    Value::String(s) => s.clone(),
+                    Value::Binary(b) => String::from_utf8_lossy(b).into_owned(),
+                    _ => continue,
+                };
+                gids.push(gid);
+            }
+            Ok(gids)
+        })
+    }
+}
+
+/// `gid` as a hexadecimal literal, gtrids are at most 64 bytes
+fn xid_literal(gid: &str) -> Result<String, Error> {
+    if gid.is_empty() || gid.len() > 64 {
+        return Err(Error::from(format!(
+            "transaction identifier must be 1 to 64 bytes long: {:?}",
+            gid
+        )));
+    }
+    Ok(format!("X'{}'", hex::encode(gid)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::xid_literal;
+
+    #[test]
+    fn test_xid_literal() {
+        assert_eq!(xid_literal("tx'1").unwrap(), "X'74782731'");
+        assert!(xid_literal("").is_err());
+        assert!(xid_literal(&"x".repeat(65)).is_err());
+    }
+}
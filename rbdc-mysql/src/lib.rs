@@ -7,6 +7,7 @@ pub mod meta_data;
 pub mod result_set;
 pub mod stmt;
 
+pub mod binlog;
 pub mod collation;
 pub mod connection;
 pub mod describe;
@@ -0,0 +1,39 @@
+use rbdc::Error;
+use std::str::FromStr;
+
+/// The compression of the client/server protocol, used when the server supports it.
+///
+/// It is used by the [`compression`](super::MySqlConnectOptions::compression) method.
+/// url example:   ?compression=zlib   or  ?compression=zstd   or   ?compression=off
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum MySqlCompression {
+    /// Packets are sent uncompressed.
+    /// This is the default if `compression` is not specified.
+    #[default]
+    Off,
+
+    /// Packets are compressed with zlib, supported by MySQL and MariaDB.
+    Zlib,
+
+    /// Packets are compressed with zstd, supported from MySQL 8.0.18.
+    Zstd,
+}
+
+impl FromStr for MySqlCompression {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        Ok(match &*s.to_ascii_lowercase() {
+            "off" | "uncompressed" => MySqlCompression::Off,
+            "zlib" => MySqlCompression::Zlib,
+            "zstd" => MySqlCompression::Zstd,
+
+            _ => {
+                return Err(Error::from(format!(
+                    "unknown value {:?} for `compression`",
+                    s
+                )));
+            }
+        })
+    }
+}
@@ -0,0 +1,108 @@
+use futures_core::future::BoxFuture;
+use rbdc::rt::AsyncRead;
+use rbdc::Error;
+use std::collections::HashSet;
+use std::fmt::{Debug, Formatter};
+use std::future::Future;
+use std::sync::Arc;
+
+/// The data sent for a file of `LOAD DATA LOCAL INFILE`.
+pub type MySqlLocalInfileReader = Box<dyn AsyncRead + Send + Unpin>;
+
+type OpenFn =
+    dyn Fn(&str) -> BoxFuture<'static, Result<MySqlLocalInfileReader, Error>> + Send + Sync;
+
+/// Serves the files of `LOAD DATA LOCAL INFILE` statements from Rust, e.g. an in-memory
+/// CSV or a generated stream, the fastest way to bulk load rows.
+///
+/// The server asks for the file named in the statement. As any server the connection
+/// talks to can ask for any name, only the names on the allow-list are served.
+///
+/// It is used by the [`local_infile_handler`](super::MySqlConnectOptions::local_infile_handler) method.
+///
+/// ```rust
+/// # use rbdc_mysql::options::{MySqlConnectOptions, MySqlLocalInfileHandler, MySqlLocalInfileReader};
+/// let handler = MySqlLocalInfileHandler::new(|_name| async {
+///     Ok(Box::new(&b"1,alice\n2,bob\n"[..]) as MySqlLocalInfileReader)
+/// })
+/// .allow("users.csv");
+/// let options = MySqlConnectOptions::new().local_infile_handler(handler);
+/// // LOAD DATA LOCAL INFILE 'users.csv' INTO TABLE users FIELDS TERMINATED BY ','
+/// ```
+#[derive(Clone)]
+pub struct MySqlLocalInfileHandler {
+    open: Arc<OpenFn>,
+    allowed: HashSet<String>,
+}
+
+impl MySqlLocalInfileHandler {
+    /// A handler that opens the data of a file by its name with `open`. No name is allowed
+    /// until added with [`allow`](Self::allow).
+    pub fn new<F, Fut, R>(open: F) -> Self
+    where
+        F: Fn(&str) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<R, Error>> + Send + 'static,
+        R: AsyncRead + Send + Unpin + 'static,
+    {
+        Self {
+            open: Arc::new(move |name| {
+                let reader = open(name);
+                Box::pin(async move { Ok(Box::new(reader.await?) as MySqlLocalInfileReader) })
+            }),
+            allowed: HashSet::new(),
+        }
+    }
+
+    /// Allows the server to ask for the file `name`, exactly as written in the statement.
+    pub fn allow(mut self, name: impl Into<String>) -> Self {
+        self.allowed.insert(name.into());
+        self
+    }
+
+    /// Whether the server may ask for the file `name`.
+    pub fn is_allowed(&self, name: &str) -> bool {
+        self.allowed.contains(name)
+    }
+
+    /// Opens the data of the file `name`, an error if it is not allowed.
+    pub(crate) async fn open(&self, name: &str) -> Result<MySqlLocalInfileReader, Error> {
+        if !self.is_allowed(name) {
+            return Err(Error::from(format!(
+                "LOAD DATA LOCAL INFILE of {:?} is not allowed",
+                name
+            )));
+        }
+        (self.open)(name).await
+    }
+}
+
+impl Debug for MySqlLocalInfileHandler {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MySqlLocalInfileHandler")
+            .field("allowed", &self.allowed)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::MySqlLocalInfileHandler;
+    use rbdc::rt::AsyncReadExt;
+
+    #[tokio::test]
+    async fn test_local_infile_handler() {
+        let handler = MySqlLocalInfileHandler::new(|name| {
+            let data = format!("{}\n", name).into_bytes();
+            async move { Ok(std::io::Cursor::new(data)) }
+        })
+        .allow("a.csv");
+
+        let mut data = String::new();
+        let mut reader = handler.open("a.csv").await.unwrap();
+        reader.read_to_string(&mut data).await.unwrap();
+        assert_eq!(data, "a.csv\n");
+
+        assert!(!handler.is_allowed("/etc/passwd"));
+        assert!(handler.open("/etc/passwd").await.is_err());
+    }
+}
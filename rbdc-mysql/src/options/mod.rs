@@ -1,10 +1,14 @@
 use std::path::{Path, PathBuf};
 use fastdate::offset_sec;
 
+mod compression;
 mod connect;
+mod local_infile;
 mod parse;
 mod ssl_mode;
 
+pub use compression::MySqlCompression;
+pub use local_infile::{MySqlLocalInfileHandler, MySqlLocalInfileReader};
 use rbdc::net::CertificateInput;
 pub use ssl_mode::MySqlSslMode;
 
@@ -25,8 +29,14 @@ pub use ssl_mode::MySqlSslMode;
 /// |---------|-------|-----------|
 /// | `ssl-mode` | `PREFERRED` | Determines whether or with what priority a secure SSL TCP/IP connection will be negotiated. See [`MySqlSslMode`]. |
 /// | `ssl-ca` | `None` | Sets the name of a file containing a list of trusted SSL Certificate Authorities. |
+/// | `ssl-cert` | `None` | Sets the name of a file containing the client SSL certificate, used for mutual TLS. |
+/// | `ssl-key` | `None` | Sets the name of a file containing the secret key used for the client certificate. |
 /// | `statement-cache-capacity` | `100` | The maximum number of prepared statements stored in the cache. Set to `0` to disable. |
 /// | `socket` | `None` | Path to the unix domain socket, which will be used instead of TCP if set. |
+/// | `multi-statements` | `true` | Whether a query may hold several statements separated by `;`. |
+/// | `compression` | `off` | Compresses the protocol with `zlib` or `zstd` if the server supports it. See [`MySqlCompression`]. |
+/// | `zstd-level` | `3` | The zstd compression level, from `1` to `22`. |
+/// | `compression-threshold` | `50` | Packets smaller than this many bytes are sent uncompressed. |
 ///
 /// ## offset_sec
 /// DateTime timezone offset sec
@@ -40,10 +50,17 @@ pub struct MySqlConnectOptions {
     pub(crate) database: Option<String>,
     pub(crate) ssl_mode: MySqlSslMode,
     pub(crate) ssl_ca: Option<CertificateInput>,
+    pub(crate) ssl_client_cert: Option<CertificateInput>,
+    pub(crate) ssl_client_key: Option<CertificateInput>,
     pub(crate) statement_cache_capacity: usize,
     pub(crate) charset: String,
     pub(crate) collation: Option<String>,
     pub(crate) offset_sec: i32,
+    pub(crate) compression: MySqlCompression,
+    pub(crate) zstd_level: u8,
+    pub(crate) compression_threshold: usize,
+    pub(crate) local_infile_handler: Option<MySqlLocalInfileHandler>,
+    pub(crate) multi_statements: bool,
 }
 
 impl Default for MySqlConnectOptions {
@@ -66,8 +83,15 @@ impl MySqlConnectOptions {
             collation: None,
             ssl_mode: MySqlSslMode::Disabled,
             ssl_ca: None,
+            ssl_client_cert: None,
+            ssl_client_key: None,
             statement_cache_capacity: 100,
             offset_sec: offset_sec(),
+            compression: MySqlCompression::Off,
+            zstd_level: 3,
+            compression_threshold: 50,
+            local_infile_handler: None,
+            multi_statements: true,
         }
     }
 
@@ -163,6 +187,43 @@ impl MySqlConnectOptions {
         self
     }
 
+    /// Sets the name of a file containing the SSL client certificate, which is presented
+    /// to the server when it requests one (mutual TLS).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use rbdc_mysql::options::{MySqlSslMode, MySqlConnectOptions};
+    /// let options = MySqlConnectOptions::new()
+    ///     .ssl_mode(MySqlSslMode::VerifyCa)
+    ///     .ssl_client_cert("path/to/client.crt")
+    ///     .ssl_client_key("path/to/client.key");
+    /// ```
+    pub fn ssl_client_cert(mut self, file_name: impl AsRef<Path>) -> Self {
+        self.ssl_client_cert = Some(CertificateInput::File(file_name.as_ref().to_owned()));
+        self
+    }
+
+    /// Sets the PEM encoded SSL client certificate.
+    pub fn ssl_client_cert_from_pem(mut self, pem_certificate: Vec<u8>) -> Self {
+        self.ssl_client_cert = Some(CertificateInput::Inline(pem_certificate));
+        self
+    }
+
+    /// Sets the name of a file containing the secret key for the SSL client certificate.
+    ///
+    /// When using the `tls-native-tls` backend the key must be in PKCS#8 format.
+    pub fn ssl_client_key(mut self, file_name: impl AsRef<Path>) -> Self {
+        self.ssl_client_key = Some(CertificateInput::File(file_name.as_ref().to_owned()));
+        self
+    }
+
+    /// Sets the PEM encoded secret key for the SSL client certificate.
+    pub fn ssl_client_key_from_pem(mut self, pem_key: Vec<u8>) -> Self {
+        self.ssl_client_key = Some(CertificateInput::Inline(pem_key));
+        self
+    }
+
     /// Sets the capacity of the connection's statement cache in a number of stored
     /// distinct statements. Caching is handled using LRU, meaning when the
     /// amount of queries hits the defined limit, the oldest statement will get
@@ -205,4 +266,60 @@ impl MySqlConnectOptions {
         self.offset_sec = offset_sec;
         self
     }
+
+    /// Sets the compression of the protocol, which saves bandwidth on large result sets
+    /// at the cost of CPU time. The connection falls back to uncompressed packets if the
+    /// server does not support the algorithm.
+    ///
+    /// By default, the protocol is not compressed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use rbdc_mysql::options::{MySqlCompression, MySqlConnectOptions};
+    /// let options = MySqlConnectOptions::new()
+    ///     .compression(MySqlCompression::Zstd)
+    ///     .zstd_level(6);
+    /// ```
+    pub fn compression(mut self, compression: MySqlCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Sets the zstd compression level, from `1` (fastest) to `22` (smallest), used by both
+    /// the client and the server with [`MySqlCompression::Zstd`].
+    ///
+    /// The default level is `3`.
+    pub fn zstd_level(mut self, level: u8) -> Self {
+        self.zstd_level = level.clamp(1, 22);
+        self
+    }
+
+    /// Sets the size in bytes under which packets are sent uncompressed, compressing them
+    /// would not pay off.
+    ///
+    /// The default threshold is `50` bytes.
+    pub fn compression_threshold(mut self, threshold: usize) -> Self {
+        self.compression_threshold = threshold;
+        self
+    }
+
+    /// Enables `LOAD DATA LOCAL INFILE`, with the files served by `handler`.
+    ///
+    /// By default, `LOCAL` is not enabled. The rows received before an error reading the
+    /// data stay loaded, run the statement in a transaction to load all of them or none.
+    pub fn local_infile_handler(mut self, handler: MySqlLocalInfileHandler) -> Self {
+        self.local_infile_handler = Some(handler);
+        self
+    }
+
+    /// Sets whether a text query may hold several statements separated by `;`, each with
+    /// its own result, see
+    /// [`fetch_result_sets`](crate::connection::MySqlConnection::fetch_result_sets).
+    ///
+    /// Enabled by default. Disabling it makes injected statements fail to run.
+    pub fn multi_statements(mut self, enabled: bool) -> Self {
+        self.multi_statements = enabled;
+        self
+    }
 }
@@ -2,6 +2,7 @@ use crate::options::MySqlConnectOptions;
 use percent_encoding::percent_decode_str;
 use rbdc::Error;
 use std::num::ParseIntError;
+use std::str::ParseBoolError;
 use std::str::FromStr;
 use url::{ParseError, Url};
 
@@ -46,6 +47,14 @@ impl FromStr for MySqlConnectOptions {
                     options = options.ssl_ca(&*value);
                 }
 
+                "ssl-cert" => {
+                    options = options.ssl_client_cert(&*value);
+                }
+
+                "ssl-key" => {
+                    options = options.ssl_client_key(&*value);
+                }
+
                 "charset" => {
                     options = options.charset(&*value);
                 }
@@ -66,6 +75,34 @@ impl FromStr for MySqlConnectOptions {
                     options = options.socket(&*value);
                 }
 
+                "multi-statements" => {
+                    options = options.multi_statements(
+                        value
+                            .parse()
+                            .map_err(|e: ParseBoolError| Error::from(e.to_string()))?,
+                    );
+                }
+
+                "compression" => {
+                    options = options.compression(value.parse()?);
+                }
+
+                "zstd-level" => {
+                    options = options.zstd_level(
+                        value
+                            .parse()
+                            .map_err(|e: ParseIntError| Error::from(e.to_string()))?,
+                    );
+                }
+
+                "compression-threshold" => {
+                    options = options.compression_threshold(
+                        value
+                            .parse()
+                            .map_err(|e: ParseIntError| Error::from(e.to_string()))?,
+                    );
+                }
+
                 _ => {}
             }
         }
@@ -89,3 +126,38 @@ fn it_parses_password_with_non_ascii_chars_correctly() {
 
     assert_eq!(Some("p@ssw0rd".into()), opts.password);
 }
+
+#[test]
+fn it_parses_ssl_client_cert_and_key() {
+    let uri = "mysql://root@localhost/db?ssl-mode=verify_ca&ssl-cert=client.crt&ssl-key=client.key";
+    let opts = MySqlConnectOptions::from_str(uri).unwrap();
+
+    assert!(opts.ssl_client_cert.is_some());
+    assert!(opts.ssl_client_key.is_some());
+}
+
+#[test]
+fn it_parses_compression() {
+    use crate::options::MySqlCompression;
+
+    let uri = "mysql://root@localhost/db?compression=zstd&zstd-level=9&compression-threshold=1024";
+    let opts = MySqlConnectOptions::from_str(uri).unwrap();
+
+    assert_eq!(opts.compression, MySqlCompression::Zstd);
+    assert_eq!(opts.zstd_level, 9);
+    assert_eq!(opts.compression_threshold, 1024);
+
+    let opts = MySqlConnectOptions::from_str("mysql://root@localhost/db").unwrap();
+    assert_eq!(opts.compression, MySqlCompression::Off);
+    assert!(MySqlConnectOptions::from_str("mysql://root@localhost/db?compression=lz4").is_err());
+}
+
+#[test]
+fn it_parses_multi_statements() {
+    let opts = MySqlConnectOptions::from_str("mysql://root@localhost/db").unwrap();
+    assert!(opts.multi_statements);
+
+    let uri = "mysql://root@localhost/db?multi-statements=false";
+    let opts = MySqlConnectOptions::from_str(uri).unwrap();
+    assert!(!opts.multi_statements);
+}
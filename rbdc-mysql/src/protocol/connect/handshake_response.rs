@@ -25,6 +25,9 @@ pub struct HandshakeResponse<'a> {
 
     /// Opaque authentication response
     pub auth_response: Option<&'a [u8]>,
+
+    /// Compression level of the zstd compression, if it is used
+    pub zstd_level: u8,
 }
 
 impl Encode<'_, Capabilities> for HandshakeResponse<'_> {
@@ -69,5 +72,9 @@ impl Encode<'_, Capabilities> for HandshakeResponse<'_> {
                 buf.push(0);
             }
         }
+
+        if capabilities.contains(Capabilities::ZSTD_COMPRESSION_ALGORITHM) {
+            buf.push(self.zstd_level);
+        }
     }
 }
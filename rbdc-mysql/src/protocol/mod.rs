@@ -2,6 +2,7 @@ pub mod auth;
 mod capabilities;
 pub mod connect;
 mod packet;
+pub mod replication;
 pub mod response;
 mod row;
 pub mod statement;
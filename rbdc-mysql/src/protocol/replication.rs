@@ -0,0 +1,126 @@
+use crate::protocol::Capabilities;
+use rbdc::io::Encode;
+
+// https://dev.mysql.com/doc/dev/mysql-server/latest/page_protocol_replication.html
+
+/// `COM_REGISTER_SLAVE`, announces the connection as a replica with the given server id.
+#[derive(Debug)]
+pub struct RegisterReplica<'a> {
+    pub server_id: u32,
+    pub hostname: &'a str,
+    pub user: &'a str,
+    pub password: &'a str,
+    pub port: u16,
+}
+
+impl Encode<'_, Capabilities> for RegisterReplica<'_> {
+    fn encode_with(&self, buf: &mut Vec<u8>, _: Capabilities) {
+        buf.push(0x15); // COM_REGISTER_SLAVE
+        buf.extend(&self.server_id.to_le_bytes());
+        for field in [self.hostname, self.user, self.password] {
+            let field = &field.as_bytes()[..field.len().min(255)];
+            buf.push(field.len() as u8);
+            buf.extend(field);
+        }
+        buf.extend(&self.port.to_le_bytes());
+        // replication rank and source id, both unused
+        buf.extend(&0_u32.to_le_bytes());
+        buf.extend(&0_u32.to_le_bytes());
+    }
+}
+
+/// End the dump with an EOF packet instead of waiting for new events.
+pub const BINLOG_DUMP_NON_BLOCK: u16 = 0x01;
+
+/// `COM_BINLOG_DUMP`, streams the binary log from a file and position.
+#[derive(Debug)]
+pub struct BinlogDump<'a> {
+    pub position: u32,
+    pub flags: u16,
+    pub server_id: u32,
+    pub filename: &'a str,
+}
+
+impl Encode<'_, Capabilities> for BinlogDump<'_> {
+    fn encode_with(&self, buf: &mut Vec<u8>, _: Capabilities) {
+        buf.push(0x12); // COM_BINLOG_DUMP
+        buf.extend(&self.position.to_le_bytes());
+        buf.extend(&self.flags.to_le_bytes());
+        buf.extend(&self.server_id.to_le_bytes());
+        buf.extend(self.filename.as_bytes());
+    }
+}
+
+/// The position is given by the GTID set of the dump.
+const BINLOG_THROUGH_GTID: u16 = 0x04;
+
+/// `COM_BINLOG_DUMP_GTID`, streams the binary log from the first transaction that is not in
+/// an (encoded) GTID set.
+#[derive(Debug)]
+pub struct BinlogDumpGtid<'a> {
+    pub flags: u16,
+    pub server_id: u32,
+    pub gtid_set: &'a [u8],
+}
+
+impl Encode<'_, Capabilities> for BinlogDumpGtid<'_> {
+    fn encode_with(&self, buf: &mut Vec<u8>, _: Capabilities) {
+        buf.push(0x1e); // COM_BINLOG_DUMP_GTID
+        buf.extend(&(self.flags | BINLOG_THROUGH_GTID).to_le_bytes());
+        buf.extend(&self.server_id.to_le_bytes());
+        // no file name, from the position 4 (after the magic number) of the first file
+        buf.extend(&0_u32.to_le_bytes());
+        buf.extend(&4_u64.to_le_bytes());
+        buf.extend(&(self.gtid_set.len() as u32).to_le_bytes());
+        buf.extend(self.gtid_set);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BinlogDump, BinlogDumpGtid, RegisterReplica, BINLOG_DUMP_NON_BLOCK};
+    use crate::protocol::Capabilities;
+    use rbdc::io::Encode;
+
+    #[test]
+    fn test_encode_replication_commands() {
+        let mut buf = Vec::new();
+        RegisterReplica {
+            server_id: 7,
+            hostname: "h",
+            user: "",
+            password: "",
+            port: 3306,
+        }
+        .encode_with(&mut buf, Capabilities::empty());
+        assert_eq!(
+            buf,
+            b"\x15\x07\x00\x00\x00\x01h\x00\x00\xea\x0c\x00\x00\x00\x00\x00\x00\x00\x00"
+        );
+
+        let mut buf = Vec::new();
+        BinlogDump {
+            position: 4,
+            flags: BINLOG_DUMP_NON_BLOCK,
+            server_id: 7,
+            filename: "binlog.000001",
+        }
+        .encode_with(&mut buf, Capabilities::empty());
+        assert_eq!(
+            buf,
+            b"\x12\x04\x00\x00\x00\x01\x00\x07\x00\x00\x00binlog.000001"
+        );
+
+        let mut buf = Vec::new();
+        BinlogDumpGtid {
+            flags: 0,
+            server_id: 7,
+            gtid_set: &[1, 2],
+        }
+        .encode_with(&mut buf, Capabilities::empty());
+        assert_eq!(
+            buf,
+            b"\x1e\x04\x00\x07\x00\x00\x00\x00\x00\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x02\x00\x00\x00\x01\x02"
+        );
+    }
+}
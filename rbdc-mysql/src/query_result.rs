@@ -1,3 +1,5 @@
+use crate::row::MySqlRow;
+
 #[derive(Debug, Default)]
 pub struct MySqlQueryResult {
     pub(super) rows_affected: u64,
@@ -18,7 +20,72 @@ impl Extend<MySqlQueryResult> for MySqlQueryResult {
     fn extend<T: IntoIterator<Item = MySqlQueryResult>>(&mut self, iter: T) {
         for elem in iter {
             self.rows_affected += elem.rows_affected;
-            self.last_insert_id = elem.last_insert_id;
+            // a statement that inserted nothing reports 0
+            if elem.last_insert_id != 0 {
+                self.last_insert_id = elem.last_insert_id;
+            }
+        }
+    }
+}
+
+/// The result of one statement of a query that returns several, such as multi statements
+/// or a `CALL` of a procedure: its rows and what it changed.
+///
+/// A `CALL` ends with an extra result without rows, the status of the call itself.
+#[derive(Debug, Default)]
+pub struct MySqlResultSet {
+    pub rows: Vec<MySqlRow>,
+    pub rows_affected: u64,
+    pub last_insert_id: u64,
+}
+
+impl MySqlResultSet {
+    /// splits the rows and results of a query into one set per statement, each result ends
+    /// the set of rows before it
+    pub(crate) fn split<R>(
+        steps: impl IntoIterator<Item = either::Either<MySqlQueryResult, R>>,
+    ) -> Vec<(Vec<R>, MySqlQueryResult)> {
+        let mut sets = Vec::new();
+        let mut rows = Vec::new();
+        for step in steps {
+            match step {
+                either::Either::Left(result) => sets.push((std::mem::take(&mut rows), result)),
+                either::Either::Right(row) => rows.push(row),
+            }
         }
+        sets
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MySqlQueryResult, MySqlResultSet};
+    use either::Either;
+
+    #[test]
+    fn test_split_result_sets() {
+        let result = |rows_affected, last_insert_id| MySqlQueryResult {
+            rows_affected,
+            last_insert_id,
+        };
+        let steps = vec![
+            Either::Left(result(1, 7)),
+            Either::Right(1),
+            Either::Right(2),
+            Either::Left(result(0, 0)),
+            Either::Left(result(0, 0)),
+        ];
+
+        let sets = MySqlResultSet::split(steps);
+        assert_eq!(sets.len(), 3);
+        assert_eq!(sets[0].0, Vec::<i32>::new());
+        assert_eq!(sets[0].1.last_insert_id, 7);
+        assert_eq!(sets[1].0, vec![1, 2]);
+        assert!(sets[2].0.is_empty());
+
+        let mut total = MySqlQueryResult::default();
+        total.extend(sets.into_iter().map(|(_, result)| result));
+        assert_eq!(total.rows_affected, 1);
+        assert_eq!(total.last_insert_id, 7);
     }
 }
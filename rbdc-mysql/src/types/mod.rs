@@ -9,6 +9,7 @@ pub mod decode;
 pub mod enums;
 pub mod geometry;
 pub mod json;
+pub mod network;
 pub mod set;
 pub mod time;
 pub mod timestamp;
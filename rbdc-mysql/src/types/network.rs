@@ -0,0 +1,144 @@
+//! Network addresses stored in MySQL.
+//!
+//! MySQL has no address types, addresses are usually kept in a `VARBINARY(16)` column filled by
+//! `INET6_ATON()` (4 bytes for IPv4, 16 bytes for IPv6) or in a `VARCHAR` column. The types here
+//! bind as raw bytes and decode from either form, told apart by the character set of the
+//! column.
+use crate::io::MySqlBufMutExt;
+use crate::types::{Decode, Encode};
+use crate::value::MySqlValue;
+use rbdc::network::{Cidr, Inet, MacAddress, MacAddress8};
+use rbdc::Error;
+use rbs::Value;
+use std::str::FromStr;
+
+// the `binary` character set of `BINARY`, `VARBINARY` and `BLOB` columns
+const BINARY_CHAR_SET: u16 = 63;
+
+macro_rules! impl_network {
+    ($($ty:ident => $name:literal;)+) => {
+        $(
+            impl Encode for $ty {
+                fn encode(self, buf: &mut Vec<u8>) -> Result<usize, Error> {
+                    let bytes = self.to_bytes();
+                    let len = bytes.len();
+                    buf.put_bytes_lenenc(bytes);
+                    Ok(len)
+                }
+            }
+
+            impl Decode for $ty {
+                fn decode(value: MySqlValue) -> Result<Self, Error> {
+                    let bytes = value.as_bytes()?;
+                    // text such as `1::1` can be as long as an address in bytes
+                    if value.type_info.char_set != BINARY_CHAR_SET {
+                        return $ty::from_str(std::str::from_utf8(bytes)?);
+                    }
+                    match $ty::from_bytes(bytes) {
+                        Ok(v) => Ok(v),
+                        Err(e) => match std::str::from_utf8(bytes) {
+                            Ok(s) => $ty::from_str(s),
+                            Err(_) => Err(e),
+                        },
+                    }
+                }
+            }
+        )+
+
+        /// Encodes a network `Value::Ext` by its name as raw bytes.
+        pub(crate) fn encode_by_name(
+            name: &str,
+            v: Value,
+            buf: &mut Vec<u8>,
+        ) -> Result<usize, Error> {
+            match name {
+                $($name => rbs::from_value::<$ty>(v)?.encode(buf),)+
+                _ => Err(Error::from(format!("unknown network type {}", name))),
+            }
+        }
+    };
+}
+
+impl_network! {
+    Inet => "Inet";
+    Cidr => "Cidr";
+    MacAddress => "Macaddr";
+    MacAddress8 => "Macaddr8";
+}
+
+/// Converts a `VARBINARY(16)` (or `VARCHAR`) column value to an [`Inet`].
+///
+/// ```rust
+/// use rbs::Value;
+/// let v = Value::Binary(vec![192, 168, 0, 1]);
+/// let inet = rbdc_mysql::types::network::to_inet(v).unwrap();
+/// assert_eq!(inet.to_string(), "192.168.0.1");
+/// ```
+pub fn to_inet(v: Value) -> Result<Inet, Error> {
+    rbs::from_value(v).map_err(|e| Error::from(e.to_string()))
+}
+
+/// Converts an address to a `Value::Binary` for a `VARBINARY(16)` column, the same bytes as
+/// `INET6_ATON()` produces.
+pub fn to_varbinary(inet: Inet) -> Value {
+    Value::Binary(inet.to_bytes())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::options::MySqlConnectOptions;
+    use crate::protocol::text::ColumnType;
+    use crate::result_set::MySqlTypeInfo;
+    use crate::types::network::{encode_by_name, to_inet, to_varbinary};
+    use crate::types::Decode;
+    use crate::value::{MySqlValue, MySqlValueFormat};
+    use rbdc::network::Inet;
+    use rbs::Value;
+    use std::sync::Arc;
+
+    fn column_value(char_set: u16, bytes: &[u8]) -> MySqlValue {
+        MySqlValue {
+            value: Some(bytes.to_vec()),
+            type_info: MySqlTypeInfo {
+                r#type: ColumnType::VarString,
+                char_set,
+            },
+            format: MySqlValueFormat::Binary,
+            option: Arc::new(MySqlConnectOptions::new()),
+        }
+    }
+
+    #[test]
+    fn test_decode_by_column_type() {
+        // `VARCHAR` text of 4 and 16 bytes
+        for s in ["1::1", "2001:db8:0:0::1a"] {
+            let inet = Inet::decode(column_value(45, s.as_bytes())).unwrap();
+            assert_eq!(inet, s.parse::<Inet>().unwrap());
+        }
+        // `VARBINARY(16)`
+        let inet = Inet::decode(column_value(63, &[10, 0, 0, 1])).unwrap();
+        assert_eq!(inet.to_string(), "10.0.0.1");
+        let inet = Inet::decode(column_value(63, b"10.0.0.1")).unwrap();
+        assert_eq!(inet.to_string(), "10.0.0.1");
+    }
+
+    #[test]
+    fn test_varbinary() {
+        let inet: Inet = "2001:db8::1".parse().unwrap();
+        let v = to_varbinary(inet);
+        assert_eq!(v.as_slice().unwrap().len(), 16);
+        assert_eq!(to_inet(v).unwrap(), inet);
+        assert_eq!(
+            to_inet(Value::String("10.0.0.1".to_string())).unwrap(),
+            "10.0.0.1".parse::<Inet>().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_encode_ext() {
+        let mut buf = vec![];
+        let inet: Inet = "10.0.0.1".parse().unwrap();
+        encode_by_name("Inet", Value::from(inet), &mut buf).unwrap();
+        assert_eq!(buf, vec![4, 10, 0, 0, 1]);
+    }
+}
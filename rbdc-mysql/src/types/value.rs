@@ -7,6 +7,7 @@ use crate::types::decode::{
 };
 use crate::types::enums::Enum;
 use crate::types::json::{decode_json, encode_json};
+use crate::types::network;
 use crate::types::set::Set;
 use crate::types::year::Year;
 use crate::types::{Decode, Encode, TypeInfo};
@@ -56,6 +57,9 @@ impl TypeInfo for Value {
                     "Json" => MySqlTypeInfo::from_type(ColumnType::Json),
                     "Enum" => MySqlTypeInfo::from_type(ColumnType::Enum),
                     "Set" => MySqlTypeInfo::from_type(ColumnType::Set),
+                    "Inet" | "Cidr" | "Macaddr" | "Macaddr8" => {
+                        MySqlTypeInfo::from_type(ColumnType::Blob)
+                    }
                     _ => MySqlTypeInfo::null(),
                 }
             }
@@ -144,6 +148,9 @@ impl Encode for Value {
                     }
                     "Enum" => Enum(v.into_string().unwrap_or_default()).encode(buf),
                     "Set" => Set(v.into_string().unwrap_or_default()).encode(buf),
+                    "Inet" | "Cidr" | "Macaddr" | "Macaddr8" => {
+                        network::encode_by_name(ext_type, *v, buf)
+                    }
                     _ => {
                         buf.put_bytes_lenenc(v.into_bytes().unwrap_or_default());
                         Ok(0)
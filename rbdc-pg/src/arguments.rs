@@ -67,6 +67,14 @@ impl PgArguments {
         Ok(())
     }
 
+    /// Adds `value` encoded as the parameter type `ty` described by the server.
+    pub fn add_as(&mut self, value: Value, ty: &PgTypeInfo) -> Result<(), Error> {
+        let type_info = self.buffer.encode_as(value, ty)?;
+        self.types.push(type_info);
+        self.buffer.count += 1;
+        Ok(())
+    }
+
     //Apply patches
     //This should only go out and ask postgres if we have not seen the type name yet
     pub(crate) async fn apply_patches(
@@ -130,6 +138,24 @@ impl PgArgumentBuffer {
         return Ok(info);
     }
 
+    /// Like [`encode`](Self::encode), but encodes `value` as the type `ty` that the server
+    /// described for the parameter.
+    pub fn encode_as(&mut self, value: Value, ty: &PgTypeInfo) -> Result<PgTypeInfo, Error> {
+        let offset = self.len();
+        self.extend(&[0; 4]);
+
+        let (info, is_null) = crate::types::value::encode_as(value, ty, self)?;
+        let len = if let IsNull::No = is_null {
+            (self.len() - offset - 4) as i32
+        } else {
+            debug_assert_eq!(self.len(), offset + 4);
+            -1_i32
+        };
+
+        self[offset..(offset + 4)].copy_from_slice(&len.to_be_bytes());
+        Ok(info)
+    }
+
     // Adds a callback to be invoked later when we know the parameter type
     #[allow(dead_code)]
     pub(crate) fn patch<F>(&mut self, callback: F)
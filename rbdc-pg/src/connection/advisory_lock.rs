@@ -0,0 +1,236 @@
+use crate::connection::PgConnection;
+use crate::message::TransactionStatus;
+use rbdc::db::Connection;
+use rbdc::{err_protocol, Error};
+use sha2::{Digest, Sha256};
+use std::ops::{Deref, DerefMut};
+
+/// The key of an advisory lock, either one `bigint` or two `integer`s. The two forms are
+/// different key spaces.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum PgAdvisoryLockKey {
+    BigInt(i64),
+    IntPair(i32, i32),
+}
+
+/// How long an advisory lock is held when it is not released explicitly.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum PgAdvisoryLockScope {
+    /// Until it is released or the session ends. Dropping the guard releases it.
+    #[default]
+    Session,
+
+    /// Until the end of the current transaction, it cannot be released earlier. Only
+    /// available inside a transaction.
+    Transaction,
+}
+
+/// A Postgres [advisory lock](https://www.postgresql.org/docs/current/explicit-locking.html#ADVISORY-LOCKS),
+/// for example to elect a leader or to make sure a job runs once.
+///
+/// ```rust
+/// # use rbdc_pg::connection::PgAdvisoryLock;
+/// let lock = PgAdvisoryLock::new("nightly-report").shared(false);
+/// ```
+///
+/// Acquiring it returns a [`PgAdvisoryLockGuard`] that gives access to the connection and
+/// releases the lock when dropped.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PgAdvisoryLock {
+    key: PgAdvisoryLockKey,
+    shared: bool,
+    scope: PgAdvisoryLockScope,
+}
+
+impl PgAdvisoryLock {
+    /// A lock whose `bigint` key is the first 8 bytes of the SHA-256 of `name`, read as a
+    /// little-endian integer.
+    pub fn new(name: impl AsRef<str>) -> Self {
+        let hash = Sha256::digest(name.as_ref().as_bytes());
+        let mut key = [0u8; 8];
+        key.copy_from_slice(&hash[..8]);
+        Self::with_key(PgAdvisoryLockKey::BigInt(i64::from_le_bytes(key)))
+    }
+
+    /// An exclusive, session scoped lock on `key`.
+    pub fn with_key(key: PgAdvisoryLockKey) -> Self {
+        Self {
+            key,
+            shared: false,
+            scope: PgAdvisoryLockScope::Session,
+        }
+    }
+
+    /// Whether the lock is shared with other shared holders instead of exclusive. Defaults
+    /// to `false`.
+    pub fn shared(mut self, shared: bool) -> Self {
+        self.shared = shared;
+        self
+    }
+
+    /// Sets how long the lock is held. Defaults to [`PgAdvisoryLockScope::Session`].
+    pub fn scope(mut self, scope: PgAdvisoryLockScope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// The key of the lock.
+    pub fn key(&self) -> &PgAdvisoryLockKey {
+        &self.key
+    }
+
+    /// Waits until the lock is acquired.
+    pub async fn acquire<'c>(
+        &self,
+        conn: &'c mut PgConnection,
+    ) -> Result<PgAdvisoryLockGuard<'c>, Error> {
+        self.check_scope(conn).await?;
+        conn.exec(&self.sql(false, "lock"), vec![]).await?;
+        Ok(PgAdvisoryLockGuard {
+            lock: self.clone(),
+            conn: Some(conn),
+        })
+    }
+
+    /// Acquires the lock if it is free, `None` if another session holds it.
+    pub async fn try_acquire<'c>(
+        &self,
+        conn: &'c mut PgConnection,
+    ) -> Result<Option<PgAdvisoryLockGuard<'c>>, Error> {
+        self.check_scope(conn).await?;
+        let acquired = conn.query_scalar(&self.sql(true, "lock"), vec![]).await?;
+        match acquired.as_bool() {
+            Some(true) => Ok(Some(PgAdvisoryLockGuard {
+                lock: self.clone(),
+                conn: Some(conn),
+            })),
+            Some(false) => Ok(None),
+            None => Err(err_protocol!("advisory lock returned {}", acquired)),
+        }
+    }
+
+    async fn check_scope(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        if self.scope == PgAdvisoryLockScope::Transaction {
+            conn.wait_until_ready().await?;
+            if matches!(conn.transaction_status, TransactionStatus::Idle) {
+                return Err(Error::from(
+                    "a transaction scoped advisory lock can only be acquired inside a transaction",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // e.g. `SELECT pg_try_advisory_xact_lock_shared(1, 2)`
+    fn sql(&self, try_lock: bool, action: &str) -> String {
+        let key = match self.key {
+            PgAdvisoryLockKey::BigInt(key) => format!("{}::bigint", key),
+            PgAdvisoryLockKey::IntPair(k1, k2) => format!("{}, {}", k1, k2),
+        };
+        format!(
+            "SELECT pg_{}advisory_{}{}{}({})",
+            if try_lock { "try_" } else { "" },
+            match self.scope {
+                PgAdvisoryLockScope::Transaction => "xact_",
+                PgAdvisoryLockScope::Session => "",
+            },
+            action,
+            if self.shared { "_shared" } else { "" },
+            key
+        )
+    }
+}
+
+/// A held advisory lock, derefs to the connection that holds it.
+///
+/// Dropping the guard of a session lock queues the release on the connection, it is sent
+/// (without needing a runtime here) before the next query. A transaction lock is released
+/// by the end of the transaction.
+pub struct PgAdvisoryLockGuard<'c> {
+    lock: PgAdvisoryLock,
+    conn: Option<&'c mut PgConnection>,
+}
+
+impl<'c> PgAdvisoryLockGuard<'c> {
+    /// The lock that is held.
+    pub fn lock(&self) -> &PgAdvisoryLock {
+        &self.lock
+    }
+
+    /// Releases the lock now and returns the connection. Returns an error if the server
+    /// reports the lock was not held.
+    pub async fn release_now(mut self) -> Result<&'c mut PgConnection, Error> {
+        let conn = self.conn.take().expect("conn is taken");
+        if self.lock.scope == PgAdvisoryLockScope::Session {
+            let released = conn
+                .query_scalar(&self.lock.sql(false, "unlock"), vec![])
+                .await?;
+            if released.as_bool() != Some(true) {
+                return Err(Error::from(format!(
+                    "advisory lock {:?} was not held",
+                    self.lock.key
+                )));
+            }
+        }
+        Ok(conn)
+    }
+
+    /// Returns the connection without releasing the lock, which then stays held until the
+    /// session (or transaction) ends.
+    pub fn leak(mut self) -> &'c mut PgConnection {
+        self.conn.take().expect("conn is taken")
+    }
+}
+
+impl Deref for PgAdvisoryLockGuard<'_> {
+    type Target = PgConnection;
+
+    fn deref(&self) -> &Self::Target {
+        self.conn.as_deref().expect("conn is taken")
+    }
+}
+
+impl DerefMut for PgAdvisoryLockGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.conn.as_deref_mut().expect("conn is taken")
+    }
+}
+
+impl Drop for PgAdvisoryLockGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            if self.lock.scope == PgAdvisoryLockScope::Session {
+                conn.queue_simple_query(&self.lock.sql(false, "unlock"));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{PgAdvisoryLock, PgAdvisoryLockKey, PgAdvisoryLockScope};
+
+    #[test]
+    fn test_advisory_lock_sql() {
+        let lock = PgAdvisoryLock::with_key(PgAdvisoryLockKey::BigInt(42));
+        assert_eq!(
+            lock.sql(false, "lock"),
+            "SELECT pg_advisory_lock(42::bigint)"
+        );
+        assert_eq!(
+            lock.sql(false, "unlock"),
+            "SELECT pg_advisory_unlock(42::bigint)"
+        );
+
+        let lock = PgAdvisoryLock::with_key(PgAdvisoryLockKey::IntPair(1, -2))
+            .shared(true)
+            .scope(PgAdvisoryLockScope::Transaction);
+        assert_eq!(
+            lock.sql(true, "lock"),
+            "SELECT pg_try_advisory_xact_lock_shared(1, -2)"
+        );
+
+        assert_eq!(PgAdvisoryLock::new("job"), PgAdvisoryLock::new("job"));
+        assert_ne!(PgAdvisoryLock::new("job"), PgAdvisoryLock::new("other"));
+    }
+}
@@ -1,10 +1,11 @@
 use crate::column::PgColumn;
 use crate::connection::PgConnection;
-use crate::message::{ParameterDescription, RowDescription};
+use crate::driver::PgDriver;
+use crate::message::{ParameterDescription, RowDescription, TransactionStatus};
 use crate::type_info::{PgCustomType, PgType, PgTypeInfo, PgTypeKind};
 use crate::types::Oid;
 use futures_core::future::BoxFuture;
-use rbdc::db::Connection;
+use rbdc::db::{Connection, Describe, DescribeColumn, Placeholder};
 use rbdc::ext::ustr::UStr;
 use rbdc::Error;
 use rbs::Value;
@@ -22,6 +23,7 @@ enum TypType {
     Enum,
     Pseudo,
     Range,
+    Multirange,
 }
 
 impl TryFrom<u8> for TypType {
@@ -35,6 +37,7 @@ impl TryFrom<u8> for TypType {
             b'e' => Self::Enum,
             b'p' => Self::Pseudo,
             b'r' => Self::Range,
+            b'm' => Self::Multirange,
             _ => return Err(()),
         };
         Ok(t)
@@ -257,6 +260,10 @@ impl PgConnection {
                     self.fetch_range_by_oid(oid, pg_type.typname).await
                 }
 
+                (Ok(TypType::Multirange), Ok(TypCategory::Range)) => {
+                    self.fetch_multirange_by_oid(oid, pg_type.typname).await
+                }
+
                 (Ok(TypType::Enum), Ok(TypCategory::Enum)) => {
                     self.fetch_enum_by_oid(oid, pg_type.typname).await
                 }
@@ -354,6 +361,42 @@ WHERE rngtypid = $1
         })
     }
 
+    fn fetch_multirange_by_oid(
+        &mut self,
+        oid: Oid,
+        name: String,
+    ) -> BoxFuture<'_, Result<PgTypeInfo, Error>> {
+        #[derive(serde::Serialize, serde::Deserialize)]
+        pub struct V {
+            pub rngtypid: Oid,
+        }
+        Box::pin(async move {
+            let rows = self
+                .get_values(
+                    r#"
+SELECT rngtypid
+FROM pg_catalog.pg_range
+WHERE rngmultitypid = $1
+                "#,
+                    vec![oid.0.into()],
+                )
+                .await?;
+            let vs: Vec<V> =
+                rbs::from_value(Value::Array(rows)).map_err(|e| Error::from(e.to_string()))?;
+            let mut range_oid = Oid(0);
+            if let Some(x) = vs.into_iter().next() {
+                range_oid = x.rngtypid;
+            }
+            let range = self.maybe_fetch_type_info_by_oid(range_oid, true).await?;
+
+            Ok(PgTypeInfo(PgType::Custom(Arc::new(PgCustomType {
+                kind: PgTypeKind::Multirange(range),
+                name: name.into(),
+                oid,
+            }))))
+        })
+    }
+
     async fn fetch_enum_by_oid(&mut self, oid: Oid, name: String) -> Result<PgTypeInfo, Error> {
         #[derive(serde::Serialize, serde::Deserialize)]
         pub struct V {
@@ -430,3 +473,269 @@ ORDER BY attnum
         })
     }
 }
+
+impl PgConnection {
+    /// The parameters and columns of `sql` as prepared by the server. Columns of a table are
+    /// nullable unless `pg_attribute.attnotnull`, and the plan marks the columns from the
+    /// nullable side of an outer join.
+    pub(super) async fn describe_statement(&mut self, sql: &str) -> Result<Describe, Error> {
+        let sql = PgDriver {}.exchange(sql);
+        let (statement, metadata) = self.get_or_prepare(&sql, &[], true, None).await?;
+
+        let mut nullable = self.columns_nullability(&metadata.columns).await?;
+        if metadata.columns.iter().any(|c| c.relation_id.is_some()) {
+            let explain = format!(
+                "EXPLAIN (VERBOSE, FORMAT JSON) EXECUTE rbdc_s_{}{}",
+                statement.0,
+                if metadata.parameters.is_empty() {
+                    String::new()
+                } else {
+                    format!("({})", vec!["NULL"; metadata.parameters.len()].join(", "))
+                }
+            );
+            let plan = self.explain_generic(&explain).await?;
+            let plan: serde_json::Value = match plan {
+                Value::String(s) => {
+                    serde_json::from_str(&s).map_err(|e| Error::from(e.to_string()))?
+                }
+                v => serde_json::to_value(&v).map_err(|e| Error::from(e.to_string()))?,
+            };
+            mark_outer_join_nullables(&plan, &mut nullable);
+        }
+
+        Ok(Describe {
+            params: metadata
+                .parameters
+                .iter()
+                .map(|ty| ty.name().to_string())
+                .collect(),
+            columns: metadata
+                .columns
+                .iter()
+                .map(|c| DescribeColumn {
+                    name: c.name.to_string(),
+                    column_type: c.type_info.name().to_string(),
+                })
+                .collect(),
+            nullable,
+        })
+    }
+
+    // `Some(!attnotnull)` for the columns of a table, `None` for expressions
+    async fn columns_nullability(
+        &mut self,
+        columns: &[PgColumn],
+    ) -> Result<Vec<Option<bool>>, Error> {
+        let mut nullable = vec![None; columns.len()];
+        let values: Vec<String> = columns
+            .iter()
+            .enumerate()
+            .filter_map(|(i, c)| match (c.relation_id, c.relation_attribute_no) {
+                (Some(relation), Some(attribute)) => Some(format!(
+                    "({}, {}::oid, {}::int2)",
+                    i, relation as u32, attribute
+                )),
+                _ => None,
+            })
+            .collect();
+        if values.is_empty() {
+            return Ok(nullable);
+        }
+
+        let rows = self
+            .get_values(
+                &format!(
+                    "SELECT c.idx, a.attnotnull FROM (VALUES {}) AS c(idx, relid, attnum) \
+                     JOIN pg_catalog.pg_attribute a ON a.attrelid = c.relid AND a.attnum = c.attnum",
+                    values.join(", ")
+                ),
+                vec![],
+            )
+            .await?;
+        for row in rows {
+            if let (Some(i), Some(not_null)) = (row["idx"].as_u64(), row["attnotnull"].as_bool())
+            {
+                if let Some(n) = nullable.get_mut(i as usize) {
+                    *n = Some(!not_null);
+                }
+            }
+        }
+        Ok(nullable)
+    }
+
+    /// Runs `explain` with a generic plan, so that the NULL parameters are not folded into it.
+    /// `SET LOCAL` lasts until the end of a transaction, or the rollback to a savepoint inside
+    /// one, which undoes it either way. `plan_cache_mode` is new in PostgreSQL 12.
+    async fn explain_generic(&mut self, explain: &str) -> Result<Value, Error> {
+        if self.server_version_num().is_some_and(|v| v < 120000) {
+            return self.query_scalar(explain, vec![]).await;
+        }
+        self.wait_until_ready().await?;
+        let idle = matches!(self.transaction_status, TransactionStatus::Idle);
+        let (begin, end) = if idle {
+            ("BEGIN", "ROLLBACK")
+        } else {
+            (
+                "SAVEPOINT rbdc_explain",
+                "ROLLBACK TO SAVEPOINT rbdc_explain; RELEASE SAVEPOINT rbdc_explain",
+            )
+        };
+        self.exec(begin, vec![]).await?;
+        let plan = match self
+            .exec("SET LOCAL plan_cache_mode = force_generic_plan", vec![])
+            .await
+        {
+            Ok(_) => self.query_scalar(explain, vec![]).await,
+            Err(e) => Err(e),
+        };
+        let ended = self.exec(end, vec![]).await;
+        let plan = plan?;
+        ended?;
+        Ok(plan)
+    }
+}
+
+// the outputs of a full join, and of the nullable side of a left or right join, can be NULL
+fn mark_outer_join_nullables(explain: &serde_json::Value, nullable: &mut [Option<bool>]) {
+    let plan = &explain[0]["Plan"];
+    let Some(outputs) = plan["Output"].as_array() else {
+        return;
+    };
+    let outputs: Vec<&str> = outputs.iter().filter_map(|o| o.as_str()).collect();
+    visit_plan(plan, &outputs, nullable);
+}
+
+fn visit_plan(plan: &serde_json::Value, outputs: &[&str], nullable: &mut [Option<bool>]) {
+    let Some(plans) = plan["Plans"].as_array() else {
+        return;
+    };
+    // the child that may not match, by its `Parent Relationship`
+    let nullable_side = match plan["Join Type"].as_str() {
+        Some("Full") => Some(None),
+        Some("Left") => Some(Some("Inner")),
+        Some("Right") => Some(Some("Outer")),
+        _ => None,
+    };
+    for child in plans {
+        if let Some(side) = nullable_side {
+            if side.is_none() || child["Parent Relationship"].as_str() == side {
+                mark_outputs(child, outputs, nullable);
+            }
+        }
+        visit_plan(child, outputs, nullable);
+    }
+}
+
+fn mark_outputs(plan: &serde_json::Value, outputs: &[&str], nullable: &mut [Option<bool>]) {
+    for output in plan["Output"].as_array().into_iter().flatten() {
+        if let Some(i) = outputs.iter().position(|o| Some(*o) == output.as_str()) {
+            if let Some(n) = nullable.get_mut(i) {
+                *n = Some(true);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::mark_outer_join_nullables;
+    use crate::connection::mock;
+    use crate::type_info::PgTypeInfo;
+    use rbdc::db::Connection;
+
+    /// The simple queries sent to describe a table query, inside a transaction if `begin`.
+    async fn described(begin: bool) -> Vec<String> {
+        let mut status = b'I';
+        let (mut conn, server) = mock::connect(move |tag, body| match tag {
+            b'P' => mock::backend(b'1', &[]),
+            b'D' => {
+                let mut out = mock::parameter_description(&[PgTypeInfo::INT4.0.oid().0]);
+                out.extend(mock::table_row_description(
+                    16384,
+                    &[("id", PgTypeInfo::INT4.0.oid().0)],
+                ));
+                out
+            }
+            b'S' => mock::ready(status),
+            _ => {
+                let sql = String::from_utf8_lossy(&body[..body.len() - 1]);
+                status = match sql.as_ref() {
+                    "BEGIN" => b'T',
+                    "ROLLBACK" => b'I',
+                    _ => status,
+                };
+                let mut out = Vec::new();
+                if sql.starts_with("EXPLAIN") {
+                    out.extend(mock::row_description(&[(
+                        "QUERY PLAN",
+                        PgTypeInfo::JSON.0.oid().0,
+                    )]));
+                    out.extend(mock::data_row(&[br#"[{"Plan": {}}]"#]));
+                }
+                out.extend(mock::command_complete("OK"));
+                out.extend(mock::ready(status));
+                out
+            }
+        })
+        .await;
+
+        if begin {
+            conn.exec("BEGIN", vec![]).await.unwrap();
+        }
+        conn.describe("select id from t where id = ?")
+            .await
+            .unwrap();
+        drop(conn);
+
+        server
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|(tag, _)| *tag == b'Q')
+            .map(|(_, body)| String::from_utf8_lossy(&body[..body.len() - 1]).to_string())
+            .filter(|sql| !sql.starts_with("SELECT c.idx"))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_explain_generic_plan() {
+        assert_eq!(
+            described(false).await,
+            [
+                "BEGIN",
+                "SET LOCAL plan_cache_mode = force_generic_plan",
+                "EXPLAIN (VERBOSE, FORMAT JSON) EXECUTE rbdc_s_1(NULL)",
+                "ROLLBACK",
+            ]
+        );
+        assert_eq!(
+            described(true).await,
+            [
+                "BEGIN",
+                "SAVEPOINT rbdc_explain",
+                "SET LOCAL plan_cache_mode = force_generic_plan",
+                "EXPLAIN (VERBOSE, FORMAT JSON) EXECUTE rbdc_s_1(NULL)",
+                "ROLLBACK TO SAVEPOINT rbdc_explain; RELEASE SAVEPOINT rbdc_explain",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mark_outer_join_nullables() {
+        let plan = serde_json::json!([{
+            "Plan": {
+                "Node Type": "Hash Join",
+                "Join Type": "Left",
+                "Output": ["a.id", "b.name"],
+                "Plans": [
+                    {"Node Type": "Seq Scan", "Parent Relationship": "Outer", "Output": ["a.id", "a.b_id"]},
+                    {"Node Type": "Hash", "Parent Relationship": "Inner", "Output": ["b.name", "b.id"],
+                     "Plans": [{"Node Type": "Seq Scan", "Parent Relationship": "Outer", "Output": ["b.name", "b.id"]}]}
+                ]
+            }
+        }]);
+        let mut nullable = vec![Some(false), Some(false)];
+        mark_outer_join_nullables(&plan, &mut nullable);
+        assert_eq!(nullable, vec![Some(false), Some(true)]);
+    }
+}
@@ -2,18 +2,129 @@ use crate::connection::{sasl, stream::PgStream, tls, PgConnection};
 use crate::message::{
     Authentication, BackendKeyData, MessageFormat, Password, ReadyForQuery, Startup,
 };
-use crate::options::PgConnectOptions;
+use crate::options::{PgChannelBinding, PgConnectOptions, PgTargetSessionAttrs};
 use crate::types::Oid;
 use rbdc::common::StatementCache;
+use rbdc::db::Connection;
 use rbdc::io::Decode;
 use rbdc::{err_protocol, Error};
+use rbs::Value;
 use std::collections::HashMap;
 
 // https://www.postgresql.org/docs/current/protocol-flow.html#id-1.10.5.7.3
 // https://www.postgresql.org/docs/current/protocol-flow.html#id-1.10.5.7.11
 
 impl PgConnection {
+    /// Connects to the first of the configured hosts that accepts the connection and whose
+    /// session matches `target_session_attrs`.
     pub async fn establish(options: &PgConnectOptions) -> Result<Self, Error> {
+        if options.hosts.is_empty() && options.target_session_attrs == PgTargetSessionAttrs::Any
+        {
+            return Self::establish_host(options).await;
+        }
+
+        let hosts = options.candidate_hosts();
+        // prefer-standby first looks for a standby, then accepts any server
+        let passes: &[PgTargetSessionAttrs] = match options.target_session_attrs {
+            PgTargetSessionAttrs::PreferStandby => {
+                &[PgTargetSessionAttrs::Standby, PgTargetSessionAttrs::Any]
+            }
+            ref attrs => std::slice::from_ref(attrs),
+        };
+
+        let mut errors = Vec::new();
+        for attrs in passes {
+            for (host, port) in &hosts {
+                let host_options = options.for_host(host, *port);
+
+                let mut conn = match Self::establish_host(&host_options).await {
+                    Ok(conn) => conn,
+                    Err(e) => {
+                        errors.push(format!("{}:{}: {}", host, port, e));
+                        continue;
+                    }
+                };
+                match conn.matches_session_attrs(*attrs).await {
+                    Ok(true) => return Ok(conn),
+                    Ok(false) => {
+                        errors.push(format!("{}:{}: session is not {}", host, port, attrs));
+                        let _ = conn.do_close().await;
+                    }
+                    Err(e) => errors.push(format!("{}:{}: {}", host, port, e)),
+                }
+            }
+        }
+
+        Err(Error::from(format!(
+            "could not connect to any host: {}",
+            errors.join("; ")
+        )))
+    }
+
+    async fn matches_session_attrs(&mut self, attrs: PgTargetSessionAttrs) -> Result<bool, Error> {
+        Ok(match attrs {
+            PgTargetSessionAttrs::Any => true,
+            PgTargetSessionAttrs::ReadWrite => !self.is_read_only().await?,
+            PgTargetSessionAttrs::ReadOnly => self.is_read_only().await?,
+            PgTargetSessionAttrs::Primary => !self.is_in_recovery().await?,
+            PgTargetSessionAttrs::Standby | PgTargetSessionAttrs::PreferStandby => {
+                self.is_in_recovery().await?
+            }
+        })
+    }
+
+    // servers since 14 report `default_transaction_read_only` and `in_hot_standby`,
+    // older ones are asked
+    async fn is_read_only(&mut self) -> Result<bool, Error> {
+        if let (Some(read_only), Some(hot_standby)) = (
+            self.server_parameter("default_transaction_read_only"),
+            self.server_parameter("in_hot_standby"),
+        ) {
+            return Ok(read_only == "on" || hot_standby == "on");
+        }
+        let v = self.query_scalar("SHOW transaction_read_only", vec![]).await?;
+        Ok(v.as_str() == Some("on"))
+    }
+
+    async fn is_in_recovery(&mut self) -> Result<bool, Error> {
+        if let Some(hot_standby) = self.server_parameter("in_hot_standby") {
+            return Ok(hot_standby == "on");
+        }
+        let v = self
+            .query_scalar("SELECT pg_catalog.pg_is_in_recovery()", vec![])
+            .await?;
+        Ok(v.as_bool() == Some(true))
+    }
+
+    /// The first column of the first row returned by `sql`, `Null` without rows.
+    pub(super) async fn query_scalar(
+        &mut self,
+        sql: &str,
+        params: Vec<Value>,
+    ) -> Result<Value, Error> {
+        let rows = self.get_values(sql, params).await?;
+        Ok(rows
+            .into_iter()
+            .next()
+            .and_then(|row| row.into_iter().next().map(|(_, v)| v))
+            .unwrap_or_default())
+    }
+
+    async fn establish_host(options: &PgConnectOptions) -> Result<Self, Error> {
+        match options.connect_timeout {
+            Some(timeout) => rbdc::rt::timeout(timeout, Self::connect_host(options))
+                .await
+                .map_err(|_| {
+                    Error::from(format!(
+                        "timed out after {:?} connecting to {}:{}",
+                        timeout, options.host, options.port
+                    ))
+                })?,
+            None => Self::connect_host(options).await,
+        }
+    }
+
+    async fn connect_host(options: &PgConnectOptions) -> Result<Self, Error> {
         let mut stream = PgStream::connect(options).await?;
 
         // Upgrade to TLS if we were asked to and the server supports it
@@ -45,6 +156,11 @@ impl PgConnection {
             params.push(("options", options));
         }
 
+        if options.replication {
+            // a walsender connected to the database, accepting both SQL and replication commands
+            params.push(("replication", "database"));
+        }
+
         stream
             .send(Startup {
                 username: Some(&options.username),
@@ -60,6 +176,7 @@ impl PgConnection {
 
         let mut process_id = 0;
         let mut secret_key = 0;
+        let mut channel_bound = false;
         let transaction_status;
 
         loop {
@@ -69,9 +186,12 @@ impl PgConnection {
                     Authentication::Ok => {
                         // the authentication exchange is successfully completed
                         // do nothing; no more information is required to continue
+                        check_channel_binding(options, channel_bound)?;
                     }
 
                     Authentication::CleartextPassword => {
+                        check_channel_binding(options, channel_bound)?;
+
                         // The frontend must now send a [PasswordMessage] containing the
                         // password in clear-text form.
 
@@ -87,6 +207,7 @@ impl PgConnection {
                         // password (with user name) encrypted via MD5, then encrypted again
                         // using the 4-byte random salt specified in the
                         // [AuthenticationMD5Password] message.
+                        check_channel_binding(options, channel_bound)?;
 
                         stream
                             .send(Password::Md5 {
@@ -98,7 +219,7 @@ impl PgConnection {
                     }
 
                     Authentication::Sasl(body) => {
-                        sasl::authenticate(&mut stream, options, body).await?;
+                        channel_bound = sasl::authenticate(&mut stream, options, body).await?;
                     }
 
                     method => {
@@ -140,11 +261,24 @@ impl PgConnection {
             process_id,
             secret_key,
             transaction_status,
+            two_phase_gid: None,
             pending_ready_for_query_count: 0,
             next_statement_id: Oid(1),
+            next_portal_id: Oid(1),
             cache_statement: StatementCache::new(options.statement_cache_capacity),
             cache_type_oid: HashMap::with_capacity(10),
             cache_type_info: HashMap::with_capacity(10),
         })
     }
 }
+
+// with `channel_binding=require` the server must not be allowed to skip or
+// downgrade the SCRAM exchange
+fn check_channel_binding(options: &PgConnectOptions, channel_bound: bool) -> Result<(), Error> {
+    if options.channel_binding == PgChannelBinding::Require && !channel_bound {
+        return Err(Error::from(
+            "channel binding required, but server authenticated client without channel binding",
+        ));
+    }
+    Ok(())
+}
@@ -1,12 +1,13 @@
 use crate::connection::PgConnection;
 use crate::message::{
     self, Bind, Close, CommandComplete, DataRow, MessageFormat, ParameterDescription, Parse, Query,
-    RowDescription,
+    RowDescription, TransactionStatus,
 };
 use crate::query::PgQuery;
 use crate::statement::PgStatementMetadata;
 use crate::type_info::PgType;
 use crate::types::Oid;
+use crate::types::TypeInfo;
 use crate::{
     arguments::PgArguments, query_result::PgQueryResult, row::PgRow, statement::PgStatement,
     type_info::PgTypeInfo, value::PgValueFormat,
@@ -16,9 +17,11 @@ use futures_core::future::BoxFuture;
 use futures_core::stream::BoxStream;
 use futures_core::Stream;
 use futures_util::{pin_mut, TryStreamExt};
+use rbdc::db::Connection;
 use rbdc::{err_protocol, try_stream, Error};
 use std::sync::Arc;
 use rbdc::io::Nothing;
+use rbs::Value;
 
 async fn prepare(
     conn: &mut PgConnection,
@@ -47,6 +50,7 @@ async fn prepare(
     conn.wait_until_ready().await?;
 
     // next we send the PARSE command to the server
+    conn.stream.set_query(sql);
     conn.stream.write(Parse {
         param_types: &*param_types,
         query: sql,
@@ -125,6 +129,16 @@ async fn recv_desc_rows(conn: &mut PgConnection) -> Result<Option<RowDescription
     Ok(rows)
 }
 
+/// The type sent in PARSE for a parameter whose type the server may infer: a string is
+/// left `unknown`, like a string literal in SQL, and so are a map, bound to a composite or
+/// json parameter, and an array, bound to the array type (or json) the query expects.
+fn inferred_type(value: &Value) -> PgTypeInfo {
+    match value {
+        Value::String(_) | Value::Map(_) | Value::Array(_) => PgTypeInfo::UNKNOWN,
+        v => v.type_info(),
+    }
+}
+
 impl PgConnection {
     // wait for CloseComplete to indicate a statement was closed
     pub(super) async fn wait_for_close_complete(&mut self, mut count: usize) -> Result<(), Error> {
@@ -161,7 +175,7 @@ impl PgConnection {
         self.pending_ready_for_query_count += 1;
     }
 
-    async fn get_or_prepare<'a>(
+    pub(super) async fn get_or_prepare<'a>(
         &mut self,
         sql: &str,
         parameters: &[PgTypeInfo],
@@ -192,10 +206,65 @@ impl PgConnection {
         Ok(statement)
     }
 
+    /// [`get_or_prepare`](Self::get_or_prepare) with the parameter types of `arguments`.
+    ///
+    /// The server first picks the types of the string, map and array parameters, which are
+    /// then converted to what the query expects (a string bound to an `int4` column, a map
+    /// bound to a composite, an array of strings bound to a `uuid[]` column). When it cannot,
+    /// e.g. the argument of `concat($1)`, the statement is prepared again with the types of
+    /// the values. A failed PARSE would abort an open transaction, so inside one it is tried
+    /// under a savepoint.
+    pub(super) async fn get_or_prepare_values(
+        &mut self,
+        sql: &str,
+        arguments: &[Value],
+        store_to_cache: bool,
+        metadata: Option<Arc<PgStatementMetadata>>,
+    ) -> Result<(Oid, Arc<PgStatementMetadata>), Error> {
+        let types: Vec<PgTypeInfo> = arguments.iter().map(|v| v.type_info()).collect();
+        let inferred: Vec<PgTypeInfo> = arguments.iter().map(inferred_type).collect();
+        self.wait_until_ready().await?;
+        if inferred == types
+            || metadata.is_some()
+            || self.cache_statement.get_mut(sql).is_some()
+            || matches!(self.transaction_status, TransactionStatus::Error)
+        {
+            return self
+                .get_or_prepare(sql, &types, store_to_cache, metadata)
+                .await;
+        }
+        let in_transaction = matches!(self.transaction_status, TransactionStatus::Transaction);
+        if in_transaction {
+            self.exec("SAVEPOINT rbdc_prepare", vec![]).await?;
+        }
+        self.stream.last_error = None;
+        let prepared = self
+            .get_or_prepare(sql, &inferred, store_to_cache, None)
+            .await;
+        // 42P18 (indeterminate_datatype), the server needs the types of the values
+        let indeterminate =
+            prepared.is_err() && self.stream.last_error.as_ref().map(|e| e.code()) == Some("42P18");
+        if indeterminate {
+            self.wait_until_ready().await?;
+        }
+        if in_transaction && (prepared.is_ok() || indeterminate) {
+            let end = if indeterminate {
+                "ROLLBACK TO SAVEPOINT rbdc_prepare; RELEASE SAVEPOINT rbdc_prepare"
+            } else {
+                "RELEASE SAVEPOINT rbdc_prepare"
+            };
+            self.exec(end, vec![]).await?;
+        }
+        if indeterminate {
+            return self.get_or_prepare(sql, &types, store_to_cache, None).await;
+        }
+        prepared
+    }
+
     async fn run<'e, 'c: 'e, 'q: 'e>(
         &'c mut self,
         query: &'q str,
-        arguments: Option<PgArguments>,
+        arguments: Option<Vec<Value>>,
         limit: u8,
         persistent: bool,
         metadata_opt: Option<Arc<PgStatementMetadata>>,
@@ -203,17 +272,23 @@ impl PgConnection {
         // before we continue, wait until we are "ready" to accept more queries
         self.wait_until_ready().await?;
 
+        // collected notices belong to the most recent query
+        self.stream.notices.clear();
+
         let mut metadata: Arc<PgStatementMetadata>;
 
-        let format = if let Some(mut arguments) = arguments {
+        let format = if let Some(arguments) = arguments {
             // prepare the statement if this our first time executing it
             // always return the statement ID here
             let (statement, metadata_) = self
-                .get_or_prepare(query, &arguments.types, persistent, metadata_opt)
+                .get_or_prepare_values(query, &arguments, persistent, metadata_opt)
                 .await?;
 
             metadata = metadata_;
 
+            // encode the arguments as the parameter types the server described
+            let mut arguments = PgArguments::from_args_described(arguments, &metadata.parameters)?;
+
             // patch holes created during encoding
             arguments.apply_patches(self, &metadata.parameters).await?;
 
@@ -222,6 +297,7 @@ impl PgConnection {
             self.wait_until_ready().await?;
 
             // bind to attach the arguments to the statement and create a portal
+            self.stream.set_query(query);
             self.stream.write(Bind {
                 portal: None,
                 statement,
@@ -249,6 +325,7 @@ impl PgConnection {
             PgValueFormat::Binary
         } else {
             // Query will trigger a ReadyForQuery
+            self.stream.set_query(query);
             self.stream.write(Query(query));
             self.pending_ready_for_query_count += 1;
 
@@ -262,6 +339,9 @@ impl PgConnection {
         self.stream.flush().await?;
 
         Ok(try_stream! {
+            // notices already attached to a previous result
+            let mut reported_notices = 0;
+
             loop {
                 let message = self.stream.recv().await?;
 
@@ -273,13 +353,21 @@ impl PgConnection {
                         // harmless messages to ignore
                     }
 
+                    MessageFormat::PortalSuspended => {
+                        // the row limit of the unnamed portal was reached,
+                        // the following Sync closes it
+                    }
+
                     MessageFormat::CommandComplete => {
                         // a SQL command completed normally
                         let cc: CommandComplete = message.decode()?;
 
                         let rows_affected = cc.rows_affected();
+                        let notices = self.stream.notices[reported_notices..].to_vec();
+                        reported_notices = self.stream.notices.len();
                         r#yield!(Either::Left(PgQueryResult {
                             rows_affected,
+                            notices,
                         }));
                     }
 
@@ -307,6 +395,7 @@ impl PgConnection {
                             data,
                             format,
                             metadata: Arc::clone(&metadata),
+                            timezone: self.stream.timezone.clone(),
                         };
 
                         r#yield!(Either::Right(row));
@@ -341,7 +430,7 @@ impl PgConnection {
         let metadata = query.statement().map(|s| Arc::clone(&s.metadata));
         let persistent = query.persistent();
         Box::pin(try_stream! {
-            let arguments = query.take_arguments()?;
+            let arguments = query.take_values();
             let s = self.run(&sql, arguments, 0, persistent, metadata).await?;
             pin_mut!(s);
 
@@ -361,7 +450,7 @@ impl PgConnection {
         let metadata = query.statement().map(|s| Arc::clone(&s.metadata));
         let persistent = query.persistent();
         Box::pin(async move {
-            let arguments = query.take_arguments()?;
+            let arguments = query.take_values();
             let s = self.run(&sql, arguments, 1, persistent, metadata).await?;
             pin_mut!(s);
             while let Some(s) = s.try_next().await? {
@@ -373,6 +462,25 @@ impl PgConnection {
         })
     }
 
+    /// Prepares `sql` for the parameter values `params`, see
+    /// [`get_or_prepare_values`](Self::get_or_prepare_values).
+    pub fn prepare_values<'a>(
+        &'a mut self,
+        sql: String,
+        params: &'a [Value],
+    ) -> BoxFuture<'a, Result<PgStatement, Error>> {
+        Box::pin(async move {
+            self.wait_until_ready().await?;
+
+            let (_, metadata) = self.get_or_prepare_values(&sql, params, true, None).await?;
+
+            Ok(PgStatement {
+                sql: sql.to_owned(),
+                metadata,
+            })
+        })
+    }
+
     pub fn prepare_with<'a>(
         &'a mut self,
         sql: String,
@@ -390,3 +498,182 @@ impl PgConnection {
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::connection::mock::{self, parse_types};
+    use crate::type_info::PgTypeInfo;
+    use rbdc::db::Connection;
+    use rbs::value::map::ValueMap;
+    use rbs::Value;
+    use std::sync::{Arc, Mutex};
+
+    /// The parameter types of each PARSE sent to run `sql` with `value`, on a server that
+    /// infers the type of an `unknown` parameter as `inferred`, or cannot infer it when `None`,
+    /// and the simple queries sent. Inside a transaction if `begin`.
+    async fn prepared(
+        sql: &str,
+        value: Value,
+        inferred: Option<u32>,
+        begin: bool,
+    ) -> (Vec<Vec<u32>>, Vec<String>) {
+        let parsed = Arc::new(Mutex::new(Vec::new()));
+        let mut status = b'I';
+        let (mut conn, server) = mock::connect({
+            let parsed = parsed.clone();
+            move |tag, body| match tag {
+                b'P' => {
+                    let mut types = parse_types(body);
+                    if types == [PgTypeInfo::UNKNOWN.0.oid().0] {
+                        match inferred {
+                            Some(oid) => types = vec![oid],
+                            None => {
+                                return mock::error(
+                                    "42P18",
+                                    "could not determine data type of parameter $1",
+                                )
+                            }
+                        }
+                    }
+                    *parsed.lock().unwrap() = types;
+                    mock::backend(b'1', &[])
+                }
+                b'D' => {
+                    let mut out = mock::parameter_description(&parsed.lock().unwrap());
+                    out.extend(mock::row_description(&[("v", PgTypeInfo::TEXT.0.oid().0)]));
+                    out
+                }
+                b'B' => mock::backend(b'2', &[]),
+                b'E' => {
+                    let mut out = mock::data_row(&[b"abc"]);
+                    out.extend(mock::command_complete("SELECT 1"));
+                    out
+                }
+                b'S' => mock::ready(status),
+                b'Q' => {
+                    if body.starts_with(b"BEGIN") {
+                        status = b'T';
+                    }
+                    [mock::command_complete("OK"), mock::ready(status)].concat()
+                }
+                _ => vec![],
+            }
+        })
+        .await;
+
+        if begin {
+            conn.exec("BEGIN", vec![]).await.unwrap();
+        }
+        let v = conn.query_scalar(sql, vec![value]).await.unwrap();
+        assert_eq!(v, Value::String("abc".to_string()));
+        drop(conn);
+
+        let received = server.await.unwrap();
+        let parses = received
+            .iter()
+            .filter(|(tag, _)| *tag == b'P')
+            .map(|(_, body)| parse_types(body))
+            .collect();
+        let queries = received
+            .iter()
+            .filter(|(tag, _)| *tag == b'Q')
+            .map(|(_, body)| String::from_utf8_lossy(&body[..body.len() - 1]).to_string())
+            .collect();
+        (parses, queries)
+    }
+
+    async fn indeterminate(sql: &str, value: Value) -> Vec<Vec<u32>> {
+        prepared(sql, value, None, false).await.0
+    }
+
+    #[tokio::test]
+    async fn test_inferred_parameter_in_transaction() {
+        let int4 = PgTypeInfo::INT4.0.oid().0;
+        let unknown = PgTypeInfo::UNKNOWN.0.oid().0;
+        // the server picks the type, the same as outside a transaction
+        let (parses, queries) = prepared(
+            "SELECT v FROM t WHERE id = $1",
+            Value::String("42".to_string()),
+            Some(int4),
+            true,
+        )
+        .await;
+        assert_eq!(parses, [vec![unknown]]);
+        assert_eq!(
+            queries,
+            [
+                "BEGIN",
+                "SAVEPOINT rbdc_prepare",
+                "RELEASE SAVEPOINT rbdc_prepare"
+            ]
+        );
+        let (parses, _) = prepared(
+            "SELECT v FROM t WHERE id = $1",
+            Value::String("42".to_string()),
+            Some(int4),
+            false,
+        )
+        .await;
+        assert_eq!(parses, [vec![unknown]]);
+
+        // the failed PARSE is rolled back before the types of the values are sent
+        let (parses, queries) = prepared(
+            "SELECT concat($1)",
+            Value::String("abc".to_string()),
+            None,
+            true,
+        )
+        .await;
+        assert_eq!(parses, [vec![unknown], vec![PgTypeInfo::VARCHAR.0.oid().0]]);
+        assert_eq!(
+            queries,
+            [
+                "BEGIN",
+                "SAVEPOINT rbdc_prepare",
+                "ROLLBACK TO SAVEPOINT rbdc_prepare; RELEASE SAVEPOINT rbdc_prepare"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_indeterminate_string_parameter() {
+        // inferred first, then hinted as varchar
+        assert_eq!(
+            indeterminate("SELECT concat($1)", Value::String("abc".to_string())).await,
+            [
+                vec![PgTypeInfo::UNKNOWN.0.oid().0],
+                vec![PgTypeInfo::VARCHAR.0.oid().0]
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_indeterminate_map_parameter() {
+        let mut map = ValueMap::new();
+        map.insert(Value::String("k".to_string()), Value::I32(1));
+        assert_eq!(
+            indeterminate("SELECT jsonb_typeof($1)", Value::Map(map)).await,
+            [
+                vec![PgTypeInfo::UNKNOWN.0.oid().0],
+                vec![PgTypeInfo::JSON.0.oid().0]
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_indeterminate_array_parameter() {
+        let v = Value::Array(vec![Value::I64(1), Value::I64(2)]);
+        assert_eq!(
+            indeterminate("SELECT array_length($1, 1)", v).await,
+            [
+                vec![PgTypeInfo::UNKNOWN.0.oid().0],
+                vec![PgTypeInfo::INT8_ARRAY.0.oid().0]
+            ]
+        );
+        let v = Value::Array(vec![Value::String("a".to_string())]);
+        assert_eq!(
+            indeterminate("SELECT unnest($1)", v).await[1],
+            [PgTypeInfo::TEXT_ARRAY.0.oid().0]
+        );
+    }
+}
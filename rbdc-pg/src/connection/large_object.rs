@@ -0,0 +1,559 @@
+use crate::connection::PgConnection;
+use crate::message::TransactionStatus;
+use crate::types::Oid;
+use futures_core::future::BoxFuture;
+use rbdc::rt::{AsyncRead, AsyncWrite, ReadBuf};
+use rbdc::{err_protocol, Error};
+use rbs::Value;
+use std::io::{self, SeekFrom};
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+// `INV_READ` and `INV_WRITE` of libpq-fs.h
+const INV_READ: i32 = 0x40000;
+const INV_WRITE: i32 = 0x20000;
+
+// the most bytes read or written by one `loread` / `lowrite` call of the async adapter
+const MAX_CHUNK: usize = 1024 * 1024;
+
+/// The access mode of an opened large object.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum PgLargeObjectMode {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl PgLargeObjectMode {
+    fn flags(self) -> i32 {
+        match self {
+            PgLargeObjectMode::Read => INV_READ,
+            PgLargeObjectMode::Write => INV_WRITE,
+            PgLargeObjectMode::ReadWrite => INV_READ | INV_WRITE,
+        }
+    }
+}
+
+/// An opened [large object](https://www.postgresql.org/docs/current/largeobjects.html),
+/// see [`PgConnection::lo_open`].
+///
+/// Large object descriptors only live until the end of the transaction they were opened
+/// in, so a transaction must be started (`BEGIN`) before opening one.
+pub struct PgLargeObject<'c> {
+    conn: &'c mut PgConnection,
+    oid: Oid,
+    fd: i32,
+}
+
+impl PgConnection {
+    /// Creates a new, empty large object and returns its oid.
+    pub async fn lo_create(&mut self) -> Result<Oid, Error> {
+        let v = self
+            .query_scalar("SELECT lo_creat($1)", vec![Value::I32(-1)])
+            .await?;
+        v.as_u64()
+            .map(|oid| Oid(oid as u32))
+            .ok_or_else(|| err_protocol!("lo_creat returned {}", v))
+    }
+
+    /// Opens the large object `oid`. Must be called inside a transaction.
+    pub async fn lo_open(
+        &mut self,
+        oid: Oid,
+        mode: PgLargeObjectMode,
+    ) -> Result<PgLargeObject<'_>, Error> {
+        self.wait_until_ready().await?;
+        if matches!(self.transaction_status, TransactionStatus::Idle) {
+            return Err(Error::from(
+                "large objects can only be opened inside a transaction",
+            ));
+        }
+        let v = self
+            .query_scalar(
+                "SELECT lo_open($1, $2)",
+                vec![oid_value(oid), Value::I32(mode.flags())],
+            )
+            .await?;
+        let fd = v
+            .as_i64()
+            .ok_or_else(|| err_protocol!("lo_open returned {}", v))?;
+        Ok(PgLargeObject {
+            conn: self,
+            oid,
+            fd: fd as i32,
+        })
+    }
+
+    /// Deletes the large object `oid`.
+    pub async fn lo_unlink(&mut self, oid: Oid) -> Result<(), Error> {
+        self.query_scalar("SELECT lo_unlink($1)", vec![oid_value(oid)])
+            .await?;
+        Ok(())
+    }
+
+    async fn lo_read(&mut self, fd: i32, len: usize) -> Result<Vec<u8>, Error> {
+        let len = len.min(i32::MAX as usize) as i32;
+        match self
+            .query_scalar(
+                "SELECT loread($1, $2)",
+                vec![Value::I32(fd), Value::I32(len)],
+            )
+            .await?
+        {
+            Value::Binary(data) => Ok(data),
+            Value::Null => Ok(vec![]),
+            v => Err(err_protocol!("loread returned {}", v)),
+        }
+    }
+
+    async fn lo_write(&mut self, fd: i32, data: Vec<u8>) -> Result<usize, Error> {
+        let v = self
+            .query_scalar(
+                "SELECT lowrite($1, $2)",
+                vec![Value::I32(fd), Value::Binary(data)],
+            )
+            .await?;
+        v.as_u64()
+            .map(|n| n as usize)
+            .ok_or_else(|| err_protocol!("lowrite returned {}", v))
+    }
+
+    async fn lo_seek(&mut self, fd: i32, pos: SeekFrom) -> Result<u64, Error> {
+        let (offset, whence) = seek_args(pos);
+        let v = self
+            .query_scalar(
+                "SELECT lo_lseek64($1, $2, $3)",
+                vec![Value::I32(fd), Value::I64(offset), Value::I32(whence)],
+            )
+            .await?;
+        v.as_u64()
+            .ok_or_else(|| err_protocol!("lo_lseek64 returned {}", v))
+    }
+}
+
+impl<'c> PgLargeObject<'c> {
+    /// The oid of the large object.
+    pub fn oid(&self) -> Oid {
+        self.oid
+    }
+
+    /// Reads up to `len` bytes from the current position. Returns fewer bytes only at the
+    /// end of the object.
+    pub async fn read(&mut self, len: usize) -> Result<Vec<u8>, Error> {
+        self.conn.lo_read(self.fd, len).await
+    }
+
+    /// Writes `data` at the current position and returns the number of bytes written.
+    pub async fn write(&mut self, data: &[u8]) -> Result<usize, Error> {
+        self.conn.lo_write(self.fd, data.to_vec()).await
+    }
+
+    /// Moves the current position, returning the new position from the start of the object.
+    pub async fn seek(&mut self, pos: SeekFrom) -> Result<u64, Error> {
+        self.conn.lo_seek(self.fd, pos).await
+    }
+
+    /// The current position from the start of the object.
+    pub async fn tell(&mut self) -> Result<u64, Error> {
+        let v = self
+            .conn
+            .query_scalar("SELECT lo_tell64($1)", vec![Value::I32(self.fd)])
+            .await?;
+        v.as_u64()
+            .ok_or_else(|| err_protocol!("lo_tell64 returned {}", v))
+    }
+
+    /// Truncates (or extends with zeros) the object to `len` bytes. The current position
+    /// does not change.
+    pub async fn truncate(&mut self, len: u64) -> Result<(), Error> {
+        self.conn
+            .query_scalar(
+                "SELECT lo_truncate64($1, $2)",
+                vec![Value::I32(self.fd), Value::I64(len as i64)],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Closes the descriptor. Descriptors that are not closed are closed at the end of the
+    /// transaction.
+    pub async fn close(self) -> Result<(), Error> {
+        self.conn
+            .query_scalar("SELECT lo_close($1)", vec![Value::I32(self.fd)])
+            .await?;
+        Ok(())
+    }
+
+    /// Wraps the object in an adapter implementing [`AsyncRead`] and [`AsyncWrite`] from the
+    /// current position.
+    pub fn into_io(self) -> PgLargeObjectIo<'c> {
+        PgLargeObjectIo {
+            oid: self.oid,
+            fd: self.fd,
+            state: IoState::Idle(self.conn),
+            buffer: vec![],
+            consumed: 0,
+        }
+    }
+}
+
+type PendingIo<'c> = BoxFuture<'c, (&'c mut PgConnection, Result<Option<Vec<u8>>, Error>)>;
+
+enum IoState<'c> {
+    Idle(&'c mut PgConnection),
+    // a `loread` (returning `Some`) or `lowrite` in flight
+    Busy(PendingIo<'c>),
+    // only while switching between the other states
+    Empty,
+}
+
+/// [`AsyncRead`] and [`AsyncWrite`] over a large object, see [`PgLargeObject::into_io`].
+///
+/// Every read or write is a round trip to the server, reads of at most 1 MiB each, so wrap
+/// it in a buffered reader or writer for small reads or writes. A write finishes during
+/// the next operation, call `flush` (or [`into_inner`](Self::into_inner)) to see its error.
+pub struct PgLargeObjectIo<'c> {
+    oid: Oid,
+    fd: i32,
+    state: IoState<'c>,
+    // read from the server but not yet returned
+    buffer: Vec<u8>,
+    consumed: usize,
+}
+
+impl<'c> PgLargeObjectIo<'c> {
+    /// The oid of the large object.
+    pub fn oid(&self) -> Oid {
+        self.oid
+    }
+
+    /// Waits for the pending write and returns the object, positioned after the bytes
+    /// returned by the reads.
+    pub async fn into_inner(mut self) -> Result<PgLargeObject<'c>, Error> {
+        std::future::poll_fn(|cx| self.poll_idle(cx)).await?;
+        let IoState::Idle(conn) = std::mem::replace(&mut self.state, IoState::Empty) else {
+            return Err(Error::from(
+                "large object: a previous operation was interrupted",
+            ));
+        };
+        let unread = self.buffer.len() - self.consumed;
+        if unread > 0 {
+            conn.lo_seek(self.fd, SeekFrom::Current(-(unread as i64)))
+                .await?;
+        }
+        Ok(PgLargeObject {
+            conn,
+            oid: self.oid,
+            fd: self.fd,
+        })
+    }
+
+    // waits for the operation in flight, keeping the data of a read. Returns whether a read
+    // completed.
+    fn poll_idle(&mut self, cx: &mut Context<'_>) -> Poll<Result<bool, Error>> {
+        match std::mem::replace(&mut self.state, IoState::Empty) {
+            IoState::Idle(conn) => {
+                self.state = IoState::Idle(conn);
+                Poll::Ready(Ok(false))
+            }
+            IoState::Busy(mut f) => match f.as_mut().poll(cx) {
+                Poll::Pending => {
+                    self.state = IoState::Busy(f);
+                    Poll::Pending
+                }
+                Poll::Ready((conn, result)) => {
+                    self.state = IoState::Idle(conn);
+                    let Some(data) = result? else {
+                        return Poll::Ready(Ok(false));
+                    };
+                    self.buffer = data;
+                    self.consumed = 0;
+                    Poll::Ready(Ok(true))
+                }
+            },
+            IoState::Empty => Poll::Ready(Err(Error::from(
+                "large object: a previous operation was interrupted",
+            ))),
+        }
+    }
+
+    fn start<F>(&mut self, f: F)
+    where
+        F: FnOnce(&'c mut PgConnection) -> PendingIo<'c>,
+    {
+        if let IoState::Idle(conn) = std::mem::replace(&mut self.state, IoState::Empty) {
+            self.state = IoState::Busy(f(conn));
+        }
+    }
+}
+
+impl AsyncRead for PgLargeObjectIo<'_> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            if this.consumed < this.buffer.len() {
+                let n = buf.remaining().min(this.buffer.len() - this.consumed);
+                buf.put_slice(&this.buffer[this.consumed..this.consumed + n]);
+                this.consumed += n;
+                return Poll::Ready(Ok(()));
+            }
+
+            if ready!(this.poll_idle(cx)).map_err(io::Error::other)? {
+                if this.buffer.is_empty() {
+                    // end of the object
+                    return Poll::Ready(Ok(()));
+                }
+                continue;
+            }
+
+            if buf.remaining() == 0 {
+                return Poll::Ready(Ok(()));
+            }
+            let fd = this.fd;
+            let len = buf.remaining().min(MAX_CHUNK);
+            this.start(|conn| {
+                Box::pin(async move {
+                    let result = conn.lo_read(fd, len).await.map(Some);
+                    (conn, result)
+                })
+            });
+        }
+    }
+}
+
+impl AsyncWrite for PgLargeObjectIo<'_> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        ready!(this.poll_idle(cx)).map_err(io::Error::other)?;
+
+        // the server position is after the bytes read but not yet returned
+        let unread = this.buffer.len() - this.consumed;
+        this.buffer.clear();
+        this.consumed = 0;
+
+        let fd = this.fd;
+        let data = buf[..buf.len().min(MAX_CHUNK)].to_vec();
+        let n = data.len();
+        this.start(|conn| {
+            Box::pin(async move {
+                let mut result = Ok(0);
+                if unread > 0 {
+                    result = conn
+                        .lo_seek(fd, SeekFrom::Current(-(unread as i64)))
+                        .await
+                        .map(|_| 0);
+                }
+                if result.is_ok() {
+                    result = conn.lo_write(fd, data).await;
+                }
+                (conn, result.map(|_| None))
+            })
+        });
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut()
+            .poll_idle(cx)
+            .map(|result| result.map(|_| ()).map_err(io::Error::other))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_flush(cx)
+    }
+}
+
+fn oid_value(oid: Oid) -> Value {
+    Value::Ext("Oid", Box::new(Value::U32(oid.0)))
+}
+
+// the offset and `whence` (`SEEK_SET`, `SEEK_CUR` or `SEEK_END`) of `lo_lseek64`
+fn seek_args(pos: SeekFrom) -> (i64, i32) {
+    match pos {
+        SeekFrom::Start(offset) => (offset as i64, 0),
+        SeekFrom::Current(offset) => (offset, 1),
+        SeekFrom::End(offset) => (offset, 2),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{seek_args, PgLargeObject, PgLargeObjectMode};
+    use crate::connection::{mock, PgConnection};
+    use crate::type_info::PgTypeInfo;
+    use crate::types::Oid;
+    use rbdc::rt::{AsyncRead, AsyncReadExt, AsyncWriteExt, ReadBuf};
+    use std::collections::HashMap;
+    use std::io::SeekFrom;
+    use std::pin::Pin;
+    use std::sync::{Arc, Mutex};
+    use std::task::Poll;
+
+    #[derive(Default)]
+    struct Object {
+        data: Vec<u8>,
+        pos: usize,
+        // the calls the server ran, with their arguments
+        calls: Vec<String>,
+    }
+
+    // a connection to a server holding one large object, opened as descriptor 0
+    async fn open(data: &[u8], fail_write: bool) -> (PgConnection, Arc<Mutex<Object>>) {
+        let object = Arc::new(Mutex::new(Object {
+            data: data.to_vec(),
+            ..Default::default()
+        }));
+        let mut statements = HashMap::new();
+        // the result of the bound call, returned by the Execute
+        let mut result = vec![];
+        let (conn, _) = mock::connect({
+            let object = object.clone();
+            move |tag, body| match tag {
+                b'P' => {
+                    let (name, query) = mock::parse_statement(body);
+                    statements.insert(name, (query, mock::parse_types(body)));
+                    mock::backend(b'1', &[])
+                }
+                b'D' => {
+                    // a statement, then its name
+                    let name = String::from_utf8_lossy(&body[1..body.len() - 1]).to_string();
+                    let (query, types) = &statements[&name];
+                    let column = match query.as_str() {
+                        "SELECT loread($1, $2)" => PgTypeInfo::BYTEA,
+                        "SELECT lowrite($1, $2)" => PgTypeInfo::INT4,
+                        _ => PgTypeInfo::INT8,
+                    };
+                    let mut out = mock::parameter_description(types);
+                    out.extend(mock::row_description(&[("v", column.0.oid().0)]));
+                    out
+                }
+                b'B' => {
+                    let (name, params) = mock::bind_params(body);
+                    let params: Vec<Vec<u8>> = params.into_iter().map(Option::unwrap).collect();
+                    let mut object = object.lock().unwrap();
+                    let pos = object.pos;
+                    let call;
+                    (call, result) = match statements[&name].0.as_str() {
+                        "SELECT loread($1, $2)" => {
+                            let len = i32::from_be_bytes(params[1][..].try_into().unwrap());
+                            let end = (pos + len as usize).min(object.data.len());
+                            object.pos = end;
+                            (format!("loread {}", len), object.data[pos..end].to_vec())
+                        }
+                        "SELECT lowrite($1, $2)" => {
+                            if fail_write {
+                                return mock::error(
+                                    "42501",
+                                    "permission denied for large object 1",
+                                );
+                            }
+                            let data = &params[1];
+                            let end = pos + data.len();
+                            if object.data.len() < end {
+                                object.data.resize(end, 0);
+                            }
+                            object.data[pos..end].copy_from_slice(data);
+                            object.pos = end;
+                            let call = format!("lowrite {}", String::from_utf8_lossy(data));
+                            (call, (data.len() as i32).to_be_bytes().to_vec())
+                        }
+                        "SELECT lo_lseek64($1, $2, $3)" => {
+                            let offset = i64::from_be_bytes(params[1][..].try_into().unwrap());
+                            let whence = i32::from_be_bytes(params[2][..].try_into().unwrap());
+                            assert_eq!(whence, 1);
+                            object.pos = (pos as i64 + offset) as usize;
+                            (
+                                format!("lo_lseek64 {}", offset),
+                                (object.pos as i64).to_be_bytes().to_vec(),
+                            )
+                        }
+                        query => panic!("{}", query),
+                    };
+                    object.calls.push(call);
+                    mock::backend(b'2', &[])
+                }
+                b'E' => {
+                    let mut out = mock::data_row(&[&result]);
+                    out.extend(mock::command_complete("SELECT 1"));
+                    out
+                }
+                b'S' => mock::ready(b'T'),
+                _ => vec![],
+            }
+        })
+        .await;
+        (conn, object)
+    }
+
+    fn large_object(conn: &mut PgConnection) -> PgLargeObject<'_> {
+        PgLargeObject {
+            conn,
+            oid: Oid(1),
+            fd: 0,
+        }
+    }
+
+    #[test]
+    fn test_seek_args() {
+        assert_eq!(seek_args(SeekFrom::Start(10)), (10, 0));
+        assert_eq!(seek_args(SeekFrom::Current(-3)), (-3, 1));
+        assert_eq!(seek_args(SeekFrom::End(0)), (0, 2));
+        assert_eq!(PgLargeObjectMode::ReadWrite.flags(), 0x60000);
+    }
+
+    #[tokio::test]
+    async fn test_read_to_end() {
+        let data: Vec<u8> = (0..100).collect();
+        let (mut conn, object) = open(&data, false).await;
+        let mut io = large_object(&mut conn).into_io();
+        let mut read = Vec::new();
+        io.read_to_end(&mut read).await.unwrap();
+        assert_eq!(read, data);
+        let object = object.lock().unwrap();
+        // read until a read returns nothing
+        assert!(object.calls.len() > 1);
+        assert!(object.calls.iter().all(|call| call.starts_with("loread")));
+        assert_eq!(object.pos, 100);
+    }
+
+    #[tokio::test]
+    async fn test_write_after_partial_read() {
+        let (mut conn, object) = open(b"hello world", false).await;
+        let mut io = large_object(&mut conn).into_io();
+        // start a read of 8 bytes, then take only 3 of them
+        std::future::poll_fn(|cx| {
+            let mut buf = [0; 8];
+            let mut buf = ReadBuf::new(&mut buf);
+            assert!(Pin::new(&mut io).poll_read(cx, &mut buf).is_pending());
+            Poll::Ready(())
+        })
+        .await;
+        let mut read = [0; 3];
+        io.read_exact(&mut read).await.unwrap();
+        assert_eq!(&read, b"hel");
+        io.write_all(b"XY").await.unwrap();
+        io.flush().await.unwrap();
+
+        let object = object.lock().unwrap();
+        assert_eq!(object.data, b"helXY world");
+        assert_eq!(object.calls, ["loread 8", "lo_lseek64 -5", "lowrite XY"]);
+    }
+
+    #[tokio::test]
+    async fn test_write_error_on_flush() {
+        let (mut conn, _) = open(b"", true).await;
+        let mut io = large_object(&mut conn).into_io();
+        // the write only starts the call
+        assert_eq!(io.write(b"abc").await.unwrap(), 3);
+        let err = io.flush().await.unwrap_err();
+        assert!(err.to_string().contains("permission denied"), "{}", err);
+    }
+}
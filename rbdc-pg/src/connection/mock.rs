@@ -0,0 +1,191 @@
+//! A scripted server speaking the backend side of the protocol, for the connection tests.
+use crate::connection::PgConnection;
+use crate::options::{PgConnectOptions, PgSslMode};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+/// A message sent by the frontend: its type byte and body.
+pub(crate) type FrontendMessage = (u8, Vec<u8>);
+
+/// Connects to a server that answers each frontend message with the backend messages `reply`
+/// returns. As a real server, after an ErrorResponse the messages up to the next Sync are
+/// skipped. The handle returns every message the server received.
+pub(crate) async fn connect<F>(mut reply: F) -> (PgConnection, JoinHandle<Vec<FrontendMessage>>)
+where
+    F: FnMut(u8, &[u8]) -> Vec<u8> + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        // the startup message, without a type byte
+        let len = socket.read_i32().await.unwrap() as usize;
+        let mut startup = vec![0; len - 4];
+        socket.read_exact(&mut startup).await.unwrap();
+        let mut out = backend(b'R', &0_i32.to_be_bytes());
+        out.extend(ready(b'I'));
+        socket.write_all(&out).await.unwrap();
+
+        let mut received = Vec::new();
+        let mut failed = false;
+        while let Ok(tag) = socket.read_u8().await {
+            let len = socket.read_i32().await.unwrap() as usize;
+            let mut body = vec![0; len - 4];
+            socket.read_exact(&mut body).await.unwrap();
+            if tag == b'X' {
+                break;
+            }
+            if !failed || tag == b'S' {
+                let out = reply(tag, &body);
+                failed = tag != b'S' && has_error(&out);
+                socket.write_all(&out).await.unwrap();
+            }
+            received.push((tag, body));
+        }
+        received
+    });
+
+    let options = PgConnectOptions::new_without_pgpass()
+        .host("127.0.0.1")
+        .port(port)
+        .username("postgres")
+        .ssl_mode(PgSslMode::Disable);
+    let conn = PgConnection::establish(&options).await.unwrap();
+    (conn, server)
+}
+
+/// Whether the backend messages `out` include an ErrorResponse.
+fn has_error(mut out: &[u8]) -> bool {
+    while out.len() >= 5 {
+        if out[0] == b'E' {
+            return true;
+        }
+        let len = i32::from_be_bytes([out[1], out[2], out[3], out[4]]) as usize;
+        out = &out[1 + len..];
+    }
+    false
+}
+
+/// A backend message.
+pub(crate) fn backend(tag: u8, body: &[u8]) -> Vec<u8> {
+    let mut buf = vec![tag];
+    buf.extend(&(body.len() as i32 + 4).to_be_bytes());
+    buf.extend(body);
+    buf
+}
+
+pub(crate) fn ready(status: u8) -> Vec<u8> {
+    backend(b'Z', &[status])
+}
+
+pub(crate) fn parameter_description(oids: &[u32]) -> Vec<u8> {
+    let mut body = (oids.len() as i16).to_be_bytes().to_vec();
+    for oid in oids {
+        body.extend(&oid.to_be_bytes());
+    }
+    backend(b't', &body)
+}
+
+/// A RowDescription of columns with their name and type oid.
+pub(crate) fn row_description(columns: &[(&str, u32)]) -> Vec<u8> {
+    table_row_description(0, columns)
+}
+
+/// A RowDescription of the columns of the table `table`, numbered from 1, or of no table when 0.
+pub(crate) fn table_row_description(table: u32, columns: &[(&str, u32)]) -> Vec<u8> {
+    let mut body = (columns.len() as i16).to_be_bytes().to_vec();
+    for (i, (name, oid)) in columns.iter().enumerate() {
+        let attribute = if table == 0 { 0 } else { i as i16 + 1 };
+        body.extend(name.as_bytes());
+        body.push(0);
+        body.extend(&table.to_be_bytes());
+        body.extend(&attribute.to_be_bytes());
+        body.extend(&oid.to_be_bytes());
+        body.extend(&(-1_i16).to_be_bytes());
+        body.extend(&(-1_i32).to_be_bytes());
+        body.extend(&1_i16.to_be_bytes());
+    }
+    backend(b'T', &body)
+}
+
+pub(crate) fn data_row(values: &[&[u8]]) -> Vec<u8> {
+    let mut body = (values.len() as i16).to_be_bytes().to_vec();
+    for value in values {
+        body.extend(&(value.len() as i32).to_be_bytes());
+        body.extend(*value);
+    }
+    backend(b'D', &body)
+}
+
+pub(crate) fn command_complete(tag: &str) -> Vec<u8> {
+    let mut body = tag.as_bytes().to_vec();
+    body.push(0);
+    backend(b'C', &body)
+}
+
+pub(crate) fn error(code: &str, message: &str) -> Vec<u8> {
+    let body = format!("SERROR\0VERROR\0C{}\0M{}\0\0", code, message);
+    backend(b'E', body.as_bytes())
+}
+
+/// An ErrorResponse at the 1-based character `position` of the query.
+pub(crate) fn error_at(code: &str, message: &str, position: usize) -> Vec<u8> {
+    let body = format!("SERROR\0VERROR\0C{}\0M{}\0P{}\0\0", code, message, position);
+    backend(b'E', body.as_bytes())
+}
+
+/// The parameter type oids of a Parse message.
+pub(crate) fn parse_types(body: &[u8]) -> Vec<u32> {
+    // the statement name and the query, then the types
+    let mut rest = body;
+    for _ in 0..2 {
+        let end = rest.iter().position(|b| *b == 0).unwrap();
+        rest = &rest[end + 1..];
+    }
+    let n = i16::from_be_bytes([rest[0], rest[1]]) as usize;
+    (0..n)
+        .map(|i| {
+            let at = 2 + i * 4;
+            u32::from_be_bytes([rest[at], rest[at + 1], rest[at + 2], rest[at + 3]])
+        })
+        .collect()
+}
+
+/// The statement name and the query of a Parse message.
+pub(crate) fn parse_statement(body: &[u8]) -> (String, String) {
+    let (name, rest) = cstring(body);
+    let (query, _) = cstring(rest);
+    (name, query)
+}
+
+/// The statement name and the parameter values of a Bind message, `None` for NULL.
+pub(crate) fn bind_params(body: &[u8]) -> (String, Vec<Option<Vec<u8>>>) {
+    // the portal and the statement names, then the parameter formats
+    let (_, rest) = cstring(body);
+    let (statement, rest) = cstring(rest);
+    let formats = i16::from_be_bytes([rest[0], rest[1]]) as usize;
+    let mut rest = &rest[2 + formats * 2..];
+    let n = i16::from_be_bytes([rest[0], rest[1]]) as usize;
+    rest = &rest[2..];
+    let mut params = Vec::with_capacity(n);
+    for _ in 0..n {
+        let len = i32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]);
+        rest = &rest[4..];
+        if len < 0 {
+            params.push(None);
+        } else {
+            params.push(Some(rest[..len as usize].to_vec()));
+            rest = &rest[len as usize..];
+        }
+    }
+    (statement, params)
+}
+
+fn cstring(body: &[u8]) -> (String, &[u8]) {
+    let end = body.iter().position(|b| *b == 0).unwrap();
+    (
+        String::from_utf8_lossy(&body[..end]).to_string(),
+        &body[end + 1..],
+    )
+}
@@ -1,19 +1,19 @@
 use crate::driver::PgDriver;
 use crate::message::{
-    Close, Message, MessageFormat, Query, ReadyForQuery, Terminate, TransactionStatus,
+    Close, Message, MessageFormat, Notice, Query, ReadyForQuery, Terminate, TransactionStatus,
 };
 use crate::query::PgQuery;
 use crate::query_result::PgQueryResult;
 use crate::row::PgRow;
 use crate::statement::PgStatementMetadata;
 use crate::type_info::PgTypeInfo;
-use crate::types::{Oid, TypeInfo};
+use crate::types::Oid;
 use either::Either;
 use futures_core::future::BoxFuture;
 use futures_core::stream::BoxStream;
 use futures_util::{FutureExt, StreamExt, TryFutureExt, TryStreamExt};
 use rbdc::common::StatementCache;
-use rbdc::db::{Connection, ExecResult, Placeholder, Row};
+use rbdc::db::{Connection, Describe, ExecResult, Placeholder, Row, TwoPhase};
 use rbdc::ext::ustr::UStr;
 use rbdc::io::Decode;
 use rbdc::Error;
@@ -22,14 +22,25 @@ use std::collections::HashMap;
 use std::fmt::{self, Debug, Formatter};
 use std::sync::Arc;
 
+pub use self::advisory_lock::{
+    PgAdvisoryLock, PgAdvisoryLockGuard, PgAdvisoryLockKey, PgAdvisoryLockScope,
+};
+pub use self::large_object::{PgLargeObject, PgLargeObjectIo, PgLargeObjectMode};
+pub use self::portal::PgPortal;
 pub use self::stream::PgStream;
 
+mod advisory_lock;
 pub(crate) mod describe;
 mod establish;
 mod executor;
+mod large_object;
+#[cfg(test)]
+pub(crate) mod mock;
+mod portal;
 mod sasl;
 mod stream;
 mod tls;
+mod two_phase;
 
 /// A connection to a PostgreSQL database.
 pub struct PgConnection {
@@ -52,6 +63,9 @@ pub struct PgConnection {
     // in PostgreSQL, the statement is prepared to a user-supplied identifier
     next_statement_id: Oid,
 
+    // sequence of portal IDs for named portals opened with `open_portal`
+    next_portal_id: Oid,
+
     // cache statement by query string to the id and columns
     cache_statement: StatementCache<(Oid, Arc<PgStatementMetadata>)>,
 
@@ -64,6 +78,9 @@ pub struct PgConnection {
 
     // current transaction status
     transaction_status: TransactionStatus,
+
+    // the gid of the open transaction begun by `begin_two_phase`, until it ends
+    two_phase_gid: Option<String>,
 }
 
 impl PgConnection {
@@ -72,6 +89,18 @@ impl PgConnection {
         self.stream.server_version_num
     }
 
+    /// The current value of a server parameter reported by the server, such as `TimeZone`,
+    /// `DateStyle`, `client_encoding` or `server_version`.
+    pub fn server_parameter(&self, name: &str) -> Option<&str> {
+        self.stream.parameter_statuses.get(name).map(|v| v.as_str())
+    }
+
+    /// The notices sent while running the most recent query, kept when the connection was
+    /// opened with [`PgNoticeHandler::Collect`](crate::options::PgNoticeHandler::Collect).
+    pub fn notices(&self) -> &[Notice] {
+        &self.stream.notices
+    }
+
     // will return when the connection is ready for another query
     pub async fn wait_until_ready(&mut self) -> Result<(), Error> {
         if !self.stream.wbuf.is_empty() {
@@ -96,23 +125,31 @@ impl PgConnection {
             .await?;
 
         self.pending_ready_for_query_count -= 1;
-        self.transaction_status = r.transaction_status;
+        self.set_transaction_status(r.transaction_status);
 
         Ok(())
     }
 
     fn handle_ready_for_query(&mut self, message: Message) -> Result<(), Error> {
         self.pending_ready_for_query_count -= 1;
-        self.transaction_status = ReadyForQuery::decode(message.contents)?.transaction_status;
+        self.set_transaction_status(ReadyForQuery::decode(message.contents)?.transaction_status);
 
         Ok(())
     }
 
+    fn set_transaction_status(&mut self, status: TransactionStatus) {
+        if matches!(status, TransactionStatus::Idle) {
+            self.two_phase_gid = None;
+        }
+        self.transaction_status = status;
+    }
+
     /// Queue a simple query (not prepared) to execute the next time this connection is used.
     ///
     /// Used for rolling back transactions and releasing advisory locks.
     pub(crate) fn queue_simple_query(&mut self, query: &str) {
         self.pending_ready_for_query_count += 1;
+        self.stream.set_query(query);
         self.stream.write(Query(query));
     }
 }
@@ -179,6 +216,15 @@ impl Connection for PgConnection {
         Box::pin(async { self.do_close().await })
     }
 
+    fn two_phase(&mut self) -> Option<&mut dyn TwoPhase> {
+        Some(self)
+    }
+
+    fn describe(&mut self, sql: &str) -> BoxFuture<'_, Result<Describe, Error>> {
+        let sql = sql.to_owned();
+        Box::pin(async move { self.describe_statement(&sql).await })
+    }
+
     fn ping(&mut self) -> BoxFuture<'_, Result<(), Error>> {
         // By sending a comment we avoid an error if the connection was in the middle of a rowset
         self.exec("/* RBDC ping */", vec![]).map_ok(|_| ()).boxed()
@@ -199,11 +245,7 @@ impl Connection for PgConnection {
                         persistent: false,
                     })
                 } else {
-                    let mut types = Vec::with_capacity(params.len());
-                    for x in &params {
-                        types.push(x.type_info());
-                    }
-                    let stmt = self.prepare_with(sql, &types).await?;
+                    let stmt = self.prepare_values(sql, &params).await?;
                     self.fetch_many(PgQuery {
                         statement: Either::Right(stmt),
                         arguments: params,
@@ -240,11 +282,7 @@ impl Connection for PgConnection {
                         persistent: false,
                     })
                 } else {
-                    let mut type_info = Vec::with_capacity(params.len());
-                    for x in &params {
-                        type_info.push(x.type_info());
-                    }
-                    let stmt = self.prepare_with(sql, &type_info).await?;
+                    let stmt = self.prepare_values(sql, &params).await?;
                     self.fetch_many(PgQuery {
                         statement: Either::Right(stmt),
                         arguments: params,
@@ -0,0 +1,301 @@
+use crate::arguments::PgArguments;
+use crate::connection::PgConnection;
+use crate::driver::PgDriver;
+use crate::message::{Bind, Close, DataRow, Execute, Flush, MessageFormat};
+use crate::row::PgRow;
+use crate::statement::PgStatementMetadata;
+use crate::types::Oid;
+use crate::value::PgValueFormat;
+use rbdc::db::Placeholder;
+use rbdc::io::Nothing;
+use rbdc::{err_protocol, Error};
+use rbs::Value;
+use std::sync::Arc;
+
+/// A named portal (server-side cursor) that returns the rows of a query a batch at a time,
+/// see [`PgConnection::open_portal`].
+///
+/// The portal lives in the current transaction. Outside of an explicit transaction the
+/// implicit one stays open until the portal is closed, so keep portals short-lived there.
+/// A portal that is dropped without [`close`](Self::close) is closed the next time the
+/// connection is used.
+pub struct PgPortal<'c> {
+    conn: &'c mut PgConnection,
+    id: Oid,
+    // the query, attached to the errors of each fetch
+    sql: String,
+    metadata: Arc<PgStatementMetadata>,
+    // all rows were returned
+    done: bool,
+    // the portal was closed (or destroyed by an error) and the Sync is queued
+    closed: bool,
+}
+
+impl PgConnection {
+    /// Binds `sql` to a new named portal without fetching any rows. The statement is
+    /// prepared (and cached) like any other query.
+    pub async fn open_portal(
+        &mut self,
+        sql: &str,
+        params: Vec<Value>,
+    ) -> Result<PgPortal<'_>, Error> {
+        let sql = PgDriver {}.exchange(sql);
+        self.wait_until_ready().await?;
+
+        let (statement, metadata) = self
+            .get_or_prepare_values(&sql, &params, true, None)
+            .await?;
+
+        let mut arguments = PgArguments::from_args_described(params, &metadata.parameters)?;
+        arguments.apply_patches(self, &metadata.parameters).await?;
+        self.wait_until_ready().await?;
+
+        let id = self.next_portal_id;
+        self.next_portal_id.incr_one();
+
+        self.stream.set_query(&sql);
+        self.stream.write(Bind {
+            portal: Some(id),
+            statement,
+            formats: &[PgValueFormat::Binary],
+            num_params: arguments.types.len() as i16,
+            params: &arguments.buffer,
+            result_formats: &[PgValueFormat::Binary],
+        });
+        // a Flush instead of a Sync keeps the (implicit) transaction and so the portal open
+        self.stream.write(Flush);
+        self.stream.flush().await?;
+
+        let mut portal = PgPortal {
+            conn: self,
+            id,
+            sql,
+            metadata,
+            done: false,
+            closed: false,
+        };
+        let bound: Result<Nothing, Error> = portal
+            .conn
+            .stream
+            .recv_expect(MessageFormat::BindComplete)
+            .await;
+        if let Err(e) = bound {
+            portal.abort();
+            return Err(e);
+        }
+        Ok(portal)
+    }
+}
+
+impl PgPortal<'_> {
+    /// Fetches up to `rows` rows (all remaining rows when `0`). Returns fewer rows only once
+    /// the portal is exhausted, after which every fetch is empty.
+    pub async fn fetch(&mut self, rows: u32) -> Result<Vec<PgRow>, Error> {
+        if self.done || self.closed {
+            return Ok(vec![]);
+        }
+
+        self.conn.stream.set_query(&self.sql);
+        self.conn.stream.write(Execute {
+            portal: Some(self.id),
+            limit: rows,
+        });
+        self.conn.stream.write(Flush);
+        if let Err(e) = self.conn.stream.flush().await {
+            self.abort();
+            return Err(e);
+        }
+
+        // the limit is only an upper bound, the portal may hold far fewer rows
+        let mut data = Vec::with_capacity(rows.min(1024) as usize);
+        loop {
+            let message = match self.conn.stream.recv().await {
+                Ok(message) => message,
+                Err(e) => {
+                    self.abort();
+                    return Err(e);
+                }
+            };
+            match message.format {
+                MessageFormat::DataRow => {
+                    let row: DataRow = match message.decode() {
+                        Ok(row) => row,
+                        Err(e) => {
+                            self.abort();
+                            return Err(e);
+                        }
+                    };
+                    data.push(PgRow {
+                        data: row,
+                        format: PgValueFormat::Binary,
+                        metadata: Arc::clone(&self.metadata),
+                        timezone: self.conn.stream.timezone.clone(),
+                    });
+                }
+
+                // the limit was reached, the next Execute resumes from here
+                MessageFormat::PortalSuspended => break,
+
+                MessageFormat::CommandComplete | MessageFormat::EmptyQueryResponse => {
+                    self.done = true;
+                    break;
+                }
+
+                _ => {
+                    self.abort();
+                    return Err(err_protocol!(
+                        "portal: unexpected message: {:?}",
+                        message.format
+                    ));
+                }
+            }
+        }
+        Ok(data)
+    }
+
+    /// Whether all rows were fetched.
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// The columns of the rows returned by the portal.
+    pub fn metadata(&self) -> &Arc<PgStatementMetadata> {
+        &self.metadata
+    }
+
+    /// Closes the portal, freeing its resources on the server.
+    pub async fn close(mut self) -> Result<(), Error> {
+        self.abort();
+        self.conn.wait_until_ready().await
+    }
+
+    // closes the portal and queues the Sync that ends the exchange. After an error the server
+    // ignores everything until that Sync, which also ends the implicit transaction and with it
+    // the portal; an error of our own (a row that does not decode) leaves the portal open
+    // until the Close
+    fn abort(&mut self) {
+        if !self.closed {
+            self.closed = true;
+            self.conn.stream.write(Close::Portal(self.id));
+            self.conn.write_sync();
+        }
+    }
+}
+
+impl Drop for PgPortal<'_> {
+    fn drop(&mut self) {
+        // queued, sent when the connection is used next
+        self.abort();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::connection::mock;
+    use crate::type_info::PgTypeInfo;
+    use rbdc::db::{Connection, Row};
+    use rbs::Value;
+
+    #[tokio::test]
+    async fn test_fetch_in_batches() {
+        let mut next = 1_i32;
+        let (mut conn, server) = mock::connect(move |tag, body| match tag {
+            b'P' => mock::backend(b'1', &[]),
+            b'D' => {
+                let mut out = mock::parameter_description(&[]);
+                out.extend(mock::row_description(&[("n", PgTypeInfo::INT4.0.oid().0)]));
+                out
+            }
+            b'B' => mock::backend(b'2', &[]),
+            // five rows, at most `limit` at a time
+            b'E' => {
+                let limit = i32::from_be_bytes(body[body.len() - 4..].try_into().unwrap());
+                let mut out = vec![];
+                while next <= 5 && (limit == 0 || out.len() < limit as usize) {
+                    out.push(mock::data_row(&[&next.to_be_bytes()]));
+                    next += 1;
+                }
+                let mut out = out.concat();
+                if next <= 5 {
+                    out.extend(mock::backend(b's', &[]));
+                } else {
+                    out.extend(mock::command_complete("SELECT 5"));
+                }
+                out
+            }
+            b'C' => mock::backend(b'3', &[]),
+            b'S' => mock::ready(b'I'),
+            _ => vec![],
+        })
+        .await;
+
+        let mut portal = conn.open_portal("SELECT n FROM t", vec![]).await.unwrap();
+        let mut batches = vec![];
+        loop {
+            let rows = portal.fetch(2).await.unwrap();
+            if rows.is_empty() {
+                break;
+            }
+            let mut batch = vec![];
+            for mut row in rows {
+                batch.push(row.get(0).unwrap());
+            }
+            batches.push(batch);
+        }
+        assert!(portal.is_done());
+        portal.close().await.unwrap();
+        // the connection is usable again
+        conn.wait_until_ready().await.unwrap();
+        drop(conn);
+
+        assert_eq!(
+            batches,
+            [
+                vec![Value::I32(1), Value::I32(2)],
+                vec![Value::I32(3), Value::I32(4)],
+                vec![Value::I32(5)],
+            ]
+        );
+        let tags: Vec<u8> = server
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(tag, _)| tag)
+            .collect();
+        assert_eq!(tags, b"PDSBHEHEHEHCS");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_error_shows_query() {
+        let (mut conn, _server) = mock::connect(|tag, _| match tag {
+            b'P' => mock::backend(b'1', &[]),
+            b'D' => {
+                let mut out = mock::parameter_description(&[]);
+                out.extend(mock::row_description(&[("n", PgTypeInfo::INT4.0.oid().0)]));
+                out
+            }
+            b'B' => mock::backend(b'2', &[]),
+            b'E' => mock::error_at("22012", "division by zero", 10),
+            b'C' => mock::backend(b'3', &[]),
+            b'Q' => [mock::command_complete("SELECT 1"), mock::ready(b'I')].concat(),
+            b'S' => mock::ready(b'I'),
+            _ => vec![],
+        })
+        .await;
+
+        // the statement is cached, another query runs before it is bound again
+        let portal = conn
+            .open_portal("SELECT n / 0 FROM t", vec![])
+            .await
+            .unwrap();
+        portal.close().await.unwrap();
+        conn.exec("SELECT 1", vec![]).await.unwrap();
+        let mut portal = conn
+            .open_portal("SELECT n / 0 FROM t", vec![])
+            .await
+            .unwrap();
+        let err = portal.fetch(2).await.unwrap_err().to_string();
+        assert!(err.starts_with("22012:division by zero"), "{}", err);
+        assert!(err.contains("LINE 1: SELECT n / 0 FROM t"), "{}", err);
+    }
+}
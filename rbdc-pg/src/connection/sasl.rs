@@ -2,26 +2,34 @@ use crate::connection::stream::PgStream;
 use crate::message::{
     Authentication, AuthenticationSasl, MessageFormat, SaslInitialResponse, SaslResponse,
 };
-use crate::options::PgConnectOptions;
+use crate::options::{PgChannelBinding, PgConnectOptions};
 use base64::engine::general_purpose::STANDARD;
 use base64::Engine;
 use hmac::{Hmac, Mac};
 use rand::Rng;
 use rbdc::{err_protocol, Error};
-use sha2::{Digest, Sha256};
+use sha2::{Digest, Sha256, Sha384, Sha512};
 use stringprep::saslprep;
 
+// gs2-cbind-flag "n": the client does not support channel binding
 const GS2_HEADER: &str = "n,,";
+// gs2-cbind-flag "y": the client supports channel binding but thinks the server does not
+const GS2_HEADER_CB_SUPPORTED: &str = "y,,";
+// gs2-cbind-flag "p": the client requires channel binding
+const GS2_HEADER_TLS_SERVER_END_POINT: &str = "p=tls-server-end-point,,";
 const CHANNEL_ATTR: &str = "c";
 const USERNAME_ATTR: &str = "n";
 const CLIENT_PROOF_ATTR: &str = "p";
 const NONCE_ATTR: &str = "r";
 
+/// Performs SCRAM-SHA-256(-PLUS) authentication.
+///
+/// Returns `true` if the exchange was bound to the TLS channel.
 pub(crate) async fn authenticate(
     stream: &mut PgStream,
     options: &PgConnectOptions,
     data: AuthenticationSasl,
-) -> Result<(), Error> {
+) -> Result<bool, Error> {
     let mut has_sasl = false;
     let mut has_sasl_plus = false;
     let mut unknown = Vec::with_capacity(10);
@@ -49,8 +57,44 @@ pub(crate) async fn authenticate(
         ));
     }
 
-    // channel-binding = "c=" base64
-    let channel_binding = format!("{}={}", CHANNEL_ATTR, STANDARD.encode(GS2_HEADER));
+    let server_cert = stream.stream.peer_certificate();
+
+    let plus = match options.channel_binding {
+        PgChannelBinding::Disable => false,
+        PgChannelBinding::Prefer => has_sasl_plus && server_cert.is_some(),
+        PgChannelBinding::Require => {
+            if server_cert.is_none() {
+                return Err(Error::from(
+                    "channel binding required, but SSL is not in use",
+                ));
+            }
+            if !has_sasl_plus {
+                return Err(Error::from(
+                    "channel binding is required, but server did not offer an authentication method that supports channel binding",
+                ));
+            }
+            true
+        }
+    };
+
+    if !plus && !has_sasl {
+        return Err(err_protocol!(
+            "server only offers SCRAM-SHA-256-PLUS, but channel binding is disabled"
+        ));
+    }
+
+    let (gs2_header, cb_data) = match server_cert {
+        Some(ref cert) if plus => (GS2_HEADER_TLS_SERVER_END_POINT, tls_server_end_point(cert)?),
+        Some(_) if options.channel_binding != PgChannelBinding::Disable => {
+            (GS2_HEADER_CB_SUPPORTED, Vec::new())
+        }
+        _ => (GS2_HEADER, Vec::new()),
+    };
+
+    // channel-binding = "c=" base64(gs2-header [cbind-data])
+    let mut cbind_input = gs2_header.as_bytes().to_vec();
+    cbind_input.extend_from_slice(&cb_data);
+    let channel_binding = format!("{}={}", CHANNEL_ATTR, STANDARD.encode(&cbind_input));
 
     // "n=" saslname ;; Usernames are prepared using SASLprep.
     let username = format!("{}={}", USERNAME_ATTR, options.username);
@@ -69,14 +113,14 @@ pub(crate) async fn authenticate(
 
     let client_first_message = format!(
         "{gs2_header}{client_first_message_bare}",
-        gs2_header = GS2_HEADER,
+        gs2_header = gs2_header,
         client_first_message_bare = client_first_message_bare
     );
 
     stream
         .send(SaslInitialResponse {
             response: &client_first_message,
-            plus: false,
+            plus,
         })
         .await?;
 
@@ -167,7 +211,88 @@ pub(crate) async fn authenticate(
     // authentication is only considered valid if this verification passes
     mac.verify_slice(&data.verifier).map_err(Error::protocol)?;
 
-    Ok(())
+    Ok(plus)
+}
+
+// https://www.rfc-editor.org/rfc/rfc5929#section-4.1
+// the hash of the server certificate, using the hash function of the certificate's
+// signature algorithm; MD5 and SHA-1 are replaced with SHA-256
+fn tls_server_end_point(cert: &[u8]) -> Result<Vec<u8>, Error> {
+    // sha384WithRSAEncryption, ecdsa-with-SHA384
+    const SHA384: [&[u8]; 2] = [
+        &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0c],
+        &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x03],
+    ];
+    // sha512WithRSAEncryption, ecdsa-with-SHA512
+    const SHA512: [&[u8]; 2] = [
+        &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0d],
+        &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x04],
+    ];
+
+    let oid = signature_algorithm(cert)
+        .ok_or_else(|| err_protocol!("could not parse server certificate"))?;
+
+    Ok(if SHA384.contains(&oid) {
+        Sha384::digest(cert).to_vec()
+    } else if SHA512.contains(&oid) {
+        Sha512::digest(cert).to_vec()
+    } else {
+        Sha256::digest(cert).to_vec()
+    })
+}
+
+// Certificate ::= SEQUENCE {
+//     tbsCertificate       TBSCertificate,
+//     signatureAlgorithm   AlgorithmIdentifier,
+//     signatureValue       BIT STRING }
+//
+// AlgorithmIdentifier ::= SEQUENCE {
+//     algorithm            OBJECT IDENTIFIER,
+//     parameters           ANY DEFINED BY algorithm OPTIONAL }
+fn signature_algorithm(cert: &[u8]) -> Option<&[u8]> {
+    const SEQUENCE: u8 = 0x30;
+    const OBJECT_IDENTIFIER: u8 = 0x06;
+
+    let (SEQUENCE, cert, _) = der_read(cert)? else {
+        return None;
+    };
+    let (SEQUENCE, _, rest) = der_read(cert)? else {
+        return None;
+    };
+    let (SEQUENCE, algorithm, _) = der_read(rest)? else {
+        return None;
+    };
+    let (OBJECT_IDENTIFIER, oid, _) = der_read(algorithm)? else {
+        return None;
+    };
+
+    Some(oid)
+}
+
+// reads a single DER element, returning its tag, contents and the remaining input
+fn der_read(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = input.split_first()?;
+    let (&first, mut rest) = rest.split_first()?;
+
+    let len = if first & 0x80 == 0 {
+        first as usize
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > 4 || rest.len() < count {
+            return None;
+        }
+        let len = rest[..count]
+            .iter()
+            .fold(0usize, |len, &b| (len << 8) | b as usize);
+        rest = &rest[count..];
+        len
+    };
+
+    if rest.len() < len {
+        return None;
+    }
+
+    Some((tag, &rest[..len], &rest[len..]))
 }
 
 // nonce is a sequence of random printable bytes
@@ -216,3 +341,47 @@ fn hi<'a>(s: &'a str, salt: &'a [u8], iter_count: u32) -> Result<[u8; 32], Error
 
     Ok(hi.into())
 }
+
+#[cfg(test)]
+mod test {
+    use super::{signature_algorithm, tls_server_end_point};
+
+    // minimal certificate skeleton: SEQUENCE { SEQUENCE {}, SEQUENCE { OID }, BIT STRING }
+    fn cert_with_algorithm(oid: &[u8]) -> Vec<u8> {
+        let mut algorithm = vec![0x06, oid.len() as u8];
+        algorithm.extend_from_slice(oid);
+        algorithm.extend_from_slice(&[0x05, 0x00]);
+
+        let mut body = vec![0x30, 0x00, 0x30, algorithm.len() as u8];
+        body.extend_from_slice(&algorithm);
+        body.extend_from_slice(&[0x03, 0x01, 0x00]);
+
+        let mut cert = vec![0x30, body.len() as u8];
+        cert.extend_from_slice(&body);
+        cert
+    }
+
+    #[test]
+    fn it_reads_signature_algorithm() {
+        let oid = [0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b];
+        let cert = cert_with_algorithm(&oid);
+
+        assert_eq!(signature_algorithm(&cert), Some(&oid[..]));
+        assert_eq!(signature_algorithm(&cert[..cert.len() - 1]), None);
+    }
+
+    #[test]
+    fn it_hashes_with_certificate_digest() {
+        // sha256WithRSAEncryption
+        let cert = cert_with_algorithm(&[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b]);
+        assert_eq!(tls_server_end_point(&cert).unwrap().len(), 32);
+
+        // ecdsa-with-SHA384
+        let cert = cert_with_algorithm(&[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x03]);
+        assert_eq!(tls_server_end_point(&cert).unwrap().len(), 48);
+
+        // sha512WithRSAEncryption
+        let cert = cert_with_algorithm(&[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0d]);
+        assert_eq!(tls_server_end_point(&cert).unwrap().len(), 64);
+    }
+}
@@ -9,7 +9,8 @@ use log::Level;
 use rbdc::err_protocol;
 
 use crate::message::*;
-use crate::options::PgConnectOptions;
+use crate::options::{PgConnectOptions, PgNoticeHandler};
+use crate::types::timezone::PgTimeZone;
 use rbdc::error::Error;
 use rbdc::io::{BufStream, Decode, Encode};
 use rbdc::net::{MaybeTlsStream, Socket};
@@ -34,6 +35,23 @@ pub struct PgStream {
     pub(crate) parameter_statuses: BTreeMap<String, String>,
 
     pub(crate) server_version_num: Option<u32>,
+
+    // the session `TimeZone`, `None` while it is UTC or could not be resolved
+    pub(crate) timezone: Option<PgTimeZone>,
+
+    pub(crate) notice_handler: PgNoticeHandler,
+
+    // notices of the most recent query, kept with `PgNoticeHandler::Collect`
+    pub(crate) notices: Vec<Notice>,
+
+    // the SQL of the statement in flight, to show the position of an error in it
+    pub(crate) query: String,
+
+    // the most recent error returned by the server, to tell errors apart by their SQLSTATE
+    pub(crate) last_error: Option<PgDatabaseError>,
+
+    // why the connection was closed, e.g. a session setting it cannot be read with
+    pub(crate) broken: Option<String>,
 }
 
 impl PgStream {
@@ -50,9 +68,21 @@ impl PgStream {
             notifications: None,
             parameter_statuses: BTreeMap::default(),
             server_version_num: None,
+            timezone: None,
+            notice_handler: options.notice_handler.clone(),
+            notices: Vec::new(),
+            query: String::new(),
+            last_error: None,
+            broken: None,
         })
     }
 
+    /// remembers `sql` as the statement in flight
+    pub(crate) fn set_query(&mut self, sql: &str) {
+        self.query.clear();
+        self.query.push_str(sql);
+    }
+
     pub(crate) async fn send<'en, T>(&mut self, message: T) -> Result<(), Error>
     where
         T: Encode<'en>,
@@ -80,6 +110,10 @@ impl PgStream {
     }
 
     pub(crate) async fn recv_unchecked(&mut self) -> Result<Message, Error> {
+        if let Some(reason) = &self.broken {
+            return Err(Error::from(format!("connection is closed: {}", reason)));
+        }
+
         // all packets in postgres start with a 5-byte header
         // this header contains the message type and the total length of the message
         let mut header: Bytes = self.inner.read(5).await?;
@@ -101,8 +135,11 @@ impl PgStream {
             match message.format {
                 MessageFormat::ErrorResponse => {
                     // An error returned from the database server.
-                    let notice = message.decode::<Notice>()?;
-                    return Err(PgDatabaseError::from(notice).into());
+                    let error = PgDatabaseError::from(message.decode::<Notice>()?);
+                    let sql = Some(&*self.query).filter(|sql| !sql.is_empty());
+                    let rendered = error.render(sql);
+                    self.last_error = Some(error);
+                    return Err(Error::from(rendered));
                 }
 
                 MessageFormat::NotificationResponse => {
@@ -119,46 +156,26 @@ impl PgStream {
                     // setting of backend parameters
 
                     let ParameterStatus { name, value } = message.decode()?;
-                    // TODO: support handle `client_encoding`, `DateStyle` change
-
-                    match name.as_str() {
-                        "server_version" => {
-                            self.server_version_num = parse_server_version(&value);
-                        }
-                        _ => {
-                            self.parameter_statuses.insert(name, value);
-                        }
+                    let handled = self.handle_parameter_status(&name, &value);
+                    self.parameter_statuses.insert(name, value);
+                    if let Err(e) = handled {
+                        // the session has changed under the connection, it is not usable anymore
+                        self.broken = Some(e.to_string());
+                        let _ = self.inner.shutdown().await;
+                        return Err(e);
                     }
 
                     continue;
                 }
 
                 MessageFormat::NoticeResponse => {
-                    // do we need this to be more configurable?
-                    // if you are reading this comment and think so, open an issue
-
                     let notice: Notice = message.decode()?;
 
-                    let lvl = match notice.severity() {
-                        PgSeverity::Fatal | PgSeverity::Panic | PgSeverity::Error => Level::Error,
-                        PgSeverity::Warning => Level::Warn,
-                        PgSeverity::Notice => Level::Info,
-                        PgSeverity::Debug => Level::Debug,
-                        PgSeverity::Info => Level::Trace,
-                        PgSeverity::Log => Level::Trace,
-                    };
-
-                    if log::log_enabled!(log::Level::Debug) {
-                        log::logger().log(
-                            &log::Record::builder()
-                                .args(format_args!("{}", notice.message()))
-                                .level(lvl)
-                                .module_path_static(Some("postgres::notice"))
-                                .target("postgres::notice")
-                                .file_static(Some(file!()))
-                                .line(Some(line!()))
-                                .build(),
-                        );
+                    match &self.notice_handler {
+                        PgNoticeHandler::Log => log_notice(&notice),
+                        PgNoticeHandler::Callback(f) => f(&notice),
+                        PgNoticeHandler::Collect => self.notices.push(notice),
+                        PgNoticeHandler::Ignore => {}
                     }
 
                     continue;
@@ -172,6 +189,65 @@ impl PgStream {
     }
 }
 
+impl PgStream {
+    fn handle_parameter_status(&mut self, name: &str, value: &str) -> Result<(), Error> {
+        match name {
+            "server_version" => {
+                self.server_version_num = parse_server_version(value);
+            }
+            // text values are always read as UTF-8
+            "client_encoding"
+                if !value.eq_ignore_ascii_case("UTF8")
+                    && !value.eq_ignore_ascii_case("UNICODE") =>
+            {
+                return Err(Error::from(format!(
+                    "unsupported client_encoding {}, only UTF8 is supported",
+                    value
+                )));
+            }
+            "TimeZone" => {
+                self.timezone = match PgTimeZone::parse(value) {
+                    Some(PgTimeZone::Fixed(0)) => None,
+                    Some(tz) => Some(tz),
+                    None => {
+                        log::warn!(
+                            "unknown TimeZone {}, timestamptz values are decoded as UTC",
+                            value
+                        );
+                        None
+                    }
+                };
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn log_notice(notice: &Notice) {
+    let lvl = match notice.severity() {
+        PgSeverity::Fatal | PgSeverity::Panic | PgSeverity::Error => Level::Error,
+        PgSeverity::Warning => Level::Warn,
+        PgSeverity::Notice => Level::Info,
+        PgSeverity::Debug => Level::Debug,
+        PgSeverity::Info => Level::Trace,
+        PgSeverity::Log => Level::Trace,
+    };
+
+    if log::log_enabled!(target: "postgres::notice", lvl) {
+        log::logger().log(
+            &log::Record::builder()
+                .args(format_args!("{}", notice.message()))
+                .level(lvl)
+                .module_path_static(Some("postgres::notice"))
+                .target("postgres::notice")
+                .file_static(Some(file!()))
+                .line(Some(line!()))
+                .build(),
+        );
+    }
+}
+
 impl Deref for PgStream {
     type Target = BufStream<MaybeTlsStream<Socket>>;
 
@@ -236,6 +312,36 @@ fn parse_server_version(s: &str) -> Option<u32> {
 #[cfg(test)]
 mod tests {
     use super::parse_server_version;
+    use crate::connection::mock;
+    use rbdc::db::Connection;
+
+    #[tokio::test]
+    async fn test_client_encoding_closes_connection() {
+        let (mut conn, _server) = mock::connect(|tag, _| match tag {
+            b'Q' => [
+                mock::backend(b'S', b"client_encoding\0LATIN1\0"),
+                mock::command_complete("SET"),
+                mock::ready(b'I'),
+            ]
+            .concat(),
+            _ => vec![],
+        })
+        .await;
+
+        let e = conn
+            .exec("SET client_encoding = 'LATIN1'", vec![])
+            .await
+            .unwrap_err();
+        assert!(
+            e.to_string().contains("unsupported client_encoding LATIN1"),
+            "{}",
+            e
+        );
+        // the session really is in LATIN1, and is not read as UTF-8 anymore
+        assert_eq!(conn.server_parameter("client_encoding"), Some("LATIN1"));
+        let e = conn.exec("SELECT 1", vec![]).await.unwrap_err();
+        assert!(e.to_string().starts_with("connection is closed"), "{}", e);
+    }
 
     #[test]
     fn test_parse_server_version_num() {
@@ -0,0 +1,153 @@
+use crate::connection::PgConnection;
+use crate::message::TransactionStatus;
+use futures_core::future::BoxFuture;
+use rbdc::db::{Connection, TwoPhase};
+use rbdc::Error;
+
+// https://www.postgresql.org/docs/current/sql-prepare-transaction.html
+
+impl TwoPhase for PgConnection {
+    fn begin_two_phase(&mut self, gid: &str) -> BoxFuture<'_, Result<(), Error>> {
+        // the gid is only given to PREPARE TRANSACTION
+        let gid = gid.to_string();
+        Box::pin(async move {
+            self.begin().await?;
+            self.two_phase_gid = Some(gid);
+            Ok(())
+        })
+    }
+
+    fn prepare(&mut self, gid: &str) -> BoxFuture<'_, Result<(), Error>> {
+        let sql = gid_literal(gid).map(|gid| format!("PREPARE TRANSACTION {}", gid));
+        Box::pin(async move {
+            self.exec(&sql?, vec![]).await?;
+            Ok(())
+        })
+    }
+
+    fn commit_prepared(&mut self, gid: &str) -> BoxFuture<'_, Result<(), Error>> {
+        let sql = gid_literal(gid).map(|gid| format!("COMMIT PREPARED {}", gid));
+        Box::pin(async move {
+            self.exec(&sql?, vec![]).await?;
+            Ok(())
+        })
+    }
+
+    fn rollback_prepared(&mut self, gid: &str) -> BoxFuture<'_, Result<(), Error>> {
+        let sql = gid_literal(gid).map(|gid| format!("ROLLBACK PREPARED {}", gid));
+        let gid = gid.to_string();
+        Box::pin(async move {
+            let sql = sql?;
+            self.wait_until_ready().await?;
+            if !matches!(self.transaction_status, TransactionStatus::Idle) {
+                // not prepared yet, the open transaction is the one to roll back
+                if self.two_phase_gid.as_deref() == Some(gid.as_str()) {
+                    return self.rollback().await;
+                }
+                // ROLLBACK PREPARED cannot run inside a transaction block
+                return Err(Error::from(match &self.two_phase_gid {
+                    Some(other) => format!(
+                        "cannot roll back prepared transaction {:?} inside the open transaction {:?}",
+                        gid, other
+                    ),
+                    None => format!(
+                        "cannot roll back prepared transaction {:?} inside an open transaction",
+                        gid
+                    ),
+                }));
+            }
+            self.exec(&sql, vec![]).await?;
+            Ok(())
+        })
+    }
+
+    fn recover(&mut self) -> BoxFuture<'_, Result<Vec<String>, Error>> {
+        Box::pin(async move {
+            let rows = self
+                .get_values(
+                    "SELECT gid FROM pg_catalog.pg_prepared_xacts WHERE database = current_database()",
+                    vec![],
+                )
+                .await?;
+            Ok(rows
+                .iter()
+                .filter_map(|row| row["gid"].as_str().map(|gid| gid.to_string()))
+                .collect())
+        })
+    }
+}
+
+/// `gid` as an escape string literal, which reads the same whatever
+/// `standard_conforming_strings` is. gids are at most 199 bytes
+fn gid_literal(gid: &str) -> Result<String, Error> {
+    if gid.is_empty() || gid.len() >= 200 {
+        return Err(Error::from(format!(
+            "transaction identifier must be 1 to 199 bytes long: {:?}",
+            gid
+        )));
+    }
+    if gid.contains('\0') {
+        return Err(Error::from(format!(
+            "transaction identifier must not contain NUL: {:?}",
+            gid
+        )));
+    }
+    Ok(format!(
+        "E'{}'",
+        gid.replace('\\', "\\\\").replace('\'', "''")
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::gid_literal;
+    use crate::connection::mock;
+    use rbdc::db::TwoPhase;
+
+    #[test]
+    fn test_gid_literal() {
+        assert_eq!(gid_literal("tx-1").unwrap(), "E'tx-1'");
+        assert_eq!(gid_literal("it's").unwrap(), "E'it''s'");
+        // a backslash can not escape the closing quote
+        assert_eq!(gid_literal("a\\'; --").unwrap(), "E'a\\\\''; --'");
+        assert!(gid_literal("a\0b").is_err());
+        assert!(gid_literal("").is_err());
+        assert!(gid_literal(&"x".repeat(200)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rollback_prepared_other_gid() {
+        let mut status = b'I';
+        let (mut conn, server) = mock::connect(move |tag, body| {
+            let sql = String::from_utf8_lossy(&body[..body.len() - 1]).to_string();
+            status = match (tag, sql.as_str()) {
+                (b'Q', "begin") => b'T',
+                (b'Q', "rollback") => b'I',
+                _ => status,
+            };
+            let mut out = mock::command_complete("OK");
+            out.extend(mock::ready(status));
+            out
+        })
+        .await;
+
+        conn.begin_two_phase("a").await.unwrap();
+        // the open transaction is not `b`
+        let err = conn.rollback_prepared("b").await.unwrap_err();
+        assert!(
+            err.to_string().contains("open transaction \"a\""),
+            "{}",
+            err
+        );
+        conn.rollback_prepared("a").await.unwrap();
+        drop(conn);
+
+        let queries: Vec<String> = server
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(_, body)| String::from_utf8_lossy(&body[..body.len() - 1]).to_string())
+            .collect();
+        assert_eq!(queries, ["begin", "rollback"]);
+    }
+}
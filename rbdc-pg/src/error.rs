@@ -111,6 +111,108 @@ impl PgDatabaseError {
     pub fn routine(&self) -> Option<&str> {
         self.0.get(b'R')
     }
+
+    /// The error as a multi-line report: the code and message, the line of the SQL at the
+    /// error position with a caret under it, then the detail, hint and the schema, table,
+    /// column and constraint the error is about.
+    ///
+    /// `sql` is the query that was sent, it is needed to show an
+    /// [`Original`](PgErrorPosition::Original) position.
+    pub fn render(&self, sql: Option<&str>) -> String {
+        let mut out = format!("{}:{}", self.code(), self.message());
+        match self.position() {
+            Some(PgErrorPosition::Original(position)) => {
+                if let Some(sql) = sql {
+                    render_position(&mut out, "LINE", sql, position);
+                }
+            }
+            Some(PgErrorPosition::Internal { position, query }) => {
+                render_position(&mut out, "QUERY", query, position);
+            }
+            None => {}
+        }
+        for (label, value) in [("DETAIL", self.detail()), ("HINT", self.hint())] {
+            if let Some(value) = value {
+                out.push_str(&format!("\n{}: {}", label, value));
+            }
+        }
+        let objects = [
+            ("SCHEMA", self.schema()),
+            ("TABLE", self.table()),
+            ("COLUMN", self.column()),
+            ("CONSTRAINT", self.constraint()),
+        ];
+        let objects: Vec<String> = objects
+            .iter()
+            .filter_map(|(label, value)| value.map(|value| format!("{}: {}", label, value)))
+            .collect();
+        if !objects.is_empty() {
+            out.push('\n');
+            out.push_str(&objects.join(", "));
+        }
+        out
+    }
+}
+
+// the longest excerpt of a line shown around the error position, in characters
+const EXCERPT_WIDTH: usize = 80;
+
+/// appends the line of `sql` holding `position` (in characters, starting at 1), cut to
+/// [`EXCERPT_WIDTH`] around it, and a caret under the position, like psql does:
+///
+/// ```text
+/// LINE 1: SELECT nme FROM users
+///                ^
+/// ```
+fn render_position(out: &mut String, label: &str, sql: &str, position: usize) {
+    let chars: Vec<char> = sql.chars().collect();
+    if position == 0 || position > chars.len() + 1 {
+        return;
+    }
+    let index = position - 1;
+
+    let start = chars[..index]
+        .iter()
+        .rposition(|c| *c == '\n')
+        .map_or(0, |i| i + 1);
+    let end = chars[index..]
+        .iter()
+        .position(|c| *c == '\n')
+        .map_or(chars.len(), |i| index + i);
+    let line_number = chars[..start].iter().filter(|c| **c == '\n').count() + 1;
+
+    // keep the position near the middle of an excerpt of a long line
+    let mut from = start;
+    let mut to = end;
+    if to - from > EXCERPT_WIDTH {
+        from = index.saturating_sub(EXCERPT_WIDTH / 2).max(start);
+        to = (from + EXCERPT_WIDTH).min(end);
+        from = to.saturating_sub(EXCERPT_WIDTH).max(start);
+    }
+
+    let mut prefix = if label == "LINE" {
+        format!("{} {}: ", label, line_number)
+    } else {
+        format!("{}: ", label)
+    };
+    if from > start {
+        prefix.push_str("...");
+    }
+    // tabs and the `\r` of `\r\n` would shift the caret
+    let excerpt: String = chars[from..to]
+        .iter()
+        .map(|c| if c.is_whitespace() { ' ' } else { *c })
+        .collect();
+
+    out.push('\n');
+    out.push_str(&prefix);
+    out.push_str(excerpt.trim_end());
+    if to < end {
+        out.push_str("...");
+    }
+    out.push('\n');
+    out.push_str(&" ".repeat(prefix.chars().count() + index - from));
+    out.push('^');
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -168,6 +270,60 @@ impl From<Notice> for PgDatabaseError {
 
 impl From<PgDatabaseError> for rbs::Error {
     fn from(err: PgDatabaseError) -> Self {
-        rbs::Error::from(format!("{}:{}",err.code(), err.message()))
+        rbs::Error::from(err.render(None))
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use super::PgDatabaseError;
+    use crate::message::Notice;
+    use bytes::Bytes;
+    use rbdc::io::Decode;
+
+    #[test]
+    fn test_render() {
+        const DATA: &[u8] = b"SERROR\0VERROR\0C42703\0Mcolumn \"nme\" does not exist\0HPerhaps you meant to reference the column \"users.name\".\0P29\0Fparse_relation.c\0L3722\0RerrorMissingColumn\0\0";
+        let err = PgDatabaseError::from(Notice::decode(Bytes::from_static(DATA)).unwrap());
+
+        let sql = "SELECT id\n\tFROM users\nWHERE nme = $1";
+        assert_eq!(
+            err.render(Some(sql)),
+            "42703:column \"nme\" does not exist
+LINE 3: WHERE nme = $1
+              ^
+HINT: Perhaps you meant to reference the column \"users.name\"."
+        );
+        assert_eq!(
+            err.render(None),
+            "42703:column \"nme\" does not exist
+HINT: Perhaps you meant to reference the column \"users.name\"."
+        );
+
+        // a long line is cut around the position
+        let sql = format!("SELECT {}nme FROM t", "a, ".repeat(40));
+        let data = format!(
+            "SERROR\0VERROR\0C42703\0Mcolumn \"nme\" does not exist\0P{}\0\0",
+            sql.find("nme").unwrap() + 1
+        );
+        let err = PgDatabaseError::from(Notice::decode(Bytes::from(data)).unwrap());
+        let rendered = err.render(Some(&sql));
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert!(lines[1].starts_with("LINE 1: ..."));
+        assert!(lines[1].ends_with("nme FROM t"));
+        assert_eq!(lines[2].find('^'), lines[1].find("nme"));
+    }
+
+    #[test]
+    fn test_render_objects() {
+        const DATA: &[u8] = b"SERROR\0VERROR\0C23505\0Mduplicate key value violates unique constraint \"users_email_key\"\0DKey (email)=(a@b.c) already exists.\0spublic\0tusers\0nusers_email_key\0\0";
+        let err = PgDatabaseError::from(Notice::decode(Bytes::from_static(DATA)).unwrap());
+
+        assert_eq!(
+            err.render(Some("INSERT INTO users (email) VALUES ($1)")),
+            "23505:duplicate key value violates unique constraint \"users_email_key\"
+DETAIL: Key (email)=(a@b.c) already exists.
+SCHEMA: public, TABLE: users, CONSTRAINT: users_email_key"
+        );
+    }
+}
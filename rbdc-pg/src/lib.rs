@@ -12,6 +12,7 @@ pub mod meta_data;
 pub mod options;
 pub mod query;
 pub mod query_result;
+pub mod replication;
 pub mod row;
 pub mod statement;
 pub mod type_info;
@@ -31,3 +31,13 @@ impl Encode<'_> for Close {
         })
     }
 }
+
+#[test]
+fn test_encode_close_portal() {
+    const EXPECTED: &[u8] = b"C\0\0\0\x0ePrbdc_p_5\0";
+
+    let mut buf = Vec::new();
+    Close::Portal(Oid(5)).encode(&mut buf);
+
+    assert_eq!(buf, EXPECTED);
+}
@@ -60,6 +60,7 @@ pub enum MessageFormat {
     BindComplete,
     CloseComplete,
     CommandComplete,
+    CopyBothResponse,
     CopyData,
     CopyDone,
     CopyInResponse,
@@ -107,6 +108,7 @@ impl MessageFormat {
             b'c' => MessageFormat::CopyDone,
             b'G' => MessageFormat::CopyInResponse,
             b'H' => MessageFormat::CopyOutResponse,
+            b'W' => MessageFormat::CopyBothResponse,
             b'D' => MessageFormat::DataRow,
             b'E' => MessageFormat::ErrorResponse,
             b'I' => MessageFormat::EmptyQueryResponse,
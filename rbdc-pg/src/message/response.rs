@@ -50,7 +50,7 @@ impl TryFrom<&str> for PgSeverity {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Notice {
     storage: Bytes,
     severity: PgSeverity,
@@ -74,6 +74,18 @@ impl Notice {
         self.get_cached_str(self.message)
     }
 
+    /// An optional secondary message carrying more detail.
+    #[inline]
+    pub fn detail(&self) -> Option<&str> {
+        self.get(b'D')
+    }
+
+    /// An optional suggestion what to do about the problem.
+    #[inline]
+    pub fn hint(&self) -> Option<&str> {
+        self.get(b'H')
+    }
+
     // Field descriptions available here:
     //  https://www.postgresql.org/docs/current/protocol-error-fields.html
 
@@ -0,0 +1,38 @@
+use rbdc::Error;
+use std::str::FromStr;
+
+/// Options for controlling the client's use of SCRAM channel binding.
+///
+/// It is used by the [`channel_binding`](super::PgConnectOptions::channel_binding) method.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum PgChannelBinding {
+    /// Never use channel binding.
+    Disable,
+
+    /// Use channel binding if it is available, that is, if the connection is encrypted
+    /// and the server offers `SCRAM-SHA-256-PLUS`.
+    #[default]
+    Prefer,
+
+    /// Fail the connection unless the server authenticates with channel binding.
+    Require,
+}
+
+impl FromStr for PgChannelBinding {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        Ok(match &*s.to_ascii_lowercase() {
+            "disable" => PgChannelBinding::Disable,
+            "prefer" => PgChannelBinding::Prefer,
+            "require" => PgChannelBinding::Require,
+
+            _ => {
+                return Err(Error::from(format!(
+                    "unknown value {:?} for `channel_binding`",
+                    s
+                )));
+            }
+        })
+    }
+}
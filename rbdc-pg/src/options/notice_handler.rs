@@ -0,0 +1,71 @@
+use crate::message::Notice;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+/// What to do with the notices (`RAISE NOTICE`, warnings, "... does not exist, skipping")
+/// the server sends while running a query.
+///
+/// It is used by the [`notice_handler`](super::PgConnectOptions::notice_handler) method.
+#[derive(Clone, Default)]
+pub enum PgNoticeHandler {
+    /// Forward notices to `log` with the target `postgres::notice`, at the level matching
+    /// their severity.
+    #[default]
+    Log,
+
+    /// Call a function with every notice.
+    Callback(Arc<dyn Fn(&Notice) + Send + Sync>),
+
+    /// Keep the notices of the most recent query, available from
+    /// [`PgQueryResult::notices`](crate::query_result::PgQueryResult::notices) and
+    /// [`PgConnection::notices`](crate::connection::PgConnection::notices).
+    Collect,
+
+    /// Drop notices.
+    Ignore,
+}
+
+impl Debug for PgNoticeHandler {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PgNoticeHandler::Log => f.write_str("Log"),
+            PgNoticeHandler::Callback(_) => f.write_str("Callback"),
+            PgNoticeHandler::Collect => f.write_str("Collect"),
+            PgNoticeHandler::Ignore => f.write_str("Ignore"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::message::Notice;
+    use crate::options::{PgConnectOptions, PgNoticeHandler};
+    use bytes::Bytes;
+    use rbdc::io::Decode;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_on_notice() {
+        let seen = Arc::new(Mutex::new(vec![]));
+        let seen_by_handler = seen.clone();
+        let options = PgConnectOptions::new_without_pgpass().on_notice(move |notice| {
+            seen_by_handler
+                .lock()
+                .unwrap()
+                .push(notice.message().to_string())
+        });
+        let notice = Notice::decode(Bytes::from_static(
+            b"SNOTICE\0VNOTICE\0C00000\0Mtable \"t\" does not exist, skipping\0Htry another\0\0",
+        ))
+        .unwrap();
+        assert_eq!(notice.hint(), Some("try another"));
+        match &options.notice_handler {
+            PgNoticeHandler::Callback(f) => f(&notice),
+            v => panic!("{:?}", v),
+        }
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec!["table \"t\" does not exist, skipping".to_string()]
+        );
+    }
+}
@@ -1,20 +1,38 @@
-use crate::options::PgConnectOptions;
+use crate::options::{service, PgConnectOptions};
 use percent_encoding::percent_decode_str;
 use rbdc::error::Error;
+use std::borrow::Cow;
+use std::env::var;
 use std::net::IpAddr;
 use std::num::ParseIntError;
 use std::str::FromStr;
+use std::time::Duration;
 use url::Url;
 
 impl FromStr for PgConnectOptions {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Error> {
+        // the url crate does not accept a comma separated host list, keep only the first host
+        // in the URL and add the rest afterwards
+        let (s, hosts) = split_host_list(s)?;
+
         let url: Url = s
             .parse()
             .map_err(|e: url::ParseError| Error::from(e.to_string()))?;
 
-        let mut options = Self::new_without_pgpass();
+        let mut options = Self::from_env();
+        let mut ports = None;
+
+        // the parameters of the service are defaults for the ones in the URI
+        let service = url
+            .query_pairs()
+            .find(|(key, _)| key == "service")
+            .map(|(_, value)| value.into_owned())
+            .or_else(|| var("PGSERVICE").ok());
+        if let Some(service) = service {
+            options = options.apply_service(&service)?;
+        }
 
         if let Some(host) = url.host_str() {
             let host_decoded = percent_decode_str(host);
@@ -28,10 +46,17 @@ impl FromStr for PgConnectOptions {
             }
         }
 
+        let default_port = options.port;
         if let Some(port) = url.port() {
             options = options.port(port);
         }
 
+        if hosts.len() > 1 {
+            // hosts without a port in the list use the default port
+            options.hosts = hosts;
+            options.port = default_port;
+        }
+
         let username = url.username();
         if !username.is_empty() {
             options = options.username(
@@ -55,88 +80,286 @@ impl FromStr for PgConnectOptions {
         }
 
         for (key, value) in url.query_pairs().into_iter() {
-            match &*key {
-                "sslmode" | "ssl-mode" => {
-                    options = options.ssl_mode(value.parse()?);
-                }
+            if key == "service" {
+                continue;
+            }
+            options = options.apply_parameter(&key, &value, &mut ports)?;
+        }
 
-                "sslrootcert" | "ssl-root-cert" | "ssl-ca" => {
-                    options = options.ssl_root_cert(&*value);
-                }
+        let options = options.apply_ports(ports)?;
 
-                "statement-cache-capacity" => {
-                    options = options.statement_cache_capacity(
-                        value
-                            .parse()
-                            .map_err(|e: ParseIntError| Error::from(e.to_string()))?,
-                    );
-                }
+        let options = options.apply_pgpass();
 
-                "host" => {
-                    if value.starts_with("/") {
-                        options = options.socket(&*value);
-                    } else {
-                        options = options.host(&*value);
-                    }
-                }
+        Ok(options)
+    }
+}
 
-                "hostaddr" => {
+impl PgConnectOptions {
+    /// Applies a connection parameter given in the query of a connection URI or in a
+    /// service file. A comma separated port list is only checked against the hosts once all
+    /// the parameters are applied.
+    pub(crate) fn apply_parameter(
+        mut self,
+        key: &str,
+        value: &str,
+        ports: &mut Option<Vec<u16>>,
+    ) -> Result<Self, Error> {
+        match key {
+            "sslmode" | "ssl-mode" => {
+                self = self.ssl_mode(value.parse()?);
+            }
+
+            "sslrootcert" | "ssl-root-cert" | "ssl-ca" => {
+                self = self.ssl_root_cert(value);
+            }
+
+            "sslcert" | "ssl-cert" => {
+                self = self.ssl_client_cert(value);
+            }
+
+            "sslkey" | "ssl-key" => {
+                self = self.ssl_client_key(value);
+            }
+
+            "channel_binding" | "channel-binding" => {
+                self = self.channel_binding(value.parse()?);
+            }
+
+            "statement-cache-capacity" => {
+                self = self.statement_cache_capacity(
                     value
-                        .parse::<IpAddr>()
-                        .map_err(|e| Error::from(e.to_string()))?;
-                    options = options.host(&*value)
-                }
+                        .parse()
+                        .map_err(|e: ParseIntError| Error::from(e.to_string()))?,
+                );
+            }
+
+            "host" if value.contains(',') => {
+                self = self.host("");
+                self.hosts = value
+                    .split(',')
+                    .map(|host| (host.to_string(), None))
+                    .collect();
+                self.host = self.hosts[0].0.clone();
+            }
 
-                "port" => {
-                    options = options.port(
-                        value
-                            .parse()
-                            .map_err(|e: ParseIntError| Error::from(e.to_string()))?,
-                    )
+            "host" => {
+                if value.starts_with("/") {
+                    self = self.socket(value);
+                } else {
+                    self = self.host(value);
                 }
+            }
 
-                "dbname" => options = options.database(&*value),
+            "hostaddr" => {
+                value
+                    .parse::<IpAddr>()
+                    .map_err(|e| Error::from(e.to_string()))?;
+                self = self.host(value)
+            }
 
-                "user" => options = options.username(&*value),
+            "port" if value.contains(',') => {
+                *ports = Some(
+                    value
+                        .split(',')
+                        .map(|port| port.parse::<u16>())
+                        .collect::<Result<Vec<u16>, ParseIntError>>()
+                        .map_err(|e| Error::from(e.to_string()))?,
+                );
+            }
 
-                "password" => options = options.password(&*value),
+            "port" => {
+                self = self.port(
+                    value
+                        .parse()
+                        .map_err(|e: ParseIntError| Error::from(e.to_string()))?,
+                )
+            }
 
-                "application_name" => options = options.application_name(&*value),
+            "connect_timeout" => {
+                let seconds = value
+                    .parse::<i64>()
+                    .map_err(|e| Error::from(e.to_string()))?;
+                self = self.connect_timeout(
+                    (seconds > 0).then(|| Duration::from_secs(seconds as u64)),
+                );
+            }
 
-                "options" => {
-                    if let Some(options) = options.options.as_mut() {
-                        options.push(' ');
-                        options.push_str(&*value);
-                    } else {
-                        options.options = Some(value.to_string());
-                    }
+            "dbname" => self = self.database(value),
+
+            "user" => self = self.username(value),
+
+            "password" => self = self.password(value),
+
+            "application_name" => self = self.application_name(value),
+
+            "options" => {
+                if let Some(options) = self.options.as_mut() {
+                    options.push(' ');
+                    options.push_str(value);
+                } else {
+                    self.options = Some(value.to_string());
                 }
+            }
 
-                k if k.starts_with("options[") => {
-                    if let Some(key) = k
-                        .strip_prefix("options[")
-                        .unwrap_or_default()
-                        .strip_suffix(']')
-                    {
-                        options = options.options([(key, &*value)]);
+            "target_session_attrs" => {
+                self = self.target_session_attrs(value.parse()?);
+            }
+
+            "load_balance_hosts" => {
+                self = self.load_balance_hosts(value.parse()?);
+            }
+
+            "replication" => {
+                self = match value {
+                    "database" => self.replication(true),
+                    "false" | "off" | "no" | "0" => self.replication(false),
+                    _ => {
+                        return Err(Error::from(format!(
+                            "unsupported replication mode `{}`, only `database` (logical replication) is supported",
+                            value
+                        )))
                     }
                 }
+            }
+
+            "service" => {
+                return Err(Error::from(format!(
+                    "nested service specifications not supported: service={}",
+                    value
+                )))
+            }
 
-                _ => log::warn!("ignoring unrecognized connect parameter: {}={}", key, value),
+            k if k.starts_with("options[") => {
+                if let Some(key) = k
+                    .strip_prefix("options[")
+                    .unwrap_or_default()
+                    .strip_suffix(']')
+                {
+                    self = self.options([(key, value)]);
+                }
             }
+
+            _ => log::warn!("ignoring unrecognized connect parameter: {}={}", key, value),
         }
+        Ok(self)
+    }
 
-        let options = options.apply_pgpass();
+    /// Applies the parameters of a service from the connection service file.
+    pub(crate) fn apply_service(mut self, name: &str) -> Result<Self, Error> {
+        let mut ports = None;
+        for (key, value) in service::load_service(name)? {
+            self = self.apply_parameter(&key, &value, &mut ports)?;
+        }
+        self.apply_ports(ports)
+    }
 
-        Ok(options)
+    // assigns a comma separated port list to the hosts
+    fn apply_ports(mut self, ports: Option<Vec<u16>>) -> Result<Self, Error> {
+        if let Some(ports) = ports {
+            if ports.len() != self.hosts.len() {
+                return Err(Error::from(format!(
+                    "could not match {} port numbers to {} hosts",
+                    ports.len(),
+                    self.hosts.len().max(1)
+                )));
+            }
+            for ((_, port), p) in self.hosts.iter_mut().zip(ports) {
+                *port = Some(p);
+            }
+            self.port = self.hosts[0].1.unwrap_or(self.port);
+        }
+        Ok(self)
     }
 }
 
+type HostPort = (String, Option<u16>);
+
+/// Splits a comma separated host list out of the authority of a connection URI. Returns the
+/// URI with only the first host and all the hosts with their ports.
+fn split_host_list(s: &str) -> Result<(Cow<'_, str>, Vec<HostPort>), Error> {
+    let Some(start) = s.find("://").map(|i| i + 3) else {
+        return Ok((Cow::Borrowed(s), vec![]));
+    };
+    let end = s[start..]
+        .find(['/', '?', '#'])
+        .map_or(s.len(), |i| start + i);
+    let authority = &s[start..end];
+    let host_start = authority.rfind('@').map_or(0, |i| i + 1);
+    let host_list = &authority[host_start..];
+    if !host_list.contains(',') {
+        return Ok((Cow::Borrowed(s), vec![]));
+    }
+
+    let mut hosts = Vec::new();
+    for entry in host_list.split(',') {
+        let (host, port) = match entry.strip_prefix('[') {
+            // an IPv6 address
+            Some(rest) => {
+                let (host, port) = rest
+                    .split_once(']')
+                    .ok_or_else(|| Error::from(format!("invalid host: {}", entry)))?;
+                (host, port.strip_prefix(':'))
+            }
+            None => match entry.rsplit_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (entry, None),
+            },
+        };
+        let port = match port {
+            Some(port) if !port.is_empty() => Some(
+                port.parse::<u16>()
+                    .map_err(|e| Error::from(format!("invalid port {}: {}", port, e)))?,
+            ),
+            _ => None,
+        };
+        let host = percent_decode_str(host)
+            .decode_utf8()
+            .map_err(|e| Error::from(e.to_string()))?;
+        hosts.push((host.into_owned(), port));
+    }
+
+    let first = host_list.split(',').next().unwrap_or_default();
+    let uri = format!("{}{}{}", &s[..start + host_start], first, &s[end..]);
+    Ok((Cow::Owned(uri), hosts))
+}
+
 #[cfg(test)]
 mod test {
     use std::str::FromStr;
 
-    use crate::options::{PgConnectOptions, PgSslMode};
+    use crate::options::{
+        PgChannelBinding, PgConnectOptions, PgLoadBalanceHosts, PgSslMode, PgTargetSessionAttrs,
+    };
+    use rbdc::net::CertificateInput;
+    use std::ffi::{OsStr, OsString};
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    // held by the tests that set environment variables, tests run in parallel
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    /// Sets an environment variable until dropped, then restores its previous value.
+    struct EnvVarGuard {
+        key: &'static str,
+        previous: Option<OsString>,
+    }
+
+    impl EnvVarGuard {
+        fn set(key: &'static str, value: impl AsRef<OsStr>) -> Self {
+            let previous = std::env::var_os(key);
+            std::env::set_var(key, value);
+            Self { key, previous }
+        }
+    }
+
+    impl Drop for EnvVarGuard {
+        fn drop(&mut self) {
+            match &self.previous {
+                Some(value) => std::env::set_var(self.key, value),
+                None => std::env::remove_var(self.key),
+            }
+        }
+    }
 
     #[test]
     fn it_parses_socket_correctly_from_parameter() {
@@ -261,10 +484,145 @@ mod test {
         );
     }
 
+    #[test]
+    fn it_parses_ssl_client_cert_and_key() {
+        let uri = "postgres:///?sslmode=verify-ca&sslcert=/certs/client.crt&sslkey=/certs/client.key";
+        let opts = PgConnectOptions::from_str(uri).unwrap();
+
+        assert!(matches!(
+            opts.ssl_client_cert,
+            Some(CertificateInput::File(ref p)) if p.to_str() == Some("/certs/client.crt")
+        ));
+        assert!(matches!(
+            opts.ssl_client_key,
+            Some(CertificateInput::File(ref p)) if p.to_str() == Some("/certs/client.key")
+        ));
+    }
+
+    #[test]
+    fn it_parses_channel_binding() {
+        let uri = "postgres:///?channel_binding=require";
+        let opts = PgConnectOptions::from_str(uri).unwrap();
+        assert_eq!(PgChannelBinding::Require, opts.channel_binding);
+
+        let opts = PgConnectOptions::from_str("postgres:///").unwrap();
+        assert_eq!(PgChannelBinding::Prefer, opts.channel_binding);
+
+        assert!(PgConnectOptions::from_str("postgres:///?channel_binding=maybe").is_err());
+    }
+
     #[test]
     fn it_parses_ssl_mode() {
         let uri = "postgres:///?port=1234";
         let opts = PgConnectOptions::from_str(uri).unwrap();
         assert_eq!(PgSslMode::Disable, opts.ssl_mode);
     }
+
+    #[test]
+    fn it_parses_replication() {
+        let opts = PgConnectOptions::from_str("postgres:///?replication=database").unwrap();
+        assert!(opts.replication);
+
+        let opts = PgConnectOptions::from_str("postgres:///").unwrap();
+        assert!(!opts.replication);
+
+        assert!(PgConnectOptions::from_str("postgres:///?replication=true").is_err());
+    }
+
+    #[test]
+    fn it_parses_multiple_hosts() {
+        let uri = "postgres://user:pw@db1:5433,db2,[::1]:5434/app?target_session_attrs=read-write";
+        let opts = PgConnectOptions::from_str(uri).unwrap();
+        assert_eq!(
+            opts.hosts,
+            vec![
+                ("db1".to_string(), Some(5433)),
+                ("db2".to_string(), None),
+                ("::1".to_string(), Some(5434)),
+            ]
+        );
+        assert_eq!(opts.host, "db1");
+        assert_eq!(opts.username, "user");
+        assert_eq!(opts.database.as_deref(), Some("app"));
+        assert_eq!(opts.target_session_attrs, PgTargetSessionAttrs::ReadWrite);
+        assert_eq!(
+            opts.candidate_hosts(),
+            vec![
+                ("db1".to_string(), 5433),
+                ("db2".to_string(), 5432),
+                ("::1".to_string(), 5434),
+            ]
+        );
+
+        let opts = PgConnectOptions::from_str(
+            "postgres:///app?host=a,b&port=5000,5001&load_balance_hosts=random",
+        )
+        .unwrap();
+        assert_eq!(
+            opts.hosts,
+            vec![("a".to_string(), Some(5000)), ("b".to_string(), Some(5001))]
+        );
+        assert_eq!(opts.load_balance_hosts, PgLoadBalanceHosts::Random);
+        assert_eq!(opts.candidate_hosts().len(), 2);
+
+        assert!(PgConnectOptions::from_str("postgres:///?host=a,b&port=1,2,3").is_err());
+        assert!(PgConnectOptions::from_str("postgres:///?target_session_attrs=master").is_err());
+    }
+
+    #[test]
+    fn it_parses_connect_timeout() {
+        let opts = PgConnectOptions::from_str("postgres:///?connect_timeout=10").unwrap();
+        assert_eq!(opts.connect_timeout, Some(Duration::from_secs(10)));
+
+        let opts = PgConnectOptions::from_str("postgres:///?connect_timeout=0").unwrap();
+        assert_eq!(opts.connect_timeout, None);
+    }
+
+    #[test]
+    fn it_parses_service() {
+        let file =
+            std::env::temp_dir().join(format!("rbdc_pg_service_{}.conf", std::process::id()));
+        std::fs::write(
+            &file,
+            "[rbdc_test]\nhost=db.example.com\nport=5433\ndbname=app\nconnect_timeout=5\n",
+        )
+        .unwrap();
+        let _lock = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let _service_file = EnvVarGuard::set("PGSERVICEFILE", &file);
+
+        // the URI overrides the service
+        let opts =
+            PgConnectOptions::from_str("postgres:///?service=rbdc_test&dbname=other").unwrap();
+        assert_eq!("db.example.com", &opts.host);
+        assert_eq!(5433, opts.port);
+        assert_eq!(Some("other"), opts.database.as_deref());
+        assert_eq!(opts.connect_timeout, Some(Duration::from_secs(5)));
+
+        assert!(PgConnectOptions::from_str("postgres:///?service=rbdc_missing").is_err());
+        std::fs::remove_file(&file).unwrap();
+    }
+
+    #[test]
+    fn it_reads_pgpass_per_host() {
+        let file = std::env::temp_dir().join(format!("rbdc_pg_pgpass_{}", std::process::id()));
+        std::fs::write(&file, "h1:5432:*:user:one\nh2:5432:*:user:two\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o600)).unwrap();
+        }
+        let _lock = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let _pgpass_file = EnvVarGuard::set("PGPASSFILE", &file);
+
+        let opts = PgConnectOptions::from_str("postgres://user@h1,h2/db").unwrap();
+        assert_eq!(Some("one"), opts.password.as_deref());
+        assert_eq!(Some("one"), opts.for_host("h1", 5432).password.as_deref());
+        assert_eq!(Some("two"), opts.for_host("h2", 5432).password.as_deref());
+        assert_eq!(None, opts.for_host("h3", 5432).password.as_deref());
+
+        // a given password is sent to every host
+        let opts = PgConnectOptions::from_str("postgres://user:pw@h1,h2/db").unwrap();
+        assert_eq!(Some("pw"), opts.for_host("h2", 5432).password.as_deref());
+        std::fs::remove_file(&file).unwrap();
+    }
 }
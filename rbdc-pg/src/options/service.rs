@@ -0,0 +1,129 @@
+use rbdc::Error;
+use std::env::var_os;
+use std::fs::File;
+use std::io::{BufRead, BufReader, ErrorKind};
+use std::path::PathBuf;
+
+/// load the parameters of a service from the per-user and then the system-wide
+/// connection service file
+///
+/// <https://www.postgresql.org/docs/current/libpq-pgservice.html>
+pub fn load_service(name: &str) -> Result<Vec<(String, String)>, Error> {
+    let mut files = Vec::new();
+    match var_os("PGSERVICEFILE") {
+        Some(file) => files.push(PathBuf::from(file)),
+        None => {
+            #[cfg(not(target_os = "windows"))]
+            let default_file = dirs::home_dir().map(|path| path.join(".pg_service.conf"));
+            #[cfg(target_os = "windows")]
+            let default_file =
+                dirs::data_dir().map(|path| path.join("postgresql").join(".pg_service.conf"));
+            files.extend(default_file);
+        }
+    }
+    if let Some(dir) = var_os("PGSYSCONFDIR") {
+        files.push(PathBuf::from(dir).join("pg_service.conf"));
+    }
+
+    for path in files {
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => {
+                return Err(Error::from(format!(
+                    "could not open service file {}: {}",
+                    path.to_string_lossy(),
+                    e
+                )))
+            }
+        };
+        let params = load_service_from_reader(BufReader::new(file), name)
+            .map_err(|e| Error::from(format!("{}: {}", path.to_string_lossy(), e)))?;
+        if let Some(params) = params {
+            return Ok(params);
+        }
+    }
+
+    Err(Error::from(format!(
+        "definition of service \"{}\" not found",
+        name
+    )))
+}
+
+/// the `key=value` lines of the `[name]` section, `None` when there is no such section
+fn load_service_from_reader(
+    reader: impl BufRead,
+    name: &str,
+) -> Result<Option<Vec<(String, String)>>, Error> {
+    let mut service: Option<Vec<(String, String)>> = None;
+
+    for (number, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| Error::from(e.to_string()))?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(section) = line.strip_prefix('[') {
+            if service.is_some() {
+                // the first section with the name wins
+                break;
+            }
+            if section.strip_suffix(']') == Some(name) {
+                service = Some(vec![]);
+            }
+            continue;
+        }
+
+        if let Some(params) = service.as_mut() {
+            let (key, value) = line.split_once('=').ok_or_else(|| {
+                Error::from(format!("syntax error in service file, line {}", number + 1))
+            })?;
+            params.push((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    Ok(service)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::load_service_from_reader;
+
+    #[test]
+    fn test_load_service_from_reader() {
+        let file = b"\
+# comment
+[other]
+host=other.example.com
+
+[mydb]
+host=db.example.com
+port = 5433
+dbname=mydb
+options=-c search_path=app
+
+[mydb]
+host=ignored.example.com
+";
+
+        let params = load_service_from_reader(&file[..], "mydb")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            params,
+            vec![
+                ("host".to_string(), "db.example.com".to_string()),
+                ("port".to_string(), "5433".to_string()),
+                ("dbname".to_string(), "mydb".to_string()),
+                ("options".to_string(), "-c search_path=app".to_string()),
+            ]
+        );
+
+        assert_eq!(
+            load_service_from_reader(&file[..], "missing").unwrap(),
+            None
+        );
+        assert!(load_service_from_reader(&b"[mydb]\nhost\n"[..], "mydb").is_err());
+    }
+}
@@ -0,0 +1,96 @@
+use rbdc::Error;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// The kind of server session that is acceptable when connecting, checked on each host
+/// in turn until one matches.
+///
+/// It is used by the [`target_session_attrs`](super::PgConnectOptions::target_session_attrs) method.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum PgTargetSessionAttrs {
+    /// Any successful connection is acceptable.
+    #[default]
+    Any,
+
+    /// The session must accept read-write transactions by default.
+    ReadWrite,
+
+    /// The session must not accept read-write transactions by default.
+    ReadOnly,
+
+    /// The server must not be in hot standby mode.
+    Primary,
+
+    /// The server must be in hot standby mode.
+    Standby,
+
+    /// First try to find a standby server, but if none of the hosts is one, try again
+    /// accepting any server.
+    PreferStandby,
+}
+
+impl FromStr for PgTargetSessionAttrs {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        Ok(match &*s.to_ascii_lowercase() {
+            "any" => PgTargetSessionAttrs::Any,
+            "read-write" => PgTargetSessionAttrs::ReadWrite,
+            "read-only" => PgTargetSessionAttrs::ReadOnly,
+            "primary" => PgTargetSessionAttrs::Primary,
+            "standby" => PgTargetSessionAttrs::Standby,
+            "prefer-standby" => PgTargetSessionAttrs::PreferStandby,
+
+            _ => {
+                return Err(Error::from(format!(
+                    "unknown value {:?} for `target_session_attrs`",
+                    s
+                )));
+            }
+        })
+    }
+}
+
+impl Display for PgTargetSessionAttrs {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            PgTargetSessionAttrs::Any => "any",
+            PgTargetSessionAttrs::ReadWrite => "read-write",
+            PgTargetSessionAttrs::ReadOnly => "read-only",
+            PgTargetSessionAttrs::Primary => "primary",
+            PgTargetSessionAttrs::Standby => "standby",
+            PgTargetSessionAttrs::PreferStandby => "prefer-standby",
+        })
+    }
+}
+
+/// The order in which the hosts of a multi-host connection are tried.
+///
+/// It is used by the [`load_balance_hosts`](super::PgConnectOptions::load_balance_hosts) method.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum PgLoadBalanceHosts {
+    /// Try the hosts in the order they are given.
+    #[default]
+    Disable,
+
+    /// Try the hosts in a random order.
+    Random,
+}
+
+impl FromStr for PgLoadBalanceHosts {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        Ok(match &*s.to_ascii_lowercase() {
+            "disable" => PgLoadBalanceHosts::Disable,
+            "random" => PgLoadBalanceHosts::Random,
+
+            _ => {
+                return Err(Error::from(format!(
+                    "unknown value {:?} for `load_balance_hosts`",
+                    s
+                )));
+            }
+        })
+    }
+}
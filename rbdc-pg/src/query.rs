@@ -35,6 +35,14 @@ impl PgQuery {
         return Ok(Some(PgArguments::from_args(self.arguments)?));
     }
 
+    #[inline]
+    pub(crate) fn take_values(self) -> Option<Vec<rbs::Value>> {
+        if self.arguments.is_empty() {
+            return None;
+        }
+        Some(self.arguments)
+    }
+
     #[inline]
     pub fn persistent(&self) -> bool {
         self.persistent
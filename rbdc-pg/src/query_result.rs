@@ -1,18 +1,28 @@
+use crate::message::Notice;
+
 #[derive(Debug, Default)]
 pub struct PgQueryResult {
     pub(super) rows_affected: u64,
+    pub(super) notices: Vec<Notice>,
 }
 
 impl PgQueryResult {
     pub fn rows_affected(&self) -> u64 {
         self.rows_affected
     }
+
+    /// The notices sent while running the statement, collected with
+    /// [`PgNoticeHandler::Collect`](crate::options::PgNoticeHandler::Collect).
+    pub fn notices(&self) -> &[Notice] {
+        &self.notices
+    }
 }
 
 impl Extend<PgQueryResult> for PgQueryResult {
     fn extend<T: IntoIterator<Item = PgQueryResult>>(&mut self, iter: T) {
         for elem in iter {
             self.rows_affected += elem.rows_affected;
+            self.notices.extend(elem.notices);
         }
     }
 }
@@ -0,0 +1,74 @@
+use rbdc::Error;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// A position in the write-ahead log, written as two hexadecimal numbers `XXXXXXXX/XXXXXXXX`.
+#[derive(
+    serde::Serialize,
+    serde::Deserialize,
+    Debug,
+    Copy,
+    Clone,
+    Default,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Hash,
+)]
+#[serde(rename = "PgLsn")]
+pub struct PgLsn(pub u64);
+
+impl PgLsn {
+    /// The invalid position `0/0`, which lets the server pick where a replication slot starts.
+    pub const INVALID: PgLsn = PgLsn(0);
+}
+
+impl From<u64> for PgLsn {
+    fn from(arg: u64) -> Self {
+        Self(arg)
+    }
+}
+
+impl From<PgLsn> for u64 {
+    fn from(arg: PgLsn) -> Self {
+        arg.0
+    }
+}
+
+impl Display for PgLsn {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:X}/{:X}", self.0 >> 32, self.0 as u32)
+    }
+}
+
+impl FromStr for PgLsn {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (high, low) = s
+            .split_once('/')
+            .ok_or_else(|| Error::from(format!("invalid lsn: {}", s)))?;
+        let high = u32::from_str_radix(high, 16)
+            .map_err(|_| Error::from(format!("invalid lsn: {}", s)))?;
+        let low =
+            u32::from_str_radix(low, 16).map_err(|_| Error::from(format!("invalid lsn: {}", s)))?;
+        Ok(Self(((high as u64) << 32) | low as u64))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::replication::PgLsn;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_display_parse() {
+        let lsn = PgLsn::from_str("16/B374D848").unwrap();
+        assert_eq!(lsn.0, 0x16_B374_D848);
+        assert_eq!(lsn.to_string(), "16/B374D848");
+        assert_eq!(PgLsn::INVALID.to_string(), "0/0");
+        assert!(PgLsn::from_str("16B374D848").is_err());
+        assert!(PgLsn::from_str("16/x").is_err());
+    }
+}
@@ -0,0 +1,502 @@
+//! Logical replication with the `pgoutput` plugin.
+//!
+//! Open the connection with [`PgConnectOptions::replication`](crate::options::PgConnectOptions::replication),
+//! create a slot with [`PgConnection::create_replication_slot`] and stream the changes of one or
+//! more publications with [`PgConnection::start_replication`].
+//!
+//! See <https://www.postgresql.org/docs/current/protocol-replication.html>
+
+use crate::connection::PgConnection;
+use crate::message::{CopyData, CopyDone, CopyResponse, MessageFormat, Query};
+use crate::type_info::PgTypeInfo;
+use crate::types::decode::Decode;
+use crate::value::{PgValue, PgValueFormat};
+use bytes::{Buf, BufMut, Bytes};
+use rbdc::db::Connection;
+use rbdc::{err_protocol, DateTime, Error};
+use rbs::Value;
+use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+mod lsn;
+pub mod pgoutput;
+
+pub use lsn::PgLsn;
+pub use pgoutput::{PgOutputDecoder, PgOutputMessage};
+
+/// microseconds between the unix epoch and the postgres epoch 2000-01-01
+const PG_EPOCH_MICROS: i64 = 946_684_800_000_000;
+
+/// Converts microseconds since 2000-01-01, as used by the replication protocol.
+pub(crate) fn pg_timestamp(micros: i64) -> Result<DateTime, Error> {
+    DateTime::decode(PgValue {
+        value: Some(micros.to_be_bytes().to_vec()),
+        type_info: PgTypeInfo::TIMESTAMPTZ,
+        format: PgValueFormat::Binary,
+        timezone: None,
+    })
+}
+
+fn pg_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_micros() as i64)
+        .unwrap_or_default()
+        - PG_EPOCH_MICROS
+}
+
+/// The result of `IDENTIFY_SYSTEM`.
+#[derive(Debug, Clone)]
+pub struct PgIdentifySystem {
+    pub system_id: String,
+    pub timeline: i64,
+    /// the current end of the write-ahead log
+    pub xlog_pos: PgLsn,
+    pub dbname: Option<String>,
+}
+
+/// A replication slot created by [`PgConnection::create_replication_slot`].
+#[derive(Debug, Clone)]
+pub struct PgReplicationSlot {
+    pub slot_name: String,
+    /// the position from which the slot streams changes
+    pub consistent_point: PgLsn,
+    pub output_plugin: Option<String>,
+}
+
+/// Options for [`PgConnection::start_replication`].
+#[derive(Debug, Clone)]
+pub struct PgReplicationOptions {
+    slot: String,
+    start_lsn: PgLsn,
+    publications: Vec<String>,
+    binary: bool,
+    messages: bool,
+    status_interval: Duration,
+}
+
+impl PgReplicationOptions {
+    /// Streams the changes of the slot from where it was last acknowledged.
+    pub fn new(slot: &str) -> Self {
+        Self {
+            slot: slot.to_string(),
+            start_lsn: PgLsn::INVALID,
+            publications: vec![],
+            binary: false,
+            messages: false,
+            status_interval: Duration::from_secs(10),
+        }
+    }
+
+    /// Sets the position to start streaming from. Changes before the position the slot was
+    /// last acknowledged at are not sent again.
+    pub fn start_lsn(mut self, lsn: PgLsn) -> Self {
+        self.start_lsn = lsn;
+        self
+    }
+
+    /// Adds a publication whose changes are streamed. At least one is required.
+    pub fn publication(mut self, name: &str) -> Self {
+        self.publications.push(name.to_string());
+        self
+    }
+
+    /// Requests the binary format for column values (PostgreSQL 14+).
+    pub fn binary(mut self, binary: bool) -> Self {
+        self.binary = binary;
+        self
+    }
+
+    /// Requests messages written by `pg_logical_emit_message` (PostgreSQL 14+).
+    pub fn messages(mut self, messages: bool) -> Self {
+        self.messages = messages;
+        self
+    }
+
+    /// Sets how often a standby status update is sent while receiving changes.
+    /// Defaults to 10 seconds.
+    pub fn status_interval(mut self, interval: Duration) -> Self {
+        self.status_interval = interval;
+        self
+    }
+
+    fn to_sql(&self) -> Result<String, Error> {
+        check_slot_name(&self.slot)?;
+        if self.publications.is_empty() {
+            return Err(Error::from(
+                "logical replication needs at least one publication",
+            ));
+        }
+        let publications = self
+            .publications
+            .iter()
+            .map(|p| format!("\"{}\"", p.replace('"', "\"\"")))
+            .collect::<Vec<_>>()
+            .join(",");
+        let mut sql = format!(
+            "START_REPLICATION SLOT {} LOGICAL {} (proto_version '1', publication_names '{}'",
+            self.slot,
+            self.start_lsn,
+            publications.replace('\'', "''")
+        );
+        if self.binary {
+            sql.push_str(", binary 'true'");
+        }
+        if self.messages {
+            sql.push_str(", messages 'true'");
+        }
+        sql.push(')');
+        Ok(sql)
+    }
+}
+
+/// slot names may only contain lower case letters, numbers and underscores
+fn check_slot_name(name: &str) -> Result<(), Error> {
+    if name.is_empty()
+        || !name
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_')
+    {
+        return Err(Error::from(format!(
+            "invalid replication slot name: {}",
+            name
+        )));
+    }
+    Ok(())
+}
+
+fn text_of(v: &Value) -> Option<String> {
+    match v {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        v => Some(v.to_string()),
+    }
+}
+
+impl PgConnection {
+    /// Runs `IDENTIFY_SYSTEM` on a replication connection.
+    pub async fn identify_system(&mut self) -> Result<PgIdentifySystem, Error> {
+        let rows = self.get_values("IDENTIFY_SYSTEM", vec![]).await?;
+        let row = rows
+            .first()
+            .ok_or_else(|| err_protocol!("IDENTIFY_SYSTEM returned no row"))?;
+        Ok(PgIdentifySystem {
+            system_id: text_of(&row["systemid"]).unwrap_or_default(),
+            timeline: row["timeline"].as_i64().unwrap_or_default(),
+            xlog_pos: PgLsn::from_str(&text_of(&row["xlogpos"]).unwrap_or_default())?,
+            dbname: text_of(&row["dbname"]),
+        })
+    }
+
+    /// Creates a logical replication slot using the `pgoutput` plugin. A temporary slot is
+    /// dropped when the connection is closed.
+    pub async fn create_replication_slot(
+        &mut self,
+        slot: &str,
+        temporary: bool,
+    ) -> Result<PgReplicationSlot, Error> {
+        check_slot_name(slot)?;
+        let sql = format!(
+            "CREATE_REPLICATION_SLOT {}{} LOGICAL pgoutput NOEXPORT_SNAPSHOT",
+            slot,
+            if temporary { " TEMPORARY" } else { "" }
+        );
+        let rows = self.get_values(&sql, vec![]).await?;
+        let row = rows
+            .first()
+            .ok_or_else(|| err_protocol!("CREATE_REPLICATION_SLOT returned no row"))?;
+        Ok(PgReplicationSlot {
+            slot_name: text_of(&row["slot_name"]).unwrap_or_else(|| slot.to_string()),
+            consistent_point: PgLsn::from_str(
+                &text_of(&row["consistent_point"]).unwrap_or_default(),
+            )?,
+            output_plugin: text_of(&row["output_plugin"]),
+        })
+    }
+
+    /// Drops a replication slot. With `wait` the command waits until a connection using
+    /// the slot is gone instead of failing.
+    pub async fn drop_replication_slot(&mut self, slot: &str, wait: bool) -> Result<(), Error> {
+        check_slot_name(slot)?;
+        let sql = format!(
+            "DROP_REPLICATION_SLOT {}{}",
+            slot,
+            if wait { " WAIT" } else { "" }
+        );
+        self.exec(&sql, vec![]).await?;
+        Ok(())
+    }
+
+    /// Starts streaming the changes of a logical replication slot. The connection is used
+    /// by the stream until [`PgReplicationStream::stop`] gives it back.
+    pub async fn start_replication(
+        mut self,
+        options: PgReplicationOptions,
+    ) -> Result<PgReplicationStream, Error> {
+        let sql = options.to_sql()?;
+        self.wait_until_ready().await?;
+
+        // the ReadyForQuery arrives once the copy is finished
+        self.pending_ready_for_query_count += 1;
+        self.stream.send(Query(&sql)).await?;
+        let _: CopyResponse = self
+            .stream
+            .recv_expect(MessageFormat::CopyBothResponse)
+            .await?;
+
+        Ok(PgReplicationStream {
+            conn: self,
+            decoder: PgOutputDecoder::new(),
+            received_lsn: options.start_lsn,
+            flushed_lsn: options.start_lsn,
+            status_interval: options.status_interval,
+            last_status: Instant::now(),
+        })
+    }
+}
+
+/// A `pgoutput` change received from the server.
+#[derive(Debug, Clone)]
+pub struct PgReplicationEvent {
+    /// the position of the change in the write-ahead log
+    pub wal_start: PgLsn,
+    /// the current end of the write-ahead log on the server
+    pub wal_end: PgLsn,
+    pub message: PgOutputMessage,
+}
+
+/// A message of the streaming replication protocol, sent inside `CopyData`.
+#[derive(Debug)]
+enum ReplicationMessage {
+    XLogData {
+        wal_start: PgLsn,
+        wal_end: PgLsn,
+        data: Bytes,
+    },
+    Keepalive {
+        wal_end: PgLsn,
+        reply: bool,
+    },
+}
+
+impl ReplicationMessage {
+    fn decode(mut buf: Bytes) -> Result<Self, Error> {
+        if buf.is_empty() {
+            return Err(err_protocol!("empty replication message"));
+        }
+        match buf.get_u8() {
+            b'w' if buf.len() >= 24 => {
+                let wal_start = PgLsn(buf.get_u64());
+                let wal_end = PgLsn(buf.get_u64());
+                let _send_time = buf.get_i64();
+                Ok(ReplicationMessage::XLogData {
+                    wal_start,
+                    wal_end,
+                    data: buf,
+                })
+            }
+            b'k' if buf.len() >= 17 => {
+                let wal_end = PgLsn(buf.get_u64());
+                let _send_time = buf.get_i64();
+                Ok(ReplicationMessage::Keepalive {
+                    wal_end,
+                    reply: buf.get_u8() != 0,
+                })
+            }
+            v => Err(err_protocol!(
+                "unexpected replication message: {:?}",
+                v as char
+            )),
+        }
+    }
+}
+
+/// Standby status update: the received, flushed and applied positions, the client clock
+/// and whether the server should reply immediately.
+fn status_update(received: PgLsn, flushed: PgLsn, now: i64, reply: bool) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(34);
+    buf.push(b'r');
+    buf.put_u64(received.0);
+    buf.put_u64(flushed.0);
+    buf.put_u64(flushed.0);
+    buf.put_i64(now);
+    buf.push(reply as u8);
+    buf
+}
+
+/// A stream of logical replication changes, see [`PgConnection::start_replication`].
+///
+/// The server only releases the write-ahead log of changes that were acknowledged with
+/// [`acknowledge`](Self::acknowledge), typically with the `end_lsn` of a processed commit.
+pub struct PgReplicationStream {
+    conn: PgConnection,
+    decoder: PgOutputDecoder,
+    received_lsn: PgLsn,
+    flushed_lsn: PgLsn,
+    status_interval: Duration,
+    last_status: Instant,
+}
+
+impl PgReplicationStream {
+    /// Receives the next change. Keepalives are answered internally. Returns `None` when
+    /// the server ended the stream.
+    pub async fn recv(&mut self) -> Result<Option<PgReplicationEvent>, Error> {
+        loop {
+            if self.last_status.elapsed() >= self.status_interval {
+                self.send_status_update(false).await?;
+            }
+            let message = self.conn.stream.recv().await?;
+            match message.format {
+                MessageFormat::CopyData => match ReplicationMessage::decode(message.contents)? {
+                    ReplicationMessage::XLogData {
+                        wal_start,
+                        wal_end,
+                        data,
+                    } => {
+                        self.received_lsn = self.received_lsn.max(wal_start);
+                        let message = self.decoder.decode(data)?;
+                        return Ok(Some(PgReplicationEvent {
+                            wal_start,
+                            wal_end,
+                            message,
+                        }));
+                    }
+                    ReplicationMessage::Keepalive { wal_end, reply } => {
+                        self.received_lsn = self.received_lsn.max(wal_end);
+                        if reply {
+                            self.send_status_update(false).await?;
+                        }
+                    }
+                },
+                MessageFormat::CopyDone => {
+                    self.conn.stream.send(CopyDone).await?;
+                    self.finish().await?;
+                    return Ok(None);
+                }
+                _ => {
+                    return Err(err_protocol!(
+                        "unexpected message during replication: {:?}",
+                        message.format
+                    ))
+                }
+            }
+        }
+    }
+
+    /// Marks the changes up to `lsn` as processed, reported with the next status update.
+    pub fn acknowledge(&mut self, lsn: PgLsn) {
+        self.flushed_lsn = self.flushed_lsn.max(lsn);
+        self.received_lsn = self.received_lsn.max(lsn);
+    }
+
+    /// The position of the last change received.
+    pub fn received_lsn(&self) -> PgLsn {
+        self.received_lsn
+    }
+
+    /// Sends a standby status update with the received and acknowledged positions.
+    pub async fn send_status_update(&mut self, reply: bool) -> Result<(), Error> {
+        let update = status_update(self.received_lsn, self.flushed_lsn, pg_now(), reply);
+        self.conn.stream.send(CopyData(update)).await?;
+        self.last_status = Instant::now();
+        Ok(())
+    }
+
+    /// Ends the stream, reporting the acknowledged position, and returns the connection.
+    pub async fn stop(mut self) -> Result<PgConnection, Error> {
+        self.send_status_update(false).await?;
+        self.conn.stream.send(CopyDone).await?;
+        loop {
+            let message = self.conn.stream.recv().await?;
+            match message.format {
+                // changes sent before the server saw our CopyDone
+                MessageFormat::CopyData => {}
+                MessageFormat::CopyDone => break,
+                _ => {
+                    return Err(err_protocol!(
+                        "unexpected message while stopping replication: {:?}",
+                        message.format
+                    ))
+                }
+            }
+        }
+        self.finish().await?;
+        Ok(self.conn)
+    }
+
+    // the CommandComplete and ReadyForQuery of START_REPLICATION follow the copy
+    async fn finish(&mut self) -> Result<(), Error> {
+        self.conn.wait_until_ready().await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::replication::{status_update, PgLsn, PgReplicationOptions, ReplicationMessage};
+    use bytes::{BufMut, Bytes};
+
+    #[test]
+    fn test_start_sql() {
+        let sql = PgReplicationOptions::new("my_slot")
+            .start_lsn(PgLsn(0x1_0000_0010))
+            .publication("pub")
+            .publication("it's")
+            .to_sql()
+            .unwrap();
+        assert_eq!(
+            sql,
+            "START_REPLICATION SLOT my_slot LOGICAL 1/10 (proto_version '1', publication_names '\"pub\",\"it''s\"')"
+        );
+        assert!(PgReplicationOptions::new("my_slot").to_sql().is_err());
+        assert!(PgReplicationOptions::new("Bad; slot")
+            .publication("p")
+            .to_sql()
+            .is_err());
+    }
+
+    #[test]
+    fn test_replication_messages() {
+        let mut buf = vec![b'w'];
+        buf.put_u64(10);
+        buf.put_u64(20);
+        buf.put_i64(0);
+        buf.extend(b"B");
+        match ReplicationMessage::decode(Bytes::from(buf)).unwrap() {
+            ReplicationMessage::XLogData {
+                wal_start,
+                wal_end,
+                data,
+            } => {
+                assert_eq!(wal_start, PgLsn(10));
+                assert_eq!(wal_end, PgLsn(20));
+                assert_eq!(&data[..], b"B");
+            }
+            v => panic!("{:?}", v),
+        }
+
+        let mut buf = vec![b'k'];
+        buf.put_u64(30);
+        buf.put_i64(0);
+        buf.push(1);
+        match ReplicationMessage::decode(Bytes::from(buf)).unwrap() {
+            ReplicationMessage::Keepalive { wal_end, reply } => {
+                assert_eq!(wal_end, PgLsn(30));
+                assert!(reply);
+            }
+            v => panic!("{:?}", v),
+        }
+        assert!(ReplicationMessage::decode(Bytes::from_static(b"k\0")).is_err());
+    }
+
+    #[test]
+    fn test_status_update() {
+        let buf = status_update(PgLsn(0x20), PgLsn(0x10), 5, true);
+        assert_eq!(buf.len(), 34);
+        assert_eq!(buf[0], b'r');
+        assert_eq!(&buf[1..9], &0x20_u64.to_be_bytes());
+        assert_eq!(&buf[9..17], &0x10_u64.to_be_bytes());
+        assert_eq!(&buf[17..25], &0x10_u64.to_be_bytes());
+        assert_eq!(&buf[25..33], &5_i64.to_be_bytes());
+        assert_eq!(buf[33], 1);
+    }
+}
@@ -0,0 +1,614 @@
+use crate::replication::{pg_timestamp, PgLsn};
+use crate::type_info::{PgCustomType, PgType, PgTypeInfo, PgTypeKind};
+use crate::types::decode::Decode;
+use crate::types::Oid;
+use crate::value::{PgValue, PgValueFormat};
+use bytes::{Buf, Bytes};
+use rbdc::io::BufExt;
+use rbdc::{err_protocol, DateTime, Error};
+use rbs::value::map::ValueMap;
+use rbs::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// A message of the `pgoutput` logical decoding plugin (protocol version 1).
+///
+/// See <https://www.postgresql.org/docs/current/protocol-logicalrep-message-formats.html>
+#[derive(Debug, Clone)]
+pub enum PgOutputMessage {
+    Begin(Begin),
+    Commit(Commit),
+    Origin(Origin),
+    Relation(Arc<Relation>),
+    Type(TypeMessage),
+    Insert(Insert),
+    Update(Update),
+    Delete(Delete),
+    Truncate(Truncate),
+    Message(LogicalMessage),
+}
+
+/// The start of a transaction.
+#[derive(Debug, Clone)]
+pub struct Begin {
+    /// the LSN of the commit record of the transaction
+    pub final_lsn: PgLsn,
+    pub commit_time: DateTime,
+    pub xid: u32,
+}
+
+/// The end of a transaction.
+#[derive(Debug, Clone)]
+pub struct Commit {
+    pub flags: u8,
+    pub commit_lsn: PgLsn,
+    /// the end of the transaction, acknowledge this once the transaction is processed
+    pub end_lsn: PgLsn,
+    pub commit_time: DateTime,
+}
+
+/// The origin of the changes of the current transaction, sent for replicated changes.
+#[derive(Debug, Clone)]
+pub struct Origin {
+    pub commit_lsn: PgLsn,
+    pub name: String,
+}
+
+/// How the old row of an update or delete is identified.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ReplicaIdentity {
+    /// the primary key columns
+    Default,
+    /// no old row is sent
+    Nothing,
+    /// all columns
+    Full,
+    /// the columns of a chosen unique index
+    Index,
+}
+
+/// A table that the following changes refer to. The server sends it before the first change
+/// of the table and whenever its definition changes.
+#[derive(Debug, Clone)]
+pub struct Relation {
+    pub id: Oid,
+    /// empty for `pg_catalog`
+    pub namespace: String,
+    pub name: String,
+    pub replica_identity: ReplicaIdentity,
+    pub columns: Vec<RelationColumn>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RelationColumn {
+    pub name: String,
+    /// whether the column is part of the replica identity
+    pub key: bool,
+    pub type_info: PgTypeInfo,
+    pub type_modifier: i32,
+}
+
+/// A non built-in type used by a following relation.
+#[derive(Debug, Clone)]
+pub struct TypeMessage {
+    pub id: Oid,
+    /// empty for `pg_catalog`
+    pub namespace: String,
+    pub name: String,
+}
+
+/// Rows are a `Value::Map` of column name to value. Columns holding an unchanged TOASTed value
+/// are not sent by the server and are left out of the map.
+#[derive(Debug, Clone)]
+pub struct Insert {
+    pub relation: Arc<Relation>,
+    pub new: Value,
+}
+
+#[derive(Debug, Clone)]
+pub struct Update {
+    pub relation: Arc<Relation>,
+    /// the old key columns, sent when the replica identity changed
+    pub key: Option<Value>,
+    /// the old row, sent for `REPLICA IDENTITY FULL`
+    pub old: Option<Value>,
+    pub new: Value,
+}
+
+#[derive(Debug, Clone)]
+pub struct Delete {
+    pub relation: Arc<Relation>,
+    /// the old key columns
+    pub key: Option<Value>,
+    /// the old row, sent for `REPLICA IDENTITY FULL`
+    pub old: Option<Value>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Truncate {
+    pub cascade: bool,
+    pub restart_identity: bool,
+    pub relations: Vec<Arc<Relation>>,
+}
+
+/// A message written by `pg_logical_emit_message`, sent when the `messages` option is set.
+#[derive(Debug, Clone)]
+pub struct LogicalMessage {
+    pub transactional: bool,
+    pub lsn: PgLsn,
+    pub prefix: String,
+    pub content: Vec<u8>,
+}
+
+/// Decodes `pgoutput` messages, remembering the relations and types announced by the server
+/// so that row data can be converted to values.
+#[derive(Debug, Default)]
+pub struct PgOutputDecoder {
+    relations: HashMap<Oid, Arc<Relation>>,
+    types: HashMap<Oid, PgTypeInfo>,
+}
+
+impl PgOutputDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a relation announced by the server.
+    pub fn relation(&self, id: Oid) -> Option<&Arc<Relation>> {
+        self.relations.get(&id)
+    }
+
+    pub fn decode(&mut self, mut buf: Bytes) -> Result<PgOutputMessage, Error> {
+        need(&buf, 1)?;
+        let tag = buf.get_u8();
+        Ok(match tag {
+            b'B' => {
+                need(&buf, 20)?;
+                PgOutputMessage::Begin(Begin {
+                    final_lsn: PgLsn(buf.get_u64()),
+                    commit_time: pg_timestamp(buf.get_i64())?,
+                    xid: buf.get_u32(),
+                })
+            }
+            b'C' => {
+                need(&buf, 25)?;
+                PgOutputMessage::Commit(Commit {
+                    flags: buf.get_u8(),
+                    commit_lsn: PgLsn(buf.get_u64()),
+                    end_lsn: PgLsn(buf.get_u64()),
+                    commit_time: pg_timestamp(buf.get_i64())?,
+                })
+            }
+            b'O' => {
+                need(&buf, 8)?;
+                PgOutputMessage::Origin(Origin {
+                    commit_lsn: PgLsn(buf.get_u64()),
+                    name: buf.get_str_nul()?,
+                })
+            }
+            b'Y' => {
+                need(&buf, 4)?;
+                let id = Oid(buf.get_u32());
+                let namespace = buf.get_str_nul()?;
+                let name = buf.get_str_nul()?;
+                self.types.insert(
+                    id,
+                    PgTypeInfo(PgType::Custom(Arc::new(PgCustomType {
+                        oid: id,
+                        name: name.clone().into(),
+                        kind: PgTypeKind::Simple,
+                    }))),
+                );
+                PgOutputMessage::Type(TypeMessage {
+                    id,
+                    namespace,
+                    name,
+                })
+            }
+            b'R' => {
+                let relation = Arc::new(self.decode_relation(&mut buf)?);
+                self.relations.insert(relation.id, relation.clone());
+                PgOutputMessage::Relation(relation)
+            }
+            b'I' => {
+                let relation = self.read_relation(&mut buf)?;
+                expect_tag(&mut buf, b'N')?;
+                let new = decode_tuple(&relation, &mut buf)?;
+                PgOutputMessage::Insert(Insert { relation, new })
+            }
+            b'U' => {
+                let relation = self.read_relation(&mut buf)?;
+                let (key, old) = decode_old_tuple(&relation, &mut buf)?;
+                expect_tag(&mut buf, b'N')?;
+                let new = decode_tuple(&relation, &mut buf)?;
+                PgOutputMessage::Update(Update {
+                    relation,
+                    key,
+                    old,
+                    new,
+                })
+            }
+            b'D' => {
+                let relation = self.read_relation(&mut buf)?;
+                let (key, old) = decode_old_tuple(&relation, &mut buf)?;
+                PgOutputMessage::Delete(Delete { relation, key, old })
+            }
+            b'T' => {
+                need(&buf, 5)?;
+                let count = buf.get_u32();
+                let options = buf.get_u8();
+                // each relation is a four byte oid
+                let mut relations = Vec::with_capacity((count as usize).min(buf.remaining() / 4));
+                for _ in 0..count {
+                    relations.push(self.read_relation(&mut buf)?);
+                }
+                PgOutputMessage::Truncate(Truncate {
+                    cascade: options & 1 != 0,
+                    restart_identity: options & 2 != 0,
+                    relations,
+                })
+            }
+            b'M' => {
+                need(&buf, 9)?;
+                let transactional = buf.get_u8() & 1 != 0;
+                let lsn = PgLsn(buf.get_u64());
+                let prefix = buf.get_str_nul()?;
+                need(&buf, 4)?;
+                let len = buf.get_u32() as usize;
+                need(&buf, len)?;
+                PgOutputMessage::Message(LogicalMessage {
+                    transactional,
+                    lsn,
+                    prefix,
+                    content: buf.split_to(len).to_vec(),
+                })
+            }
+            _ => {
+                return Err(err_protocol!(
+                    "unsupported pgoutput message: {:?}",
+                    tag as char
+                ))
+            }
+        })
+    }
+
+    fn decode_relation(&self, buf: &mut Bytes) -> Result<Relation, Error> {
+        need(buf, 4)?;
+        let id = Oid(buf.get_u32());
+        let namespace = buf.get_str_nul()?;
+        let name = buf.get_str_nul()?;
+        need(buf, 3)?;
+        let replica_identity = match buf.get_u8() {
+            b'd' => ReplicaIdentity::Default,
+            b'n' => ReplicaIdentity::Nothing,
+            b'f' => ReplicaIdentity::Full,
+            b'i' => ReplicaIdentity::Index,
+            v => return Err(err_protocol!("unknown replica identity: {:?}", v as char)),
+        };
+        let count = buf.get_u16();
+        let mut columns = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            need(buf, 1)?;
+            let key = buf.get_u8() & 1 != 0;
+            let name = buf.get_str_nul()?;
+            need(buf, 8)?;
+            let oid = Oid(buf.get_u32());
+            let type_info = PgTypeInfo::try_from_oid(oid)
+                .or_else(|| self.types.get(&oid).cloned())
+                .unwrap_or_else(|| PgTypeInfo::with_oid(oid));
+            columns.push(RelationColumn {
+                name,
+                key,
+                type_info,
+                type_modifier: buf.get_i32(),
+            });
+        }
+        Ok(Relation {
+            id,
+            namespace,
+            name,
+            replica_identity,
+            columns,
+        })
+    }
+
+    fn read_relation(&self, buf: &mut Bytes) -> Result<Arc<Relation>, Error> {
+        need(buf, 4)?;
+        let id = Oid(buf.get_u32());
+        self.relations
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| err_protocol!("pgoutput change for unknown relation {}", id.0))
+    }
+}
+
+fn need(buf: &Bytes, len: usize) -> Result<(), Error> {
+    if buf.remaining() < len {
+        return Err(err_protocol!("pgoutput message is truncated"));
+    }
+    Ok(())
+}
+
+fn expect_tag(buf: &mut Bytes, tag: u8) -> Result<(), Error> {
+    need(buf, 1)?;
+    match buf.get_u8() {
+        v if v == tag => Ok(()),
+        v => Err(err_protocol!(
+            "expected pgoutput tuple {:?} but received {:?}",
+            tag as char,
+            v as char
+        )),
+    }
+}
+
+/// Reads the optional `K` (key) or `O` (old row) tuple of an update or delete.
+fn decode_old_tuple(
+    relation: &Relation,
+    buf: &mut Bytes,
+) -> Result<(Option<Value>, Option<Value>), Error> {
+    need(buf, 1)?;
+    match buf[0] {
+        b'K' => {
+            buf.advance(1);
+            Ok((Some(decode_tuple(relation, buf)?), None))
+        }
+        b'O' => {
+            buf.advance(1);
+            Ok((None, Some(decode_tuple(relation, buf)?)))
+        }
+        _ => Ok((None, None)),
+    }
+}
+
+fn decode_tuple(relation: &Relation, buf: &mut Bytes) -> Result<Value, Error> {
+    need(buf, 2)?;
+    let count = buf.get_u16() as usize;
+    if count > relation.columns.len() {
+        return Err(err_protocol!(
+            "pgoutput tuple has {} columns but relation {} has {}",
+            count,
+            relation.name,
+            relation.columns.len()
+        ));
+    }
+    let mut map = ValueMap::with_capacity(count);
+    for column in &relation.columns[..count] {
+        need(buf, 1)?;
+        let format = match buf.get_u8() {
+            b'n' => {
+                map.insert(Value::String(column.name.clone()), Value::Null);
+                continue;
+            }
+            // unchanged TOASTed value, not sent
+            b'u' => continue,
+            b't' => PgValueFormat::Text,
+            b'b' => PgValueFormat::Binary,
+            v => {
+                return Err(err_protocol!(
+                    "unknown pgoutput column kind: {:?}",
+                    v as char
+                ))
+            }
+        };
+        need(buf, 4)?;
+        let len = buf.get_u32() as usize;
+        need(buf, len)?;
+        let data = buf.split_to(len).to_vec();
+        let value = match (&column.type_info.0, format) {
+            // a type the server did not describe, keep its text
+            (PgType::DeclareWithOid(_), PgValueFormat::Text) => {
+                Value::String(String::from_utf8(data).map_err(|e| Error::from(e.to_string()))?)
+            }
+            _ => Value::decode(PgValue {
+                value: Some(data),
+                type_info: column.type_info.clone(),
+                format,
+                timezone: None,
+            })
+            .map_err(|e| Error::from(format!("column {}: {}", column.name, e)))?,
+        };
+        map.insert(Value::String(column.name.clone()), value);
+    }
+    Ok(Value::Map(map))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::replication::pgoutput::{PgOutputDecoder, PgOutputMessage, ReplicaIdentity};
+    use crate::replication::PgLsn;
+    use crate::types::Oid;
+    use bytes::{BufMut, Bytes};
+    use rbs::Value;
+
+    fn relation() -> Bytes {
+        let mut buf = vec![b'R'];
+        buf.put_u32(16390);
+        buf.extend(b"public\0users\0");
+        buf.push(b'd');
+        buf.put_u16(3);
+        buf.push(1);
+        buf.extend(b"id\0");
+        buf.put_u32(23);
+        buf.put_i32(-1);
+        buf.push(0);
+        buf.extend(b"name\0");
+        buf.put_u32(25);
+        buf.put_i32(-1);
+        buf.push(0);
+        buf.extend(b"bio\0");
+        buf.put_u32(25);
+        buf.put_i32(-1);
+        Bytes::from(buf)
+    }
+
+    fn tuple(buf: &mut Vec<u8>, id: &str, name: Option<&str>, bio_unchanged: bool) {
+        buf.put_u16(3);
+        buf.push(b't');
+        buf.put_u32(id.len() as u32);
+        buf.extend(id.as_bytes());
+        match name {
+            Some(name) => {
+                buf.push(b't');
+                buf.put_u32(name.len() as u32);
+                buf.extend(name.as_bytes());
+            }
+            None => buf.push(b'n'),
+        }
+        buf.push(if bio_unchanged { b'u' } else { b'n' });
+    }
+
+    #[test]
+    fn test_transaction() {
+        let mut decoder = PgOutputDecoder::new();
+
+        let mut begin = vec![b'B'];
+        begin.put_u64(0x16_B374_D848);
+        begin.put_i64(0);
+        begin.put_u32(742);
+        match decoder.decode(Bytes::from(begin)).unwrap() {
+            PgOutputMessage::Begin(b) => {
+                assert_eq!(b.final_lsn, PgLsn(0x16_B374_D848));
+                assert_eq!(b.xid, 742);
+                assert_eq!(b.commit_time.unix_timestamp_millis(), 946_684_800_000);
+            }
+            v => panic!("{:?}", v),
+        }
+
+        match decoder.decode(relation()).unwrap() {
+            PgOutputMessage::Relation(r) => {
+                assert_eq!(r.name, "users");
+                assert_eq!(r.replica_identity, ReplicaIdentity::Default);
+                assert_eq!(r.columns.len(), 3);
+                assert!(r.columns[0].key);
+            }
+            v => panic!("{:?}", v),
+        }
+        assert!(decoder.relation(Oid(16390)).is_some());
+
+        let mut insert = vec![b'I'];
+        insert.put_u32(16390);
+        insert.push(b'N');
+        tuple(&mut insert, "1", Some("ann"), false);
+        match decoder.decode(Bytes::from(insert)).unwrap() {
+            PgOutputMessage::Insert(i) => {
+                assert_eq!(i.relation.name, "users");
+                assert_eq!(i.new["id"], Value::I32(1));
+                assert_eq!(i.new["name"], Value::String("ann".to_string()));
+                assert_eq!(i.new["bio"], Value::Null);
+            }
+            v => panic!("{:?}", v),
+        }
+
+        let mut update = vec![b'U'];
+        update.put_u32(16390);
+        update.push(b'K');
+        tuple(&mut update, "1", None, false);
+        update.push(b'N');
+        tuple(&mut update, "2", Some("bob"), true);
+        match decoder.decode(Bytes::from(update)).unwrap() {
+            PgOutputMessage::Update(u) => {
+                assert_eq!(u.key.unwrap()["id"], Value::I32(1));
+                assert!(u.old.is_none());
+                assert_eq!(u.new["id"], Value::I32(2));
+                assert_eq!(u.new.as_map().unwrap().len(), 2);
+            }
+            v => panic!("{:?}", v),
+        }
+
+        let mut delete = vec![b'D'];
+        delete.put_u32(16390);
+        delete.push(b'O');
+        tuple(&mut delete, "2", Some("bob"), false);
+        match decoder.decode(Bytes::from(delete)).unwrap() {
+            PgOutputMessage::Delete(d) => {
+                assert!(d.key.is_none());
+                assert_eq!(d.old.unwrap()["name"], Value::String("bob".to_string()));
+            }
+            v => panic!("{:?}", v),
+        }
+
+        let mut truncate = vec![b'T'];
+        truncate.put_u32(1);
+        truncate.push(1);
+        truncate.put_u32(16390);
+        match decoder.decode(Bytes::from(truncate)).unwrap() {
+            PgOutputMessage::Truncate(t) => {
+                assert!(t.cascade);
+                assert!(!t.restart_identity);
+                assert_eq!(t.relations.len(), 1);
+            }
+            v => panic!("{:?}", v),
+        }
+
+        let mut commit = vec![b'C', 0];
+        commit.put_u64(0x16_B374_D848);
+        commit.put_u64(0x16_B374_D878);
+        commit.put_i64(1_000_000);
+        match decoder.decode(Bytes::from(commit)).unwrap() {
+            PgOutputMessage::Commit(c) => {
+                assert_eq!(c.end_lsn, PgLsn(0x16_B374_D878));
+                assert_eq!(c.commit_time.unix_timestamp_millis(), 946_684_801_000);
+            }
+            v => panic!("{:?}", v),
+        }
+    }
+
+    #[test]
+    fn test_unknown_relation() {
+        let mut decoder = PgOutputDecoder::new();
+        let mut insert = vec![b'I'];
+        insert.put_u32(1);
+        insert.push(b'N');
+        insert.put_u16(0);
+        assert!(decoder.decode(Bytes::from(insert)).is_err());
+        assert!(decoder.decode(Bytes::from_static(b"B\0\0")).is_err());
+
+        let mut truncate = vec![b'T'];
+        truncate.put_u32(u32::MAX);
+        truncate.push(0);
+        truncate.put_u32(1);
+        assert!(decoder.decode(Bytes::from(truncate)).is_err());
+    }
+
+    #[test]
+    fn test_custom_type() {
+        let mut decoder = PgOutputDecoder::new();
+        let mut ty = vec![b'Y'];
+        ty.put_u32(16500);
+        ty.extend(b"public\0hstore\0");
+        decoder.decode(Bytes::from(ty)).unwrap();
+
+        let mut rel = vec![b'R'];
+        rel.put_u32(16391);
+        rel.extend(b"public\0docs\0");
+        rel.push(b'f');
+        rel.put_u16(2);
+        rel.push(0);
+        rel.extend(b"attrs\0");
+        rel.put_u32(16500);
+        rel.put_i32(-1);
+        rel.push(0);
+        rel.extend(b"other\0");
+        rel.put_u32(16600);
+        rel.put_i32(-1);
+        decoder.decode(Bytes::from(rel)).unwrap();
+
+        let mut insert = vec![b'I'];
+        insert.put_u32(16391);
+        insert.push(b'N');
+        insert.put_u16(2);
+        insert.push(b't');
+        insert.put_u32(8);
+        insert.extend(b"\"a\"=>\"1\"");
+        insert.push(b't');
+        insert.put_u32(3);
+        insert.extend(b"xyz");
+        match decoder.decode(Bytes::from(insert)).unwrap() {
+            PgOutputMessage::Insert(i) => {
+                assert_eq!(i.new["attrs"]["a"], Value::String("1".to_string()));
+                assert_eq!(i.new["other"], Value::String("xyz".to_string()));
+            }
+            v => panic!("{:?}", v),
+        }
+    }
+}
@@ -2,6 +2,7 @@ use crate::column::PgColumn;
 use crate::message::DataRow;
 use crate::meta_data::PgMetaData;
 use crate::statement::PgStatementMetadata;
+use crate::types::timezone::PgTimeZone;
 use crate::types::decode::Decode;
 use crate::value::{PgValue, PgValueFormat, PgValueRef};
 use rbdc::db::MetaData;
@@ -15,6 +16,8 @@ pub struct PgRow {
     pub(crate) data: DataRow,
     pub(crate) format: PgValueFormat,
     pub(crate) metadata: Arc<PgStatementMetadata>,
+    // the session time zone that `timestamptz` values are shown in
+    pub(crate) timezone: Option<PgTimeZone>,
 }
 
 impl PgRow {
@@ -44,6 +47,7 @@ impl PgRow {
             value: value,
             type_info: column.type_info.clone(),
             format: self.format,
+            timezone: None,
         })
     }
 }
@@ -68,7 +72,85 @@ impl rbdc::db::Row for PgRow {
     fn get(&mut self, i: usize) -> Result<Value, Error> {
         match self.try_take(i) {
             Err(e) => Err(Error::from(format!("get error  index:{},error:{}", i, e))),
-            Ok(v) => Value::decode(v),
+            Ok(v) => Value::decode(v.with_timezone(self.timezone.clone())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::column::PgColumn;
+    use crate::message::DataRow;
+    use crate::row::PgRow;
+    use crate::statement::PgStatementMetadata;
+    use crate::type_info::PgTypeInfo;
+    use crate::types::range::{PgRange, PgRangeBound};
+    use crate::types::timezone::PgTimeZone;
+    use crate::value::PgValueFormat;
+    use rbdc::db::Row;
+    use rbdc::io::Decode;
+    use rbdc::DateTime;
+    use rbs::Value;
+    use std::sync::Arc;
+
+    // 2000-01-01 00:00:00 UTC
+    const MIDNIGHT: [u8; 8] = [0; 8];
+
+    /// a row with one column of type `type_info` holding `value`, in the `+08` session zone
+    fn row_of(type_info: PgTypeInfo, value: &[u8]) -> PgRow {
+        let mut data = vec![0, 1];
+        data.extend((value.len() as i32).to_be_bytes());
+        data.extend(value);
+        let mut metadata = PgStatementMetadata::default();
+        metadata.columns.push(PgColumn {
+            ordinal: 0,
+            name: "t".into(),
+            type_info,
+            relation_id: None,
+            relation_attribute_no: None,
+        });
+        metadata.column_names.insert("t".into(), 0);
+        PgRow {
+            data: DataRow::decode(data.into()).unwrap(),
+            format: PgValueFormat::Binary,
+            metadata: Arc::new(metadata),
+            timezone: PgTimeZone::parse("<+08>-08"),
+        }
+    }
+
+    fn assert_in_session_time_zone(v: Value) {
+        let dt: DateTime = rbs::from_value(v).unwrap();
+        assert_eq!(dt.offset(), 8 * 3600);
+        assert_eq!(dt.unix_timestamp(), 946_684_800);
+    }
+
+    #[test]
+    fn test_timestamptz_in_session_time_zone() {
+        let mut row = row_of(PgTypeInfo::TIMESTAMPTZ, &MIDNIGHT);
+        assert_in_session_time_zone(row.get(0).unwrap());
+
+        // one dimension of one element
+        let mut array = vec![0, 0, 0, 1, 0, 0, 0, 0];
+        array.extend(PgTypeInfo::TIMESTAMPTZ.0.oid().0.to_be_bytes());
+        array.extend([0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 8]);
+        array.extend(MIDNIGHT);
+        let mut row = row_of(PgTypeInfo::TIMESTAMPTZ_ARRAY, &array);
+        match row.get(0).unwrap() {
+            Value::Array(mut v) => assert_in_session_time_zone(v.remove(0)),
+            v => panic!("not an array: {}", v),
+        }
+
+        // `[midnight,)`
+        let mut range = vec![0x02 | 0x10, 0, 0, 0, 8];
+        range.extend(MIDNIGHT);
+        let mut row = row_of(PgTypeInfo::TSTZ_RANGE, &range);
+        let range: PgRange<Value> = match row.get(0).unwrap() {
+            Value::Ext("TstzRange", v) => rbs::from_value(*v).unwrap(),
+            v => panic!("not a tstzrange: {}", v),
+        };
+        match range.lower {
+            PgRangeBound::Inclusive(v) => assert_in_session_time_zone(v),
+            bound => panic!("unexpected lower bound {:?}", bound),
         }
     }
 }
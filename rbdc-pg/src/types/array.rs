@@ -2,6 +2,8 @@ use crate::arguments::PgArgumentBuffer;
 use crate::type_info::{PgType, PgTypeInfo, PgTypeKind};
 use crate::types::decode::Decode;
 use crate::types::encode::{Encode, IsNull};
+use crate::types::json::encode_json;
+use crate::types::timezone::PgTimeZone;
 use crate::types::{Oid, TypeInfo};
 use crate::value::{PgValue, PgValueFormat};
 use bytes::Buf;
@@ -56,14 +58,14 @@ impl<T: Decode + TypeInfo> Decode for Vec<T> {
                     return Err(format!("encountered an array with a lower bound of {} in the first dimension; only arrays starting at one are supported", lower).into());
                 }
 
-                let mut elements = Vec::with_capacity(len as usize);
+                // each element takes at least its length
+                let mut elements = Vec::with_capacity((len.max(0) as usize).min(buf.len() / 4));
 
                 for _ in 0..len {
-                    elements.push(T::decode(PgValue::get(
-                        &mut buf,
-                        format,
-                        element_type_info.clone(),
-                    ))?)
+                    elements.push(T::decode(
+                        PgValue::get(&mut buf, format, element_type_info.clone())?
+                            .with_timezone(value.timezone.clone()),
+                    )?)
                 }
 
                 Ok(elements)
@@ -82,6 +84,7 @@ impl<T: Decode + TypeInfo> Decode for Vec<T> {
                     }
                     PgTypeKind::Enum(_) => {}
                     PgTypeKind::Range(_) => {}
+                    PgTypeKind::Multirange(_) => {}
                 }
 
                 let s = value.as_str()?;
@@ -98,11 +101,11 @@ impl<T: Decode + TypeInfo> Decode for Vec<T> {
 
                 // NOTE: Nearly *all* types use ',' as the sequence delimiter. Yes, there is one
                 //       that does not. The BOX (not PostGIS) type uses ';' as a delimiter.
-
-                // TODO: When we add support for BOX we need to figure out some way to make the
-                //       delimiter selection
-
-                let delimiter = ',';
+                let delimiter = if element_type_info == PgTypeInfo::BOX {
+                    ';'
+                } else {
+                    ','
+                };
                 let mut done = false;
                 let mut in_quotes = false;
                 let mut in_escape = false;
@@ -152,6 +155,7 @@ impl<T: Decode + TypeInfo> Decode for Vec<T> {
                         value: value_opt,
                         type_info: element_type_info.clone(),
                         format,
+                        timezone: None,
                     })?);
 
                     value.clear();
@@ -163,31 +167,400 @@ impl<T: Decode + TypeInfo> Decode for Vec<T> {
     }
 }
 
-fn element_type_info<T: TypeInfo>(arg: &Vec<T>) -> PgTypeInfo {
-    if arg.len() == 0 {
-        PgTypeInfo::UNKNOWN
+/// Infers the element type of a `Value::Array` from its elements, looking through nested
+/// arrays and NULLs. Mixed integer or float widths widen to `INT8` and `FLOAT8`.
+///
+/// Returns `None` for arrays of maps, arrays of mixed types, and arrays without any
+/// non-NULL element.
+pub(crate) fn infer_element_type(values: &[Value]) -> Option<PgTypeInfo> {
+    let mut element: Option<PgTypeInfo> = None;
+    for v in values {
+        let ty = match v {
+            Value::Null => continue,
+            Value::Array(v) => match infer_element_type(v) {
+                Some(ty) => ty,
+                None if v.iter().all(is_null_or_empty) => continue,
+                None => return None,
+            },
+            Value::Map(_) => return None,
+            Value::String(_) => PgTypeInfo::TEXT,
+            v => v.type_info(),
+        };
+        if ty == PgTypeInfo::UNKNOWN {
+            return None;
+        }
+        element = Some(match element {
+            None => ty,
+            Some(prev) if prev == ty => prev,
+            Some(prev) => widen(&prev, &ty)?,
+        });
+    }
+    element
+}
+
+fn is_null_or_empty(v: &Value) -> bool {
+    match v {
+        Value::Null => true,
+        Value::Array(v) => v.iter().all(is_null_or_empty),
+        _ => false,
+    }
+}
+
+fn widen(a: &PgTypeInfo, b: &PgTypeInfo) -> Option<PgTypeInfo> {
+    let ints = [PgTypeInfo::INT4, PgTypeInfo::INT8];
+    let floats = [PgTypeInfo::FLOAT4, PgTypeInfo::FLOAT8];
+    if ints.contains(a) && ints.contains(b) {
+        Some(PgTypeInfo::INT8)
+    } else if floats.contains(a) && floats.contains(b) {
+        Some(PgTypeInfo::FLOAT8)
     } else {
-        arg[0].type_info()
+        None
     }
 }
 
-impl Encode for Vec<Value> {
-    fn encode(self, buf: &mut PgArgumentBuffer) -> Result<IsNull, Error> {
-        let type_info = element_type_info(&self);
-        buf.extend(&1_i32.to_be_bytes()); // number of dimensions
-        buf.extend(&0_i32.to_be_bytes()); // flags
-                                          // element type
-        match type_info.0 {
-            PgType::DeclareWithName(name) => buf.patch_type_by_name(&name),
-            ty => {
-                buf.extend(&ty.oid().0.to_be_bytes());
+/// The length of each dimension of a (possibly nested) `Value::Array`. Every sub-array of a
+/// dimension must have the same length.
+fn dimensions(values: &[Value]) -> Result<Vec<i32>, Error> {
+    let mut dims = vec![values.len() as i32];
+    if let Some(Value::Array(first)) = values.first() {
+        let inner = dimensions(first)?;
+        for v in values {
+            match v {
+                Value::Array(v) if dimensions(v)? == inner => {}
+                _ => {
+                    return Err(Error::from(
+                        "multidimensional arrays must have sub-arrays with matching dimensions",
+                    ))
+                }
             }
         }
-        buf.extend(&(self.len() as i32).to_be_bytes()); // len
+        dims.extend(inner);
+    }
+    Ok(dims)
+}
+
+fn flatten(values: Vec<Value>, out: &mut Vec<Value>) {
+    for v in values {
+        match v {
+            Value::Array(v) => flatten(v, out),
+            v => out.push(v),
+        }
+    }
+}
+
+/// Encodes a (possibly multi-dimensional) `Value::Array` in the binary array format with
+/// `element` as the element type. NULL elements are allowed.
+///
+/// binary format: ndim, has-null flag, element oid, then length and lower bound of each
+/// dimension, then the length prefixed elements in row-major order
+pub(crate) fn encode_array(
+    values: Vec<Value>,
+    element: &PgTypeInfo,
+    buf: &mut PgArgumentBuffer,
+) -> Result<IsNull, Error> {
+    let dims = if values.is_empty() {
+        vec![]
+    } else {
+        dimensions(&values)?
+    };
+    let count = dims
+        .iter()
+        .try_fold(1_usize, |n, len| n.checked_mul(*len as usize))
+        .ok_or_else(|| Error::from("array has too many elements"))?;
+    let mut elements = Vec::with_capacity(count);
+    flatten(values, &mut elements);
+    let has_null = elements.iter().any(Value::is_null);
+
+    buf.extend(&(dims.len() as i32).to_be_bytes());
+    buf.extend(&(has_null as i32).to_be_bytes());
+    match &element.0 {
+        PgType::DeclareWithName(name) => buf.patch_type_by_name(name),
+        ty => buf.extend(&ty.try_oid().unwrap_or(Oid(0)).0.to_be_bytes()),
+    }
+    for len in &dims {
+        buf.extend(&len.to_be_bytes());
         buf.extend(&1_i32.to_be_bytes()); // lower bound
-        for element in self {
-            buf.encode(element)?;
+    }
+    for v in elements {
+        buf.encode_as(v, element)?;
+    }
+    Ok(IsNull::No)
+}
+
+/// Decodes an array of any number of dimensions into nested `Value::Array`s.
+pub(crate) fn decode_value(value: PgValue) -> Result<Value, Error> {
+    let element_type_info = value
+        .type_info
+        .try_array_element()
+        .map(Cow::into_owned)
+        .unwrap_or(PgTypeInfo::UNKNOWN);
+    match value.format() {
+        PgValueFormat::Binary => {
+            let format = value.format();
+            let mut buf = value.as_bytes()?;
+            if buf.len() < 12 {
+                return Err(Error::from("array value is too short"));
+            }
+            let ndim = buf.get_i32();
+            let _flags = buf.get_i32();
+            let element_type_oid = Oid(buf.get_u32());
+            let element_type_info =
+                PgTypeInfo::try_from_oid(element_type_oid).unwrap_or(element_type_info);
+            if ndim <= 0 {
+                return Ok(Value::Array(vec![]));
+            }
+            if buf.len() < ndim as usize * 8 {
+                return Err(Error::from("array value is truncated"));
+            }
+            let mut dims = Vec::with_capacity(ndim as usize);
+            for _ in 0..ndim {
+                dims.push(buf.get_i32().max(0) as usize);
+                let _lower = buf.get_i32();
+            }
+            // each element takes at least its length
+            let count = dims
+                .iter()
+                .try_fold(1_usize, |n, len| n.checked_mul(*len))
+                .filter(|n| *n <= buf.len() / 4)
+                .ok_or_else(|| Error::from("array value is truncated"))?;
+            let mut elements = Vec::with_capacity(count);
+            for _ in 0..count {
+                if buf.len() < 4 {
+                    return Err(Error::from("array value is truncated"));
+                }
+                elements.push(Value::decode(
+                    PgValue::get(&mut buf, format, element_type_info.clone())?
+                        .with_timezone(value.timezone.clone()),
+                )?);
+            }
+            Ok(nest(&mut elements.into_iter(), &dims))
+        }
+        PgValueFormat::Text => {
+            let s = value.as_str()?;
+            // arrays with a lower bound other than one are prefixed with `[0:1]=`
+            let s = match s.find('=') {
+                Some(i) if s.starts_with('[') => &s[i + 1..],
+                _ => s,
+            };
+            let delimiter = if element_type_info == PgTypeInfo::BOX {
+                ';'
+            } else {
+                ','
+            };
+            let mut chars = s.trim().chars().peekable();
+            parse_text(&mut chars, delimiter, &element_type_info, &value.timezone)
         }
-        Ok(IsNull::No)
+    }
+}
+
+fn nest(elements: &mut impl Iterator<Item = Value>, dims: &[usize]) -> Value {
+    match dims.split_first() {
+        Some((len, [])) => Value::Array(elements.take(*len).collect()),
+        Some((len, rest)) => Value::Array((0..*len).map(|_| nest(elements, rest)).collect()),
+        None => Value::Array(vec![]),
+    }
+}
+
+/// Parses one `{...}` level of the text array format.
+fn parse_text(
+    chars: &mut std::iter::Peekable<std::str::Chars>,
+    delimiter: char,
+    element_type_info: &PgTypeInfo,
+    timezone: &Option<PgTimeZone>,
+) -> Result<Value, Error> {
+    if chars.next() != Some('{') {
+        return Err(Error::from("invalid array literal"));
+    }
+    let mut elements = vec![];
+    if chars.peek() == Some(&'}') {
+        chars.next();
+        return Ok(Value::Array(elements));
+    }
+    loop {
+        if chars.peek() == Some(&'{') {
+            elements.push(parse_text(chars, delimiter, element_type_info, timezone)?);
+        } else {
+            let mut value = String::new();
+            let mut quoted = false;
+            let mut in_quotes = false;
+            while let Some(&ch) = chars.peek() {
+                if !in_quotes && (ch == delimiter || ch == '}') {
+                    break;
+                }
+                chars.next();
+                match ch {
+                    '\\' => value.extend(chars.next()),
+                    '"' => {
+                        in_quotes = !in_quotes;
+                        quoted = true;
+                    }
+                    _ => value.push(ch),
+                }
+            }
+            let value = if !quoted && value == "NULL" {
+                None
+            } else {
+                Some(value.into_bytes())
+            };
+            elements.push(Value::decode(PgValue {
+                value,
+                type_info: element_type_info.clone(),
+                format: PgValueFormat::Text,
+                timezone: timezone.clone(),
+            })?);
+        }
+        match chars.next() {
+            Some('}') => return Ok(Value::Array(elements)),
+            Some(c) if c == delimiter => {}
+            _ => return Err(Error::from("invalid array literal")),
+        }
+    }
+}
+
+impl Encode for Vec<Value> {
+    fn encode(self, buf: &mut PgArgumentBuffer) -> Result<IsNull, Error> {
+        match infer_element_type(&self) {
+            Some(element) => encode_array(self, &element, buf),
+            None => encode_json(Value::Array(self), buf),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::arguments::PgArgumentBuffer;
+    use crate::type_info::PgTypeInfo;
+    use crate::types::array::{decode_value, infer_element_type};
+    use crate::types::encode::Encode;
+    use crate::types::geometry::Point;
+    use crate::value::{PgValue, PgValueFormat};
+    use rbdc::network::Inet;
+    use rbs::Value;
+
+    fn ints(values: &[i32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_be_bytes()).collect()
+    }
+
+    #[test]
+    fn test_infer_element_type() {
+        let v = vec![Value::I32(1), Value::Null, Value::I64(2)];
+        assert_eq!(infer_element_type(&v), Some(PgTypeInfo::INT8));
+        let v = vec![Value::from(Point { x: 1.0, y: 2.0 }), Value::Null];
+        assert_eq!(infer_element_type(&v), Some(PgTypeInfo::POINT));
+        let inet: Inet = "10.0.0.1".parse().unwrap();
+        let v = vec![Value::from(inet)];
+        assert_eq!(infer_element_type(&v), Some(PgTypeInfo::INET));
+        let v = vec![Value::Array(vec![Value::F32(1.0)]), Value::Array(vec![])];
+        assert_eq!(infer_element_type(&v), Some(PgTypeInfo::FLOAT4));
+        let v = vec![Value::I32(1), Value::String("x".to_string())];
+        assert_eq!(infer_element_type(&v), None);
+        assert_eq!(infer_element_type(&[Value::Null]), None);
+    }
+
+    #[test]
+    fn test_encode_int_array() {
+        let mut buf = PgArgumentBuffer::default();
+        vec![Value::I32(1), Value::Null, Value::I32(3)]
+            .encode(&mut buf)
+            .unwrap();
+        let mut expect = ints(&[1, 1, 23, 3, 1]);
+        expect.extend(ints(&[4, 1, -1, 4, 3]));
+        assert_eq!(&buf[..], &expect[..]);
+    }
+
+    #[test]
+    fn test_encode_decode_2d() {
+        let v = vec![
+            Value::Array(vec![Value::I64(1), Value::I32(2)]),
+            Value::Array(vec![Value::I64(3), Value::Null]),
+        ];
+        let mut buf = PgArgumentBuffer::default();
+        v.encode(&mut buf).unwrap();
+        assert_eq!(&buf[..20], &ints(&[2, 1, 20, 2, 1])[..]);
+        let decoded = decode_value(PgValue {
+            value: Some(buf.to_vec()),
+            type_info: PgTypeInfo::INT8_ARRAY,
+            format: PgValueFormat::Binary,
+            timezone: None,
+        })
+        .unwrap();
+        assert_eq!(
+            decoded,
+            Value::Array(vec![
+                Value::Array(vec![Value::I64(1), Value::I64(2)]),
+                Value::Array(vec![Value::I64(3), Value::Null]),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_encode_ragged_and_mixed() {
+        let mut buf = PgArgumentBuffer::default();
+        let ragged = vec![
+            Value::Array(vec![Value::I32(1)]),
+            Value::Array(vec![Value::I32(2), Value::I32(3)]),
+        ];
+        assert!(ragged.encode(&mut buf).is_err());
+        // mixed element types fall back to json
+        let mut buf = PgArgumentBuffer::default();
+        vec![Value::I32(1), Value::String("a".to_string())]
+            .encode(&mut buf)
+            .unwrap();
+        assert_eq!(&buf[..], &b"\x01[1,\"a\"]"[..]);
+    }
+
+    #[test]
+    fn test_decode_bogus_dimensions() {
+        let decode = |dims: &[i32]| {
+            let mut bytes = ints(&[dims.len() as i32, 0, 23]);
+            for len in dims {
+                bytes.extend(ints(&[*len, 1]));
+            }
+            bytes.extend(ints(&[4, 1]));
+            decode_value(PgValue {
+                value: Some(bytes),
+                type_info: PgTypeInfo::INT4_ARRAY,
+                format: PgValueFormat::Binary,
+                timezone: None,
+            })
+        };
+        assert_eq!(decode(&[1]).unwrap(), Value::Array(vec![Value::I32(1)]));
+        // the element count overflows
+        assert!(decode(&[i32::MAX, i32::MAX, i32::MAX]).is_err());
+        // more elements than the value holds
+        assert!(decode(&[i32::MAX]).is_err());
+        assert!(decode(&[2]).is_err());
+    }
+
+    #[test]
+    fn test_decode_text() {
+        let v = decode_value(PgValue {
+            value: Some(br#"{{1,NULL},{"3",4}}"#.to_vec()),
+            type_info: PgTypeInfo::INT4_ARRAY,
+            format: PgValueFormat::Text,
+            timezone: None,
+        })
+        .unwrap();
+        assert_eq!(
+            v,
+            Value::Array(vec![
+                Value::Array(vec![Value::I32(1), Value::Null]),
+                Value::Array(vec![Value::I32(3), Value::I32(4)]),
+            ])
+        );
+        let v = decode_value(PgValue {
+            value: Some(br#"[0:1]={"a,b","NULL"}"#.to_vec()),
+            type_info: PgTypeInfo::TEXT_ARRAY,
+            format: PgValueFormat::Text,
+            timezone: None,
+        })
+        .unwrap();
+        assert_eq!(
+            v,
+            Value::Array(vec![
+                Value::String("a,b".to_string()),
+                Value::String("NULL".to_string()),
+            ])
+        );
     }
 }
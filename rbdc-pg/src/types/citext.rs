@@ -0,0 +1,39 @@
+use crate::arguments::PgArgumentBuffer;
+use crate::types::decode::Decode;
+use crate::types::encode::{Encode, IsNull};
+use crate::value::PgValue;
+use rbdc::Error;
+use rbs::Value;
+use std::fmt::{Display, Formatter};
+
+/// A case-insensitive string of the `citext` extension, sent over the wire the same as `text`.
+///
+/// Decoded columns are a `Value::String`. Bind a `PgCiText` where the parameter type cannot be
+/// inferred from the query, the type oid is resolved by name when the statement is prepared.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default, Eq, PartialEq, Hash)]
+#[serde(rename = "Citext")]
+pub struct PgCiText(pub String);
+
+impl Display for PgCiText {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<PgCiText> for Value {
+    fn from(arg: PgCiText) -> Self {
+        Value::Ext("Citext", Box::new(Value::String(arg.0)))
+    }
+}
+
+impl Decode for PgCiText {
+    fn decode(value: PgValue) -> Result<Self, Error> {
+        Ok(Self(String::decode(value)?))
+    }
+}
+
+impl Encode for PgCiText {
+    fn encode(self, buf: &mut PgArgumentBuffer) -> Result<IsNull, Error> {
+        self.0.encode(buf)
+    }
+}
@@ -0,0 +1,358 @@
+//! Converts bind values to the parameter types described by the server.
+//!
+//! A statement is parsed once and then executed with whatever values the caller passes, so the
+//! `Value` variant of an argument does not always match the type of its parameter (a string bound
+//! to an `int4` column, an `i64` bound to a `numeric` column, an `i32` bound to a statement that
+//! was first prepared with an `i64`). Each argument is converted to the described type before it
+//! is encoded. Integers out of the range of the type and strings that do not parse are rejected,
+//! as are integers beyond 2^24 (`float4`) or 2^53 (`float8`), where a float no longer holds every
+//! integer. A float bound to a `float4` is rounded to the nearest `float4`, as the server rounds
+//! a float literal.
+use crate::type_info::{PgType, PgTypeInfo, PgTypeKind};
+use crate::types::TypeInfo;
+use rbdc::decimal::Decimal;
+use rbdc::Error;
+use rbs::Value;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Converts `value` to a `Value` whose encoding is the parameter type `ty`.
+///
+/// Values of types without a conversion are returned unchanged, except for strings, maps and
+/// arrays: their bytes are not the binary format of such a type, so they are rejected.
+pub(crate) fn coerce(value: Value, ty: &PgTypeInfo) -> Result<Value, Error> {
+    if value.is_null() || value.type_info() == *ty {
+        return Ok(value);
+    }
+    match &ty.0 {
+        PgType::Bool => to_bool(value, ty).map(Value::Bool),
+        PgType::Int4 => {
+            to_int(value, ty, i32::MIN as i64, i32::MAX as i64).map(|v| Value::I32(v as i32))
+        }
+        PgType::Int8 => to_int(value, ty, i64::MIN, i64::MAX).map(Value::I64),
+        PgType::Oid => to_int(value, ty, 0, u32::MAX as i64)
+            .map(|v| Value::Ext("Oid", Box::new(Value::U64(v as u64)))),
+        PgType::Float4 => to_float(value, ty, f32::MANTISSA_DIGITS).and_then(|v| {
+            // a finite double beyond the range of a float4 would become infinite
+            if v.is_finite() && (v as f32).is_infinite() {
+                return Err(invalid(&Value::F64(v), ty, "out of range"));
+            }
+            Ok(Value::F32(v as f32))
+        }),
+        PgType::Float8 => to_float(value, ty, f64::MANTISSA_DIGITS).map(Value::F64),
+        PgType::Numeric => to_decimal(value, ty).map(Value::from),
+        PgType::Uuid => match string_of(value, &["Uuid"]) {
+            Ok(s) => uuid::Uuid::from_str(&s)
+                .map(|v| Value::Ext("Uuid", Box::new(Value::String(v.to_string()))))
+                .map_err(|e| invalid(&Value::String(s), ty, e)),
+            Err(v) => Err(invalid(&v, ty, "")),
+        },
+        PgType::Date => match value {
+            Value::String(s) => fastdate::Date::from_str(&s)
+                .map(|v| Value::Ext("Date", Box::new(Value::String(v.to_string()))))
+                .map_err(|e| invalid(&Value::String(s), ty, e)),
+            v => Err(invalid(&v, ty, "")),
+        },
+        PgType::Time => match value {
+            Value::String(s) => fastdate::Time::from_str(&s)
+                .map(|v| Value::Ext("Time", Box::new(Value::String(v.to_string()))))
+                .map_err(|e| invalid(&Value::String(s), ty, e)),
+            v => Err(invalid(&v, ty, "")),
+        },
+        PgType::Timestamp | PgType::Timestamptz => match value {
+            // timestamps are all encoded as microseconds since 2000-01-01
+            v @ Value::Ext("Timestamp" | "DateTime" | "Timestamptz", _) => Ok(v),
+            Value::String(s) => fastdate::DateTime::from_str(&s)
+                .map(|v| Value::Ext("DateTime", Box::new(Value::String(v.to_string()))))
+                .map_err(|e| invalid(&Value::String(s), ty, e)),
+            v => Err(invalid(&v, ty, "")),
+        },
+        PgType::Text | PgType::Varchar | PgType::Bpchar | PgType::Name => match value {
+            v @ (Value::Bool(_)
+            | Value::I32(_)
+            | Value::I64(_)
+            | Value::U32(_)
+            | Value::U64(_)
+            | Value::F32(_)
+            | Value::F64(_)) => Ok(Value::String(v.to_string())),
+            v => string_of(v, &[])
+                .map(Value::String)
+                .map_err(|v| invalid(&v, ty, "")),
+        },
+        PgType::Bytea => match value {
+            Value::Binary(v) => Ok(Value::Binary(v)),
+            Value::String(s) => Ok(Value::Binary(s.into_bytes())),
+            Value::Ext("Bytea", v) => Ok(*v),
+            v => Err(invalid(&v, ty, "")),
+        },
+        // the binary format of an enum is its label
+        _ if matches!(ty.kind(), PgTypeKind::Enum(_)) || *ty == PgTypeInfo::UNKNOWN => Ok(value),
+        _ => match value {
+            v @ (Value::String(_) | Value::Map(_) | Value::Array(_)) => {
+                Err(invalid(&v, ty, "no conversion"))
+            }
+            v => Ok(v),
+        },
+    }
+}
+
+/// Returns the text of `value` as a json document. A string is taken to already be json, the
+/// same as a string literal in SQL.
+pub(crate) fn to_json_text(value: Value) -> Result<String, Error> {
+    match string_of(value, &["Json", "Jsonb"]) {
+        Ok(s) => Ok(s),
+        Err(v) => serde_json::to_string(&v).map_err(|e| Error::from(e.to_string())),
+    }
+}
+
+/// The string held by a `Value::String`, or by a `Value::Ext` named in `names` (any name when
+/// `names` is empty).
+fn string_of(value: Value, names: &[&str]) -> Result<String, Value> {
+    match value {
+        Value::String(s) => Ok(s),
+        Value::Ext(name, v) if v.is_str() && (names.is_empty() || names.contains(&name)) => {
+            Ok(v.into_string().unwrap_or_default())
+        }
+        v => Err(v),
+    }
+}
+
+fn invalid(value: &Value, ty: &PgTypeInfo, reason: impl Display) -> Error {
+    let reason = reason.to_string();
+    if reason.is_empty() {
+        Error::from(format!("cannot convert {} to {}", value, ty.name()))
+    } else {
+        Error::from(format!(
+            "cannot convert {} to {}: {}",
+            value,
+            ty.name(),
+            reason
+        ))
+    }
+}
+
+fn to_bool(value: Value, ty: &PgTypeInfo) -> Result<bool, Error> {
+    match &value {
+        Value::Bool(v) => Ok(*v),
+        Value::I32(0) | Value::I64(0) | Value::U32(0) | Value::U64(0) => Ok(false),
+        Value::I32(1) | Value::I64(1) | Value::U32(1) | Value::U64(1) => Ok(true),
+        Value::String(s) => match s.trim().to_ascii_lowercase().as_str() {
+            "t" | "true" | "y" | "yes" | "on" | "1" => Ok(true),
+            "f" | "false" | "n" | "no" | "off" | "0" => Ok(false),
+            _ => Err(invalid(&value, ty, "")),
+        },
+        _ => Err(invalid(&value, ty, "")),
+    }
+}
+
+pub(crate) fn to_int(value: Value, ty: &PgTypeInfo, min: i64, max: i64) -> Result<i64, Error> {
+    let v: i128 = match &value {
+        Value::I32(v) => *v as i128,
+        Value::I64(v) => *v as i128,
+        Value::U32(v) => *v as i128,
+        Value::U64(v) => *v as i128,
+        Value::F32(v) if v.fract() == 0.0 => *v as i128,
+        Value::F64(v) if v.fract() == 0.0 => *v as i128,
+        Value::String(s) => s
+            .trim()
+            .parse::<i128>()
+            .map_err(|e| invalid(&value, ty, e))?,
+        Value::Ext("Int2" | "Int4" | "Int8" | "Oid", v) => return to_int(*v.clone(), ty, min, max),
+        Value::Ext("Decimal", v) => match v.as_str().map(str::parse::<i128>) {
+            Some(Ok(v)) => v,
+            _ => return Err(invalid(&value, ty, "not an integer")),
+        },
+        _ => return Err(invalid(&value, ty, "")),
+    };
+    if v < min as i128 || v > max as i128 {
+        return Err(invalid(&value, ty, "out of range"));
+    }
+    Ok(v as i64)
+}
+
+/// `value` as a float, an integer only up to 2^`digits` where a float of `digits` mantissa bits
+/// still holds every integer.
+fn to_float(value: Value, ty: &PgTypeInfo, digits: u32) -> Result<f64, Error> {
+    let exact = |v: i128| {
+        if v.unsigned_abs() > 1u128 << digits {
+            return Err(invalid(&value, ty, "out of precision"));
+        }
+        Ok(v as f64)
+    };
+    match &value {
+        Value::I32(v) => exact(*v as i128),
+        Value::I64(v) => exact(*v as i128),
+        Value::U32(v) => exact(*v as i128),
+        Value::U64(v) => exact(*v as i128),
+        Value::F32(v) => Ok(*v as f64),
+        Value::F64(v) => Ok(*v),
+        Value::String(s) => s.trim().parse().map_err(|e| invalid(&value, ty, e)),
+        Value::Ext("Decimal" | "Float4" | "Float8", v) => match v.as_ref() {
+            Value::String(s) => s.parse().map_err(|e| invalid(&value, ty, e)),
+            v => to_float(v.clone(), ty, digits),
+        },
+        _ => Err(invalid(&value, ty, "")),
+    }
+}
+
+fn to_decimal(value: Value, ty: &PgTypeInfo) -> Result<Decimal, Error> {
+    let s = match &value {
+        Value::I32(_) | Value::I64(_) | Value::U32(_) | Value::U64(_) => value.to_string(),
+        Value::F32(v) if v.is_finite() => value.to_string(),
+        Value::F64(v) if v.is_finite() => value.to_string(),
+        Value::String(s) => s.trim().to_string(),
+        Value::Ext("Decimal" | "Numeric", v) => v.as_str().unwrap_or_default().to_string(),
+        _ => return Err(invalid(&value, ty, "")),
+    };
+    Decimal::from_str(&s).map_err(|e| invalid(&value, ty, e))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::arguments::PgArguments;
+    use crate::type_info::PgTypeInfo;
+    use crate::types::coerce::{coerce, to_json_text};
+    use rbdc::decimal::Decimal;
+    use rbs::value::map::ValueMap;
+    use rbs::Value;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_coerce_numbers() {
+        assert_eq!(
+            coerce(Value::String(" 42".to_string()), &PgTypeInfo::INT4).unwrap(),
+            Value::I32(42)
+        );
+        assert_eq!(
+            coerce(Value::I32(7), &PgTypeInfo::INT8).unwrap(),
+            Value::I64(7)
+        );
+        assert_eq!(
+            coerce(Value::I64(7), &PgTypeInfo::NUMERIC).unwrap(),
+            Value::from(Decimal::from_str("7").unwrap())
+        );
+        assert_eq!(
+            coerce(Value::I64(3), &PgTypeInfo::FLOAT8).unwrap(),
+            Value::F64(3.0)
+        );
+        assert_eq!(
+            coerce(Value::I64(1 << 24), &PgTypeInfo::FLOAT4).unwrap(),
+            Value::F32(16777216.0)
+        );
+        assert_eq!(
+            coerce(Value::F64(0.1), &PgTypeInfo::FLOAT4).unwrap(),
+            Value::F32(0.1)
+        );
+        assert_eq!(
+            coerce(Value::String("t".to_string()), &PgTypeInfo::BOOL).unwrap(),
+            Value::Bool(true)
+        );
+        assert_eq!(
+            coerce(Value::I32(5), &PgTypeInfo::TEXT).unwrap(),
+            Value::String("5".to_string())
+        );
+    }
+
+    #[test]
+    fn test_coerce_errors() {
+        let e = coerce(Value::I64(1 << 40), &PgTypeInfo::INT4).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "cannot convert 1099511627776 to INT4: out of range"
+        );
+        assert!(coerce(Value::F64(1.5), &PgTypeInfo::INT8).is_err());
+        assert_eq!(
+            coerce(Value::I64((1 << 53) + 1), &PgTypeInfo::FLOAT8)
+                .unwrap_err()
+                .to_string(),
+            "cannot convert 9007199254740993 to FLOAT8: out of precision"
+        );
+        assert!(coerce(Value::I32((1 << 24) + 1), &PgTypeInfo::FLOAT4).is_err());
+        assert!(coerce(Value::F64(1e300), &PgTypeInfo::FLOAT4).is_err());
+        assert!(coerce(Value::String("abc".to_string()), &PgTypeInfo::NUMERIC).is_err());
+        assert!(coerce(Value::String("not-a-uuid".to_string()), &PgTypeInfo::UUID).is_err());
+        assert!(coerce(Value::Binary(vec![1]), &PgTypeInfo::DATE).is_err());
+        // the bytes of a string would be taken as the binary format
+        assert_eq!(
+            coerce(Value::String("1 day".to_string()), &PgTypeInfo::INTERVAL)
+                .unwrap_err()
+                .to_string(),
+            "cannot convert \"1 day\" to INTERVAL: no conversion"
+        );
+        assert!(coerce(Value::String("12.50".to_string()), &PgTypeInfo::MONEY).is_err());
+    }
+
+    #[test]
+    fn test_coerce_strings() {
+        assert_eq!(
+            coerce(
+                Value::String("67E55044-10B1-426F-9247-BB680E5FE0C8".to_string()),
+                &PgTypeInfo::UUID
+            )
+            .unwrap(),
+            Value::Ext(
+                "Uuid",
+                Box::new(Value::String(
+                    "67e55044-10b1-426f-9247-bb680e5fe0c8".to_string()
+                ))
+            )
+        );
+        assert_eq!(
+            coerce(Value::String("2024-02-29".to_string()), &PgTypeInfo::DATE).unwrap(),
+            Value::Ext("Date", Box::new(Value::String("2024-02-29".to_string())))
+        );
+        assert_eq!(
+            to_json_text(Value::String("{\"a\":1}".to_string())).unwrap(),
+            "{\"a\":1}"
+        );
+        assert_eq!(
+            to_json_text(Value::Array(vec![Value::String("a\"b".to_string())])).unwrap(),
+            "[\"a\\\"b\"]"
+        );
+    }
+
+    #[test]
+    fn test_described_arguments() {
+        let args = PgArguments::from_args_described(
+            vec![Value::String("42".to_string()), Value::I32(1)],
+            &[PgTypeInfo::INT8, PgTypeInfo::NUMERIC],
+        )
+        .unwrap();
+        assert_eq!(
+            &args.buffer[..12],
+            &[0, 0, 0, 8, 0, 0, 0, 0, 0, 0, 0, 42][..]
+        );
+        let args = PgArguments::from_args_described(
+            vec![Value::String("-2".to_string())],
+            &[PgTypeInfo::INT2],
+        )
+        .unwrap();
+        assert_eq!(&args.buffer[..], &[0, 0, 0, 2, 0xff, 0xfe][..]);
+        let e = PgArguments::from_args_described(
+            vec![Value::I32(1), Value::String("x".to_string())],
+            &[PgTypeInfo::INT4, PgTypeInfo::INT4],
+        )
+        .err()
+        .unwrap();
+        assert!(e
+            .to_string()
+            .starts_with("parameter $2: cannot convert \"x\" to INT4"));
+        // sixteen bytes, the size of an interval
+        let e = PgArguments::from_args_described(
+            vec![Value::String("0123456789abcdef".to_string())],
+            &[PgTypeInfo::INTERVAL],
+        )
+        .err()
+        .unwrap();
+        assert!(e
+            .to_string()
+            .starts_with("parameter $1: cannot convert \"0123456789abcdef\" to INTERVAL"));
+        let e = PgArguments::from_args_described(
+            vec![Value::Map(ValueMap::new())],
+            &[PgTypeInfo::MONEY],
+        )
+        .err()
+        .unwrap();
+        assert!(e.to_string().contains("to MONEY"), "{}", e);
+    }
+}
@@ -0,0 +1,230 @@
+use crate::arguments::PgArgumentBuffer;
+use crate::type_info::{PgType, PgTypeInfo};
+use crate::types::decode::Decode;
+use crate::types::encode::IsNull;
+use crate::types::Oid;
+use crate::value::{PgValue, PgValueFormat};
+use bytes::Buf;
+use rbdc::Error;
+use rbs::value::map::ValueMap;
+use rbs::Value;
+
+/// Decodes a composite (row type) value into a `Value::Map` of field name to field value,
+/// each field decoded as its own type.
+///
+/// binary format: field count, then the type oid and length prefixed value of each field
+///
+/// text format `(1,"a b",)`, an unquoted empty field is NULL
+pub(crate) fn decode(arg: PgValue, fields: &[(String, PgTypeInfo)]) -> Result<Value, Error> {
+    let format = arg.format();
+    let mut map = ValueMap::with_capacity(fields.len());
+    match format {
+        PgValueFormat::Binary => {
+            let mut buf = arg.as_bytes()?;
+            if buf.len() < 4 {
+                return Err(Error::from("composite value is too short"));
+            }
+            let count = buf.get_i32();
+            for i in 0..count.max(0) as usize {
+                if buf.len() < 8 {
+                    return Err(Error::from("composite value is truncated"));
+                }
+                let oid = Oid(buf.get_u32());
+                let (name, type_info) = field(fields, i, oid);
+                let value = Value::decode(
+                    PgValue::get(&mut buf, format, type_info)?.with_timezone(arg.timezone.clone()),
+                )?;
+                map.insert(Value::String(name), value);
+            }
+        }
+        PgValueFormat::Text => {
+            let values = parse_text(arg.as_str()?)?;
+            for (i, value) in values.into_iter().enumerate() {
+                let (name, type_info) = field(fields, i, Oid(0));
+                let value = Value::decode(PgValue {
+                    value: value.map(String::into_bytes),
+                    type_info,
+                    format,
+                    timezone: arg.timezone.clone(),
+                })?;
+                map.insert(Value::String(name), value);
+            }
+        }
+    }
+    Ok(Value::Map(map))
+}
+
+/// the name and type of the field at `index`, falling back to `f<n>` and the wire oid
+fn field(fields: &[(String, PgTypeInfo)], index: usize, oid: Oid) -> (String, PgTypeInfo) {
+    match fields.get(index) {
+        Some((name, type_info)) => (name.clone(), type_info.clone()),
+        None => (
+            format!("f{}", index + 1),
+            PgTypeInfo::try_from_oid(oid).unwrap_or(PgTypeInfo::UNKNOWN),
+        ),
+    }
+}
+
+/// Splits the text format of a composite into its fields.
+fn parse_text(s: &str) -> Result<Vec<Option<String>>, Error> {
+    let inner = s
+        .trim()
+        .strip_prefix('(')
+        .and_then(|s| s.strip_suffix(')'))
+        .ok_or_else(|| Error::from(format!("invalid composite literal: {}", s)))?;
+    let mut values = vec![];
+    let mut value = String::new();
+    let mut quoted = false;
+    let mut in_quotes = false;
+    let mut chars = inner.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some(c) = chars.next() {
+                    value.push(c);
+                }
+            }
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                chars.next();
+                value.push('"');
+            }
+            '"' => {
+                in_quotes = !in_quotes;
+                quoted = true;
+            }
+            ',' if !in_quotes => {
+                values.push(take_field(&mut value, &mut quoted));
+            }
+            _ => value.push(c),
+        }
+    }
+    values.push(take_field(&mut value, &mut quoted));
+    Ok(values)
+}
+
+fn take_field(value: &mut String, quoted: &mut bool) -> Option<String> {
+    let field = if value.is_empty() && !*quoted {
+        None
+    } else {
+        Some(std::mem::take(value))
+    };
+    value.clear();
+    *quoted = false;
+    field
+}
+
+/// Encodes a `Value::Map` as the composite type `ty`. Fields missing from the map are NULL.
+pub(crate) fn encode(
+    map: ValueMap,
+    ty: &PgTypeInfo,
+    fields: &[(String, PgTypeInfo)],
+    buf: &mut PgArgumentBuffer,
+) -> Result<IsNull, Error> {
+    let mut map = map;
+    buf.extend(&(fields.len() as i32).to_be_bytes());
+    for (name, field_type) in fields {
+        match &field_type.0 {
+            PgType::DeclareWithName(name) => buf.patch_type_by_name(name),
+            field_ty => buf.extend(&field_ty.oid().0.to_be_bytes()),
+        }
+        let value = map.remove(&Value::String(name.clone()));
+        buf.encode_as(value, field_type)?;
+    }
+    if let Some((key, _)) = map.into_iter().next() {
+        return Err(Error::from(format!(
+            "composite type {} has no field {}",
+            ty.name(),
+            key
+        )));
+    }
+    Ok(IsNull::No)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::arguments::PgArgumentBuffer;
+    use crate::type_info::{PgCustomType, PgType, PgTypeInfo, PgTypeKind};
+    use crate::types::composite::parse_text;
+    use crate::types::decode::Decode;
+    use crate::types::Oid;
+    use crate::value::{PgValue, PgValueFormat};
+    use rbs::value::map::ValueMap;
+    use rbs::Value;
+    use std::sync::Arc;
+
+    fn composite() -> PgTypeInfo {
+        PgTypeInfo(PgType::Custom(Arc::new(PgCustomType {
+            oid: Oid(16400),
+            name: "item".into(),
+            kind: PgTypeKind::Composite(Arc::from(vec![
+                ("id".to_string(), PgTypeInfo::INT4),
+                ("name".to_string(), PgTypeInfo::TEXT),
+            ])),
+        })))
+    }
+
+    #[test]
+    fn test_parse_text() {
+        assert_eq!(
+            parse_text(r#"(1,"a ""b""",)"#).unwrap(),
+            vec![Some("1".to_string()), Some("a \"b\"".to_string()), None]
+        );
+        assert_eq!(
+            parse_text(r#"("",x)"#).unwrap(),
+            vec![Some(String::new()), Some("x".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_encode_decode_binary() {
+        let mut map = ValueMap::new();
+        map.insert("id".into(), Value::I32(7));
+        map.insert("name".into(), Value::String("pen".to_string()));
+        let mut buf = PgArgumentBuffer::default();
+        buf.encode_as(Value::Map(map.clone()), &composite())
+            .unwrap();
+        // skip the length prefix of the parameter
+        let bytes = buf[4..].to_vec();
+        assert_eq!(&bytes[..8], &[0, 0, 0, 2, 0, 0, 0, 23][..]);
+        let v = Value::decode(PgValue {
+            value: Some(bytes),
+            type_info: composite(),
+            format: PgValueFormat::Binary,
+            timezone: None,
+        })
+        .unwrap();
+        assert_eq!(v, Value::Map(map));
+    }
+
+    #[test]
+    fn test_encode_missing_and_unknown_fields() {
+        let mut map = ValueMap::new();
+        map.insert("id".into(), Value::I32(7));
+        let mut buf = PgArgumentBuffer::default();
+        buf.encode_as(Value::Map(map.clone()), &composite())
+            .unwrap();
+        // the name field is NULL
+        assert_eq!(
+            &buf[buf.len() - 8..],
+            &[0, 0, 0, 25, 255, 255, 255, 255][..]
+        );
+        map.insert("price".into(), Value::I32(1));
+        let mut buf = PgArgumentBuffer::default();
+        assert!(buf.encode_as(Value::Map(map), &composite()).is_err());
+    }
+
+    #[test]
+    fn test_decode_text() {
+        let v = Value::decode(PgValue {
+            value: Some(b"(3,\"a,b\")".to_vec()),
+            type_info: composite(),
+            format: PgValueFormat::Text,
+            timezone: None,
+        })
+        .unwrap();
+        let mut map = ValueMap::new();
+        map.insert("id".into(), Value::I32(3));
+        map.insert("name".into(), Value::String("a,b".to_string()));
+        assert_eq!(v, Value::Map(map));
+    }
+}
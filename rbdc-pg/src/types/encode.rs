@@ -1,4 +1,5 @@
 use crate::arguments::{PgArgumentBuffer, PgArguments};
+use crate::type_info::PgTypeInfo;
 use rbdc::Error;
 use rbs::Value;
 
@@ -22,4 +23,21 @@ impl PgArguments {
         }
         Ok(arg)
     }
+
+    /// Encodes `args` as the `parameters` types of a prepared statement.
+    pub fn from_args_described(args: Vec<Value>, parameters: &[PgTypeInfo]) -> Result<Self, Error> {
+        let mut arg = PgArguments {
+            types: Vec::with_capacity(args.len()),
+            buffer: PgArgumentBuffer::default(),
+        };
+        for (i, x) in args.into_iter().enumerate() {
+            match parameters.get(i) {
+                Some(ty) => arg
+                    .add_as(x, ty)
+                    .map_err(|e| Error::from(format!("parameter ${}: {}", i + 1, e)))?,
+                None => arg.add(x)?,
+            }
+        }
+        Ok(arg)
+    }
 }
@@ -0,0 +1,611 @@
+use crate::arguments::PgArgumentBuffer;
+use crate::type_info::PgTypeInfo;
+use crate::types::decode::Decode;
+use crate::types::encode::{Encode, IsNull};
+use crate::value::{PgValue, PgValueFormat};
+use bytes::Buf;
+use rbdc::Error;
+use rbs::Value;
+use std::fmt::{Display, Formatter};
+
+// Geometric types are serialized as `Value::Ext(<pg type name>, Map)`, so that they keep their
+// Postgres type when bound as parameters, while serde_json and friends see a plain object
+// such as `{"x":1.0,"y":2.0}`.
+macro_rules! impl_geometry_serialize {
+    ($ty:ident, $ext:literal { $($field:ident: $field_ty:ty),+ }) => {
+        impl serde::Serialize for $ty {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                #[derive(serde::Serialize)]
+                struct Fields<'a> {
+                    $($field: &'a $field_ty),+
+                }
+                serializer.serialize_newtype_struct($ext, &Fields { $($field: &self.$field),+ })
+            }
+        }
+
+        impl From<$ty> for Value {
+            fn from(arg: $ty) -> Self {
+                rbs::value!(arg)
+            }
+        }
+    };
+}
+
+/// Postgres `POINT`, a point on a plane.
+///
+/// text format `(x,y)`
+#[derive(serde::Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl_geometry_serialize!(Point, "Point" { x: f64, y: f64 });
+
+/// Postgres `LSEG`, a finite line segment.
+///
+/// text format `[(x1,y1),(x2,y2)]`
+#[derive(serde::Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct LineSegment {
+    pub start: Point,
+    pub end: Point,
+}
+
+impl_geometry_serialize!(LineSegment, "Lseg" { start: Point, end: Point });
+
+/// Postgres `BOX`, a rectangular box given by two opposite corners.
+///
+/// Postgres reorders the corners on input so that `high` is the upper right
+/// and `low` the lower left corner.
+///
+/// text format `(x1,y1),(x2,y2)`
+#[derive(serde::Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct PgBox {
+    pub high: Point,
+    pub low: Point,
+}
+
+impl_geometry_serialize!(PgBox, "Box" { high: Point, low: Point });
+
+/// Postgres `PATH`, an open or closed list of connected points.
+///
+/// text format `[(x1,y1),...]` (open) or `((x1,y1),...)` (closed)
+#[derive(serde::Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct PgPath {
+    pub closed: bool,
+    pub points: Vec<Point>,
+}
+
+impl_geometry_serialize!(PgPath, "Path" { closed: bool, points: Vec<Point> });
+
+/// Postgres `POLYGON`, a closed list of points.
+///
+/// text format `((x1,y1),...)`
+#[derive(serde::Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Polygon {
+    pub points: Vec<Point>,
+}
+
+impl_geometry_serialize!(Polygon, "Polygon" { points: Vec<Point> });
+
+/// Postgres `LINE`, an infinite line given by the equation `Ax + By + C = 0`.
+///
+/// text format `{A,B,C}`
+#[derive(serde::Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct Line {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+}
+
+impl_geometry_serialize!(Line, "Line" { a: f64, b: f64, c: f64 });
+
+/// Postgres `CIRCLE`, given by a center point and radius.
+///
+/// text format `<(x,y),r>`
+#[derive(serde::Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct Circle {
+    pub center: Point,
+    pub radius: f64,
+}
+
+impl_geometry_serialize!(Circle, "Circle" { center: Point, radius: f64 });
+
+impl Display for Point {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({},{})", self.x, self.y)
+    }
+}
+
+impl Display for LineSegment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{},{}]", self.start, self.end)
+    }
+}
+
+impl Display for PgBox {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{},{}", self.high, self.low)
+    }
+}
+
+impl Display for PgPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (open, close) = if self.closed { ("(", ")") } else { ("[", "]") };
+        f.write_str(open)?;
+        write_points(f, &self.points)?;
+        f.write_str(close)
+    }
+}
+
+impl Display for Polygon {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("(")?;
+        write_points(f, &self.points)?;
+        f.write_str(")")
+    }
+}
+
+impl Display for Line {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{{{},{},{}}}", self.a, self.b, self.c)
+    }
+}
+
+impl Display for Circle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<{},{}>", self.center, self.radius)
+    }
+}
+
+fn write_points(f: &mut Formatter<'_>, points: &[Point]) -> std::fmt::Result {
+    for (i, p) in points.iter().enumerate() {
+        if i != 0 {
+            f.write_str(",")?;
+        }
+        Display::fmt(p, f)?;
+    }
+    Ok(())
+}
+
+/// Reads the float8 values of a geometric value.
+///
+/// The text representations only differ in their brackets, so the numbers are
+/// read in order after the brackets have been dropped.
+fn parse_numbers(s: &str, type_name: &str) -> Result<Vec<f64>, Error> {
+    s.split(['(', ')', '[', ']', '{', '}', '<', '>', ','])
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(|v| {
+            v.parse::<f64>()
+                .map_err(|e| Error::from(format!("invalid {} value {:?}: {}", type_name, s, e)))
+        })
+        .collect()
+}
+
+fn expect_len(buf: &[u8], len: usize, type_name: &str) -> Result<(), Error> {
+    if buf.len() < len {
+        return Err(Error::from(format!(
+            "invalid {} value: expected {} bytes, got {}",
+            type_name,
+            len,
+            buf.len()
+        )));
+    }
+    Ok(())
+}
+
+fn points_from_numbers(numbers: &[f64], type_name: &str) -> Result<Vec<Point>, Error> {
+    if !numbers.len().is_multiple_of(2) {
+        return Err(Error::from(format!(
+            "invalid {} value: odd number of coordinates",
+            type_name
+        )));
+    }
+    Ok(numbers
+        .chunks_exact(2)
+        .map(|xy| Point { x: xy[0], y: xy[1] })
+        .collect())
+}
+
+fn get_point(buf: &mut &[u8]) -> Point {
+    Point {
+        x: buf.get_f64(),
+        y: buf.get_f64(),
+    }
+}
+
+fn put_point(buf: &mut PgArgumentBuffer, p: &Point) {
+    buf.extend(&p.x.to_be_bytes());
+    buf.extend(&p.y.to_be_bytes());
+}
+
+fn get_points(buf: &mut &[u8], type_name: &str) -> Result<Vec<Point>, Error> {
+    expect_len(buf, 4, type_name)?;
+    let npts = buf.get_i32();
+    if npts < 0 {
+        return Err(Error::from(format!(
+            "invalid {} value: negative number of points",
+            type_name
+        )));
+    }
+    expect_len(buf, npts as usize * 16, type_name)?;
+    Ok((0..npts).map(|_| get_point(buf)).collect())
+}
+
+fn put_points(buf: &mut PgArgumentBuffer, points: &[Point]) {
+    buf.extend(&(points.len() as i32).to_be_bytes());
+    for p in points {
+        put_point(buf, p);
+    }
+}
+
+impl Decode for Point {
+    fn decode(value: PgValue) -> Result<Self, Error> {
+        match value.format() {
+            PgValueFormat::Binary => {
+                let mut buf = value.as_bytes()?;
+                expect_len(buf, 16, "point")?;
+                Ok(get_point(&mut buf))
+            }
+            PgValueFormat::Text => match parse_numbers(value.as_str()?, "point")?[..] {
+                [x, y] => Ok(Point { x, y }),
+                _ => Err(Error::from(format!(
+                    "invalid point value {:?}",
+                    value.as_str()?
+                ))),
+            },
+        }
+    }
+}
+
+impl Encode for Point {
+    fn encode(self, buf: &mut PgArgumentBuffer) -> Result<IsNull, Error> {
+        put_point(buf, &self);
+        Ok(IsNull::No)
+    }
+}
+
+impl Decode for LineSegment {
+    fn decode(value: PgValue) -> Result<Self, Error> {
+        match value.format() {
+            PgValueFormat::Binary => {
+                let mut buf = value.as_bytes()?;
+                expect_len(buf, 32, "lseg")?;
+                Ok(LineSegment {
+                    start: get_point(&mut buf),
+                    end: get_point(&mut buf),
+                })
+            }
+            PgValueFormat::Text => match parse_numbers(value.as_str()?, "lseg")?[..] {
+                [x1, y1, x2, y2] => Ok(LineSegment {
+                    start: Point { x: x1, y: y1 },
+                    end: Point { x: x2, y: y2 },
+                }),
+                _ => Err(Error::from(format!(
+                    "invalid lseg value {:?}",
+                    value.as_str()?
+                ))),
+            },
+        }
+    }
+}
+
+impl Encode for LineSegment {
+    fn encode(self, buf: &mut PgArgumentBuffer) -> Result<IsNull, Error> {
+        put_point(buf, &self.start);
+        put_point(buf, &self.end);
+        Ok(IsNull::No)
+    }
+}
+
+impl Decode for PgBox {
+    fn decode(value: PgValue) -> Result<Self, Error> {
+        match value.format() {
+            PgValueFormat::Binary => {
+                let mut buf = value.as_bytes()?;
+                expect_len(buf, 32, "box")?;
+                Ok(PgBox {
+                    high: get_point(&mut buf),
+                    low: get_point(&mut buf),
+                })
+            }
+            PgValueFormat::Text => match parse_numbers(value.as_str()?, "box")?[..] {
+                [x1, y1, x2, y2] => Ok(PgBox {
+                    high: Point { x: x1, y: y1 },
+                    low: Point { x: x2, y: y2 },
+                }),
+                _ => Err(Error::from(format!(
+                    "invalid box value {:?}",
+                    value.as_str()?
+                ))),
+            },
+        }
+    }
+}
+
+impl Encode for PgBox {
+    fn encode(self, buf: &mut PgArgumentBuffer) -> Result<IsNull, Error> {
+        // box_recv expects the corners in any order and normalizes them
+        put_point(buf, &self.high);
+        put_point(buf, &self.low);
+        Ok(IsNull::No)
+    }
+}
+
+impl Decode for PgPath {
+    fn decode(value: PgValue) -> Result<Self, Error> {
+        match value.format() {
+            PgValueFormat::Binary => {
+                let mut buf = value.as_bytes()?;
+                expect_len(buf, 1, "path")?;
+                let closed = buf.get_u8() != 0;
+                Ok(PgPath {
+                    closed,
+                    points: get_points(&mut buf, "path")?,
+                })
+            }
+            PgValueFormat::Text => {
+                let s = value.as_str()?;
+                Ok(PgPath {
+                    // an open path is written with square brackets
+                    closed: !s.trim_start().starts_with('['),
+                    points: points_from_numbers(&parse_numbers(s, "path")?, "path")?,
+                })
+            }
+        }
+    }
+}
+
+impl Encode for PgPath {
+    fn encode(self, buf: &mut PgArgumentBuffer) -> Result<IsNull, Error> {
+        buf.push(self.closed as u8);
+        put_points(buf, &self.points);
+        Ok(IsNull::No)
+    }
+}
+
+impl Decode for Polygon {
+    fn decode(value: PgValue) -> Result<Self, Error> {
+        match value.format() {
+            PgValueFormat::Binary => {
+                let mut buf = value.as_bytes()?;
+                Ok(Polygon {
+                    points: get_points(&mut buf, "polygon")?,
+                })
+            }
+            PgValueFormat::Text => Ok(Polygon {
+                points: points_from_numbers(
+                    &parse_numbers(value.as_str()?, "polygon")?,
+                    "polygon",
+                )?,
+            }),
+        }
+    }
+}
+
+impl Encode for Polygon {
+    fn encode(self, buf: &mut PgArgumentBuffer) -> Result<IsNull, Error> {
+        put_points(buf, &self.points);
+        Ok(IsNull::No)
+    }
+}
+
+impl Decode for Line {
+    fn decode(value: PgValue) -> Result<Self, Error> {
+        match value.format() {
+            PgValueFormat::Binary => {
+                let mut buf = value.as_bytes()?;
+                expect_len(buf, 24, "line")?;
+                Ok(Line {
+                    a: buf.get_f64(),
+                    b: buf.get_f64(),
+                    c: buf.get_f64(),
+                })
+            }
+            PgValueFormat::Text => match parse_numbers(value.as_str()?, "line")?[..] {
+                [a, b, c] => Ok(Line { a, b, c }),
+                _ => Err(Error::from(format!(
+                    "invalid line value {:?}",
+                    value.as_str()?
+                ))),
+            },
+        }
+    }
+}
+
+impl Encode for Line {
+    fn encode(self, buf: &mut PgArgumentBuffer) -> Result<IsNull, Error> {
+        buf.extend(&self.a.to_be_bytes());
+        buf.extend(&self.b.to_be_bytes());
+        buf.extend(&self.c.to_be_bytes());
+        Ok(IsNull::No)
+    }
+}
+
+impl Decode for Circle {
+    fn decode(value: PgValue) -> Result<Self, Error> {
+        match value.format() {
+            PgValueFormat::Binary => {
+                let mut buf = value.as_bytes()?;
+                expect_len(buf, 24, "circle")?;
+                Ok(Circle {
+                    center: get_point(&mut buf),
+                    radius: buf.get_f64(),
+                })
+            }
+            PgValueFormat::Text => match parse_numbers(value.as_str()?, "circle")?[..] {
+                [x, y, radius] => Ok(Circle {
+                    center: Point { x, y },
+                    radius,
+                }),
+                _ => Err(Error::from(format!(
+                    "invalid circle value {:?}",
+                    value.as_str()?
+                ))),
+            },
+        }
+    }
+}
+
+impl Encode for Circle {
+    fn encode(self, buf: &mut PgArgumentBuffer) -> Result<IsNull, Error> {
+        put_point(buf, &self.center);
+        buf.extend(&self.radius.to_be_bytes());
+        Ok(IsNull::No)
+    }
+}
+
+/// Returns the `PgTypeInfo` of a geometric `Value::Ext` name.
+pub(crate) fn type_info_by_name(name: &str) -> Option<PgTypeInfo> {
+    Some(match name {
+        "Point" => PgTypeInfo::POINT,
+        "Lseg" => PgTypeInfo::LSEG,
+        "Box" => PgTypeInfo::BOX,
+        "Path" => PgTypeInfo::PATH,
+        "Polygon" => PgTypeInfo::POLYGON,
+        "Line" => PgTypeInfo::LINE,
+        "Circle" => PgTypeInfo::CIRCLE,
+        _ => return None,
+    })
+}
+
+/// Encodes a geometric `Value::Ext` by its name.
+pub(crate) fn encode_by_name(
+    name: &str,
+    v: Value,
+    buf: &mut PgArgumentBuffer,
+) -> Result<IsNull, Error> {
+    match name {
+        "Point" => rbs::from_value::<Point>(v)?.encode(buf),
+        "Lseg" => rbs::from_value::<LineSegment>(v)?.encode(buf),
+        "Box" => rbs::from_value::<PgBox>(v)?.encode(buf),
+        "Path" => rbs::from_value::<PgPath>(v)?.encode(buf),
+        "Polygon" => rbs::from_value::<Polygon>(v)?.encode(buf),
+        "Line" => rbs::from_value::<Line>(v)?.encode(buf),
+        "Circle" => rbs::from_value::<Circle>(v)?.encode(buf),
+        _ => Err(Error::from(format!("unknown geometric type {}", name))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::type_info::PgTypeInfo;
+    use crate::types::decode::Decode;
+    use crate::types::geometry::{Circle, Line, LineSegment, PgBox, PgPath, Point, Polygon};
+    use crate::value::{PgValue, PgValueFormat};
+    use rbs::Value;
+
+    fn text(s: &str, type_info: PgTypeInfo) -> PgValue {
+        PgValue {
+            value: Some(s.as_bytes().to_vec()),
+            type_info,
+            format: PgValueFormat::Text,
+            timezone: None,
+        }
+    }
+
+    fn binary(bytes: Vec<u8>, type_info: PgTypeInfo) -> PgValue {
+        PgValue {
+            value: Some(bytes),
+            type_info,
+            format: PgValueFormat::Binary,
+            timezone: None,
+        }
+    }
+
+    fn floats(values: &[f64]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_be_bytes()).collect()
+    }
+
+    #[test]
+    fn test_decode_text() {
+        assert_eq!(
+            Point::decode(text("(1.5,-2)", PgTypeInfo::POINT)).unwrap(),
+            Point { x: 1.5, y: -2.0 }
+        );
+        assert_eq!(
+            LineSegment::decode(text("[(1,2),(3,4)]", PgTypeInfo::LSEG)).unwrap(),
+            LineSegment {
+                start: Point { x: 1.0, y: 2.0 },
+                end: Point { x: 3.0, y: 4.0 },
+            }
+        );
+        assert_eq!(
+            PgBox::decode(text("(3,4),(1,2)", PgTypeInfo::BOX)).unwrap(),
+            PgBox {
+                high: Point { x: 3.0, y: 4.0 },
+                low: Point { x: 1.0, y: 2.0 },
+            }
+        );
+        let path = PgPath::decode(text("[(0,0),(1,1)]", PgTypeInfo::PATH)).unwrap();
+        assert!(!path.closed);
+        assert_eq!(path.points.len(), 2);
+        let path = PgPath::decode(text("((0,0),(1,1),(2,0))", PgTypeInfo::PATH)).unwrap();
+        assert!(path.closed);
+        assert_eq!(path.points.len(), 3);
+        assert_eq!(
+            Polygon::decode(text("((0,0),(1,1),(2,0))", PgTypeInfo::POLYGON))
+                .unwrap()
+                .points
+                .len(),
+            3
+        );
+        assert_eq!(
+            Line::decode(text("{1,-1,0}", PgTypeInfo::LINE)).unwrap(),
+            Line {
+                a: 1.0,
+                b: -1.0,
+                c: 0.0
+            }
+        );
+        assert_eq!(
+            Circle::decode(text("<(1,2),3>", PgTypeInfo::CIRCLE)).unwrap(),
+            Circle {
+                center: Point { x: 1.0, y: 2.0 },
+                radius: 3.0,
+            }
+        );
+        assert!(Point::decode(text("(1,2,3)", PgTypeInfo::POINT)).is_err());
+    }
+
+    #[test]
+    fn test_decode_binary() {
+        assert_eq!(
+            Point::decode(binary(floats(&[1.0, 2.0]), PgTypeInfo::POINT)).unwrap(),
+            Point { x: 1.0, y: 2.0 }
+        );
+        let mut path = vec![1u8];
+        path.extend(2_i32.to_be_bytes());
+        path.extend(floats(&[0.0, 0.0, 1.0, 1.0]));
+        let path = PgPath::decode(binary(path, PgTypeInfo::PATH)).unwrap();
+        assert!(path.closed);
+        assert_eq!(path.points[1], Point { x: 1.0, y: 1.0 });
+        assert!(Circle::decode(binary(floats(&[1.0, 2.0]), PgTypeInfo::CIRCLE)).is_err());
+    }
+
+    #[test]
+    fn test_value_serde() {
+        let v = Value::from(Point { x: 1.0, y: 2.0 });
+        match &v {
+            Value::Ext("Point", inner) => assert!(matches!(**inner, Value::Map(_))),
+            _ => panic!("unexpected {:?}", v),
+        }
+        let p: Point = rbs::from_value(v).unwrap();
+        assert_eq!(p, Point { x: 1.0, y: 2.0 });
+        assert_eq!(
+            serde_json::to_string(&Point { x: 1.0, y: 2.0 }).unwrap(),
+            r#"{"x":1.0,"y":2.0}"#
+        );
+
+        let circle = Circle {
+            center: Point { x: 1.0, y: 2.0 },
+            radius: 3.0,
+        };
+        let c: Circle = rbs::from_value(Value::from(circle)).unwrap();
+        assert_eq!(c, circle);
+    }
+}
@@ -0,0 +1,331 @@
+use crate::arguments::PgArgumentBuffer;
+use crate::types::decode::Decode;
+use crate::types::encode::{Encode, IsNull};
+use crate::value::{PgValue, PgValueFormat};
+use bytes::Buf;
+use rbdc::Error;
+use rbs::value::map::ValueMap;
+use rbs::Value;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+use std::str::FromStr;
+
+/// The `hstore` extension type, a set of string keys each with a string or NULL value.
+///
+/// Decoded columns are a `Value::Map`, a `Value::Map` (or a `PgHstore`) can be bound to an
+/// `hstore` parameter. The type oid is resolved by name when the statement is prepared.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default, Eq, PartialEq)]
+#[serde(rename = "Hstore")]
+pub struct PgHstore(pub BTreeMap<String, Option<String>>);
+
+impl Deref for PgHstore {
+    type Target = BTreeMap<String, Option<String>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for PgHstore {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl FromIterator<(String, Option<String>)> for PgHstore {
+    fn from_iter<T: IntoIterator<Item = (String, Option<String>)>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl PgHstore {
+    /// Converts a `Value::Map` (or an `Ext("Hstore", map)`) to an hstore. Values that are not
+    /// strings are stored as their text.
+    pub fn from_value(v: Value) -> Result<Self, Error> {
+        let map = match v {
+            Value::Map(map) => map,
+            Value::Ext(_, v) => return Self::from_value(*v),
+            v => return Err(Error::from(format!("cannot convert {} to hstore", v))),
+        };
+        let mut hstore = PgHstore::default();
+        for (k, v) in map {
+            let k = match k {
+                Value::String(k) => k,
+                k => k.to_string(),
+            };
+            let v = match v {
+                Value::Null => None,
+                Value::String(v) => Some(v),
+                Value::Ext(_, v) if v.is_str() => v.into_string(),
+                v => Some(v.to_string()),
+            };
+            hstore.insert(k, v);
+        }
+        Ok(hstore)
+    }
+}
+
+impl From<PgHstore> for Value {
+    fn from(arg: PgHstore) -> Self {
+        Value::Ext("Hstore", Box::new(to_map(arg)))
+    }
+}
+
+fn to_map(arg: PgHstore) -> Value {
+    let mut map = ValueMap::with_capacity(arg.len());
+    for (k, v) in arg.0 {
+        map.insert(
+            Value::String(k),
+            v.map(Value::String).unwrap_or(Value::Null),
+        );
+    }
+    Value::Map(map)
+}
+
+/// text format `"a"=>"1", "b"=>NULL`
+impl Display for PgHstore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, (k, v)) in self.iter().enumerate() {
+            if i != 0 {
+                f.write_str(", ")?;
+            }
+            write_quoted(f, k)?;
+            f.write_str("=>")?;
+            match v {
+                Some(v) => write_quoted(f, v)?,
+                None => f.write_str("NULL")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_quoted(f: &mut Formatter<'_>, s: &str) -> std::fmt::Result {
+    f.write_str("\"")?;
+    for c in s.chars() {
+        if c == '"' || c == '\\' {
+            f.write_str("\\")?;
+        }
+        write!(f, "{}", c)?;
+    }
+    f.write_str("\"")
+}
+
+impl FromStr for PgHstore {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut hstore = PgHstore::default();
+        let mut chars = s.chars().peekable();
+        loop {
+            skip_whitespace(&mut chars);
+            if chars.peek().is_none() {
+                return Ok(hstore);
+            }
+            let (key, _) = read_token(&mut chars)?;
+            skip_whitespace(&mut chars);
+            if chars.next() != Some('=') || chars.next() != Some('>') {
+                return Err(Error::from(format!("invalid hstore literal: {}", s)));
+            }
+            skip_whitespace(&mut chars);
+            let value = match read_token(&mut chars)? {
+                (v, false) if v.eq_ignore_ascii_case("NULL") => None,
+                (v, _) => Some(v),
+            };
+            hstore.insert(key, value);
+            skip_whitespace(&mut chars);
+            match chars.next() {
+                None => return Ok(hstore),
+                Some(',') => {}
+                Some(_) => return Err(Error::from(format!("invalid hstore literal: {}", s))),
+            }
+        }
+    }
+}
+
+fn skip_whitespace(chars: &mut std::iter::Peekable<std::str::Chars>) {
+    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+        chars.next();
+    }
+}
+
+/// Reads a quoted or unquoted key or value, returning it and whether it was quoted.
+fn read_token(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<(String, bool), Error> {
+    let mut token = String::new();
+    if chars.peek() == Some(&'"') {
+        chars.next();
+        loop {
+            match chars.next() {
+                Some('"') => return Ok((token, true)),
+                Some('\\') => token.extend(chars.next()),
+                Some(c) => token.push(c),
+                None => return Err(Error::from("unterminated quoted string in hstore")),
+            }
+        }
+    }
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() || c == ',' || c == '=' {
+            break;
+        }
+        chars.next();
+        token.push(c);
+    }
+    if token.is_empty() {
+        return Err(Error::from("hstore key or value is empty"));
+    }
+    Ok((token, false))
+}
+
+/// binary format: pair count, then each length prefixed key and value (a length of -1 is NULL)
+impl Decode for PgHstore {
+    fn decode(value: PgValue) -> Result<Self, Error> {
+        match value.format() {
+            PgValueFormat::Binary => {
+                let mut buf = value.as_bytes()?;
+                let count = read_len(&mut buf)?;
+                let mut hstore = PgHstore::default();
+                for _ in 0..count.max(0) {
+                    let key =
+                        read_string(&mut buf)?.ok_or_else(|| Error::from("hstore key is NULL"))?;
+                    let value = read_string(&mut buf)?;
+                    hstore.insert(key, value);
+                }
+                Ok(hstore)
+            }
+            PgValueFormat::Text => PgHstore::from_str(value.as_str()?),
+        }
+    }
+}
+
+fn read_len(buf: &mut &[u8]) -> Result<i32, Error> {
+    if buf.len() < 4 {
+        return Err(Error::from("hstore value is truncated"));
+    }
+    Ok(buf.get_i32())
+}
+
+fn read_string(buf: &mut &[u8]) -> Result<Option<String>, Error> {
+    let len = read_len(buf)?;
+    if len < 0 {
+        return Ok(None);
+    }
+    let len = len as usize;
+    if buf.len() < len {
+        return Err(Error::from("hstore value is truncated"));
+    }
+    let s = String::from_utf8(buf[..len].to_vec()).map_err(|e| Error::from(e.to_string()))?;
+    buf.advance(len);
+    Ok(Some(s))
+}
+
+impl Encode for PgHstore {
+    fn encode(self, buf: &mut PgArgumentBuffer) -> Result<IsNull, Error> {
+        buf.extend(&(self.len() as i32).to_be_bytes());
+        for (k, v) in self.0 {
+            buf.extend(&(k.len() as i32).to_be_bytes());
+            buf.extend(k.as_bytes());
+            match v {
+                Some(v) => {
+                    buf.extend(&(v.len() as i32).to_be_bytes());
+                    buf.extend(v.as_bytes());
+                }
+                None => buf.extend(&(-1_i32).to_be_bytes()),
+            }
+        }
+        Ok(IsNull::No)
+    }
+}
+
+/// Decodes an hstore column into a `Value::Map`.
+pub(crate) fn decode_value(arg: PgValue) -> Result<Value, Error> {
+    Ok(to_map(PgHstore::decode(arg)?))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::arguments::PgArgumentBuffer;
+    use crate::type_info::{PgCustomType, PgType, PgTypeInfo, PgTypeKind};
+    use crate::types::decode::Decode;
+    use crate::types::encode::Encode;
+    use crate::types::hstore::PgHstore;
+    use crate::types::Oid;
+    use crate::value::{PgValue, PgValueFormat};
+    use rbs::Value;
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    fn hstore() -> PgHstore {
+        PgHstore::from_iter([
+            ("a".to_string(), Some("1".to_string())),
+            ("b c".to_string(), None),
+            ("q\"".to_string(), Some("x\\y".to_string())),
+        ])
+    }
+
+    #[test]
+    fn test_text() {
+        let s = hstore().to_string();
+        assert_eq!(s, r#""a"=>"1", "b c"=>NULL, "q\""=>"x\\y""#);
+        assert_eq!(PgHstore::from_str(&s).unwrap(), hstore());
+        assert_eq!(
+            PgHstore::from_str("a=>1,b=>NULL, c => \"NULL\"").unwrap(),
+            PgHstore::from_iter([
+                ("a".to_string(), Some("1".to_string())),
+                ("b".to_string(), None),
+                ("c".to_string(), Some("NULL".to_string())),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_binary() {
+        let mut buf = PgArgumentBuffer::default();
+        hstore().encode(&mut buf).unwrap();
+        assert_eq!(&buf[..13], &[0, 0, 0, 3, 0, 0, 0, 1, b'a', 0, 0, 0, 1][..]);
+        let v = PgValue {
+            value: Some(buf.to_vec()),
+            type_info: PgTypeInfo::with_name("hstore"),
+            format: PgValueFormat::Binary,
+            timezone: None,
+        };
+        assert_eq!(PgHstore::decode(v).unwrap(), hstore());
+    }
+
+    #[test]
+    fn test_encode_as_described() {
+        let ty = PgTypeInfo(PgType::Custom(Arc::new(PgCustomType {
+            oid: Oid(16500),
+            name: "hstore".into(),
+            kind: PgTypeKind::Simple,
+        })));
+        let mut map = rbs::value::map::ValueMap::new();
+        map.insert("k".into(), Value::Null);
+        let mut buf = PgArgumentBuffer::default();
+        buf.encode_as(Value::Map(map), &ty).unwrap();
+        assert_eq!(
+            &buf[..],
+            &[0, 0, 0, 13, 0, 0, 0, 1, 0, 0, 0, 1, b'k', 255, 255, 255, 255][..]
+        );
+        let v = Value::decode(PgValue {
+            value: Some(buf[4..].to_vec()),
+            type_info: ty,
+            format: PgValueFormat::Binary,
+            timezone: None,
+        })
+        .unwrap();
+        assert_eq!(v.as_map().map(|m| m.len()), Some(1));
+    }
+
+    #[test]
+    fn test_from_value() {
+        let v = Value::from(hstore());
+        assert_eq!(PgHstore::from_value(v).unwrap(), hstore());
+        let mut map = rbs::value::map::ValueMap::new();
+        map.insert("n".into(), Value::I32(1));
+        assert_eq!(
+            PgHstore::from_value(Value::Map(map)).unwrap(),
+            PgHstore::from_iter([("n".to_string(), Some("1".to_string()))])
+        );
+    }
+}
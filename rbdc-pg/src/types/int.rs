@@ -203,6 +203,7 @@ mod test {
             value: Some(bytes.to_vec()),
             type_info: PgTypeInfo::INT8,
             format: PgValueFormat::Binary,
+            timezone: None,
         }).unwrap();
         assert_eq!(r, 3);
     }
@@ -215,6 +216,7 @@ mod test {
             value: Some(bytes.to_vec()),
             type_info: PgTypeInfo::INT8,
             format: PgValueFormat::Binary,
+            timezone: None,
         }).unwrap();
         assert_eq!(r, 3);
     }
@@ -227,6 +229,7 @@ mod test {
             value: Some(bytes.to_vec()),
             type_info: PgTypeInfo::INT8,
             format: PgValueFormat::Binary,
+            timezone: None,
         }).unwrap();
         assert_eq!(r, 3);
     }
@@ -239,6 +242,7 @@ mod test {
             value: Some(bytes.to_vec()),
             type_info: PgTypeInfo::INT8,
             format: PgValueFormat::Binary,
+            timezone: None,
         }).unwrap();
         assert_eq!(r, 3);
     }
@@ -251,6 +255,7 @@ mod test {
             value: Some(bytes.to_vec()),
             type_info: PgTypeInfo::INT8,
             format: PgValueFormat::Binary,
+            timezone: None,
         }).unwrap();
         assert_eq!(r, 3);
     }
@@ -263,6 +268,7 @@ mod test {
             value: Some(bytes.to_vec()),
             type_info: PgTypeInfo::INT8,
             format: PgValueFormat::Binary,
+            timezone: None,
         }).unwrap();
         assert_eq!(r, 3);
     }
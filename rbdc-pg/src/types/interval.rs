@@ -0,0 +1,588 @@
+use crate::arguments::PgArgumentBuffer;
+use crate::types::decode::Decode;
+use crate::types::encode::{Encode, IsNull};
+use crate::value::{PgValue, PgValueFormat};
+use byteorder::{BigEndian, ReadBytesExt};
+use rbdc::Error;
+use rbs::Value;
+use std::fmt::{Display, Formatter, Write};
+use std::io::Cursor;
+use std::ops::{Add, Neg, Sub};
+use std::str::FromStr;
+use std::time::Duration;
+
+const MICROS_PER_SECOND: i64 = 1_000_000;
+const MICROS_PER_MINUTE: i64 = 60 * MICROS_PER_SECOND;
+const MICROS_PER_HOUR: i64 = 60 * MICROS_PER_MINUTE;
+const MICROS_PER_DAY: i64 = 24 * MICROS_PER_HOUR;
+const DAYS_PER_MONTH: i64 = 30;
+
+/// Postgres `INTERVAL`.
+///
+/// Months and days are kept apart from the time part, because their length
+/// depends on the date they are applied to.
+///
+/// serialized as `Value::Ext("Interval", {"months":..,"days":..,"microseconds":..})`,
+/// deserializes from that map or from a textual interval such as `"1 day 02:00:00"` or `"P1DT2H"`.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub struct PgInterval {
+    pub months: i32,
+    pub days: i32,
+    pub microseconds: i64,
+}
+
+impl PgInterval {
+    pub fn new(months: i32, days: i32, microseconds: i64) -> Self {
+        Self {
+            months,
+            days,
+            microseconds,
+        }
+    }
+
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        Some(Self {
+            months: self.months.checked_add(rhs.months)?,
+            days: self.days.checked_add(rhs.days)?,
+            microseconds: self.microseconds.checked_add(rhs.microseconds)?,
+        })
+    }
+
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        Some(Self {
+            months: self.months.checked_sub(rhs.months)?,
+            days: self.days.checked_sub(rhs.days)?,
+            microseconds: self.microseconds.checked_sub(rhs.microseconds)?,
+        })
+    }
+
+    pub fn checked_neg(self) -> Option<Self> {
+        Some(Self {
+            months: self.months.checked_neg()?,
+            days: self.days.checked_neg()?,
+            microseconds: self.microseconds.checked_neg()?,
+        })
+    }
+}
+
+impl Add for PgInterval {
+    type Output = PgInterval;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        self.checked_add(rhs)
+            .expect("overflow when adding intervals")
+    }
+}
+
+impl Sub for PgInterval {
+    type Output = PgInterval;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.checked_sub(rhs)
+            .expect("overflow when subtracting intervals")
+    }
+}
+
+impl Neg for PgInterval {
+    type Output = PgInterval;
+
+    fn neg(self) -> Self::Output {
+        self.checked_neg().expect("overflow when negating interval")
+    }
+}
+
+impl TryFrom<Duration> for PgInterval {
+    type Error = Error;
+
+    /// Only the time part is set; Postgres does not support sub-microsecond precision.
+    fn try_from(value: Duration) -> Result<Self, Error> {
+        if !value.subsec_nanos().is_multiple_of(1000) {
+            return Err(Error::from(
+                "PostgreSQL `INTERVAL` does not support nanoseconds precision",
+            ));
+        }
+        let microseconds = i64::try_from(value.as_micros())
+            .map_err(|_| Error::from(format!("{:?} is too large for an `INTERVAL`", value)))?;
+        Ok(Self::new(0, 0, microseconds))
+    }
+}
+
+impl TryFrom<PgInterval> for Duration {
+    type Error = Error;
+
+    /// Days are taken as 24 hours. Intervals with months, or which are negative,
+    /// have no fixed length and cannot be converted.
+    fn try_from(value: PgInterval) -> Result<Self, Error> {
+        if value.months != 0 {
+            return Err(Error::from(format!(
+                "interval `{}` has a month part and no fixed duration",
+                value
+            )));
+        }
+        let micros = (value.days as i64)
+            .checked_mul(MICROS_PER_DAY)
+            .and_then(|d| d.checked_add(value.microseconds))
+            .ok_or_else(|| Error::from(format!("interval `{}` is out of range", value)))?;
+        if micros < 0 {
+            return Err(Error::from(format!(
+                "interval `{}` is negative and cannot be a Duration",
+                value
+            )));
+        }
+        Ok(Duration::from_micros(micros as u64))
+    }
+}
+
+impl serde::Serialize for PgInterval {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        #[derive(serde::Serialize)]
+        struct Fields {
+            months: i32,
+            days: i32,
+            microseconds: i64,
+        }
+        serializer.serialize_newtype_struct(
+            "Interval",
+            &Fields {
+                months: self.months,
+                days: self.days,
+                microseconds: self.microseconds,
+            },
+        )
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for PgInterval {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Text(String),
+            Fields {
+                #[serde(default)]
+                months: i32,
+                #[serde(default)]
+                days: i32,
+                #[serde(default)]
+                microseconds: i64,
+            },
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Text(s) => s.parse().map_err(D::Error::custom),
+            Repr::Fields {
+                months,
+                days,
+                microseconds,
+            } => Ok(PgInterval::new(months, days, microseconds)),
+        }
+    }
+}
+
+impl From<PgInterval> for Value {
+    fn from(arg: PgInterval) -> Self {
+        rbs::value!(arg)
+    }
+}
+
+/// Writes the interval in the Postgres default (`IntervalStyle = postgres`) output format,
+/// e.g. `1 year 2 mons 3 days 04:05:06.5`
+impl Display for PgInterval {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let years = self.months / 12;
+        let months = self.months % 12;
+        let mut parts = Vec::with_capacity(4);
+        if years != 0 {
+            parts.push(format!("{} year{}", years, plural(years as i64)));
+        }
+        if months != 0 {
+            parts.push(format!("{} mon{}", months, plural(months as i64)));
+        }
+        if self.days != 0 {
+            parts.push(format!("{} day{}", self.days, plural(self.days as i64)));
+        }
+        if self.microseconds != 0 || parts.is_empty() {
+            let negative = self.microseconds < 0;
+            let total = self.microseconds.unsigned_abs();
+            let hours = total / MICROS_PER_HOUR as u64;
+            let minutes = total / MICROS_PER_MINUTE as u64 % 60;
+            let seconds = total / MICROS_PER_SECOND as u64 % 60;
+            let fraction = total % MICROS_PER_SECOND as u64;
+
+            let mut time = String::with_capacity(16);
+            if negative {
+                time.push('-');
+            } else if self.months < 0 || self.days < 0 {
+                time.push('+');
+            }
+            write!(time, "{:02}:{:02}:{:02}", hours, minutes, seconds)?;
+            if fraction != 0 {
+                let fraction = format!("{:06}", fraction);
+                write!(time, ".{}", fraction.trim_end_matches('0'))?;
+            }
+            parts.push(time);
+        }
+        f.write_str(&parts.join(" "))
+    }
+}
+
+// Postgres writes "-1 days", only a positive one is singular
+fn plural(n: i64) -> &'static str {
+    if n == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
+
+impl FromStr for PgInterval {
+    type Err = Error;
+
+    /// Parses the Postgres (`1 year 2 mons -3 days +04:05:06`, `@ 1 day ago`)
+    /// and ISO 8601 (`P1Y2M3DT4H5M6S`) interval formats.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        let result = if trimmed.starts_with('P') || trimmed.starts_with("-P") {
+            parse_iso8601(trimmed)
+        } else {
+            parse_postgres(trimmed)
+        };
+        result.ok_or_else(|| Error::from(format!("invalid interval value {:?}", s)))
+    }
+}
+
+// accumulates interval fields, cascading fractional parts down like Postgres does
+#[derive(Default)]
+struct IntervalBuilder {
+    months: f64,
+    days: f64,
+    microseconds: f64,
+}
+
+impl IntervalBuilder {
+    fn add_months(&mut self, v: f64) {
+        let whole = v.trunc();
+        self.months += whole;
+        self.add_days((v - whole) * DAYS_PER_MONTH as f64);
+    }
+
+    fn add_days(&mut self, v: f64) {
+        let whole = v.trunc();
+        self.days += whole;
+        self.microseconds += (v - whole) * MICROS_PER_DAY as f64;
+    }
+
+    fn add_unit(&mut self, v: f64, unit: &str) -> Option<()> {
+        match unit {
+            "millennium" | "millennia" | "millenniums" | "mil" | "mils" => {
+                self.add_months(v * 12000.0)
+            }
+            "century" | "centuries" | "c" | "cent" => self.add_months(v * 1200.0),
+            "decade" | "decades" | "dec" | "decs" => self.add_months(v * 120.0),
+            "year" | "years" | "yr" | "yrs" | "y" => self.add_months(v * 12.0),
+            "month" | "months" | "mon" | "mons" => self.add_months(v),
+            "week" | "weeks" | "w" => self.add_days(v * 7.0),
+            "day" | "days" | "d" => self.add_days(v),
+            "hour" | "hours" | "hr" | "hrs" | "h" => {
+                self.microseconds += v * MICROS_PER_HOUR as f64
+            }
+            "minute" | "minutes" | "min" | "mins" | "m" => {
+                self.microseconds += v * MICROS_PER_MINUTE as f64
+            }
+            "second" | "seconds" | "sec" | "secs" | "s" => {
+                self.microseconds += v * MICROS_PER_SECOND as f64
+            }
+            "millisecond" | "milliseconds" | "msec" | "msecs" | "ms" => {
+                self.microseconds += v * 1000.0
+            }
+            "microsecond" | "microseconds" | "usec" | "usecs" | "us" => self.microseconds += v,
+            _ => return None,
+        }
+        Some(())
+    }
+
+    fn build(self, negate: bool) -> Option<PgInterval> {
+        let sign = if negate { -1.0 } else { 1.0 };
+        let months = (self.months * sign).round();
+        let days = (self.days * sign).round();
+        let microseconds = (self.microseconds * sign).round();
+        if months.abs() > i32::MAX as f64
+            || days.abs() > i32::MAX as f64
+            || microseconds.abs() > i64::MAX as f64
+        {
+            return None;
+        }
+        Some(PgInterval::new(
+            months as i32,
+            days as i32,
+            microseconds as i64,
+        ))
+    }
+}
+
+// [+-]HH:MM[:SS[.ffffff]]
+fn parse_time(s: &str) -> Option<f64> {
+    let (negative, s) = match s.as_bytes().first()? {
+        b'-' => (true, &s[1..]),
+        b'+' => (false, &s[1..]),
+        _ => (false, s),
+    };
+    let mut parts = s.split(':');
+    let hours: f64 = parts.next()?.parse().ok()?;
+    let minutes: f64 = parts.next()?.parse().ok()?;
+    let seconds: f64 = match parts.next() {
+        Some(v) => v.parse().ok()?,
+        None => 0.0,
+    };
+    if parts.next().is_some() {
+        return None;
+    }
+    let micros = hours * MICROS_PER_HOUR as f64
+        + minutes * MICROS_PER_MINUTE as f64
+        + seconds * MICROS_PER_SECOND as f64;
+    Some(if negative { -micros } else { micros })
+}
+
+fn parse_postgres(s: &str) -> Option<PgInterval> {
+    let s = s.strip_prefix('@').unwrap_or(s).trim();
+    let (s, ago) = match s.strip_suffix("ago") {
+        Some(rest) => (rest.trim_end(), true),
+        None => (s, false),
+    };
+
+    let mut builder = IntervalBuilder::default();
+    let mut tokens = s.split_whitespace().peekable();
+    let mut any = false;
+
+    while let Some(token) = tokens.next() {
+        any = true;
+        if token.contains(':') {
+            builder.microseconds += parse_time(token)?;
+            continue;
+        }
+
+        // either "<number> <unit>" or "<number><unit>"
+        let split = token
+            .find(|c: char| c.is_ascii_alphabetic())
+            .unwrap_or(token.len());
+        let (number, unit) = token.split_at(split);
+        let number: f64 = number.parse().ok()?;
+        let unit = if unit.is_empty() {
+            match tokens.next() {
+                Some(unit) => unit.to_ascii_lowercase(),
+                // a bare number is taken as seconds
+                None => "s".to_string(),
+            }
+        } else {
+            unit.to_ascii_lowercase()
+        };
+        builder.add_unit(number, &unit)?;
+    }
+
+    if !any {
+        return None;
+    }
+
+    builder.build(ago)
+}
+
+fn parse_iso8601(s: &str) -> Option<PgInterval> {
+    let (negate, s) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s),
+    };
+    let s = s.strip_prefix('P')?;
+    if s.is_empty() {
+        return None;
+    }
+
+    let mut builder = IntervalBuilder::default();
+    let mut in_time = false;
+    let mut number = String::new();
+
+    for ch in s.chars() {
+        match ch {
+            'T' if !in_time && number.is_empty() => in_time = true,
+            '0'..='9' | '.' | '-' | '+' | ',' => number.push(if ch == ',' { '.' } else { ch }),
+            designator => {
+                let v: f64 = number.parse().ok()?;
+                number.clear();
+                match (in_time, designator) {
+                    (false, 'Y') => builder.add_months(v * 12.0),
+                    (false, 'M') => builder.add_months(v),
+                    (false, 'W') => builder.add_days(v * 7.0),
+                    (false, 'D') => builder.add_days(v),
+                    (true, 'H') => builder.microseconds += v * MICROS_PER_HOUR as f64,
+                    (true, 'M') => builder.microseconds += v * MICROS_PER_MINUTE as f64,
+                    (true, 'S') => builder.microseconds += v * MICROS_PER_SECOND as f64,
+                    _ => return None,
+                }
+            }
+        }
+    }
+
+    if !number.is_empty() {
+        return None;
+    }
+
+    builder.build(negate)
+}
+
+impl Decode for PgInterval {
+    fn decode(value: PgValue) -> Result<Self, Error> {
+        match value.format() {
+            PgValueFormat::Binary => {
+                let mut buf = Cursor::new(value.as_bytes()?);
+                let microseconds = buf.read_i64::<BigEndian>()?;
+                let days = buf.read_i32::<BigEndian>()?;
+                let months = buf.read_i32::<BigEndian>()?;
+                Ok(PgInterval::new(months, days, microseconds))
+            }
+            PgValueFormat::Text => value.as_str()?.parse(),
+        }
+    }
+}
+
+impl Encode for PgInterval {
+    fn encode(self, buf: &mut PgArgumentBuffer) -> Result<IsNull, Error> {
+        buf.extend(&self.microseconds.to_be_bytes());
+        buf.extend(&self.days.to_be_bytes());
+        buf.extend(&self.months.to_be_bytes());
+        Ok(IsNull::No)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::type_info::PgTypeInfo;
+    use crate::types::decode::Decode;
+    use crate::types::interval::PgInterval;
+    use crate::value::{PgValue, PgValueFormat};
+    use rbs::Value;
+    use std::time::Duration;
+
+    #[test]
+    fn test_parse_postgres() {
+        assert_eq!(
+            "1 year 2 mons 3 days 04:05:06.789"
+                .parse::<PgInterval>()
+                .unwrap(),
+            PgInterval::new(14, 3, 14_706_789_000)
+        );
+        assert_eq!(
+            "-1 days +02:03:00".parse::<PgInterval>().unwrap(),
+            PgInterval::new(0, -1, 7_380_000_000)
+        );
+        assert_eq!(
+            "00:00:00".parse::<PgInterval>().unwrap(),
+            PgInterval::default()
+        );
+        assert_eq!(
+            "-00:00:01.5".parse::<PgInterval>().unwrap(),
+            PgInterval::new(0, 0, -1_500_000)
+        );
+        assert_eq!(
+            "@ 1 day 2 hours ago".parse::<PgInterval>().unwrap(),
+            PgInterval::new(0, -1, -7_200_000_000)
+        );
+        assert_eq!(
+            "1.5 months".parse::<PgInterval>().unwrap(),
+            PgInterval::new(1, 15, 0)
+        );
+        assert!("1 fortnight".parse::<PgInterval>().is_err());
+        assert!("".parse::<PgInterval>().is_err());
+    }
+
+    #[test]
+    fn test_parse_iso8601() {
+        assert_eq!(
+            "P1Y2M3DT4H5M6.5S".parse::<PgInterval>().unwrap(),
+            PgInterval::new(14, 3, 14_706_500_000)
+        );
+        assert_eq!(
+            "PT-1H".parse::<PgInterval>().unwrap(),
+            PgInterval::new(0, 0, -3_600_000_000)
+        );
+        assert_eq!(
+            "-P1W".parse::<PgInterval>().unwrap(),
+            PgInterval::new(0, -7, 0)
+        );
+        assert!("P1H".parse::<PgInterval>().is_err());
+        assert!("P".parse::<PgInterval>().is_err());
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            PgInterval::new(14, 3, 14_706_789_000).to_string(),
+            "1 year 2 mons 3 days 04:05:06.789"
+        );
+        assert_eq!(
+            PgInterval::new(0, -1, 7_380_000_000).to_string(),
+            "-1 days +02:03:00"
+        );
+        assert_eq!(PgInterval::default().to_string(), "00:00:00");
+        let i = PgInterval::new(-3, 12, -1);
+        assert_eq!(i.to_string().parse::<PgInterval>().unwrap(), i);
+    }
+
+    #[test]
+    fn test_duration() {
+        let i = PgInterval::try_from(Duration::from_millis(1500)).unwrap();
+        assert_eq!(i, PgInterval::new(0, 0, 1_500_000));
+        assert!(PgInterval::try_from(Duration::from_nanos(1)).is_err());
+
+        let d = Duration::try_from(PgInterval::new(0, 1, 1)).unwrap();
+        assert_eq!(d, Duration::from_micros(86_400_000_001));
+        assert!(Duration::try_from(PgInterval::new(1, 0, 0)).is_err());
+        assert!(Duration::try_from(PgInterval::new(0, 0, -1)).is_err());
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let a = PgInterval::new(1, 2, 3);
+        let b = PgInterval::new(10, 20, 30);
+        assert_eq!(a + b, PgInterval::new(11, 22, 33));
+        assert_eq!(b - a, PgInterval::new(9, 18, 27));
+        assert_eq!(-a, PgInterval::new(-1, -2, -3));
+        assert_eq!(PgInterval::new(i32::MAX, 0, 0).checked_add(a), None);
+    }
+
+    #[test]
+    fn test_decode_binary() {
+        let mut bytes = Vec::new();
+        bytes.extend(3_600_000_000_i64.to_be_bytes());
+        bytes.extend(2_i32.to_be_bytes());
+        bytes.extend(1_i32.to_be_bytes());
+        let v = PgInterval::decode(PgValue {
+            value: Some(bytes),
+            type_info: PgTypeInfo::INTERVAL,
+            format: PgValueFormat::Binary,
+            timezone: None,
+        })
+        .unwrap();
+        assert_eq!(v, PgInterval::new(1, 2, 3_600_000_000));
+    }
+
+    #[test]
+    fn test_value_serde() {
+        let i = PgInterval::new(1, 2, 3);
+        let v = Value::from(i);
+        assert!(matches!(v, Value::Ext("Interval", _)));
+        assert_eq!(rbs::from_value::<PgInterval>(v).unwrap(), i);
+
+        let v = Value::Ext("Interval", Box::new(Value::String("1 day".to_string())));
+        assert_eq!(
+            rbs::from_value::<PgInterval>(v).unwrap(),
+            PgInterval::new(0, 1, 0)
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use crate::arguments::PgArgumentBuffer;
+use crate::types::decode::Decode;
+use crate::types::encode::{Encode, IsNull};
+use crate::value::{PgValue, PgValueFormat};
+use rbdc::Error;
+use rbs::Value;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// the version byte in front of the binary format of `ltree`, `lquery` and `ltxtquery`
+const LTREE_VERSION: u8 = 1;
+
+/// A label path of the `ltree` extension, such as `Top.Science.Astronomy`.
+///
+/// Decoded columns are a `Value::String`, a string (or a `PgLTree`) can be bound to an `ltree`
+/// parameter. The type oid is resolved by name when the statement is prepared.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default, Eq, PartialEq, Hash)]
+#[serde(rename = "Ltree")]
+pub struct PgLTree(pub String);
+
+impl PgLTree {
+    /// Joins `labels` into a path. Labels may hold letters, digits, `_` and `-`.
+    pub fn from_labels<I, S>(labels: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let labels: Vec<String> = labels
+            .into_iter()
+            .map(|label| label.as_ref().to_string())
+            .collect();
+        PgLTree::from_str(&labels.join("."))
+    }
+
+    pub fn labels(&self) -> impl Iterator<Item = &str> {
+        self.0.split('.').filter(|label| !label.is_empty())
+    }
+}
+
+impl FromStr for PgLTree {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !s.is_empty() {
+            for label in s.split('.') {
+                let valid = !label.is_empty()
+                    && label
+                        .chars()
+                        .all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+                if !valid {
+                    return Err(Error::from(format!(
+                        "invalid ltree label {:?} in {}",
+                        label, s
+                    )));
+                }
+            }
+        }
+        Ok(PgLTree(s.to_string()))
+    }
+}
+
+impl Display for PgLTree {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<PgLTree> for Value {
+    fn from(arg: PgLTree) -> Self {
+        Value::Ext("Ltree", Box::new(Value::String(arg.0)))
+    }
+}
+
+/// A pattern of the `ltree` extension matching label paths, such as `*.Astronomy.*`.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default, Eq, PartialEq, Hash)]
+#[serde(rename = "Lquery")]
+pub struct PgLQuery(pub String);
+
+impl Display for PgLQuery {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<PgLQuery> for Value {
+    fn from(arg: PgLQuery) -> Self {
+        Value::Ext("Lquery", Box::new(Value::String(arg.0)))
+    }
+}
+
+/// binary format: the version byte, then the text
+fn decode_text(value: PgValue) -> Result<String, Error> {
+    let bytes = match value.format() {
+        PgValueFormat::Binary => match value.as_bytes()?.split_first() {
+            Some((&LTREE_VERSION, bytes)) => bytes,
+            Some((version, _)) => {
+                return Err(Error::from(format!(
+                    "unsupported ltree version {}",
+                    version
+                )))
+            }
+            None => return Err(Error::from("ltree value is empty")),
+        },
+        PgValueFormat::Text => value.as_bytes()?,
+    };
+    String::from_utf8(bytes.to_vec()).map_err(|e| Error::from(e.to_string()))
+}
+
+fn encode_text(s: &str, buf: &mut PgArgumentBuffer) -> Result<IsNull, Error> {
+    buf.push(LTREE_VERSION);
+    buf.extend(s.as_bytes());
+    Ok(IsNull::No)
+}
+
+impl Decode for PgLTree {
+    fn decode(value: PgValue) -> Result<Self, Error> {
+        Ok(Self(decode_text(value)?))
+    }
+}
+
+impl Encode for PgLTree {
+    fn encode(self, buf: &mut PgArgumentBuffer) -> Result<IsNull, Error> {
+        encode_text(&self.0, buf)
+    }
+}
+
+impl Decode for PgLQuery {
+    fn decode(value: PgValue) -> Result<Self, Error> {
+        Ok(Self(decode_text(value)?))
+    }
+}
+
+impl Encode for PgLQuery {
+    fn encode(self, buf: &mut PgArgumentBuffer) -> Result<IsNull, Error> {
+        encode_text(&self.0, buf)
+    }
+}
+
+/// Decodes an `ltree`, `lquery` or `ltxtquery` column into a `Value::String`.
+pub(crate) fn decode_value(arg: PgValue) -> Result<Value, Error> {
+    Ok(Value::String(decode_text(arg)?))
+}
+
+/// Encodes a string as an `ltree`, `lquery` or `ltxtquery` parameter.
+pub(crate) fn encode_value(v: Value, buf: &mut PgArgumentBuffer) -> Result<IsNull, Error> {
+    match v {
+        Value::String(s) => encode_text(&s, buf),
+        Value::Ext(_, v) if v.is_str() => encode_text(v.as_str().unwrap_or_default(), buf),
+        v => Err(Error::from(format!("cannot convert {} to ltree", v))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::arguments::PgArgumentBuffer;
+    use crate::type_info::PgTypeInfo;
+    use crate::types::decode::Decode;
+    use crate::types::encode::Encode;
+    use crate::types::ltree::PgLTree;
+    use crate::value::{PgValue, PgValueFormat};
+    use std::str::FromStr;
+
+    #[test]
+    fn test_labels() {
+        let path = PgLTree::from_labels(["Top", "Science", "Astronomy"]).unwrap();
+        assert_eq!(path.to_string(), "Top.Science.Astronomy");
+        assert_eq!(path.labels().count(), 3);
+        assert!(PgLTree::from_str("Top..Science").is_err());
+        assert!(PgLTree::from_str("Top.a b").is_err());
+    }
+
+    #[test]
+    fn test_binary() {
+        let path = PgLTree::from_str("a.b").unwrap();
+        let mut buf = PgArgumentBuffer::default();
+        path.clone().encode(&mut buf).unwrap();
+        assert_eq!(&buf[..], b"\x01a.b");
+        let v = PgValue {
+            value: Some(buf.to_vec()),
+            type_info: PgTypeInfo::with_name("ltree"),
+            format: PgValueFormat::Binary,
+            timezone: None,
+        };
+        assert_eq!(PgLTree::decode(v).unwrap(), path);
+    }
+}
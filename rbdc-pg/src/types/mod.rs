@@ -5,21 +5,32 @@ pub mod array;
 pub mod bigdecimal;
 pub mod bool;
 pub mod byte;
+pub mod citext;
+pub mod coerce;
+pub mod composite;
 pub mod date;
 pub mod datetime;
 pub mod decimal;
 pub mod decode;
 pub mod encode;
 pub mod float;
+pub mod geometry;
+pub mod hstore;
 pub mod int;
+pub mod interval;
 pub mod json;
+pub mod ltree;
 pub mod money;
+pub mod network;
 pub mod numeric;
+pub mod range;
 pub mod string;
+pub mod text_search;
 pub mod time;
 pub mod timestamp;
 pub mod timestamptz;
 pub mod timetz;
+pub mod timezone;
 pub mod uuid;
 pub mod value;
 use crate::type_info::PgTypeInfo;
@@ -0,0 +1,218 @@
+use crate::arguments::PgArgumentBuffer;
+use crate::types::decode::Decode;
+use crate::types::encode::{Encode, IsNull};
+use crate::value::{PgValue, PgValueFormat};
+use rbdc::network::{ip_from_bytes, Cidr, Inet, IpNetwork, MacAddress, MacAddress8};
+use rbdc::Error;
+use rbs::Value;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+// https://github.com/postgres/postgres/blob/REL_14_STABLE/src/include/utils/inet.h
+const PGSQL_AF_INET: u8 = 2;
+const PGSQL_AF_INET6: u8 = 3;
+
+/// binary format: family, bits, is_cidr, address length, address
+fn decode_network(value: &PgValue) -> Result<IpNetwork, Error> {
+    match value.format() {
+        PgValueFormat::Binary => {
+            let bytes = value.as_bytes()?;
+            if bytes.len() < 4 {
+                return Err(Error::from("inet value is too short"));
+            }
+            let (family, bits, len) = (bytes[0], bytes[1], bytes[3] as usize);
+            let addr = bytes
+                .get(4..4 + len)
+                .ok_or_else(|| Error::from("inet value is truncated"))?;
+            let ip = ip_from_bytes(addr)?;
+            match (family, ip) {
+                (PGSQL_AF_INET, IpAddr::V4(_)) | (PGSQL_AF_INET6, IpAddr::V6(_)) => {}
+                _ => return Err(Error::from(format!("unknown inet family {}", family))),
+            }
+            IpNetwork::new(ip, bits).map_err(|e| Error::from(e.to_string()))
+        }
+        PgValueFormat::Text => {
+            IpNetwork::from_str(value.as_str()?).map_err(|e| Error::from(e.to_string()))
+        }
+    }
+}
+
+fn encode_network(
+    network: IpNetwork,
+    is_cidr: bool,
+    buf: &mut PgArgumentBuffer,
+) -> Result<IsNull, Error> {
+    match network.ip() {
+        IpAddr::V4(ip) => {
+            buf.extend(&[PGSQL_AF_INET, network.prefix(), is_cidr as u8, 4]);
+            buf.extend(&ip.octets());
+        }
+        IpAddr::V6(ip) => {
+            buf.extend(&[PGSQL_AF_INET6, network.prefix(), is_cidr as u8, 16]);
+            buf.extend(&ip.octets());
+        }
+    }
+    Ok(IsNull::No)
+}
+
+impl Decode for Inet {
+    fn decode(value: PgValue) -> Result<Self, Error> {
+        Ok(Self(decode_network(&value)?))
+    }
+}
+
+impl Encode for Inet {
+    fn encode(self, buf: &mut PgArgumentBuffer) -> Result<IsNull, Error> {
+        encode_network(self.0, false, buf)
+    }
+}
+
+impl Decode for Cidr {
+    fn decode(value: PgValue) -> Result<Self, Error> {
+        Ok(Self(decode_network(&value)?))
+    }
+}
+
+impl Encode for Cidr {
+    fn encode(self, buf: &mut PgArgumentBuffer) -> Result<IsNull, Error> {
+        encode_network(self.0, true, buf)
+    }
+}
+
+impl Decode for IpNetwork {
+    fn decode(value: PgValue) -> Result<Self, Error> {
+        decode_network(&value)
+    }
+}
+
+impl Decode for IpAddr {
+    fn decode(value: PgValue) -> Result<Self, Error> {
+        Ok(decode_network(&value)?.ip())
+    }
+}
+
+impl Encode for IpAddr {
+    fn encode(self, buf: &mut PgArgumentBuffer) -> Result<IsNull, Error> {
+        Inet::from(self).encode(buf)
+    }
+}
+
+impl Decode for MacAddress {
+    fn decode(value: PgValue) -> Result<Self, Error> {
+        match value.format() {
+            PgValueFormat::Binary => MacAddress::from_bytes(value.as_bytes()?),
+            PgValueFormat::Text => MacAddress::from_str(value.as_str()?),
+        }
+    }
+}
+
+impl Encode for MacAddress {
+    fn encode(self, buf: &mut PgArgumentBuffer) -> Result<IsNull, Error> {
+        buf.extend(&self.0);
+        Ok(IsNull::No)
+    }
+}
+
+impl Decode for MacAddress8 {
+    fn decode(value: PgValue) -> Result<Self, Error> {
+        match value.format() {
+            PgValueFormat::Binary => MacAddress8::from_bytes(value.as_bytes()?),
+            PgValueFormat::Text => MacAddress8::from_str(value.as_str()?),
+        }
+    }
+}
+
+impl Encode for MacAddress8 {
+    fn encode(self, buf: &mut PgArgumentBuffer) -> Result<IsNull, Error> {
+        buf.extend(&self.0);
+        Ok(IsNull::No)
+    }
+}
+
+/// Encodes a network `Value::Ext` by its name.
+pub(crate) fn encode_by_name(
+    name: &str,
+    v: Value,
+    buf: &mut PgArgumentBuffer,
+) -> Result<IsNull, Error> {
+    match name {
+        "Inet" => rbs::from_value::<Inet>(v)?.encode(buf),
+        "Cidr" => rbs::from_value::<Cidr>(v)?.encode(buf),
+        "Macaddr" => rbs::from_value::<MacAddress>(v)?.encode(buf),
+        "Macaddr8" => rbs::from_value::<MacAddress8>(v)?.encode(buf),
+        _ => Err(Error::from(format!("unknown network type {}", name))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::arguments::PgArgumentBuffer;
+    use crate::type_info::PgTypeInfo;
+    use crate::types::decode::Decode;
+    use crate::types::encode::Encode;
+    use crate::types::network::encode_by_name;
+    use crate::value::{PgValue, PgValueFormat};
+    use rbdc::network::{Cidr, Inet, MacAddress, MacAddress8};
+    use rbs::Value;
+    use std::net::IpAddr;
+
+    fn binary(ty: PgTypeInfo, bytes: Vec<u8>) -> PgValue {
+        PgValue {
+            value: Some(bytes),
+            type_info: ty,
+            format: PgValueFormat::Binary,
+            timezone: None,
+        }
+    }
+
+    #[test]
+    fn test_inet_binary() {
+        let inet: Inet = "192.168.0.1/24".parse().unwrap();
+        let mut buf = PgArgumentBuffer::default();
+        inet.encode(&mut buf).unwrap();
+        assert_eq!(&buf[..], &[2, 24, 0, 4, 192, 168, 0, 1][..]);
+        let decoded = Inet::decode(binary(PgTypeInfo::INET, buf.to_vec())).unwrap();
+        assert_eq!(decoded, inet);
+    }
+
+    #[test]
+    fn test_cidr_v6_binary() {
+        let cidr: Cidr = "2001:db8::/32".parse().unwrap();
+        let mut buf = PgArgumentBuffer::default();
+        cidr.encode(&mut buf).unwrap();
+        assert_eq!(&buf[..4], &[3, 32, 1, 16][..]);
+        let decoded = Cidr::decode(binary(PgTypeInfo::CIDR, buf.to_vec())).unwrap();
+        assert_eq!(decoded, cidr);
+    }
+
+    #[test]
+    fn test_text() {
+        let v = PgValue {
+            value: Some(b"::1".to_vec()),
+            type_info: PgTypeInfo::INET,
+            format: PgValueFormat::Text,
+            timezone: None,
+        };
+        assert_eq!(IpAddr::decode(v).unwrap(), "::1".parse::<IpAddr>().unwrap());
+        let v = PgValue {
+            value: Some(b"08:00:2b:01:02:03".to_vec()),
+            type_info: PgTypeInfo::MACADDR,
+            format: PgValueFormat::Text,
+            timezone: None,
+        };
+        assert_eq!(
+            MacAddress::decode(v).unwrap(),
+            MacAddress([8, 0, 0x2b, 1, 2, 3])
+        );
+    }
+
+    #[test]
+    fn test_mac_binary() {
+        let mac = MacAddress8([8, 0, 0x2b, 1, 2, 3, 4, 5]);
+        let mut buf = PgArgumentBuffer::default();
+        encode_by_name("Macaddr8", Value::from(mac), &mut buf).unwrap();
+        assert_eq!(&buf[..], &mac.0[..]);
+        let decoded = Value::decode(binary(PgTypeInfo::MACADDR8, buf.to_vec())).unwrap();
+        assert_eq!(decoded, Value::from(mac));
+    }
+}
@@ -0,0 +1,885 @@
+use crate::arguments::PgArgumentBuffer;
+use crate::type_info::{PgType, PgTypeInfo, PgTypeKind};
+use crate::types::decode::Decode;
+use crate::types::encode::{Encode, IsNull};
+use crate::types::timezone::PgTimeZone;
+use crate::types::value;
+use crate::value::{PgValue, PgValueFormat};
+use bytes::Buf;
+use rbdc::date::Date;
+use rbdc::datetime::DateTime;
+use rbdc::decimal::Decimal;
+use rbdc::timestamp::Timestamp;
+use rbdc::Error;
+use rbs::Value;
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+use std::str::FromStr;
+
+// https://github.com/postgres/postgres/blob/REL_14_STABLE/src/include/utils/rangetypes.h
+const RANGE_EMPTY: u8 = 0x01;
+const RANGE_LB_INC: u8 = 0x02;
+const RANGE_UB_INC: u8 = 0x04;
+const RANGE_LB_INF: u8 = 0x08;
+const RANGE_UB_INF: u8 = 0x10;
+
+/// One end of a [`PgRange`].
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PgRangeBound<T> {
+    Inclusive(T),
+    Exclusive(T),
+    Unbounded,
+}
+
+impl<T> PgRangeBound<T> {
+    pub fn value(&self) -> Option<&T> {
+        match self {
+            PgRangeBound::Inclusive(v) | PgRangeBound::Exclusive(v) => Some(v),
+            PgRangeBound::Unbounded => None,
+        }
+    }
+
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> PgRangeBound<U> {
+        match self {
+            PgRangeBound::Inclusive(v) => PgRangeBound::Inclusive(f(v)),
+            PgRangeBound::Exclusive(v) => PgRangeBound::Exclusive(f(v)),
+            PgRangeBound::Unbounded => PgRangeBound::Unbounded,
+        }
+    }
+}
+
+/// Postgres range types: `INT4RANGE`, `INT8RANGE`, `NUMRANGE`, `TSRANGE`, `TSTZRANGE`,
+/// `DATERANGE` and user defined ranges.
+///
+/// text format `[lower,upper)`, `(,upper]` or `empty`
+///
+/// As a `Value` a range is `Value::Ext("Int4Range", {lower, upper, empty})`, where the ext name
+/// is the range type ("Range" for user defined ranges).
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PgRange<T> {
+    pub lower: PgRangeBound<T>,
+    pub upper: PgRangeBound<T>,
+    #[serde(default)]
+    pub empty: bool,
+}
+
+impl<T> PgRange<T> {
+    pub fn new(lower: PgRangeBound<T>, upper: PgRangeBound<T>) -> Self {
+        Self {
+            lower,
+            upper,
+            empty: false,
+        }
+    }
+
+    /// the range `empty`, containing no points
+    pub fn empty() -> Self {
+        Self {
+            lower: PgRangeBound::Unbounded,
+            upper: PgRangeBound::Unbounded,
+            empty: true,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.empty
+    }
+
+    pub fn map<U>(self, mut f: impl FnMut(T) -> U) -> PgRange<U> {
+        PgRange {
+            lower: self.lower.map(&mut f),
+            upper: self.upper.map(&mut f),
+            empty: self.empty,
+        }
+    }
+
+    fn flags(&self) -> u8 {
+        if self.empty {
+            return RANGE_EMPTY;
+        }
+        let mut flags = 0;
+        match self.lower {
+            PgRangeBound::Inclusive(_) => flags |= RANGE_LB_INC,
+            PgRangeBound::Exclusive(_) => {}
+            PgRangeBound::Unbounded => flags |= RANGE_LB_INF,
+        }
+        match self.upper {
+            PgRangeBound::Inclusive(_) => flags |= RANGE_UB_INC,
+            PgRangeBound::Exclusive(_) => {}
+            PgRangeBound::Unbounded => flags |= RANGE_UB_INF,
+        }
+        flags
+    }
+
+    fn encode_with(
+        self,
+        buf: &mut PgArgumentBuffer,
+        mut f: impl FnMut(T, &mut PgArgumentBuffer) -> Result<IsNull, Error>,
+    ) -> Result<IsNull, Error> {
+        buf.push(self.flags());
+        if self.empty {
+            return Ok(IsNull::No);
+        }
+        for bound in [self.lower, self.upper] {
+            match bound {
+                PgRangeBound::Inclusive(v) | PgRangeBound::Exclusive(v) => {
+                    encode_prefixed(buf, |buf| f(v, buf))?
+                }
+                PgRangeBound::Unbounded => {}
+            }
+        }
+        Ok(IsNull::No)
+    }
+}
+
+/// Postgres 14 multirange types, an ordered list of non-overlapping ranges.
+///
+/// text format `{[1,3),[5,7)}`
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(transparent)]
+pub struct PgMultirange<T>(pub Vec<PgRange<T>>);
+
+impl<T> Default for PgMultirange<T> {
+    fn default() -> Self {
+        Self(vec![])
+    }
+}
+
+impl<T> Deref for PgMultirange<T> {
+    type Target = Vec<PgRange<T>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for PgMultirange<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T> From<Vec<PgRange<T>>> for PgMultirange<T> {
+    fn from(arg: Vec<PgRange<T>>) -> Self {
+        Self(arg)
+    }
+}
+
+impl<T: Display> Display for PgRange<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.empty {
+            return f.write_str("empty");
+        }
+        match &self.lower {
+            PgRangeBound::Inclusive(v) => write!(f, "[{}", QuoteBound(v))?,
+            PgRangeBound::Exclusive(v) => write!(f, "({}", QuoteBound(v))?,
+            PgRangeBound::Unbounded => f.write_str("(")?,
+        }
+        f.write_str(",")?;
+        match &self.upper {
+            PgRangeBound::Inclusive(v) => write!(f, "{}]", QuoteBound(v)),
+            PgRangeBound::Exclusive(v) => write!(f, "{})", QuoteBound(v)),
+            PgRangeBound::Unbounded => f.write_str(")"),
+        }
+    }
+}
+
+impl<T: Display> Display for PgMultirange<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("{")?;
+        for (i, range) in self.0.iter().enumerate() {
+            if i != 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{}", range)?;
+        }
+        f.write_str("}")
+    }
+}
+
+/// quotes a bound the way `range_out` does when it contains special characters
+struct QuoteBound<'a, T>(&'a T);
+
+impl<T: Display> Display for QuoteBound<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let s = self.0.to_string();
+        let special = s.is_empty()
+            || s.chars().any(|c| {
+                matches!(c, '"' | '\\' | ',' | '(' | ')' | '[' | ']') || c.is_whitespace()
+            });
+        if !special {
+            return f.write_str(&s);
+        }
+        f.write_str("\"")?;
+        for c in s.chars() {
+            if c == '"' || c == '\\' {
+                write!(f, "\\{}", c)?;
+            } else {
+                write!(f, "{}", c)?;
+            }
+        }
+        f.write_str("\"")
+    }
+}
+
+impl FromStr for PgRange<String> {
+    type Err = Error;
+
+    /// parses the text format of a range, keeping the bounds as strings
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("empty") {
+            return Ok(PgRange::empty());
+        }
+        let mut chars = s.chars();
+        let lower_inclusive = match chars.next() {
+            Some('[') => true,
+            Some('(') => false,
+            _ => return Err(Error::from(format!("invalid range literal: {}", s))),
+        };
+        let (lower, lower_quoted, _) = read_bound(&mut chars, &[','], s)?;
+        let (upper, upper_quoted, end) = read_bound(&mut chars, &[')', ']'], s)?;
+        if !chars.as_str().trim().is_empty() {
+            return Err(Error::from(format!("invalid range literal: {}", s)));
+        }
+        let lower = match (lower.is_empty() && !lower_quoted, lower_inclusive) {
+            (true, _) => PgRangeBound::Unbounded,
+            (false, true) => PgRangeBound::Inclusive(lower),
+            (false, false) => PgRangeBound::Exclusive(lower),
+        };
+        let upper = match (upper.is_empty() && !upper_quoted, end == ']') {
+            (true, _) => PgRangeBound::Unbounded,
+            (false, true) => PgRangeBound::Inclusive(upper),
+            (false, false) => PgRangeBound::Exclusive(upper),
+        };
+        Ok(PgRange::new(lower, upper))
+    }
+}
+
+impl FromStr for PgMultirange<String> {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let inner = s
+            .strip_prefix('{')
+            .and_then(|s| s.strip_suffix('}'))
+            .ok_or_else(|| Error::from(format!("invalid multirange literal: {}", s)))?;
+        let mut ranges = vec![];
+        let mut rest = inner.trim_start();
+        while !rest.is_empty() {
+            let len = if rest.len() >= 5 && rest[..5].eq_ignore_ascii_case("empty") {
+                5
+            } else {
+                range_literal_len(rest)
+                    .ok_or_else(|| Error::from(format!("invalid multirange literal: {}", s)))?
+            };
+            ranges.push(rest[..len].parse::<PgRange<String>>()?);
+            rest = rest[len..].trim_start();
+            rest = rest.strip_prefix(',').unwrap_or(rest).trim_start();
+        }
+        Ok(Self(ranges))
+    }
+}
+
+/// reads one bound up to (and consuming) one of `ends`, returning the unescaped bound, whether
+/// any part of it was quoted and the terminating character
+fn read_bound(
+    chars: &mut std::str::Chars,
+    ends: &[char],
+    literal: &str,
+) -> Result<(String, bool, char), Error> {
+    let mut value = String::new();
+    let mut quoted = false;
+    let mut in_quotes = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(c) => value.push(c),
+                None => break,
+            },
+            '"' if in_quotes && chars.as_str().starts_with('"') => {
+                chars.next();
+                value.push('"');
+            }
+            '"' => {
+                in_quotes = !in_quotes;
+                quoted = true;
+            }
+            _ if !in_quotes && ends.contains(&c) => return Ok((value, quoted, c)),
+            _ => value.push(c),
+        }
+    }
+    Err(Error::from(format!("invalid range literal: {}", literal)))
+}
+
+/// length of the range literal at the start of `s`
+fn range_literal_len(s: &str) -> Option<usize> {
+    let mut in_quotes = false;
+    let mut escaped = false;
+    for (i, c) in s.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => in_quotes = !in_quotes,
+            ')' | ']' if !in_quotes && i != 0 => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// writes the length prefixed value produced by `f`
+fn encode_prefixed(
+    buf: &mut PgArgumentBuffer,
+    f: impl FnOnce(&mut PgArgumentBuffer) -> Result<IsNull, Error>,
+) -> Result<(), Error> {
+    let offset = buf.len();
+    buf.extend(&[0; 4]);
+    let len = match f(buf)? {
+        IsNull::No => (buf.len() - offset - 4) as i32,
+        IsNull::Yes => -1,
+    };
+    buf[offset..(offset + 4)].copy_from_slice(&len.to_be_bytes());
+    Ok(())
+}
+
+/// the element type of a range type
+fn range_element(ty: &PgTypeInfo) -> Result<PgTypeInfo, Error> {
+    match ty.kind() {
+        PgTypeKind::Range(element) => Ok(element.clone()),
+        PgTypeKind::Domain(base) => range_element(base),
+        _ => Err(Error::from(format!("{} is not a range type", ty.name()))),
+    }
+}
+
+/// the range type of a multirange type
+fn multirange_range(ty: &PgTypeInfo) -> Result<PgTypeInfo, Error> {
+    match ty.kind() {
+        PgTypeKind::Multirange(range) => Ok(range.clone()),
+        PgTypeKind::Domain(base) => multirange_range(base),
+        _ => Err(Error::from(format!(
+            "{} is not a multirange type",
+            ty.name()
+        ))),
+    }
+}
+
+fn decode_range<T: Decode>(
+    mut buf: &[u8],
+    format: PgValueFormat,
+    element: &PgTypeInfo,
+    timezone: &Option<PgTimeZone>,
+) -> Result<PgRange<T>, Error> {
+    match format {
+        PgValueFormat::Binary => {
+            if buf.is_empty() {
+                return Err(Error::from("range value is empty"));
+            }
+            let flags = buf.get_u8();
+            if flags & RANGE_EMPTY != 0 {
+                return Ok(PgRange::empty());
+            }
+            let read = |inf: u8, inc: u8, buf: &mut &[u8]| -> Result<PgRangeBound<T>, Error> {
+                if flags & inf != 0 {
+                    return Ok(PgRangeBound::Unbounded);
+                }
+                if buf.len() < 4 {
+                    return Err(Error::from("range bound is truncated"));
+                }
+                let v = T::decode(
+                    PgValue::get(buf, format, element.clone())?.with_timezone(timezone.clone()),
+                )?;
+                Ok(if flags & inc != 0 {
+                    PgRangeBound::Inclusive(v)
+                } else {
+                    PgRangeBound::Exclusive(v)
+                })
+            };
+            let lower = read(RANGE_LB_INF, RANGE_LB_INC, &mut buf)?;
+            let upper = read(RANGE_UB_INF, RANGE_UB_INC, &mut buf)?;
+            Ok(PgRange::new(lower, upper))
+        }
+        PgValueFormat::Text => {
+            let range: PgRange<String> = std::str::from_utf8(buf)?.parse()?;
+            decode_text_bounds(range, element, timezone)
+        }
+    }
+}
+
+fn decode_text_bounds<T: Decode>(
+    range: PgRange<String>,
+    element: &PgTypeInfo,
+    timezone: &Option<PgTimeZone>,
+) -> Result<PgRange<T>, Error> {
+    let decode = |v: String| {
+        T::decode(PgValue {
+            value: Some(v.into_bytes()),
+            type_info: element.clone(),
+            format: PgValueFormat::Text,
+            timezone: timezone.clone(),
+        })
+    };
+    Ok(PgRange {
+        lower: bound_try_map(range.lower, decode)?,
+        upper: bound_try_map(range.upper, decode)?,
+        empty: range.empty,
+    })
+}
+
+fn bound_try_map<T, U>(
+    bound: PgRangeBound<T>,
+    f: impl FnOnce(T) -> Result<U, Error>,
+) -> Result<PgRangeBound<U>, Error> {
+    Ok(match bound {
+        PgRangeBound::Inclusive(v) => PgRangeBound::Inclusive(f(v)?),
+        PgRangeBound::Exclusive(v) => PgRangeBound::Exclusive(f(v)?),
+        PgRangeBound::Unbounded => PgRangeBound::Unbounded,
+    })
+}
+
+impl<T: Decode> Decode for PgRange<T> {
+    fn decode(value: PgValue) -> Result<Self, Error> {
+        let element = range_element(&value.type_info)?;
+        decode_range(value.as_bytes()?, value.format(), &element, &value.timezone)
+    }
+}
+
+impl<T: Decode> Decode for PgMultirange<T> {
+    fn decode(value: PgValue) -> Result<Self, Error> {
+        let range_type = multirange_range(&value.type_info)?;
+        let element = range_element(&range_type)?;
+        match value.format() {
+            PgValueFormat::Binary => {
+                let mut buf = value.as_bytes()?;
+                if buf.len() < 4 {
+                    return Err(Error::from("multirange value is empty"));
+                }
+                let len = buf.get_i32();
+                // each range takes at least its length and flags
+                let mut ranges = Vec::with_capacity((len.max(0) as usize).min(buf.len() / 5));
+                for _ in 0..len {
+                    if buf.len() < 4 {
+                        return Err(Error::from("multirange value is truncated"));
+                    }
+                    let range_len = buf.get_i32();
+                    if range_len < 0 || buf.len() < range_len as usize {
+                        return Err(Error::from("multirange value is truncated"));
+                    }
+                    let range_len = range_len as usize;
+                    ranges.push(decode_range(
+                        &buf[..range_len],
+                        PgValueFormat::Binary,
+                        &element,
+                        &value.timezone,
+                    )?);
+                    buf.advance(range_len);
+                }
+                Ok(Self(ranges))
+            }
+            PgValueFormat::Text => {
+                let multirange: PgMultirange<String> = value.as_str()?.parse()?;
+                multirange
+                    .0
+                    .into_iter()
+                    .map(|r| decode_text_bounds(r, &element, &value.timezone))
+                    .collect::<Result<Vec<_>, Error>>()
+                    .map(Self)
+            }
+        }
+    }
+}
+
+impl<T: Encode> Encode for PgRange<T> {
+    fn encode(self, buf: &mut PgArgumentBuffer) -> Result<IsNull, Error> {
+        self.encode_with(buf, |v, buf| v.encode(buf))
+    }
+}
+
+impl<T: Encode> Encode for PgMultirange<T> {
+    fn encode(self, buf: &mut PgArgumentBuffer) -> Result<IsNull, Error> {
+        buf.extend(&(self.0.len() as i32).to_be_bytes());
+        for range in self.0 {
+            encode_prefixed(buf, |buf| range.encode(buf))?;
+        }
+        Ok(IsNull::No)
+    }
+}
+
+macro_rules! impl_range_into_value {
+    ($($ty:ty => $range:literal, $multirange:literal;)+) => {
+        $(
+            impl From<PgRange<$ty>> for Value {
+                fn from(arg: PgRange<$ty>) -> Self {
+                    Value::Ext($range, Box::new(rbs::value!(arg)))
+                }
+            }
+
+            impl From<PgMultirange<$ty>> for Value {
+                fn from(arg: PgMultirange<$ty>) -> Self {
+                    Value::Ext($multirange, Box::new(rbs::value!(arg)))
+                }
+            }
+        )+
+    };
+}
+
+impl_range_into_value! {
+    i32 => "Int4Range", "Int4Multirange";
+    i64 => "Int8Range", "Int8Multirange";
+    Decimal => "NumRange", "NumMultirange";
+    Timestamp => "TsRange", "TsMultirange";
+    DateTime => "TstzRange", "TstzMultirange";
+    Date => "DateRange", "DateMultirange";
+}
+
+/// The `Value::Ext` name of a (multi)range type.
+fn ext_name(ty: &PgTypeInfo) -> &'static str {
+    match ty.0 {
+        PgType::Int4Range => "Int4Range",
+        PgType::Int8Range => "Int8Range",
+        PgType::NumRange => "NumRange",
+        PgType::TsRange => "TsRange",
+        PgType::TstzRange => "TstzRange",
+        PgType::DateRange => "DateRange",
+        PgType::Int4Multirange => "Int4Multirange",
+        PgType::Int8Multirange => "Int8Multirange",
+        PgType::NumMultirange => "NumMultirange",
+        PgType::TsMultirange => "TsMultirange",
+        PgType::TstzMultirange => "TstzMultirange",
+        PgType::DateMultirange => "DateMultirange",
+        _ => match ty.kind() {
+            PgTypeKind::Multirange(_) => "Multirange",
+            _ => "Range",
+        },
+    }
+}
+
+pub(crate) fn type_info_by_name(name: &str) -> Option<PgTypeInfo> {
+    Some(match name {
+        "Int4Range" => PgTypeInfo::INT4_RANGE,
+        "Int8Range" => PgTypeInfo::INT8_RANGE,
+        "NumRange" => PgTypeInfo::NUM_RANGE,
+        "TsRange" => PgTypeInfo::TS_RANGE,
+        "TstzRange" => PgTypeInfo::TSTZ_RANGE,
+        "DateRange" => PgTypeInfo::DATE_RANGE,
+        "Int4Multirange" => PgTypeInfo::INT4_MULTIRANGE,
+        "Int8Multirange" => PgTypeInfo::INT8_MULTIRANGE,
+        "NumMultirange" => PgTypeInfo::NUM_MULTIRANGE,
+        "TsMultirange" => PgTypeInfo::TS_MULTIRANGE,
+        "TstzMultirange" => PgTypeInfo::TSTZ_MULTIRANGE,
+        "DateMultirange" => PgTypeInfo::DATE_MULTIRANGE,
+        _ => return None,
+    })
+}
+
+/// Decodes a range or multirange into `Value::Ext(<type name>, ..)`, with the bounds decoded as
+/// their element type.
+pub(crate) fn decode_value(arg: PgValue) -> Result<Value, Error> {
+    let name = ext_name(&arg.type_info);
+    let value = match arg.type_info.kind() {
+        PgTypeKind::Multirange(_) => rbs::value(PgMultirange::<Value>::decode(arg)?)?,
+        _ => rbs::value(PgRange::<Value>::decode(arg)?)?,
+    };
+    Ok(Value::Ext(name, Box::new(value)))
+}
+
+/// Whether `name` is the `Value::Ext` name of a (multi)range.
+pub(crate) fn is_ext_name(name: &str) -> bool {
+    matches!(name, "Range" | "Multirange") || type_info_by_name(name).is_some()
+}
+
+/// Encodes a range `Value::Ext` by its name, converting the bounds to the element type of the
+/// range (so `{lower: {Inclusive: 1}}` binds as an `int4` inside an `int4range`).
+pub(crate) fn encode_by_name(
+    name: &str,
+    v: Value,
+    buf: &mut PgArgumentBuffer,
+) -> Result<IsNull, Error> {
+    let ty = type_info_by_name(name).ok_or_else(|| {
+        Error::from(format!(
+            "can not bind range type {}, only built-in ranges are supported as parameters",
+            name
+        ))
+    })?;
+    encode_as(v, &ty, buf)
+}
+
+/// Encodes a range `Value::Ext` as the (multi)range type `ty` described by the server, which
+/// is also how a user-defined range is bound.
+pub(crate) fn encode_as(
+    v: Value,
+    ty: &PgTypeInfo,
+    buf: &mut PgArgumentBuffer,
+) -> Result<IsNull, Error> {
+    match ty.kind() {
+        PgTypeKind::Multirange(range_type) => {
+            let element = range_element(range_type)?;
+            let ranges: PgMultirange<Value> = rbs::from_value(v)?;
+            buf.extend(&(ranges.len() as i32).to_be_bytes());
+            for range in ranges.0 {
+                encode_prefixed(buf, |buf| {
+                    range.encode_with(buf, |v, buf| encode_element(&element, v, buf))
+                })?;
+            }
+            Ok(IsNull::No)
+        }
+        _ => {
+            let element = range_element(ty)?;
+            let range: PgRange<Value> = rbs::from_value(v)?;
+            range.encode_with(buf, |v, buf| encode_element(&element, v, buf))
+        }
+    }
+}
+
+fn encode_element(
+    element: &PgTypeInfo,
+    v: Value,
+    buf: &mut PgArgumentBuffer,
+) -> Result<IsNull, Error> {
+    let invalid = |v: &Value| {
+        Error::from(format!(
+            "invalid range bound {} for element type {}",
+            v,
+            element.name()
+        ))
+    };
+    match (&element.0, v) {
+        (_, Value::Null) => Ok(IsNull::Yes),
+        (PgType::Int4, v) => {
+            let n = v.as_i64().ok_or_else(|| invalid(&v))?;
+            i32::try_from(n).map_err(|_| invalid(&v))?.encode(buf)
+        }
+        (PgType::Int8, v) => v.as_i64().ok_or_else(|| invalid(&v))?.encode(buf),
+        (PgType::Numeric, v) => {
+            let s = match &v {
+                Value::String(s) => s.clone(),
+                Value::Ext(_, inner) => inner.as_str().ok_or_else(|| invalid(&v))?.to_string(),
+                _ => v.to_string(),
+            };
+            Decimal::from_str(&s).map_err(|_| invalid(&v))?.encode(buf)
+        }
+        (PgType::Date, Value::String(s)) => {
+            Date(fastdate::Date::from_str(&s).map_err(|e| Error::from(e.to_string()))?).encode(buf)
+        }
+        (PgType::Timestamp | PgType::Timestamptz, Value::String(s)) => {
+            DateTime(fastdate::DateTime::from_str(&s).map_err(|e| Error::from(e.to_string()))?)
+                .encode(buf)
+        }
+        (PgType::Timestamp | PgType::Timestamptz, v @ (Value::I64(_) | Value::I32(_))) => {
+            Timestamp(v.as_i64().unwrap_or_default()).encode(buf)
+        }
+        (_, v) => value::encode_as(v, element, buf).map(|(_, is_null)| is_null),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::arguments::PgArgumentBuffer;
+    use crate::type_info::{PgCustomType, PgType, PgTypeInfo, PgTypeKind};
+    use crate::types::decode::Decode;
+    use crate::types::encode::Encode;
+    use crate::types::range::{encode_by_name, PgMultirange, PgRange, PgRangeBound};
+    use crate::types::value::encode_as;
+    use crate::types::Oid;
+    use crate::value::{PgValue, PgValueFormat};
+    use rbs::Value;
+    use std::sync::Arc;
+
+    fn binary(ty: PgTypeInfo, bytes: Vec<u8>) -> PgValue {
+        PgValue {
+            value: Some(bytes),
+            type_info: ty,
+            format: PgValueFormat::Binary,
+            timezone: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_text() {
+        let r: PgRange<String> = "[1,10)".parse().unwrap();
+        assert_eq!(
+            r,
+            PgRange::new(
+                PgRangeBound::Inclusive("1".to_string()),
+                PgRangeBound::Exclusive("10".to_string())
+            )
+        );
+        let r: PgRange<String> = "(,\"a,b\"]".parse().unwrap();
+        assert_eq!(r.lower, PgRangeBound::Unbounded);
+        assert_eq!(r.upper, PgRangeBound::Inclusive("a,b".to_string()));
+        let r: PgRange<String> = "[\"\",)".parse().unwrap();
+        assert_eq!(r.lower, PgRangeBound::Inclusive(String::new()));
+        assert_eq!(r.upper, PgRangeBound::Unbounded);
+        assert!("empty".parse::<PgRange<String>>().unwrap().is_empty());
+        assert!("1,2".parse::<PgRange<String>>().is_err());
+    }
+
+    #[test]
+    fn test_parse_multirange() {
+        let m: PgMultirange<String> = "{[1,3), [5,7)}".parse().unwrap();
+        assert_eq!(m.len(), 2);
+        assert_eq!(m[1].to_string(), "[5,7)");
+        let m: PgMultirange<String> = "{}".parse().unwrap();
+        assert!(m.is_empty());
+    }
+
+    #[test]
+    fn test_display() {
+        let r = PgRange::new(PgRangeBound::Exclusive(1), PgRangeBound::Unbounded);
+        assert_eq!(r.to_string(), "(1,)");
+        let r = PgRange::new(
+            PgRangeBound::Inclusive("a b".to_string()),
+            PgRangeBound::Inclusive("c".to_string()),
+        );
+        assert_eq!(r.to_string(), "[\"a b\",c]");
+        assert_eq!(PgRange::<i32>::empty().to_string(), "empty");
+    }
+
+    #[test]
+    fn test_encode_decode_int4() {
+        let range = PgRange::new(PgRangeBound::Inclusive(1), PgRangeBound::Exclusive(10));
+        let mut buf = PgArgumentBuffer::default();
+        range.clone().encode(&mut buf).unwrap();
+        assert_eq!(
+            &buf[..],
+            &[0x02, 0, 0, 0, 4, 0, 0, 0, 1, 0, 0, 0, 4, 0, 0, 0, 10][..]
+        );
+        let decoded = PgRange::<i32>::decode(binary(PgTypeInfo::INT4_RANGE, buf.to_vec())).unwrap();
+        assert_eq!(decoded, range);
+    }
+
+    #[test]
+    fn test_decode_unbounded_and_empty() {
+        // (,5]
+        let bytes = vec![0x08 | 0x04, 0, 0, 0, 8, 0, 0, 0, 0, 0, 0, 0, 5];
+        let r = PgRange::<i64>::decode(binary(PgTypeInfo::INT8_RANGE, bytes)).unwrap();
+        assert_eq!(
+            r,
+            PgRange::new(PgRangeBound::Unbounded, PgRangeBound::Inclusive(5))
+        );
+        let r = PgRange::<i64>::decode(binary(PgTypeInfo::INT8_RANGE, vec![0x01])).unwrap();
+        assert!(r.is_empty());
+    }
+
+    #[test]
+    fn test_multirange_roundtrip() {
+        let m = PgMultirange(vec![
+            PgRange::new(PgRangeBound::Inclusive(1), PgRangeBound::Exclusive(3)),
+            PgRange::new(PgRangeBound::Inclusive(5), PgRangeBound::Unbounded),
+        ]);
+        let mut buf = PgArgumentBuffer::default();
+        m.clone().encode(&mut buf).unwrap();
+        let decoded =
+            PgMultirange::<i32>::decode(binary(PgTypeInfo::INT4_MULTIRANGE, buf.to_vec())).unwrap();
+        assert_eq!(decoded, m);
+    }
+
+    #[test]
+    fn test_decode_truncated() {
+        let range = PgRange::new(PgRangeBound::Inclusive(1), PgRangeBound::Exclusive(10));
+        let mut buf = PgArgumentBuffer::default();
+        range.clone().encode(&mut buf).unwrap();
+        // cut inside each length prefix and each bound
+        for end in 1..buf.len() {
+            let value = binary(PgTypeInfo::INT4_RANGE, buf[..end].to_vec());
+            assert!(PgRange::<i32>::decode(value).is_err(), "{} bytes", end);
+        }
+        // a bound longer than the value
+        let bytes = vec![0x02, 0, 0, 0, 9, 0, 0, 0, 1, 0, 0, 0, 4, 0, 0, 0, 10];
+        assert!(PgRange::<i32>::decode(binary(PgTypeInfo::INT4_RANGE, bytes)).is_err());
+
+        let m = PgMultirange(vec![range]);
+        let mut buf = PgArgumentBuffer::default();
+        m.encode(&mut buf).unwrap();
+        for end in 0..buf.len() {
+            let value = binary(PgTypeInfo::INT4_MULTIRANGE, buf[..end].to_vec());
+            assert!(PgMultirange::<i32>::decode(value).is_err(), "{} bytes", end);
+        }
+        // more ranges than the value holds
+        let bytes = vec![0, 0, 0, 2, 0, 0, 0, 1, 0x01];
+        assert!(PgMultirange::<i32>::decode(binary(PgTypeInfo::INT4_MULTIRANGE, bytes)).is_err());
+        // a negative range length
+        let bytes = vec![0, 0, 0, 1, 0xff, 0xff, 0xff, 0xfe, 0x01];
+        assert!(PgMultirange::<i32>::decode(binary(PgTypeInfo::INT4_MULTIRANGE, bytes)).is_err());
+    }
+
+    #[test]
+    fn test_value_roundtrip() {
+        let range = PgRange::new(PgRangeBound::Inclusive(1), PgRangeBound::Exclusive(10));
+        let v = Value::from(range.clone());
+        let mut buf = PgArgumentBuffer::default();
+        match v {
+            Value::Ext(name, inner) => {
+                assert_eq!(name, "Int4Range");
+                encode_by_name(name, *inner, &mut buf).unwrap();
+            }
+            _ => panic!("not an ext"),
+        }
+        let decoded = Value::decode(binary(PgTypeInfo::INT4_RANGE, buf.to_vec())).unwrap();
+        assert_eq!(decoded, Value::from(range));
+    }
+
+    #[test]
+    fn test_custom_range_roundtrip() {
+        let floatrange = PgTypeInfo(PgType::Custom(Arc::new(PgCustomType {
+            oid: Oid(16500),
+            name: "floatrange".into(),
+            kind: PgTypeKind::Range(PgTypeInfo::FLOAT8),
+        })));
+        let floatmultirange = PgTypeInfo(PgType::Custom(Arc::new(PgCustomType {
+            oid: Oid(16501),
+            name: "floatmultirange".into(),
+            kind: PgTypeKind::Multirange(floatrange.clone()),
+        })));
+        let range = PgRange::new(PgRangeBound::Inclusive(1.5), PgRangeBound::Unbounded);
+        let mut bytes = PgArgumentBuffer::default();
+        range.clone().encode(&mut bytes).unwrap();
+        let mut multirange = PgArgumentBuffer::default();
+        PgMultirange(vec![range]).encode(&mut multirange).unwrap();
+
+        for (ty, bytes) in [(floatrange, bytes), (floatmultirange, multirange)] {
+            let v = Value::decode(binary(ty.clone(), bytes.to_vec())).unwrap();
+            assert!(matches!(v, Value::Ext("Range" | "Multirange", _)), "{}", v);
+            let mut buf = PgArgumentBuffer::default();
+            let (info, _) = encode_as(v.clone(), &ty, &mut buf).unwrap();
+            assert_eq!(info, ty);
+            assert_eq!(&buf[..], &bytes[..]);
+            // without the described type the range is not sent as NULL
+            let e = v.encode(&mut PgArgumentBuffer::default()).err().unwrap();
+            assert!(e.to_string().contains("only built-in ranges"), "{}", e);
+        }
+    }
+
+    #[test]
+    fn test_value_bound_coercion() {
+        // int8 bounds in an int4range are narrowed to int4
+        let range = PgRange::new(
+            PgRangeBound::Inclusive(Value::I64(1)),
+            PgRangeBound::Unbounded,
+        );
+        let mut buf = PgArgumentBuffer::default();
+        encode_by_name("Int4Range", rbs::value!(range), &mut buf).unwrap();
+        assert_eq!(&buf[..], &[0x02 | 0x10, 0, 0, 0, 4, 0, 0, 0, 1][..]);
+        let range = PgRange::new(
+            PgRangeBound::Inclusive(Value::I64(i64::MAX)),
+            PgRangeBound::Unbounded,
+        );
+        assert!(encode_by_name("Int4Range", rbs::value!(range), &mut buf).is_err());
+    }
+
+    #[test]
+    fn test_text_decode() {
+        let v = PgValue {
+            value: Some(b"[2,)".to_vec()),
+            type_info: PgTypeInfo::INT4_RANGE,
+            format: PgValueFormat::Text,
+            timezone: None,
+        };
+        let r = PgRange::<i32>::decode(v).unwrap();
+        assert_eq!(
+            r,
+            PgRange::new(PgRangeBound::Inclusive(2), PgRangeBound::Unbounded)
+        );
+    }
+}
@@ -0,0 +1,840 @@
+//! Full text search types `tsvector` and `tsquery`.
+//!
+//! https://www.postgresql.org/docs/current/datatype-textsearch.html
+use crate::arguments::PgArgumentBuffer;
+use crate::types::decode::Decode;
+use crate::types::encode::{Encode, IsNull};
+use crate::value::{PgValue, PgValueFormat};
+use bytes::Buf;
+use rbdc::Error;
+use rbs::Value;
+use std::fmt::{Display, Formatter, Write};
+use std::iter::Peekable;
+use std::str::{Chars, FromStr};
+
+/// the largest position of a lexeme in a tsvector
+const MAX_POSITION: u16 = (1 << 14) - 1;
+
+/// The weight of a lexeme position, `D` is the default and is not shown in the text format.
+#[derive(
+    serde::Serialize, serde::Deserialize, Debug, Clone, Copy, Default, Eq, PartialEq, Hash,
+)]
+pub enum TsWeight {
+    A,
+    B,
+    C,
+    #[default]
+    D,
+}
+
+impl TsWeight {
+    fn from_char(c: char) -> Option<Self> {
+        match c.to_ascii_uppercase() {
+            'A' => Some(TsWeight::A),
+            'B' => Some(TsWeight::B),
+            'C' => Some(TsWeight::C),
+            'D' => Some(TsWeight::D),
+            _ => None,
+        }
+    }
+
+    fn as_char(&self) -> char {
+        match self {
+            TsWeight::A => 'A',
+            TsWeight::B => 'B',
+            TsWeight::C => 'C',
+            TsWeight::D => 'D',
+        }
+    }
+
+    /// the two high bits of a binary tsvector position
+    fn to_bits(self) -> u16 {
+        match self {
+            TsWeight::A => 3,
+            TsWeight::B => 2,
+            TsWeight::C => 1,
+            TsWeight::D => 0,
+        }
+    }
+
+    fn from_bits(bits: u16) -> Self {
+        match bits & 3 {
+            3 => TsWeight::A,
+            2 => TsWeight::B,
+            1 => TsWeight::C,
+            _ => TsWeight::D,
+        }
+    }
+
+    /// the bit of the weight in the weight mask of a tsquery operand
+    fn mask(self) -> u8 {
+        match self {
+            TsWeight::A => 1 << 3,
+            TsWeight::B => 1 << 2,
+            TsWeight::C => 1 << 1,
+            TsWeight::D => 1,
+        }
+    }
+}
+
+/// A position (1 to 16383) of a lexeme in the document and its weight.
+#[derive(
+    serde::Serialize, serde::Deserialize, Debug, Clone, Copy, Default, Eq, PartialEq, Hash,
+)]
+pub struct TsPosition {
+    pub position: u16,
+    #[serde(default)]
+    pub weight: TsWeight,
+}
+
+/// A lexeme of a tsvector with its positions, which may be empty.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct TsLexeme {
+    pub lexeme: String,
+    #[serde(default)]
+    pub positions: Vec<TsPosition>,
+}
+
+impl TsLexeme {
+    pub fn new(lexeme: impl Into<String>) -> Self {
+        Self {
+            lexeme: lexeme.into(),
+            positions: vec![],
+        }
+    }
+}
+
+/// A `tsvector`, a sorted list of distinct lexemes.
+///
+/// Decoded columns are a `Value::Array` of lexemes, each a map of `lexeme` and `positions`.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default, Eq, PartialEq, Hash)]
+#[serde(rename = "TsVector")]
+pub struct PgTsVector(pub Vec<TsLexeme>);
+
+impl From<PgTsVector> for Value {
+    fn from(arg: PgTsVector) -> Self {
+        Value::Ext("TsVector", Box::new(rbs::value(arg.0).unwrap_or_default()))
+    }
+}
+
+/// text format `'a':1A,2 'b'`
+impl Display for PgTsVector {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, lexeme) in self.0.iter().enumerate() {
+            if i != 0 {
+                f.write_char(' ')?;
+            }
+            write_lexeme(f, &lexeme.lexeme)?;
+            for (i, p) in lexeme.positions.iter().enumerate() {
+                f.write_char(if i == 0 { ':' } else { ',' })?;
+                write!(f, "{}", p.position)?;
+                if p.weight != TsWeight::D {
+                    f.write_char(p.weight.as_char())?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_lexeme(f: &mut Formatter<'_>, lexeme: &str) -> std::fmt::Result {
+    f.write_char('\'')?;
+    for c in lexeme.chars() {
+        if c == '\'' || c == '\\' {
+            f.write_char(c)?;
+        }
+        f.write_char(c)?;
+    }
+    f.write_char('\'')
+}
+
+/// Reads a quoted or unquoted lexeme, an unquoted lexeme ends at whitespace or at any of `stop`.
+fn read_lexeme(chars: &mut Peekable<Chars>, stop: &[char]) -> Result<String, Error> {
+    let mut lexeme = String::new();
+    if chars.peek() == Some(&'\'') {
+        chars.next();
+        loop {
+            match chars.next() {
+                Some('\'') if chars.peek() == Some(&'\'') => {
+                    chars.next();
+                    lexeme.push('\'');
+                }
+                Some('\'') => return Ok(lexeme),
+                Some('\\') => lexeme.extend(chars.next()),
+                Some(c) => lexeme.push(c),
+                None => return Err(Error::from("unterminated quoted lexeme")),
+            }
+        }
+    }
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() || stop.contains(&c) {
+            break;
+        }
+        chars.next();
+        if c == '\\' {
+            lexeme.extend(chars.next());
+        } else {
+            lexeme.push(c);
+        }
+    }
+    if lexeme.is_empty() {
+        return Err(Error::from("empty lexeme"));
+    }
+    Ok(lexeme)
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+        chars.next();
+    }
+}
+
+impl FromStr for PgTsVector {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lexemes = vec![];
+        let mut chars = s.chars().peekable();
+        loop {
+            skip_whitespace(&mut chars);
+            if chars.peek().is_none() {
+                return Ok(PgTsVector(lexemes));
+            }
+            let mut lexeme = TsLexeme::new(read_lexeme(&mut chars, &[':'])?);
+            if chars.peek() == Some(&':') {
+                loop {
+                    chars.next();
+                    let mut digits = String::new();
+                    while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+                        digits.push(c);
+                    }
+                    let position = digits
+                        .parse()
+                        .map_err(|_| Error::from(format!("invalid tsvector position in {}", s)))?;
+                    let weight = match chars.peek().and_then(|c| TsWeight::from_char(*c)) {
+                        Some(w) => {
+                            chars.next();
+                            w
+                        }
+                        None => TsWeight::D,
+                    };
+                    lexeme.positions.push(TsPosition { position, weight });
+                    if chars.peek() != Some(&',') {
+                        break;
+                    }
+                }
+            }
+            lexemes.push(lexeme);
+        }
+    }
+}
+
+/// Reads a nul terminated string.
+fn read_cstring(buf: &mut &[u8]) -> Result<String, Error> {
+    let end = buf
+        .iter()
+        .position(|b| *b == 0)
+        .ok_or_else(|| Error::from("text search value is truncated"))?;
+    let s = String::from_utf8(buf[..end].to_vec()).map_err(|e| Error::from(e.to_string()))?;
+    buf.advance(end + 1);
+    Ok(s)
+}
+
+fn check_len(buf: &[u8], len: usize) -> Result<(), Error> {
+    if buf.len() < len {
+        return Err(Error::from("text search value is truncated"));
+    }
+    Ok(())
+}
+
+/// binary format: lexeme count, then each lexeme as a nul terminated string, the position
+/// count and the positions, with the weight in the two high bits
+impl Decode for PgTsVector {
+    fn decode(value: PgValue) -> Result<Self, Error> {
+        match value.format() {
+            PgValueFormat::Binary => {
+                let mut buf = value.as_bytes()?;
+                check_len(buf, 4)?;
+                let count = buf.get_i32();
+                // a lexeme takes at least its nul and the position count
+                let mut lexemes = Vec::with_capacity((count.max(0) as usize).min(buf.len() / 3));
+                for _ in 0..count {
+                    let mut lexeme = TsLexeme::new(read_cstring(&mut buf)?);
+                    check_len(buf, 2)?;
+                    let npos = buf.get_u16() as usize;
+                    check_len(buf, npos * 2)?;
+                    for _ in 0..npos {
+                        let v = buf.get_u16();
+                        lexeme.positions.push(TsPosition {
+                            position: v & MAX_POSITION,
+                            weight: TsWeight::from_bits(v >> 14),
+                        });
+                    }
+                    lexemes.push(lexeme);
+                }
+                Ok(PgTsVector(lexemes))
+            }
+            PgValueFormat::Text => PgTsVector::from_str(value.as_str()?),
+        }
+    }
+}
+
+impl Encode for PgTsVector {
+    fn encode(self, buf: &mut PgArgumentBuffer) -> Result<IsNull, Error> {
+        buf.extend(&(self.0.len() as i32).to_be_bytes());
+        for mut lexeme in self.0 {
+            if lexeme.lexeme.contains('\0') {
+                return Err(Error::from("a lexeme can not contain a nul character"));
+            }
+            buf.extend(lexeme.lexeme.as_bytes());
+            buf.push(0);
+            // the server requires positions in ascending order
+            lexeme.positions.sort_by_key(|p| p.position);
+            lexeme.positions.dedup_by_key(|p| p.position);
+            buf.extend(&(lexeme.positions.len() as u16).to_be_bytes());
+            for p in lexeme.positions {
+                if p.position == 0 || p.position > MAX_POSITION {
+                    return Err(Error::from(format!(
+                        "tsvector position {} is out of range",
+                        p.position
+                    )));
+                }
+                buf.extend(&(p.weight.to_bits() << 14 | p.position).to_be_bytes());
+            }
+        }
+        Ok(IsNull::No)
+    }
+}
+
+/// An operand of a tsquery: a lexeme, optionally restricted to positions with one of `weights`
+/// and, with `prefix`, matching any lexeme starting with it (`'super':*`).
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct TsQueryOperand {
+    pub lexeme: String,
+    #[serde(default)]
+    pub weights: Vec<TsWeight>,
+    #[serde(default)]
+    pub prefix: bool,
+}
+
+impl TsQueryOperand {
+    pub fn new(lexeme: impl Into<String>) -> Self {
+        Self {
+            lexeme: lexeme.into(),
+            weights: vec![],
+            prefix: false,
+        }
+    }
+}
+
+/// An item of a [`PgTsQuery`]. `Phrase(n)` is `<n>`, followed by (`<->` is `Phrase(1)`).
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
+pub enum TsQueryItem {
+    Operand(TsQueryOperand),
+    Not,
+    And,
+    Or,
+    Phrase(u16),
+}
+
+impl TsQueryItem {
+    fn precedence(&self) -> u8 {
+        match self {
+            TsQueryItem::Or => 1,
+            TsQueryItem::And => 2,
+            TsQueryItem::Phrase(_) => 3,
+            TsQueryItem::Not => 4,
+            TsQueryItem::Operand(_) => 5,
+        }
+    }
+}
+
+/// A `tsquery` as a list of items in prefix order: an operator comes before its operands, a
+/// binary operator before its left and then its right operand. `'a' & !'b'` is
+/// `[And, Operand(a), Not, Operand(b)]`.
+///
+/// Decoded columns are a `Value::Array` of items.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default, Eq, PartialEq, Hash)]
+#[serde(rename = "TsQuery")]
+pub struct PgTsQuery(pub Vec<TsQueryItem>);
+
+impl From<PgTsQuery> for Value {
+    fn from(arg: PgTsQuery) -> Self {
+        Value::Ext("TsQuery", Box::new(rbs::value(arg.0).unwrap_or_default()))
+    }
+}
+
+/// The end of the operator at `items[start]` with its operands.
+fn operand_end(items: &[TsQueryItem], start: usize) -> Result<usize, Error> {
+    let item = items
+        .get(start)
+        .ok_or_else(|| Error::from("tsquery operator is missing an operand"))?;
+    match item {
+        TsQueryItem::Operand(_) => Ok(start + 1),
+        TsQueryItem::Not => operand_end(items, start + 1),
+        _ => {
+            let left = operand_end(items, start + 1)?;
+            operand_end(items, left)
+        }
+    }
+}
+
+impl PgTsQuery {
+    fn check(&self) -> Result<(), Error> {
+        if !self.0.is_empty() && operand_end(&self.0, 0)? != self.0.len() {
+            return Err(Error::from("tsquery has items after the end of the query"));
+        }
+        Ok(())
+    }
+
+    /// Reorders items between prefix order and the order the server stores them in, where
+    /// the right operand of a binary operator comes before the left one. Swapping is its
+    /// own inverse.
+    fn swap_operands(items: &[TsQueryItem], out: &mut Vec<TsQueryItem>) -> Result<(), Error> {
+        let Some(item) = items.first() else {
+            return Err(Error::from("tsquery operator is missing an operand"));
+        };
+        out.push(item.clone());
+        match item {
+            TsQueryItem::Operand(_) => {}
+            TsQueryItem::Not => Self::swap_operands(&items[1..], out)?,
+            _ => {
+                let first = operand_end(items, 1)?;
+                let second = operand_end(items, first)?;
+                Self::swap_operands(&items[first..second], out)?;
+                Self::swap_operands(&items[1..first], out)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn fmt_item(&self, f: &mut Formatter<'_>, start: usize) -> Result<usize, std::fmt::Error> {
+        let item = &self.0[start];
+        match item {
+            TsQueryItem::Operand(operand) => {
+                write_lexeme(f, &operand.lexeme)?;
+                if operand.prefix || !operand.weights.is_empty() {
+                    f.write_char(':')?;
+                    if operand.prefix {
+                        f.write_char('*')?;
+                    }
+                    for w in &operand.weights {
+                        f.write_char(w.as_char())?;
+                    }
+                }
+                Ok(start + 1)
+            }
+            TsQueryItem::Not => {
+                f.write_char('!')?;
+                self.fmt_operand(f, start + 1, item, false)
+            }
+            _ => {
+                let left = self.fmt_operand(f, start + 1, item, false)?;
+                match item {
+                    TsQueryItem::And => f.write_str(" & ")?,
+                    TsQueryItem::Or => f.write_str(" | ")?,
+                    TsQueryItem::Phrase(1) => f.write_str(" <-> ")?,
+                    TsQueryItem::Phrase(n) => write!(f, " <{}> ", n)?,
+                    _ => {}
+                }
+                self.fmt_operand(f, left, item, true)
+            }
+        }
+    }
+
+    fn fmt_operand(
+        &self,
+        f: &mut Formatter<'_>,
+        start: usize,
+        parent: &TsQueryItem,
+        right: bool,
+    ) -> Result<usize, std::fmt::Error> {
+        let item = &self.0[start];
+        let parens = item.precedence() < parent.precedence()
+            || (right && item.precedence() == parent.precedence());
+        if parens {
+            f.write_str("( ")?;
+        }
+        let end = self.fmt_item(f, start)?;
+        if parens {
+            f.write_str(" )")?;
+        }
+        Ok(end)
+    }
+}
+
+/// text format `'a' & ( 'b':*A | !'c' ) <-> 'd'`
+impl Display for PgTsQuery {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.check().is_err() {
+            return Err(std::fmt::Error);
+        }
+        if !self.0.is_empty() {
+            self.fmt_item(f, 0)?;
+        }
+        Ok(())
+    }
+}
+
+/// A recursive descent parser of the tsquery text format. From lowest to highest precedence
+/// the operators are `|`, `&`, `<->`, `!`.
+struct QueryParser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl QueryParser<'_> {
+    fn peek(&mut self) -> Option<char> {
+        skip_whitespace(&mut self.chars);
+        self.chars.peek().copied()
+    }
+
+    fn binary(&mut self, level: u8, out: &mut Vec<TsQueryItem>) -> Result<(), Error> {
+        let mut left = vec![];
+        self.operand(level + 1, &mut left)?;
+        loop {
+            let op = match (level, self.peek()) {
+                (1, Some('|')) => TsQueryItem::Or,
+                (2, Some('&')) => TsQueryItem::And,
+                (3, Some('<')) => TsQueryItem::Phrase(0),
+                _ => break,
+            };
+            self.chars.next();
+            let op = match op {
+                TsQueryItem::Phrase(_) => TsQueryItem::Phrase(self.distance()?),
+                op => op,
+            };
+            let mut right = vec![];
+            self.operand(level + 1, &mut right)?;
+            left.insert(0, op);
+            left.extend(right);
+        }
+        out.extend(left);
+        Ok(())
+    }
+
+    fn operand(&mut self, level: u8, out: &mut Vec<TsQueryItem>) -> Result<(), Error> {
+        if level <= 3 {
+            return self.binary(level, out);
+        }
+        match self.peek() {
+            Some('!') => {
+                self.chars.next();
+                out.push(TsQueryItem::Not);
+                self.operand(level, out)
+            }
+            Some('(') => {
+                self.chars.next();
+                self.binary(1, out)?;
+                match self.peek() {
+                    Some(')') => {
+                        self.chars.next();
+                        Ok(())
+                    }
+                    _ => Err(Error::from("tsquery is missing a closing parenthesis")),
+                }
+            }
+            Some(_) => {
+                let lexeme = read_lexeme(&mut self.chars, &[':', '&', '|', '!', '(', ')', '<'])?;
+                let mut operand = TsQueryOperand::new(lexeme);
+                if self.chars.next_if_eq(&':').is_some() {
+                    while let Some(c) = self.chars.peek().copied() {
+                        if c == '*' {
+                            operand.prefix = true;
+                        } else if let Some(w) = TsWeight::from_char(c) {
+                            if !operand.weights.contains(&w) {
+                                operand.weights.push(w);
+                            }
+                        } else {
+                            break;
+                        }
+                        self.chars.next();
+                    }
+                }
+                out.push(TsQueryItem::Operand(operand));
+                Ok(())
+            }
+            None => Err(Error::from("tsquery is missing an operand")),
+        }
+    }
+
+    /// Reads the distance of `<->` or `<n>` after the `<`.
+    fn distance(&mut self) -> Result<u16, Error> {
+        let mut s = String::new();
+        for c in self.chars.by_ref() {
+            if c == '>' {
+                return match s.trim() {
+                    "-" => Ok(1),
+                    n => n
+                        .parse()
+                        .map_err(|_| Error::from(format!("invalid tsquery distance <{}>", s))),
+                };
+            }
+            s.push(c);
+        }
+        Err(Error::from("tsquery distance is missing a closing >"))
+    }
+}
+
+impl FromStr for PgTsQuery {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = QueryParser {
+            chars: s.chars().peekable(),
+        };
+        let mut items = vec![];
+        if parser.peek().is_none() {
+            return Ok(PgTsQuery(items));
+        }
+        parser.binary(1, &mut items)?;
+        if parser.peek().is_some() {
+            return Err(Error::from(format!("invalid tsquery: {}", s)));
+        }
+        Ok(PgTsQuery(items))
+    }
+}
+
+// https://github.com/postgres/postgres/blob/REL_16_STABLE/src/include/tsearch/ts_type.h
+const QI_VAL: u8 = 1;
+const QI_OPR: u8 = 2;
+const OP_NOT: u8 = 1;
+const OP_AND: u8 = 2;
+const OP_OR: u8 = 3;
+const OP_PHRASE: u8 = 4;
+
+/// binary format: item count, then each item as stored by the server, where the right operand
+/// of a binary operator comes before the left one. An operand is `QI_VAL`, the weight mask,
+/// the prefix flag and a nul terminated lexeme, an operator is `QI_OPR`, the operator and for
+/// a phrase the distance.
+impl Decode for PgTsQuery {
+    fn decode(value: PgValue) -> Result<Self, Error> {
+        match value.format() {
+            PgValueFormat::Binary => {
+                let mut buf = value.as_bytes()?;
+                check_len(buf, 4)?;
+                let count = buf.get_i32();
+                // an operator takes at least its type and the operator
+                let mut items = Vec::with_capacity((count.max(0) as usize).min(buf.len() / 2));
+                for _ in 0..count {
+                    check_len(buf, 2)?;
+                    let item = match buf.get_u8() {
+                        QI_VAL => {
+                            check_len(buf, 2)?;
+                            let mask = buf.get_u8();
+                            let prefix = buf.get_u8() != 0;
+                            let lexeme = read_cstring(&mut buf)?;
+                            let weights = [TsWeight::A, TsWeight::B, TsWeight::C, TsWeight::D]
+                                .into_iter()
+                                .filter(|w| mask & w.mask() != 0)
+                                .collect();
+                            TsQueryItem::Operand(TsQueryOperand {
+                                lexeme,
+                                weights,
+                                prefix,
+                            })
+                        }
+                        QI_OPR => match buf.get_u8() {
+                            OP_NOT => TsQueryItem::Not,
+                            OP_AND => TsQueryItem::And,
+                            OP_OR => TsQueryItem::Or,
+                            OP_PHRASE => {
+                                check_len(buf, 2)?;
+                                TsQueryItem::Phrase(buf.get_i16() as u16)
+                            }
+                            op => {
+                                return Err(Error::from(format!("unknown tsquery operator {}", op)))
+                            }
+                        },
+                        ty => return Err(Error::from(format!("unknown tsquery item type {}", ty))),
+                    };
+                    items.push(item);
+                }
+                let mut query = vec![];
+                if !items.is_empty() {
+                    PgTsQuery::swap_operands(&items, &mut query)?;
+                }
+                Ok(PgTsQuery(query))
+            }
+            PgValueFormat::Text => PgTsQuery::from_str(value.as_str()?),
+        }
+    }
+}
+
+impl Encode for PgTsQuery {
+    fn encode(self, buf: &mut PgArgumentBuffer) -> Result<IsNull, Error> {
+        self.check()?;
+        let mut items = vec![];
+        if !self.0.is_empty() {
+            PgTsQuery::swap_operands(&self.0, &mut items)?;
+        }
+        buf.extend(&(items.len() as i32).to_be_bytes());
+        for item in items {
+            match item {
+                TsQueryItem::Operand(operand) => {
+                    if operand.lexeme.contains('\0') {
+                        return Err(Error::from("a lexeme can not contain a nul character"));
+                    }
+                    let mask = operand.weights.iter().fold(0, |mask, w| mask | w.mask());
+                    buf.extend(&[QI_VAL, mask, operand.prefix as u8]);
+                    buf.extend(operand.lexeme.as_bytes());
+                    buf.push(0);
+                }
+                TsQueryItem::Not => buf.extend(&[QI_OPR, OP_NOT]),
+                TsQueryItem::And => buf.extend(&[QI_OPR, OP_AND]),
+                TsQueryItem::Or => buf.extend(&[QI_OPR, OP_OR]),
+                TsQueryItem::Phrase(distance) => {
+                    buf.extend(&[QI_OPR, OP_PHRASE]);
+                    buf.extend(&(distance as i16).to_be_bytes());
+                }
+            }
+        }
+        Ok(IsNull::No)
+    }
+}
+
+/// Decodes a tsvector column into a `Value::Array` of lexemes.
+pub(crate) fn decode_tsvector(arg: PgValue) -> Result<Value, Error> {
+    rbs::value(PgTsVector::decode(arg)?.0).map_err(|e| Error::from(e.to_string()))
+}
+
+/// Decodes a tsquery column into a `Value::Array` of items.
+pub(crate) fn decode_tsquery(arg: PgValue) -> Result<Value, Error> {
+    rbs::value(PgTsQuery::decode(arg)?.0).map_err(|e| Error::from(e.to_string()))
+}
+
+/// Encodes the text format or the lexeme list of a tsvector.
+pub(crate) fn encode_tsvector(v: Value, buf: &mut PgArgumentBuffer) -> Result<IsNull, Error> {
+    match v {
+        Value::String(s) => PgTsVector::from_str(&s)?.encode(buf),
+        Value::Ext(_, v) => encode_tsvector(*v, buf),
+        v => PgTsVector(rbs::from_value(v)?).encode(buf),
+    }
+}
+
+/// Encodes the text format or the item list of a tsquery.
+pub(crate) fn encode_tsquery(v: Value, buf: &mut PgArgumentBuffer) -> Result<IsNull, Error> {
+    match v {
+        Value::String(s) => PgTsQuery::from_str(&s)?.encode(buf),
+        Value::Ext(_, v) => encode_tsquery(*v, buf),
+        v => PgTsQuery(rbs::from_value(v)?).encode(buf),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::arguments::PgArgumentBuffer;
+    use crate::type_info::PgTypeInfo;
+    use crate::types::decode::Decode;
+    use crate::types::encode::Encode;
+    use crate::types::text_search::{
+        PgTsQuery, PgTsVector, TsLexeme, TsPosition, TsQueryItem, TsQueryOperand, TsWeight,
+    };
+    use crate::value::{PgValue, PgValueFormat};
+    use rbs::Value;
+    use std::str::FromStr;
+
+    fn binary(bytes: Vec<u8>) -> PgValue {
+        PgValue {
+            value: Some(bytes),
+            type_info: PgTypeInfo::with_name("tsquery"),
+            format: PgValueFormat::Binary,
+            timezone: None,
+        }
+    }
+
+    #[test]
+    fn test_tsvector_text() {
+        let v = PgTsVector::from_str("'a':1A,2 fat 'it''s':3").unwrap();
+        assert_eq!(
+            v.0[0],
+            TsLexeme {
+                lexeme: "a".to_string(),
+                positions: vec![
+                    TsPosition {
+                        position: 1,
+                        weight: TsWeight::A
+                    },
+                    TsPosition {
+                        position: 2,
+                        weight: TsWeight::D
+                    },
+                ],
+            }
+        );
+        assert_eq!(v.0[1], TsLexeme::new("fat"));
+        assert_eq!(v.to_string(), "'a':1A,2 'fat' 'it''s':3");
+    }
+
+    #[test]
+    fn test_tsvector_binary() {
+        let v = PgTsVector::from_str("'cat':3B 'fat':2,1").unwrap();
+        let mut buf = PgArgumentBuffer::default();
+        v.encode(&mut buf).unwrap();
+        let mut expect = vec![0, 0, 0, 2];
+        expect.extend(b"cat\0");
+        expect.extend([0, 1, 0x80, 3]);
+        expect.extend(b"fat\0");
+        expect.extend([0, 2, 0, 1, 0, 2]);
+        assert_eq!(&buf[..], &expect[..]);
+        let decoded = PgTsVector::decode(binary(expect)).unwrap();
+        assert_eq!(decoded.to_string(), "'cat':3B 'fat':1,2");
+    }
+
+    #[test]
+    fn test_tsquery_text() {
+        let q = PgTsQuery::from_str("a & (b:*A | !c) <-> d").unwrap();
+        let mut b = TsQueryOperand::new("b");
+        b.prefix = true;
+        b.weights = vec![TsWeight::A];
+        assert_eq!(
+            q.0,
+            vec![
+                TsQueryItem::And,
+                TsQueryItem::Operand(TsQueryOperand::new("a")),
+                TsQueryItem::Phrase(1),
+                TsQueryItem::Or,
+                TsQueryItem::Operand(b),
+                TsQueryItem::Not,
+                TsQueryItem::Operand(TsQueryOperand::new("c")),
+                TsQueryItem::Operand(TsQueryOperand::new("d")),
+            ]
+        );
+        assert_eq!(q.to_string(), "'a' & ( 'b':*A | !'c' ) <-> 'd'");
+        assert_eq!(
+            PgTsQuery::from_str("a <2> (b & c)").unwrap().to_string(),
+            "'a' <2> ( 'b' & 'c' )"
+        );
+        assert!(PgTsQuery::from_str("a & (b").is_err());
+        assert!(PgTsQuery::from_str("a &").is_err());
+    }
+
+    #[test]
+    fn test_tsquery_binary() {
+        let q = PgTsQuery::from_str("'a' & !'b'").unwrap();
+        let mut buf = PgArgumentBuffer::default();
+        q.clone().encode(&mut buf).unwrap();
+        // stored with the right operand first
+        let expect = vec![0, 0, 0, 4, 2, 2, 2, 1, 1, 0, 0, b'b', 0, 1, 0, 0, b'a', 0];
+        assert_eq!(&buf[..], &expect[..]);
+        assert_eq!(PgTsQuery::decode(binary(expect)).unwrap(), q);
+        assert!(PgTsQuery(vec![TsQueryItem::And]).encode(&mut buf).is_err());
+    }
+
+    #[test]
+    fn test_decode_bogus_count() {
+        let mut bytes = i32::MAX.to_be_bytes().to_vec();
+        bytes.extend(b"a\0");
+        bytes.extend([0, 0]);
+        assert!(PgTsVector::decode(binary(bytes)).is_err());
+        let bytes = vec![0x7f, 0xff, 0xff, 0xff, 2, 2];
+        assert!(PgTsQuery::decode(binary(bytes)).is_err());
+    }
+
+    #[test]
+    fn test_value() {
+        let v = Value::from(PgTsQuery::from_str("a | b").unwrap());
+        let mut buf = PgArgumentBuffer::default();
+        crate::types::text_search::encode_tsquery(v, &mut buf).unwrap();
+        assert_eq!(&buf[..6], &[0, 0, 0, 3, 2, 3][..]);
+    }
+}
@@ -0,0 +1,478 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// The session `TimeZone` reported by the server, used to give decoded `timestamptz`
+/// values the offset the server would display them with.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum PgTimeZone {
+    /// a fixed offset in seconds east of UTC
+    Fixed(i32),
+    /// a zone of the system time zone database (`$TZDIR` or `/usr/share/zoneinfo`)
+    Zone(Arc<ZoneRules>),
+}
+
+impl PgTimeZone {
+    /// Parses the value of the `TimeZone` parameter. Returns `None` for a zone name that is
+    /// not a fixed offset and not found in the time zone database.
+    pub(crate) fn parse(name: &str) -> Option<Self> {
+        let name = name.trim();
+        match &*name.to_ascii_uppercase() {
+            "UTC" | "UCT" | "GMT" | "Z" | "ZULU" | "UNIVERSAL" | "GREENWICH" | "ETC/UTC"
+            | "ETC/UCT" | "ETC/GMT" | "ETC/UNIVERSAL" | "ETC/ZULU" | "ETC/GREENWICH" => {
+                return Some(PgTimeZone::Fixed(0))
+            }
+            _ => {}
+        }
+        // a bare offset, `+08` or `-05:30`, is read POSIX style as the server does: the hours
+        // west of UTC, so `+08` is 8 hours behind UTC
+        if name.starts_with(['+', '-']) {
+            let mut s = name;
+            return parse_offset(&mut s)
+                .filter(|_| s.is_empty())
+                .map(|offset| PgTimeZone::Fixed(-offset));
+        }
+        // a POSIX zone without daylight saving time, such as the `<+08>-08` postgres reports
+        // for `SET TIME ZONE '+08'`
+        if let Some(rule) = PosixRule::parse(name).filter(|r| r.dst.is_none()) {
+            return Some(PgTimeZone::Fixed(rule.std_offset));
+        }
+        ZoneRules::load(name).map(PgTimeZone::Zone)
+    }
+
+    /// The offset in seconds east of UTC at the unix time `t`.
+    pub(crate) fn offset_at(&self, t: i64) -> i32 {
+        match self {
+            PgTimeZone::Fixed(offset) => *offset,
+            PgTimeZone::Zone(rules) => rules.offset_at(t),
+        }
+    }
+}
+
+/// The transitions of a TZif file, see RFC 8536.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ZoneRules {
+    transitions: Vec<i64>,
+    // for each transition, the offset in force from it on
+    offsets: Vec<i32>,
+    // the offset before the first transition
+    initial: i32,
+    // the rule for instants after the last transition
+    footer: Option<PosixRule>,
+}
+
+impl ZoneRules {
+    /// The rules of the zone file `name`, read once per path: the zone is resolved while a
+    /// message is received, at startup and on every `SET TIME ZONE`.
+    fn load(name: &str) -> Option<Arc<Self>> {
+        static ZONES: OnceLock<Mutex<HashMap<PathBuf, Option<Arc<ZoneRules>>>>> = OnceLock::new();
+
+        if name.is_empty() || name.starts_with('/') || name.split('/').any(|p| p == "..") {
+            return None;
+        }
+        let dir = std::env::var_os("TZDIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("/usr/share/zoneinfo"));
+        let path = dir.join(name);
+        let mut zones = ZONES
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        zones
+            .entry(path)
+            .or_insert_with_key(|path| {
+                let data = std::fs::read(path).ok()?;
+                Self::parse(&data).map(Arc::new)
+            })
+            .clone()
+    }
+
+    pub(crate) fn parse(data: &[u8]) -> Option<Self> {
+        let (header, body) = read_header(data)?;
+        // version 2+ files repeat the data with 64 bit times after the version 1 block
+        let (header, body, time_size) = if header.version >= b'2' {
+            let (header, body) = read_header(body.get(header.v1_len(4)..)?)?;
+            (header, body, 8)
+        } else {
+            (header, body, 4)
+        };
+
+        let mut pos = 0;
+        let mut transitions = Vec::with_capacity(header.timecnt);
+        for _ in 0..header.timecnt {
+            let bytes = body.get(pos..pos + time_size)?;
+            transitions.push(match time_size {
+                8 => i64::from_be_bytes(bytes.try_into().ok()?),
+                _ => i32::from_be_bytes(bytes.try_into().ok()?) as i64,
+            });
+            pos += time_size;
+        }
+        let indices = body.get(pos..pos + header.timecnt)?;
+        pos += header.timecnt;
+        let mut types = Vec::with_capacity(header.typecnt);
+        for _ in 0..header.typecnt {
+            let ttinfo = body.get(pos..pos + 6)?;
+            types.push(i32::from_be_bytes(ttinfo[..4].try_into().ok()?));
+            pos += 6;
+        }
+        let offsets = indices
+            .iter()
+            .map(|i| types.get(*i as usize).copied())
+            .collect::<Option<Vec<i32>>>()?;
+
+        let footer = if time_size == 8 {
+            let rest = body.get(header.v1_len(8)..)?;
+            std::str::from_utf8(rest)
+                .ok()
+                .and_then(|s| s.strip_prefix('\n'))
+                .and_then(|s| s.split('\n').next())
+                .and_then(PosixRule::parse)
+        } else {
+            None
+        };
+
+        Some(ZoneRules {
+            transitions,
+            offsets,
+            initial: *types.first()?,
+            footer,
+        })
+    }
+
+    fn offset_at(&self, t: i64) -> i32 {
+        match self.transitions.partition_point(|x| *x <= t) {
+            0 => self.initial,
+            i if i == self.transitions.len() => match &self.footer {
+                Some(footer) => footer.offset_at(t),
+                None => self.offsets[i - 1],
+            },
+            i => self.offsets[i - 1],
+        }
+    }
+}
+
+struct TzifHeader {
+    version: u8,
+    isutcnt: usize,
+    isstdcnt: usize,
+    leapcnt: usize,
+    timecnt: usize,
+    typecnt: usize,
+    charcnt: usize,
+}
+
+impl TzifHeader {
+    /// the length of the data block with times of `time_size` bytes
+    fn v1_len(&self, time_size: usize) -> usize {
+        self.timecnt * time_size
+            + self.timecnt
+            + self.typecnt * 6
+            + self.charcnt
+            + self.leapcnt * (time_size + 4)
+            + self.isstdcnt
+            + self.isutcnt
+    }
+}
+
+fn read_header(data: &[u8]) -> Option<(TzifHeader, &[u8])> {
+    if data.len() < 44 || &data[..4] != b"TZif" {
+        return None;
+    }
+    let count = |i: usize| {
+        u32::from_be_bytes([
+            data[20 + i * 4],
+            data[21 + i * 4],
+            data[22 + i * 4],
+            data[23 + i * 4],
+        ]) as usize
+    };
+    Some((
+        TzifHeader {
+            version: data[4],
+            isutcnt: count(0),
+            isstdcnt: count(1),
+            leapcnt: count(2),
+            timecnt: count(3),
+            typecnt: count(4),
+            charcnt: count(5),
+        },
+        &data[44..],
+    ))
+}
+
+/// A POSIX `TZ` rule such as `CET-1CEST,M3.5.0,M10.5.0/3`, offsets are seconds east of UTC.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PosixRule {
+    std_offset: i32,
+    dst: Option<(i32, DstDate, DstDate)>,
+}
+
+/// `Mm.w.d/time`: day `d` (0 = Sunday) of week `w` (5 = last) of month `m`, at local time
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct DstDate {
+    month: u32,
+    week: u32,
+    weekday: u32,
+    time: i32,
+}
+
+impl PosixRule {
+    pub(crate) fn parse(s: &str) -> Option<Self> {
+        let mut s = s;
+        skip_name(&mut s)?;
+        // POSIX offsets count west of UTC
+        let std_offset = -parse_offset(&mut s)?;
+        if s.is_empty() {
+            return Some(PosixRule {
+                std_offset,
+                dst: None,
+            });
+        }
+        skip_name(&mut s)?;
+        let dst_offset = if s.starts_with(',') {
+            std_offset + 3600
+        } else {
+            -parse_offset(&mut s)?
+        };
+        let s = s.strip_prefix(',')?;
+        let (start, end) = s.split_once(',')?;
+        Some(PosixRule {
+            std_offset,
+            dst: Some((dst_offset, DstDate::parse(start)?, DstDate::parse(end)?)),
+        })
+    }
+
+    fn offset_at(&self, t: i64) -> i32 {
+        let Some((dst_offset, start, end)) = self.dst else {
+            return self.std_offset;
+        };
+        let year = civil_from_days((t + self.std_offset as i64).div_euclid(86400)).0;
+        // the start is given in standard time, the end in daylight saving time
+        let start = start.local_time(year) - self.std_offset as i64;
+        let end = end.local_time(year) - dst_offset as i64;
+        let in_dst = if start < end {
+            start <= t && t < end
+        } else {
+            // southern hemisphere, daylight saving time spans the new year
+            !(end <= t && t < start)
+        };
+        if in_dst {
+            dst_offset
+        } else {
+            self.std_offset
+        }
+    }
+}
+
+impl DstDate {
+    fn parse(s: &str) -> Option<Self> {
+        let (date, time) = match s.split_once('/') {
+            Some((date, time)) => (date, Some(time)),
+            None => (s, None),
+        };
+        let mut parts = date.strip_prefix('M')?.split('.');
+        let month = parts.next()?.parse().ok()?;
+        let week = parts.next()?.parse().ok()?;
+        let weekday = parts.next()?.parse().ok()?;
+        if !(1..=12).contains(&month) || !(1..=5).contains(&week) || weekday > 6 {
+            return None;
+        }
+        let time = match time {
+            Some(time) => {
+                let mut time = time;
+                let t = parse_offset(&mut time)?;
+                if !time.is_empty() {
+                    return None;
+                }
+                t
+            }
+            None => 7200,
+        };
+        Some(DstDate {
+            month,
+            week,
+            weekday,
+            time,
+        })
+    }
+
+    /// seconds since the unix epoch of this date in `year`, in local time
+    fn local_time(&self, year: i64) -> i64 {
+        let first = days_from_civil(year, self.month, 1);
+        // 1970-01-01 was a Thursday
+        let first_weekday = (first + 4).rem_euclid(7) as u32;
+        let mut day = 1 + (self.weekday + 7 - first_weekday) % 7 + (self.week - 1) * 7;
+        let (next_year, next_month) = if self.month == 12 {
+            (year + 1, 1)
+        } else {
+            (year, self.month + 1)
+        };
+        let days_in_month = (days_from_civil(next_year, next_month, 1) - first) as u32;
+        while day > days_in_month {
+            day -= 7;
+        }
+        (first + day as i64 - 1) * 86400 + self.time as i64
+    }
+}
+
+fn skip_name(s: &mut &str) -> Option<()> {
+    if let Some(rest) = s.strip_prefix('<') {
+        let end = rest.find('>')?;
+        *s = &rest[end + 1..];
+        return Some(());
+    }
+    let end = s
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(s.len());
+    if end < 3 {
+        return None;
+    }
+    *s = &s[end..];
+    Some(())
+}
+
+/// `[+-]hh[:mm[:ss]]` in seconds
+fn parse_offset(s: &mut &str) -> Option<i32> {
+    let (sign, rest) = match s.as_bytes().first() {
+        Some(b'-') => (-1, &s[1..]),
+        Some(b'+') => (1, &s[1..]),
+        _ => (1, *s),
+    };
+    let end = rest
+        .find(|c: char| !c.is_ascii_digit() && c != ':')
+        .unwrap_or(rest.len());
+    let mut seconds = 0;
+    let mut parts = 0;
+    for (i, part) in rest[..end].split(':').enumerate() {
+        if i > 2 || part.is_empty() || part.len() > 3 {
+            return None;
+        }
+        seconds += part.parse::<i32>().ok()? * [3600, 60, 1][i];
+        parts += 1;
+    }
+    if parts == 0 {
+        return None;
+    }
+    *s = &rest[end..];
+    Some(sign * seconds)
+}
+
+// http://howardhinnant.github.io/date_algorithms.html
+fn days_from_civil(y: i64, m: u32, d: u32) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let m = m as i64;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + d as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn civil_from_days(z: i64) -> (i64, u32, u32) {
+    let z = z + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    (y, m, d)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::types::timezone::{days_from_civil, PgTimeZone, PosixRule, ZoneRules};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_fixed() {
+        assert_eq!(PgTimeZone::parse("UTC"), Some(PgTimeZone::Fixed(0)));
+        assert_eq!(PgTimeZone::parse("Etc/UTC"), Some(PgTimeZone::Fixed(0)));
+        assert_eq!(
+            PgTimeZone::parse("<+08>-08"),
+            Some(PgTimeZone::Fixed(8 * 3600))
+        );
+        assert_eq!(
+            PgTimeZone::parse("UTC+5"),
+            Some(PgTimeZone::Fixed(-5 * 3600))
+        );
+        assert_eq!(PgTimeZone::parse("-05:30"), Some(PgTimeZone::Fixed(19800)));
+        assert_eq!(PgTimeZone::parse("+08"), Some(PgTimeZone::Fixed(-28800)));
+        assert_eq!(PgTimeZone::parse("No/Such_Zone"), None);
+        assert_eq!(PgTimeZone::parse("../../etc/passwd"), None);
+    }
+
+    #[test]
+    fn test_posix_rule() {
+        let rule = PosixRule::parse("CET-1CEST,M3.5.0,M10.5.0/3").unwrap();
+        let at = |y, m, d, secs| days_from_civil(y, m, d) * 86400 + secs;
+        assert_eq!(rule.offset_at(at(2024, 1, 15, 0)), 3600);
+        assert_eq!(rule.offset_at(at(2024, 7, 15, 0)), 7200);
+        // 2024-03-31 01:00 UTC is 02:00 CET, the switch
+        assert_eq!(rule.offset_at(at(2024, 3, 31, 3599)), 3600);
+        assert_eq!(rule.offset_at(at(2024, 3, 31, 3600)), 7200);
+        // 2024-10-27 01:00 UTC is 03:00 CEST, the switch back
+        assert_eq!(rule.offset_at(at(2024, 10, 27, 3599)), 7200);
+        assert_eq!(rule.offset_at(at(2024, 10, 27, 3600)), 3600);
+
+        let south = PosixRule::parse("AEST-10AEDT,M10.1.0,M4.1.0/3").unwrap();
+        assert_eq!(south.offset_at(at(2024, 1, 15, 0)), 11 * 3600);
+        assert_eq!(south.offset_at(at(2024, 7, 15, 0)), 10 * 3600);
+    }
+
+    #[test]
+    fn test_tzif() {
+        // a version 2 file with one transition from +01 to +02 at t = 1000 and a footer rule
+        fn header(version: u8, timecnt: u32, typecnt: u32, charcnt: u32) -> Vec<u8> {
+            let mut buf = b"TZif".to_vec();
+            buf.push(version);
+            buf.extend([0; 15]);
+            for count in [0, 0, 0, timecnt, typecnt, charcnt] {
+                buf.extend(count.to_be_bytes());
+            }
+            buf
+        }
+        let mut data = header(b'2', 1, 2, 4);
+        data.extend(1000_i32.to_be_bytes());
+        data.push(1);
+        data.extend(3600_i32.to_be_bytes());
+        data.extend([0, 0]);
+        data.extend(7200_i32.to_be_bytes());
+        data.extend([1, 0]);
+        data.extend(b"AB\0\0");
+        data.extend(header(b'2', 1, 2, 4));
+        data.extend(1000_i64.to_be_bytes());
+        data.push(1);
+        data.extend(3600_i32.to_be_bytes());
+        data.extend([0, 0]);
+        data.extend(7200_i32.to_be_bytes());
+        data.extend([1, 0]);
+        data.extend(b"AB\0\0");
+        data.extend(b"\n<+02>-2\n");
+
+        let rules = ZoneRules::parse(&data).unwrap();
+        assert_eq!(rules.offset_at(0), 3600);
+        assert_eq!(rules.offset_at(999), 3600);
+        assert_eq!(rules.offset_at(1000), 7200);
+        assert_eq!(rules.offset_at(1_000_000_000), 7200);
+        assert_eq!(rules.transitions, vec![1000]);
+        assert!(rules.footer.is_some());
+        assert!(ZoneRules::parse(b"TZif").is_none());
+    }
+
+    #[test]
+    fn test_load_cached() {
+        // read once, whether or not the system has a time zone database
+        match (
+            ZoneRules::load("Europe/Paris"),
+            ZoneRules::load("Europe/Paris"),
+        ) {
+            (Some(a), Some(b)) => assert!(Arc::ptr_eq(&a, &b)),
+            (None, None) => {}
+            _ => panic!("the zone was read twice"),
+        }
+        assert!(ZoneRules::load("No/Such_Zone").is_none());
+    }
+}
@@ -2,10 +2,20 @@ use crate::arguments::PgArgumentBuffer;
 use crate::type_info::PgType;
 use crate::type_info::PgTypeInfo;
 use crate::type_info::PgTypeKind;
+use crate::types::array;
+use crate::types::coerce;
+use crate::types::composite;
 use crate::types::decode::Decode;
 use crate::types::encode::{Encode, IsNull};
+use crate::types::geometry::{self, Circle, Line, LineSegment, PgBox, PgPath, Point, Polygon};
+use crate::types::hstore;
+use crate::types::interval::PgInterval;
 use crate::types::json::{decode_json, encode_json};
+use crate::types::ltree;
 use crate::types::money::Money;
+use crate::types::network;
+use crate::types::range;
+use crate::types::text_search;
 use crate::types::timestamptz::Timestamptz;
 use crate::types::timetz::Timetz;
 use crate::types::Oid;
@@ -15,6 +25,7 @@ use rbdc::date::Date;
 use rbdc::datetime::DateTime;
 use rbdc::decimal::Decimal;
 use rbdc::json::Json;
+use rbdc::network::{Cidr, Inet, MacAddress, MacAddress8};
 use rbdc::timestamp::Timestamp;
 use rbdc::types::time::Time;
 use rbdc::uuid::Uuid;
@@ -35,7 +46,10 @@ impl TypeInfo for Value {
             Value::F64(_) => PgTypeInfo::FLOAT8,
             Value::String(_) => PgTypeInfo::VARCHAR,
             Value::Binary(_) => PgTypeInfo::BYTEA,
-            Value::Array(_) => PgTypeInfo::JSON,
+            // the array type of its elements, json for an array of maps or of mixed types
+            Value::Array(v) => array::infer_element_type(v)
+                .and_then(|element| element.0.to_array_type())
+                .unwrap_or(PgTypeInfo::JSON),
             Value::Map(_) => PgTypeInfo::JSON,
             Value::Ext(type_name, _) => {
                 match *type_name {
@@ -89,9 +103,22 @@ impl TypeInfo for Value {
                     "TstzRange" => PgTypeInfo::TSTZ_RANGE,
                     "DateRange" => PgTypeInfo::DATE_RANGE,
                     "Int8Range" => PgTypeInfo::INT8_RANGE,
+                    "Int4Multirange" => PgTypeInfo::INT4_MULTIRANGE,
+                    "NumMultirange" => PgTypeInfo::NUM_MULTIRANGE,
+                    "TsMultirange" => PgTypeInfo::TS_MULTIRANGE,
+                    "TstzMultirange" => PgTypeInfo::TSTZ_MULTIRANGE,
+                    "DateMultirange" => PgTypeInfo::DATE_MULTIRANGE,
+                    "Int8Multirange" => PgTypeInfo::INT8_MULTIRANGE,
                     "Jsonpath" => PgTypeInfo::JSONPATH,
                     "Money" => PgTypeInfo::MONEY,
                     "Void" => PgTypeInfo::VOID,
+                    // extension types, their oid is looked up by name
+                    "Hstore" => PgTypeInfo::with_name("hstore"),
+                    "Ltree" => PgTypeInfo::with_name("ltree"),
+                    "Lquery" => PgTypeInfo::with_name("lquery"),
+                    "Citext" => PgTypeInfo::with_name("citext"),
+                    "TsVector" => PgTypeInfo::with_name("tsvector"),
+                    "TsQuery" => PgTypeInfo::with_name("tsquery"),
                     "Custom" => PgTypeInfo::UNKNOWN,
                     "DeclareWithName" => PgTypeInfo::UNKNOWN,
                     "DeclareWithOid" => PgTypeInfo::UNKNOWN,
@@ -121,109 +148,21 @@ impl Decode for Value {
             PgType::Text => Value::String(Decode::decode(arg)?),
             PgType::Oid => Value::Ext("Oid", Box::new(Value::U32(Decode::decode(arg)?))),
             PgType::Json => decode_json(arg)?,
-            PgType::Point => Value::Ext(
-                "Point",
-                Box::new(Value::Binary({
-                    match arg.format() {
-                        PgValueFormat::Binary => arg.as_bytes()?.to_owned(),
-                        PgValueFormat::Text => arg.as_str()?.as_bytes().to_vec(),
-                    }
-                })),
-            ),
-            PgType::Lseg => Value::Ext(
-                "Lseg",
-                Box::new(Value::Binary({
-                    match arg.format() {
-                        PgValueFormat::Binary => arg.as_bytes()?.to_owned(),
-                        PgValueFormat::Text => arg.as_str()?.as_bytes().to_vec(),
-                    }
-                })),
-            ),
-            PgType::Path => Value::Ext(
-                "Path",
-                Box::new(Value::Binary({
-                    match arg.format() {
-                        PgValueFormat::Binary => arg.as_bytes()?.to_owned(),
-                        PgValueFormat::Text => arg.as_str()?.as_bytes().to_vec(),
-                    }
-                })),
-            ),
-            PgType::Box => Value::Ext(
-                "Box",
-                Box::new(Value::Binary({
-                    match arg.format() {
-                        PgValueFormat::Binary => arg.as_bytes()?.to_owned(),
-                        PgValueFormat::Text => arg.as_str()?.as_bytes().to_vec(),
-                    }
-                })),
-            ),
-            PgType::Polygon => Value::Ext(
-                "Polygon",
-                Box::new(Value::Binary({
-                    match arg.format() {
-                        PgValueFormat::Binary => arg.as_bytes()?.to_owned(),
-                        PgValueFormat::Text => arg.as_str()?.as_bytes().to_vec(),
-                    }
-                })),
-            ),
-            PgType::Line => Value::Ext(
-                "Line",
-                Box::new(Value::Binary({
-                    match arg.format() {
-                        PgValueFormat::Binary => arg.as_bytes()?.to_owned(),
-                        PgValueFormat::Text => arg.as_str()?.as_bytes().to_vec(),
-                    }
-                })),
-            ),
-            PgType::Cidr => Value::Ext(
-                "Cidr",
-                Box::new(Value::Binary({
-                    match arg.format() {
-                        PgValueFormat::Binary => arg.as_bytes()?.to_owned(),
-                        PgValueFormat::Text => arg.as_str()?.as_bytes().to_vec(),
-                    }
-                })),
-            ),
+            PgType::Point => Point::decode(arg)?.into(),
+            PgType::Lseg => LineSegment::decode(arg)?.into(),
+            PgType::Path => PgPath::decode(arg)?.into(),
+            PgType::Box => PgBox::decode(arg)?.into(),
+            PgType::Polygon => Polygon::decode(arg)?.into(),
+            PgType::Line => Line::decode(arg)?.into(),
+            PgType::Cidr => Cidr::decode(arg)?.into(),
 
             PgType::Float4 => Value::F32(Decode::decode(arg)?),
             PgType::Float8 => Value::F32(Decode::decode(arg)?),
             PgType::Unknown => Value::Null,
-            PgType::Circle => Value::Ext(
-                "Circle",
-                Box::new(Value::Binary({
-                    match arg.format() {
-                        PgValueFormat::Binary => arg.as_bytes()?.to_owned(),
-                        PgValueFormat::Text => arg.as_str()?.as_bytes().to_vec(),
-                    }
-                })),
-            ),
-            PgType::Macaddr8 => Value::Ext(
-                "Macaddr8",
-                Box::new(Value::Binary({
-                    match arg.format() {
-                        PgValueFormat::Binary => arg.as_bytes()?.to_owned(),
-                        PgValueFormat::Text => arg.as_str()?.as_bytes().to_vec(),
-                    }
-                })),
-            ),
-            PgType::Macaddr => Value::Ext(
-                "Macaddr",
-                Box::new(Value::Binary({
-                    match arg.format() {
-                        PgValueFormat::Binary => arg.as_bytes()?.to_owned(),
-                        PgValueFormat::Text => arg.as_str()?.as_bytes().to_vec(),
-                    }
-                })),
-            ),
-            PgType::Inet => Value::Ext(
-                "Inet",
-                Box::new(Value::Binary({
-                    match arg.format() {
-                        PgValueFormat::Binary => arg.as_bytes()?.to_owned(),
-                        PgValueFormat::Text => arg.as_str()?.as_bytes().to_vec(),
-                    }
-                })),
-            ),
+            PgType::Circle => Circle::decode(arg)?.into(),
+            PgType::Macaddr8 => MacAddress8::decode(arg)?.into(),
+            PgType::Macaddr => MacAddress::decode(arg)?.into(),
+            PgType::Inet => Inet::decode(arg)?.into(),
             PgType::Bpchar | PgType::Varchar => Value::String(Decode::decode(arg)?),
             PgType::Date => {
                 let v: Date = Decode::decode(arg)?;
@@ -240,16 +179,19 @@ impl Decode for Value {
                 v
             }
             .into(),
-            PgType::Timestamptz => Timestamptz::decode(arg)?.into(),
-            PgType::Interval => Value::Ext(
-                "Interval",
-                Box::new(Value::Binary({
-                    match arg.format() {
-                        PgValueFormat::Binary => arg.as_bytes()?.to_owned(),
-                        PgValueFormat::Text => arg.as_str()?.as_bytes().to_vec(),
+            PgType::Timestamptz => {
+                let timezone = arg.timezone.clone();
+                let v = Timestamptz::decode(arg)?;
+                match timezone {
+                    Some(tz) => {
+                        let offset = tz.offset_at(v.unix_timestamp());
+                        v.set_offset(offset)
                     }
-                })),
-            ),
+                    None => v,
+                }
+                .into()
+            }
+            PgType::Interval => PgInterval::decode(arg)?.into(),
             PgType::Timetz => Timetz::decode(arg)?.into(),
             PgType::Bit => Value::Ext(
                 "Bit",
@@ -281,60 +223,18 @@ impl Decode for Value {
             ),
             PgType::Uuid => Uuid::decode(arg)?.into(),
             PgType::Jsonb => decode_json(arg)?,
-            PgType::Int4Range => Value::Ext(
-                "Int4Range",
-                Box::new(Value::Binary({
-                    match arg.format() {
-                        PgValueFormat::Binary => arg.as_bytes()?.to_owned(),
-                        PgValueFormat::Text => arg.as_str()?.as_bytes().to_vec(),
-                    }
-                })),
-            ),
-            PgType::NumRange => Value::Ext(
-                "NumRange",
-                Box::new(Value::Binary({
-                    match arg.format() {
-                        PgValueFormat::Binary => arg.as_bytes()?.to_owned(),
-                        PgValueFormat::Text => arg.as_str()?.as_bytes().to_vec(),
-                    }
-                })),
-            ),
-            PgType::TsRange => Value::Ext(
-                "TsRange",
-                Box::new(Value::Binary({
-                    match arg.format() {
-                        PgValueFormat::Binary => arg.as_bytes()?.to_owned(),
-                        PgValueFormat::Text => arg.as_str()?.as_bytes().to_vec(),
-                    }
-                })),
-            ),
-            PgType::TstzRange => Value::Ext(
-                "TstzRange",
-                Box::new(Value::Binary({
-                    match arg.format() {
-                        PgValueFormat::Binary => arg.as_bytes()?.to_owned(),
-                        PgValueFormat::Text => arg.as_str()?.as_bytes().to_vec(),
-                    }
-                })),
-            ),
-            PgType::DateRange => Value::Ext(
-                "DateRange",
-                Box::new(Value::Binary({
-                    match arg.format() {
-                        PgValueFormat::Binary => arg.as_bytes()?.to_owned(),
-                        PgValueFormat::Text => arg.as_str()?.as_bytes().to_vec(),
-                    }
-                })),
-            ),
-            PgType::Int8Range => Value::Ext(
-                "Int8Range",
-                Box::new(Value::Binary({
-                    match arg.format() {
-                        PgValueFormat::Binary => arg.as_bytes()?.to_owned(),
-                        PgValueFormat::Text => arg.as_str()?.as_bytes().to_vec(),
-                    }
-                })),
-            ),
+            PgType::Int4Range => range::decode_value(arg)?,
+            PgType::NumRange => range::decode_value(arg)?,
+            PgType::TsRange => range::decode_value(arg)?,
+            PgType::TstzRange => range::decode_value(arg)?,
+            PgType::DateRange => range::decode_value(arg)?,
+            PgType::Int8Range => range::decode_value(arg)?,
+            PgType::Int4Multirange => range::decode_value(arg)?,
+            PgType::NumMultirange => range::decode_value(arg)?,
+            PgType::TsMultirange => range::decode_value(arg)?,
+            PgType::TstzMultirange => range::decode_value(arg)?,
+            PgType::DateMultirange => range::decode_value(arg)?,
+            PgType::Int8Multirange => range::decode_value(arg)?,
             PgType::Jsonpath => Value::Ext(
                 "Jsonpath",
                 Box::new(Value::Binary({
@@ -356,6 +256,21 @@ impl Decode for Value {
             ),
             PgType::Custom(ref pg_custom_type) => match &pg_custom_type.kind {
                 PgTypeKind::Enum(_) => Value::String(Decode::decode(arg)?),
+                PgTypeKind::Range(_) | PgTypeKind::Multirange(_) => range::decode_value(arg)?,
+                PgTypeKind::Composite(fields) => {
+                    let fields = fields.clone();
+                    composite::decode(arg, &fields)?
+                }
+                // a domain is transmitted as its base type
+                PgTypeKind::Domain(base) => Value::decode(PgValue {
+                    type_info: base.clone(),
+                    ..arg
+                })?,
+                PgTypeKind::Array(_) => array::decode_value(arg)?,
+                _ if is_extension(&pg_custom_type.name) => {
+                    let name = pg_custom_type.name.clone();
+                    decode_extension(&name, arg)?
+                }
                 _ => Value::Ext(
                     "Custom",
                     Box::new(Value::Binary({
@@ -384,51 +299,57 @@ impl Decode for Value {
                     }
                 })),
             ),
-            PgType::JsonArray => Value::Array(Decode::decode(arg)?),
-            PgType::LineArray => Value::Array(Decode::decode(arg)?),
-            PgType::CidrArray => Value::Array(Decode::decode(arg)?),
-            PgType::CircleArray => Value::Array(Decode::decode(arg)?),
-            PgType::Macaddr8Array => Value::Array(Decode::decode(arg)?),
-            PgType::BoolArray => Value::Array(Decode::decode(arg)?),
-            PgType::ByteaArray => Value::Array(Decode::decode(arg)?),
-            PgType::CharArray => Value::Array(Decode::decode(arg)?),
-            PgType::NameArray => Value::Array(Decode::decode(arg)?),
-            PgType::Int2Array => Value::Array(Decode::decode(arg)?),
-            PgType::Int4Array => Value::Array(Decode::decode(arg)?),
-            PgType::TextArray => Value::Array(Decode::decode(arg)?),
-            PgType::BpcharArray => Value::Array(Decode::decode(arg)?),
-            PgType::VarcharArray => Value::Array(Decode::decode(arg)?),
-            PgType::Int8Array => Value::Array(Decode::decode(arg)?),
-            PgType::PointArray => Value::Array(Decode::decode(arg)?),
-            PgType::LsegArray => Value::Array(Decode::decode(arg)?),
-            PgType::PathArray => Value::Array(Decode::decode(arg)?),
-            PgType::BoxArray => Value::Array(Decode::decode(arg)?),
-            PgType::Float4Array => Value::Array(Decode::decode(arg)?),
-            PgType::Float8Array => Value::Array(Decode::decode(arg)?),
-            PgType::PolygonArray => Value::Array(Decode::decode(arg)?),
-            PgType::OidArray => Value::Array(Decode::decode(arg)?),
-            PgType::MacaddrArray => Value::Array(Decode::decode(arg)?),
-            PgType::InetArray => Value::Array(Decode::decode(arg)?),
-            PgType::TimestampArray => Value::Array(Decode::decode(arg)?),
-            PgType::DateArray => Value::Array(Decode::decode(arg)?),
-            PgType::TimeArray => Value::Array(Decode::decode(arg)?),
-            PgType::TimestamptzArray => Value::Array(Decode::decode(arg)?),
-            PgType::IntervalArray => Value::Array(Decode::decode(arg)?),
-            PgType::NumericArray => Value::Array(Decode::decode(arg)?),
-            PgType::TimetzArray => Value::Array(Decode::decode(arg)?),
-            PgType::BitArray => Value::Array(Decode::decode(arg)?),
-            PgType::VarbitArray => Value::Array(Decode::decode(arg)?),
-            PgType::RecordArray => Value::Array(Decode::decode(arg)?),
-            PgType::UuidArray => Value::Array(Decode::decode(arg)?),
-            PgType::JsonbArray => Value::Array(Decode::decode(arg)?),
-            PgType::Int4RangeArray => Value::Array(Decode::decode(arg)?),
-            PgType::NumRangeArray => Value::Array(Decode::decode(arg)?),
-            PgType::TsRangeArray => Value::Array(Decode::decode(arg)?),
-            PgType::TstzRangeArray => Value::Array(Decode::decode(arg)?),
-            PgType::DateRangeArray => Value::Array(Decode::decode(arg)?),
-            PgType::Int8RangeArray => Value::Array(Decode::decode(arg)?),
-            PgType::JsonpathArray => Value::Array(Decode::decode(arg)?),
-            PgType::MoneyArray => Value::Array(Decode::decode(arg)?),
+            PgType::JsonArray => array::decode_value(arg)?,
+            PgType::LineArray => array::decode_value(arg)?,
+            PgType::CidrArray => array::decode_value(arg)?,
+            PgType::CircleArray => array::decode_value(arg)?,
+            PgType::Macaddr8Array => array::decode_value(arg)?,
+            PgType::BoolArray => array::decode_value(arg)?,
+            PgType::ByteaArray => array::decode_value(arg)?,
+            PgType::CharArray => array::decode_value(arg)?,
+            PgType::NameArray => array::decode_value(arg)?,
+            PgType::Int2Array => array::decode_value(arg)?,
+            PgType::Int4Array => array::decode_value(arg)?,
+            PgType::TextArray => array::decode_value(arg)?,
+            PgType::BpcharArray => array::decode_value(arg)?,
+            PgType::VarcharArray => array::decode_value(arg)?,
+            PgType::Int8Array => array::decode_value(arg)?,
+            PgType::PointArray => array::decode_value(arg)?,
+            PgType::LsegArray => array::decode_value(arg)?,
+            PgType::PathArray => array::decode_value(arg)?,
+            PgType::BoxArray => array::decode_value(arg)?,
+            PgType::Float4Array => array::decode_value(arg)?,
+            PgType::Float8Array => array::decode_value(arg)?,
+            PgType::PolygonArray => array::decode_value(arg)?,
+            PgType::OidArray => array::decode_value(arg)?,
+            PgType::MacaddrArray => array::decode_value(arg)?,
+            PgType::InetArray => array::decode_value(arg)?,
+            PgType::TimestampArray => array::decode_value(arg)?,
+            PgType::DateArray => array::decode_value(arg)?,
+            PgType::TimeArray => array::decode_value(arg)?,
+            PgType::TimestamptzArray => array::decode_value(arg)?,
+            PgType::IntervalArray => array::decode_value(arg)?,
+            PgType::NumericArray => array::decode_value(arg)?,
+            PgType::TimetzArray => array::decode_value(arg)?,
+            PgType::BitArray => array::decode_value(arg)?,
+            PgType::VarbitArray => array::decode_value(arg)?,
+            PgType::RecordArray => array::decode_value(arg)?,
+            PgType::UuidArray => array::decode_value(arg)?,
+            PgType::JsonbArray => array::decode_value(arg)?,
+            PgType::Int4RangeArray => array::decode_value(arg)?,
+            PgType::NumRangeArray => array::decode_value(arg)?,
+            PgType::TsRangeArray => array::decode_value(arg)?,
+            PgType::TstzRangeArray => array::decode_value(arg)?,
+            PgType::DateRangeArray => array::decode_value(arg)?,
+            PgType::Int8RangeArray => array::decode_value(arg)?,
+            PgType::Int4MultirangeArray => array::decode_value(arg)?,
+            PgType::NumMultirangeArray => array::decode_value(arg)?,
+            PgType::TsMultirangeArray => array::decode_value(arg)?,
+            PgType::TstzMultirangeArray => array::decode_value(arg)?,
+            PgType::DateMultirangeArray => array::decode_value(arg)?,
+            PgType::Int8MultirangeArray => array::decode_value(arg)?,
+            PgType::JsonpathArray => array::decode_value(arg)?,
+            PgType::MoneyArray => array::decode_value(arg)?,
         })
     }
 }
@@ -449,7 +370,7 @@ impl Encode for Value {
                 v.encode(buf)?
             }
             Value::Binary(v) => v.encode(buf)?,
-            Value::Array(v) => encode_json(Value::Array(v), buf)?,
+            Value::Array(v) => v.encode(buf)?,
             Value::Map(v) => encode_json(Value::Map(v), buf)?,
             Value::Ext(type_name, v) => {
                 match type_name {
@@ -483,27 +404,27 @@ impl Encode for Value {
                     "Text" => v.into_string().unwrap_or_default().encode(buf)?,
                     "Oid" => Oid::from(v.as_u64().unwrap_or_default() as u32).encode(buf)?,
                     "Json" => Json(v.into_string().unwrap_or_default()).encode(buf)?,
-                    "Point" => v.into_bytes().unwrap_or_default().encode(buf)?,
-                    "Lseg" => v.into_bytes().unwrap_or_default().encode(buf)?,
-                    "Path" => v.into_bytes().unwrap_or_default().encode(buf)?,
-                    "Box" => v.into_bytes().unwrap_or_default().encode(buf)?,
-                    "Polygon" => v.into_bytes().unwrap_or_default().encode(buf)?,
-                    "Line" => v.into_bytes().unwrap_or_default().encode(buf)?,
-                    "Cidr" => v.into_bytes().unwrap_or_default().encode(buf)?,
+                    "Point" => geometry::encode_by_name(type_name, *v, buf)?,
+                    "Lseg" => geometry::encode_by_name(type_name, *v, buf)?,
+                    "Path" => geometry::encode_by_name(type_name, *v, buf)?,
+                    "Box" => geometry::encode_by_name(type_name, *v, buf)?,
+                    "Polygon" => geometry::encode_by_name(type_name, *v, buf)?,
+                    "Line" => geometry::encode_by_name(type_name, *v, buf)?,
+                    "Cidr" => network::encode_by_name(type_name, *v, buf)?,
                     "Float4" => (v.as_f64().unwrap_or_default() as f32).encode(buf)?,
                     "Float8" => v.as_f64().unwrap_or_default().encode(buf)?,
                     "Unknown" => v.into_bytes().unwrap_or_default().encode(buf)?,
-                    "Circle" => v.into_bytes().unwrap_or_default().encode(buf)?,
-                    "Macaddr8" => v.into_bytes().unwrap_or_default().encode(buf)?,
-                    "Macaddr" => v.into_bytes().unwrap_or_default().encode(buf)?,
-                    "Inet" => v.into_bytes().unwrap_or_default().encode(buf)?,
+                    "Circle" => geometry::encode_by_name(type_name, *v, buf)?,
+                    "Macaddr8" => network::encode_by_name(type_name, *v, buf)?,
+                    "Macaddr" => network::encode_by_name(type_name, *v, buf)?,
+                    "Inet" => network::encode_by_name(type_name, *v, buf)?,
                     "Bpchar" => v.into_bytes().unwrap_or_default().encode(buf)?,
                     "Varchar" => v.into_bytes().unwrap_or_default().encode(buf)?,
                     "Timestamptz" => {
                         let tz: Timestamptz = rbs::from_value(Value::Ext(type_name, v))?;
                         tz.encode(buf)?
                     }
-                    "Interval" => v.into_bytes().unwrap_or_default().encode(buf)?,
+                    "Interval" => rbs::from_value::<PgInterval>(*v)?.encode(buf)?,
                     "Timetz" => {
                         Timetz(rbs::from_value(*v).map_err(|e| Error::from(e.to_string()))?)
                             .encode(buf)?
@@ -515,15 +436,29 @@ impl Encode for Value {
                         .encode(buf)?,
                     "Record" => v.into_bytes().unwrap_or_default().encode(buf)?,
                     "Jsonb" => Json(v.into_string().unwrap_or_default()).encode(buf)?,
-                    "Int4Range" => v.into_bytes().unwrap_or_default().encode(buf)?,
-                    "NumRange" => v.into_bytes().unwrap_or_default().encode(buf)?,
-                    "TsRange" => v.into_bytes().unwrap_or_default().encode(buf)?,
-                    "TstzRange" => v.into_bytes().unwrap_or_default().encode(buf)?,
-                    "DateRange" => v.into_bytes().unwrap_or_default().encode(buf)?,
-                    "Int8Range" => v.into_bytes().unwrap_or_default().encode(buf)?,
+                    "Int4Range" => range::encode_by_name(type_name, *v, buf)?,
+                    "NumRange" => range::encode_by_name(type_name, *v, buf)?,
+                    "TsRange" => range::encode_by_name(type_name, *v, buf)?,
+                    "TstzRange" => range::encode_by_name(type_name, *v, buf)?,
+                    "DateRange" => range::encode_by_name(type_name, *v, buf)?,
+                    "Int8Range" => range::encode_by_name(type_name, *v, buf)?,
+                    "Int4Multirange" => range::encode_by_name(type_name, *v, buf)?,
+                    "NumMultirange" => range::encode_by_name(type_name, *v, buf)?,
+                    "TsMultirange" => range::encode_by_name(type_name, *v, buf)?,
+                    "TstzMultirange" => range::encode_by_name(type_name, *v, buf)?,
+                    "DateMultirange" => range::encode_by_name(type_name, *v, buf)?,
+                    "Int8Multirange" => range::encode_by_name(type_name, *v, buf)?,
+                    // a user-defined range, bound through the described type
+                    "Range" | "Multirange" => range::encode_by_name(type_name, *v, buf)?,
                     "Jsonpath" => v.into_bytes().unwrap_or_default().encode(buf)?,
                     "Money" => Money(v.as_i64().unwrap_or_default()).encode(buf)?,
                     "Void" => v.into_bytes().unwrap_or_default().encode(buf)?,
+                    "Hstore" => hstore::PgHstore::from_value(*v)?.encode(buf)?,
+                    "Ltree" => ltree::encode_value(*v, buf)?,
+                    "Lquery" => ltree::encode_value(*v, buf)?,
+                    "Citext" => v.into_string().unwrap_or_default().encode(buf)?,
+                    "TsVector" => text_search::encode_tsvector(*v, buf)?,
+                    "TsQuery" => text_search::encode_tsquery(*v, buf)?,
                     "Custom" => v.into_bytes().unwrap_or_default().encode(buf)?,
                     "DeclareWithName" => v.into_bytes().unwrap_or_default().encode(buf)?,
                     "DeclareWithOid" => v.into_bytes().unwrap_or_default().encode(buf)?,
@@ -533,3 +468,90 @@ impl Encode for Value {
         })
     }
 }
+
+/// Encodes `value` as the parameter type `ty` described by the server, returning the type
+/// that was written.
+pub(crate) fn encode_as(
+    value: Value,
+    ty: &PgTypeInfo,
+    buf: &mut PgArgumentBuffer,
+) -> Result<(PgTypeInfo, IsNull), Error> {
+    match (value, ty.kind()) {
+        (Value::Null, _) => Ok((ty.clone(), IsNull::Yes)),
+        (value, PgTypeKind::Domain(base)) => encode_as(value, base, buf),
+        (value, _) if is_extension(ty.name()) => {
+            Ok((ty.clone(), encode_extension(value, ty.name(), buf)?))
+        }
+        (Value::Ext(name, v), PgTypeKind::Range(_) | PgTypeKind::Multirange(_))
+            if range::is_ext_name(name) =>
+        {
+            Ok((ty.clone(), range::encode_as(*v, ty, buf)?))
+        }
+        (Value::Map(map), PgTypeKind::Composite(fields)) => {
+            Ok((ty.clone(), composite::encode(map, ty, fields, buf)?))
+        }
+        (Value::Array(values), PgTypeKind::Array(element)) => {
+            Ok((ty.clone(), array::encode_array(values, element, buf)?))
+        }
+        (value, _) if *ty == PgTypeInfo::JSON || *ty == PgTypeInfo::JSONB => {
+            if *ty == PgTypeInfo::JSONB {
+                buf.push(1);
+            }
+            buf.extend(coerce::to_json_text(value)?.into_bytes());
+            Ok((ty.clone(), IsNull::No))
+        }
+        (value @ (Value::Map(_) | Value::Array(_)), _)
+            if matches!(
+                ty.0,
+                PgType::Text | PgType::Varchar | PgType::Bpchar | PgType::Name | PgType::Unknown
+            ) =>
+        {
+            // a text parameter
+            buf.extend(coerce::to_json_text(value)?.into_bytes());
+            Ok((ty.clone(), IsNull::No))
+        }
+        (value, _) if *ty == PgTypeInfo::INT2 => {
+            // a `Value` has no two byte integer to convert to
+            let v = coerce::to_int(value, ty, i16::MIN as i64, i16::MAX as i64)?;
+            Ok((ty.clone(), (v as i16).encode(buf)?))
+        }
+        (value, _) => {
+            let value = coerce::coerce(value, ty)?;
+            let info = value.type_info();
+            Ok((info, value.encode(buf)?))
+        }
+    }
+}
+
+/// Whether `name` is an extension type (or a type without a fixed oid) decoded by name.
+fn is_extension(name: &str) -> bool {
+    matches!(
+        name,
+        "hstore" | "ltree" | "lquery" | "ltxtquery" | "citext" | "tsvector" | "tsquery"
+    )
+}
+
+fn decode_extension(name: &str, arg: PgValue) -> Result<Value, Error> {
+    match name {
+        "hstore" => hstore::decode_value(arg),
+        "ltree" | "lquery" | "ltxtquery" => ltree::decode_value(arg),
+        "tsvector" => text_search::decode_tsvector(arg),
+        "tsquery" => text_search::decode_tsquery(arg),
+        // citext
+        _ => Ok(Value::String(Decode::decode(arg)?)),
+    }
+}
+
+fn encode_extension(value: Value, name: &str, buf: &mut PgArgumentBuffer) -> Result<IsNull, Error> {
+    match name {
+        "hstore" => hstore::PgHstore::from_value(value)?.encode(buf),
+        "ltree" | "lquery" | "ltxtquery" => ltree::encode_value(value, buf),
+        "tsvector" => text_search::encode_tsvector(value, buf),
+        "tsquery" => text_search::encode_tsquery(value, buf),
+        // citext
+        _ => match coerce::coerce(value, &PgTypeInfo::TEXT)? {
+            Value::String(s) => s.encode(buf),
+            v => Err(Error::from(format!("cannot convert {} to {}", v, name))),
+        },
+    }
+}
@@ -1,4 +1,5 @@
 use crate::type_info::PgTypeInfo;
+use crate::types::timezone::PgTimeZone;
 use bytes::Buf;
 use rbdc::Error;
 use std::borrow::Cow;
@@ -25,26 +26,48 @@ pub struct PgValue {
     pub(crate) value: Option<Vec<u8>>,
     pub(crate) type_info: PgTypeInfo,
     pub(crate) format: PgValueFormat,
+    // the session time zone a `timestamptz` (also inside an array, composite or range) is
+    // shown in, passed on to the values it holds
+    pub(crate) timezone: Option<PgTimeZone>,
+}
+
+/// Reads the length prefix of a value, `None` for NULL, checking that `buf` holds the value.
+fn read_len(buf: &mut &[u8]) -> Result<Option<usize>, Error> {
+    if buf.len() < 4 {
+        return Err(Error::from("value length is truncated"));
+    }
+    let len = buf.get_i32();
+    if len == -1 {
+        return Ok(None);
+    }
+    if len < 0 || buf.len() < len as usize {
+        return Err(Error::from(format!(
+            "value of length {} is truncated to {} bytes",
+            len,
+            buf.len()
+        )));
+    }
+    Ok(Some(len as usize))
 }
 
 impl<'r> PgValueRef<'r> {
-    pub(crate) fn get(buf: &mut &'r [u8], format: PgValueFormat, ty: PgTypeInfo) -> Self {
-        let mut element_len = buf.get_i32();
-
-        let element_val = if element_len == -1 {
-            element_len = 0;
-            None
-        } else {
-            Some(&buf[..(element_len as usize)])
+    pub(crate) fn get(
+        buf: &mut &'r [u8],
+        format: PgValueFormat,
+        ty: PgTypeInfo,
+    ) -> Result<Self, Error> {
+        let (element_val, len) = match read_len(buf)? {
+            None => (None, 0),
+            Some(len) => (Some(&buf[..len]), len),
         };
 
-        buf.advance(element_len as usize);
+        buf.advance(len);
 
-        PgValueRef {
+        Ok(PgValueRef {
             value: element_val,
             type_info: ty,
             format,
-        }
+        })
     }
 
     pub fn format(&self) -> PgValueFormat {
@@ -64,23 +87,25 @@ impl<'r> PgValueRef<'r> {
 }
 
 impl PgValue {
-    pub fn get(buf: &mut &[u8], format: PgValueFormat, ty: PgTypeInfo) -> Self {
-        let mut element_len = buf.get_i32();
-
-        let element_val = if element_len == -1 {
-            element_len = 0;
-            None
-        } else {
-            Some(buf[..(element_len as usize)].to_vec())
+    pub fn get(buf: &mut &[u8], format: PgValueFormat, ty: PgTypeInfo) -> Result<Self, Error> {
+        let (element_val, len) = match read_len(buf)? {
+            None => (None, 0),
+            Some(len) => (Some(buf[..len].to_vec()), len),
         };
 
-        buf.advance(element_len as usize);
+        buf.advance(len);
 
-        PgValue {
+        Ok(PgValue {
             value: element_val,
             type_info: ty,
             format,
-        }
+            timezone: None,
+        })
+    }
+
+    pub(crate) fn with_timezone(mut self, timezone: Option<PgTimeZone>) -> Self {
+        self.timezone = timezone;
+        self
     }
 
     #[inline]
@@ -132,6 +157,7 @@ impl<'r> PgValueRef<'r> {
             value: value,
             format: self.format,
             type_info: self.type_info.clone(),
+            timezone: None,
         }
     }
 
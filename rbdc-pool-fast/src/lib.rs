@@ -3,7 +3,7 @@
 use dark_std::sync::AtomicDuration;
 use futures_core::future::BoxFuture;
 use log::info;
-use rbdc::db::{Connection, ExecResult, Row};
+use rbdc::db::{Connection, Describe, ExecResult, Row, TwoPhase};
 use rbdc::pool::ConnectionGuard;
 use rbdc::pool::ConnectionManager;
 use rbdc::pool::Pool;
@@ -197,6 +197,15 @@ impl Connection for ConnManagerProxy {
         }
         self.conn.as_mut().unwrap().rollback()
     }
+    fn two_phase(&mut self) -> Option<&mut dyn TwoPhase> {
+        self.conn.as_mut()?.two_phase()
+    }
+    fn describe(&mut self, sql: &str) -> BoxFuture<'_, Result<Describe, Error>> {
+        if self.conn.is_none() {
+            return Box::pin(async { Err(Error::from("conn is drop")) });
+        }
+        self.conn.as_mut().unwrap().describe(sql)
+    }
 }
 
 #[cfg(test)]
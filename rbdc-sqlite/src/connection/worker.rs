@@ -11,6 +11,7 @@ use crate::{SqliteArguments, SqliteQueryResult, SqliteRow, SqliteStatement};
 use either::Either;
 use futures_channel::oneshot;
 use futures_intrusive::sync::{Mutex, MutexGuard};
+use rbdc::db::{Describe, DescribeColumn};
 use rbdc::error::Error;
 
 // Each SQLite connection has a dedicated thread.
@@ -37,6 +38,10 @@ pub enum Command {
         query: Box<str>,
         tx: oneshot::Sender<Result<SqliteStatement, Error>>,
     },
+    Describe {
+        query: Box<str>,
+        tx: oneshot::Sender<Result<Describe, Error>>,
+    },
     Execute {
         query: Box<str>,
         arguments: Option<SqliteArguments>,
@@ -108,6 +113,9 @@ impl ConnectionWorker {
                             }))
                             .ok();
                         }
+                        Command::Describe { query, tx } => {
+                            tx.send(describe(&mut conn, &query)).ok();
+                        }
                         Command::Execute {
                             query,
                             arguments,
@@ -173,6 +181,14 @@ impl ConnectionWorker {
         .await?
     }
 
+    pub(crate) async fn describe(&mut self, query: &str) -> Result<Describe, Error> {
+        self.oneshot_cmd(|tx| Command::Describe {
+            query: query.into(),
+            tx,
+        })
+        .await?
+    }
+
     pub(crate) async fn execute(
         &mut self,
         query: String,
@@ -293,6 +309,38 @@ fn prepare(conn: &mut ConnectionState, query: &str) -> Result<SqliteStatement, E
     })
 }
 
+fn describe(conn: &mut ConnectionState, query: &str) -> Result<Describe, Error> {
+    let statement = conn.statements.get(query, true)?;
+
+    let mut describe = Describe::default();
+    let mut described = false;
+
+    while let Some(statement) = statement.prepare_next(&mut conn.handle)? {
+        // sqlite does not know the type of a parameter
+        let parameters = describe.params.len() + statement.handle.bind_parameter_count();
+        describe.params.resize(parameters, String::new());
+
+        // like `prepare`, the columns are those of the first non-empty statement
+        if !statement.columns.is_empty() && !described {
+            described = true;
+            for (i, column) in statement.columns.iter().enumerate() {
+                describe.columns.push(DescribeColumn {
+                    name: column.name.to_string(),
+                    // an expression has no declared type
+                    column_type: if column.type_info.is_null() {
+                        String::new()
+                    } else {
+                        column.type_info.name().to_string()
+                    },
+                });
+                describe.nullable.push(statement.handle.column_nullable(i)?);
+            }
+        }
+    }
+
+    Ok(describe)
+}
+
 fn update_cached_statements_size(conn: &ConnectionState, size: &AtomicUsize) {
     size.store(conn.statements.len(), Ordering::Release);
 }
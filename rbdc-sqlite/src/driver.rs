@@ -49,8 +49,51 @@ impl Placeholder for SqliteDriver {
 
 #[cfg(test)]
 mod test {
+    use crate::SqliteConnectOptions;
+    use rbdc::db::{Connection, DescribeColumn};
+    use std::str::FromStr;
+
     #[test]
     fn test_default() {}
+
+    #[tokio::test]
+    async fn test_describe() {
+        let mut conn = SqliteConnectOptions::from_str("sqlite::memory:")
+            .unwrap()
+            .connect()
+            .await
+            .unwrap();
+        conn.exec(
+            "CREATE TABLE user (id INTEGER PRIMARY KEY, name TEXT NOT NULL, age INTEGER)",
+            vec![],
+        )
+        .await
+        .unwrap();
+
+        let describe = conn
+            .describe("SELECT id, name, age, ? AS p FROM user WHERE age > ?")
+            .await
+            .unwrap();
+        assert_eq!(describe.params, vec!["".to_string(), "".to_string()]);
+        assert_eq!(
+            describe
+                .columns
+                .iter()
+                .map(|c| c.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["id", "name", "age", "p"]
+        );
+        assert_eq!(
+            describe.columns[1],
+            DescribeColumn {
+                name: "name".to_string(),
+                column_type: "TEXT".to_string(),
+            }
+        );
+        assert_eq!(describe.columns[3].column_type, "");
+        assert_eq!(describe.nullable[1], Some(false));
+        assert_eq!(describe.nullable[2], Some(true));
+    }
 }
 // #[cfg(test)]
 // mod test {
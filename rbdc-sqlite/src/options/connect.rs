@@ -6,7 +6,7 @@ use futures_core::future::BoxFuture;
 use futures_core::stream::BoxStream;
 use futures_util::FutureExt;
 use futures_util::{StreamExt, TryStreamExt};
-use rbdc::db::{Connection, ExecResult, Row};
+use rbdc::db::{Connection, Describe, ExecResult, Row};
 use rbdc::error::Error;
 use rbs::Value;
 use std::fmt::Write;
@@ -142,4 +142,9 @@ impl Connection for SqliteConnection {
             Ok(())
         })
     }
+
+    fn describe(&mut self, sql: &str) -> BoxFuture<'_, Result<Describe, Error>> {
+        let sql = sql.to_owned();
+        Box::pin(async move { self.worker.describe(&sql).await })
+    }
 }
@@ -151,6 +151,64 @@ pub trait Connection: Send+Sync {
             Ok(())
         })
     }
+
+    /// the two-phase commit support of the connection, `None` if the database has none
+    fn two_phase(&mut self) -> Option<&mut dyn TwoPhase> {
+        None
+    }
+
+    /// describe the parameters and result columns of `sql` without executing it
+    fn describe(&mut self, sql: &str) -> BoxFuture<'_, Result<Describe, Error>> {
+        let _ = sql;
+        Box::pin(async { Err(Error::from("describe is not supported by this driver")) })
+    }
+}
+
+/// The parameters and result columns of a statement, returned by [`Connection::describe`].
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize, Eq, PartialEq)]
+pub struct Describe {
+    /// The type of each parameter, as named by the database. A type the database does not
+    /// report is an empty string.
+    pub params: Vec<String>,
+    /// The result columns, empty for statements that return no rows
+    pub columns: Vec<DescribeColumn>,
+    /// Whether each column may be `NULL`, `None` when it is not known
+    pub nullable: Vec<Option<bool>>,
+}
+
+/// A result column of a described statement.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize, Eq, PartialEq)]
+pub struct DescribeColumn {
+    pub name: String,
+    /// the type, as named by the database, empty when it is not known
+    pub column_type: String,
+}
+
+/// Two-phase commit (`PREPARE TRANSACTION` on Postgres, `XA` on MySQL).
+///
+/// A prepared transaction is persisted by the database without being committed. It is no
+/// longer tied to the connection and is later committed or rolled back by its global
+/// transaction id `gid`, from any connection and even after a restart of the database.
+///
+/// ```log
+/// begin_two_phase(gid) -> ... statements ... -> prepare(gid) -> commit_prepared(gid)
+/// ```
+pub trait TwoPhase: Connection {
+    /// Starts a transaction that will be prepared as `gid`.
+    fn begin_two_phase(&mut self, gid: &str) -> BoxFuture<'_, Result<(), Error>>;
+
+    /// Prepares the transaction started by `begin_two_phase`.
+    fn prepare(&mut self, gid: &str) -> BoxFuture<'_, Result<(), Error>>;
+
+    /// Commits the prepared transaction `gid`.
+    fn commit_prepared(&mut self, gid: &str) -> BoxFuture<'_, Result<(), Error>>;
+
+    /// Rolls back the transaction `gid`, either prepared or still open on this connection.
+    fn rollback_prepared(&mut self, gid: &str) -> BoxFuture<'_, Result<(), Error>>;
+
+    /// The ids of the prepared transactions that are neither committed nor rolled back,
+    /// to be resolved after a crash.
+    fn recover(&mut self) -> BoxFuture<'_, Result<Vec<String>, Error>>;
 }
 
 impl Connection for Box<dyn Connection> {
@@ -191,6 +249,12 @@ impl Connection for Box<dyn Connection> {
     fn commit(&mut self) -> BoxFuture<Result<(), Error>> {
         self.deref_mut().commit()
     }
+    fn two_phase(&mut self) -> Option<&mut dyn TwoPhase> {
+        self.deref_mut().two_phase()
+    }
+    fn describe(&mut self, sql: &str) -> BoxFuture<'_, Result<Describe, Error>> {
+        self.deref_mut().describe(sql)
+    }
 }
 
 /// Result set from executing a query against a statement
@@ -13,6 +13,8 @@ use std::mem::replace;
 use rbs::err_protocol;
 
 /// X.509 Certificate input, either a file path or a PEM encoded inline certificate(s).
+///
+/// Also used for client private keys, which follow the same file-or-inline rules.
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub enum CertificateInput {
     /// PEM encoded certificate(s)
@@ -25,8 +27,9 @@ impl From<String> for CertificateInput {
     fn from(value: String) -> Self {
         let trimmed = value.trim();
         // Some heuristics according to https://tools.ietf.org/html/rfc7468
-        if trimmed.starts_with("-----BEGIN CERTIFICATE-----")
-            && trimmed.contains("-----END CERTIFICATE-----")
+        if (trimmed.starts_with("-----BEGIN CERTIFICATE-----")
+            && trimmed.contains("-----END CERTIFICATE-----"))
+            || (trimmed.starts_with("-----BEGIN ") && trimmed.contains(" PRIVATE KEY-----"))
         {
             CertificateInput::Inline(value.as_bytes().to_vec())
         } else {
@@ -75,17 +78,45 @@ where
         matches!(self, Self::Tls(_))
     }
 
+    /// Returns the DER encoded end-entity certificate presented by the server,
+    /// or `None` if the stream is not encrypted.
+    pub fn peer_certificate(&self) -> Option<Vec<u8>> {
+        match self {
+            #[cfg(feature = "tls-rustls")]
+            MaybeTlsStream::Tls(s) => s
+                .get_ref()
+                .1
+                .peer_certificates()
+                .and_then(|certs| certs.first())
+                .map(|cert| cert.to_vec()),
+
+            #[cfg(feature = "tls-native-tls")]
+            MaybeTlsStream::Tls(s) => s
+                .get_ref()
+                .peer_certificate()
+                .ok()
+                .flatten()
+                .and_then(|cert| cert.to_der().ok()),
+
+            _ => None,
+        }
+    }
+
     pub async fn upgrade(
         &mut self,
         host: &str,
         accept_invalid_certs: bool,
         accept_invalid_hostnames: bool,
         root_cert_path: Option<&CertificateInput>,
+        client_cert_path: Option<&CertificateInput>,
+        client_key_path: Option<&CertificateInput>,
     ) -> Result<(), Error> {
         let connector = configure_tls_connector(
             accept_invalid_certs,
             accept_invalid_hostnames,
             root_cert_path,
+            client_cert_path,
+            client_key_path,
         )
         .await?;
 
@@ -119,8 +150,10 @@ async fn configure_tls_connector(
     accept_invalid_certs: bool,
     accept_invalid_hostnames: bool,
     root_cert_path: Option<&CertificateInput>,
+    client_cert_path: Option<&CertificateInput>,
+    client_key_path: Option<&CertificateInput>,
 ) -> Result<crate::rt::TlsConnector, Error> {
-    use crate::rt::native_tls::{Certificate, TlsConnector};
+    use crate::rt::native_tls::{Certificate, Identity, TlsConnector};
 
     let mut builder = TlsConnector::builder();
     builder
@@ -135,6 +168,23 @@ async fn configure_tls_connector(
             builder.add_root_certificate(cert);
         }
     }
+
+    match (client_cert_path, client_key_path) {
+        (Some(cert_path), Some(key_path)) => {
+            let cert = cert_path.data().await.map_err(|err| err_protocol!("{}", err))?;
+            let key = key_path.data().await.map_err(|err| err_protocol!("{}", err))?;
+            let identity =
+                Identity::from_pkcs8(&cert, &key).map_err(|err| err_protocol!("{}", err))?;
+
+            builder.identity(identity);
+        }
+        (None, None) => {}
+        _ => {
+            return Err(Error::from(
+                "client certificate and client key must be configured together",
+            ));
+        }
+    }
     let connector = builder.build().map_err(|err| err_protocol!("{}", err))?.into();
 
     Ok(connector)
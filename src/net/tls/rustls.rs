@@ -6,7 +6,7 @@ use rustls::client::danger::ServerCertVerifier;
 use rustls::client::WebPkiServerVerifier as WebPkiVerifier;
 use rustls::crypto::{verify_tls12_signature, verify_tls13_signature};
 use rustls::pki_types::ServerName;
-use rustls::pki_types::{CertificateDer, TrustAnchor, UnixTime};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, TrustAnchor, UnixTime};
 use rustls::{CertificateError, ClientConfig, DigitallySignedStruct, RootCertStore};
 use std::io::Cursor;
 use std::sync::Arc;
@@ -15,12 +15,12 @@ pub async fn configure_tls_connector(
     accept_invalid_certs: bool,
     accept_invalid_hostnames: bool,
     root_cert_path: Option<&CertificateInput>,
+    client_cert_path: Option<&CertificateInput>,
+    client_key_path: Option<&CertificateInput>,
 ) -> Result<crate::rt::TlsConnector, Error> {
     let config = ClientConfig::builder().dangerous();
     let config = if accept_invalid_certs {
-        config
-            .with_custom_certificate_verifier(Arc::new(DummyTlsVerifier))
-            .with_no_client_auth()
+        config.with_custom_certificate_verifier(Arc::new(DummyTlsVerifier))
     } else {
         let mut cert_store = RootCertStore::empty();
         cert_store.extend(webpki_roots::TLS_SERVER_ROOTS.iter().map(|ta| TrustAnchor {
@@ -43,20 +43,53 @@ pub async fn configure_tls_connector(
             let verifier = WebPkiVerifier::builder(Arc::new(cert_store))
                 .build()
                 .map_err(|e| Error::from(e.to_string()))?;
-            config
-                .with_custom_certificate_verifier(Arc::new(NoHostnameTlsVerifier { verifier }))
-                .with_no_client_auth()
+            config.with_custom_certificate_verifier(Arc::new(NoHostnameTlsVerifier { verifier }))
         } else {
+            config.cfg.with_root_certificates(cert_store)
+        }
+    };
+
+    let config = match (client_cert_path, client_key_path) {
+        (Some(cert_path), Some(key_path)) => {
+            let cert_chain = certs_from_pem(cert_path).await?;
+            let key_der = private_key_from_pem(key_path).await?;
             config
-                .cfg
-                .with_root_certificates(cert_store)
-                .with_no_client_auth()
+                .with_client_auth_cert(cert_chain, key_der)
+                .map_err(|err| Error::from(err.to_string()))?
+        }
+        (None, None) => config.with_no_client_auth(),
+        _ => {
+            return Err(Error::from(
+                "client certificate and client key must be configured together",
+            ));
         }
     };
 
     Ok(Arc::new(config).into())
 }
 
+async fn certs_from_pem(input: &CertificateInput) -> Result<Vec<CertificateDer<'static>>, Error> {
+    let data = input.data().await?;
+    let mut cursor = Cursor::new(data);
+    let certs = rustls_pemfile::certs(&mut cursor)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| Error::from(format!("Invalid certificate {}", input)))?;
+    if certs.is_empty() {
+        return Err(Error::from(format!("No certificate found in {}", input)));
+    }
+    Ok(certs)
+}
+
+async fn private_key_from_pem(input: &CertificateInput) -> Result<PrivateKeyDer<'static>, Error> {
+    let data = input.data().await?;
+    let mut cursor = Cursor::new(data);
+    match rustls_pemfile::private_key(&mut cursor) {
+        Ok(Some(key)) => Ok(key),
+        // don't print inline key material into the error
+        Ok(None) | Err(_) => Err(Error::from("Invalid or missing client private key")),
+    }
+}
+
 #[derive(Debug)]
 struct DummyTlsVerifier;
 
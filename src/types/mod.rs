@@ -8,6 +8,8 @@ pub mod decimal;
 /// `{"a":"b"}`
 /// `[{"a":"b"}]`
 pub mod json;
+/// `192.168.0.1/24`, `08:00:2b:01:02:03`
+pub mod network;
 /// `00:00:00.000000`
 pub mod time;
 /// 1713491896
@@ -20,6 +22,7 @@ pub use self::date::*;
 pub use self::datetime::*;
 pub use self::decimal::*;
 pub use self::json::*;
+pub use self::network::*;
 pub use self::time::*;
 pub use self::timestamp::*;
 pub use self::uuid::*;
@@ -0,0 +1,376 @@
+use crate::Error;
+use rbs::Value;
+use serde::{Deserializer, Serializer};
+use std::fmt::{Debug, Display, Formatter};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+pub use ipnetwork::IpNetwork;
+
+/// An IPv4 or IPv6 host address with an optional netmask, such as Postgres `INET`.
+///
+/// `192.168.0.1`, `192.168.0.1/24`, `::1`
+///
+/// The netmask is omitted when displaying a single host (`/32` or `/128`).
+#[derive(Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Inet(pub IpNetwork);
+
+/// An IPv4 or IPv6 network, such as Postgres `CIDR`. The bits to the right of the netmask are zero.
+///
+/// `192.168.0.0/24`
+#[derive(Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Cidr(pub IpNetwork);
+
+/// A 6 byte MAC address, such as Postgres `MACADDR`.
+///
+/// `08:00:2b:01:02:03`
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Default)]
+pub struct MacAddress(pub [u8; 6]);
+
+/// An 8 byte (EUI-64) MAC address, such as Postgres `MACADDR8`.
+///
+/// `08:00:2b:01:02:03:04:05`
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Default)]
+pub struct MacAddress8(pub [u8; 8]);
+
+impl Inet {
+    pub fn ip(&self) -> IpAddr {
+        self.0.ip()
+    }
+
+    pub fn prefix(&self) -> u8 {
+        self.0.prefix()
+    }
+
+    /// Reads an address stored as 4 (IPv4) or 16 (IPv6) raw bytes,
+    /// such as a MySQL `VARBINARY(16)` column filled by `INET6_ATON()`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        ip_from_bytes(bytes).map(Self::from)
+    }
+
+    /// The address as 4 (IPv4) or 16 (IPv6) raw bytes, the netmask is dropped.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        ip_to_bytes(self.ip())
+    }
+}
+
+impl Cidr {
+    /// Creates a network, failing if `ip` has bits set to the right of the netmask.
+    pub fn new(ip: IpAddr, prefix: u8) -> Result<Self, Error> {
+        let network = IpNetwork::new(ip, prefix).map_err(|e| Error::from(e.to_string()))?;
+        if network.network() != ip {
+            return Err(Error::from(format!(
+                "invalid cidr value {}/{}: has bits set to right of mask",
+                ip, prefix
+            )));
+        }
+        Ok(Self(network))
+    }
+
+    pub fn ip(&self) -> IpAddr {
+        self.0.ip()
+    }
+
+    pub fn prefix(&self) -> u8 {
+        self.0.prefix()
+    }
+
+    /// Reads a network stored as 4 or 16 raw bytes, which is a single host network.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self(IpNetwork::from(ip_from_bytes(bytes)?)))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        ip_to_bytes(self.ip())
+    }
+}
+
+impl MacAddress {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self(bytes.try_into().map_err(|_| {
+            Error::from(format!("invalid macaddr length {}", bytes.len()))
+        })?))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_vec()
+    }
+}
+
+impl MacAddress8 {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self(bytes.try_into().map_err(|_| {
+            Error::from(format!("invalid macaddr8 length {}", bytes.len()))
+        })?))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_vec()
+    }
+}
+
+/// Converts 4 or 16 raw bytes to an address.
+pub fn ip_from_bytes(bytes: &[u8]) -> Result<IpAddr, Error> {
+    match bytes.len() {
+        4 => Ok(IpAddr::V4(Ipv4Addr::from(
+            <[u8; 4]>::try_from(bytes).unwrap(),
+        ))),
+        16 => Ok(IpAddr::V6(Ipv6Addr::from(
+            <[u8; 16]>::try_from(bytes).unwrap(),
+        ))),
+        len => Err(Error::from(format!(
+            "invalid ip address length {}, expected 4 or 16 bytes",
+            len
+        ))),
+    }
+}
+
+/// Converts an address to 4 (IPv4) or 16 (IPv6) raw bytes.
+pub fn ip_to_bytes(ip: IpAddr) -> Vec<u8> {
+    match ip {
+        IpAddr::V4(v4) => v4.octets().to_vec(),
+        IpAddr::V6(v6) => v6.octets().to_vec(),
+    }
+}
+
+impl From<IpAddr> for Inet {
+    fn from(arg: IpAddr) -> Self {
+        Self(IpNetwork::from(arg))
+    }
+}
+
+impl From<IpNetwork> for Inet {
+    fn from(arg: IpNetwork) -> Self {
+        Self(arg)
+    }
+}
+
+impl From<Inet> for IpNetwork {
+    fn from(arg: Inet) -> Self {
+        arg.0
+    }
+}
+
+impl From<Inet> for IpAddr {
+    fn from(arg: Inet) -> Self {
+        arg.ip()
+    }
+}
+
+impl From<Cidr> for IpNetwork {
+    fn from(arg: Cidr) -> Self {
+        arg.0
+    }
+}
+
+impl FromStr for Inet {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(
+            IpNetwork::from_str(s.trim()).map_err(|e| Error::from(e.to_string()))?,
+        ))
+    }
+}
+
+impl FromStr for Cidr {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let network = IpNetwork::from_str(s.trim()).map_err(|e| Error::from(e.to_string()))?;
+        Self::new(network.ip(), network.prefix())
+    }
+}
+
+fn parse_mac<const N: usize>(s: &str) -> Result<[u8; N], Error> {
+    let invalid = || Error::from(format!("invalid mac address: {}", s));
+    let digits: String = s
+        .trim()
+        .chars()
+        .filter(|c| !matches!(c, ':' | '-' | '.'))
+        .collect();
+    if digits.len() != N * 2 || !digits.is_ascii() {
+        return Err(invalid());
+    }
+    let mut bytes = [0u8; N];
+    for (i, b) in bytes.iter_mut().enumerate() {
+        *b = u8::from_str_radix(&digits[i * 2..i * 2 + 2], 16).map_err(|_| invalid())?;
+    }
+    Ok(bytes)
+}
+
+fn fmt_mac(bytes: &[u8], f: &mut Formatter<'_>) -> std::fmt::Result {
+    for (i, b) in bytes.iter().enumerate() {
+        if i != 0 {
+            f.write_str(":")?;
+        }
+        write!(f, "{:02x}", b)?;
+    }
+    Ok(())
+}
+
+impl FromStr for MacAddress {
+    type Err = Error;
+
+    /// accepts `08:00:2b:01:02:03`, `08-00-2b-01-02-03`, `0800.2b01.0203` and `08002b010203`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(parse_mac(s)?))
+    }
+}
+
+impl FromStr for MacAddress8 {
+    type Err = Error;
+
+    /// accepts the formats of [`MacAddress`], a 6 byte address is widened to EUI-64 as postgres does
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match parse_mac::<8>(s) {
+            Ok(v) => Ok(Self(v)),
+            Err(e) => match parse_mac::<6>(s) {
+                Ok(v) => Ok(Self::from(MacAddress(v))),
+                Err(_) => Err(e),
+            },
+        }
+    }
+}
+
+impl From<MacAddress> for MacAddress8 {
+    /// inserts `FF:FE` in the middle, as postgres does for `macaddr::macaddr8`
+    fn from(arg: MacAddress) -> Self {
+        let m = arg.0;
+        Self([m[0], m[1], m[2], 0xff, 0xfe, m[3], m[4], m[5]])
+    }
+}
+
+impl Display for Inet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let max = match self.ip() {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        if self.prefix() == max {
+            write!(f, "{}", self.ip())
+        } else {
+            write!(f, "{}/{}", self.ip(), self.prefix())
+        }
+    }
+}
+
+impl Display for Cidr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.ip(), self.prefix())
+    }
+}
+
+impl Display for MacAddress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fmt_mac(&self.0, f)
+    }
+}
+
+impl Display for MacAddress8 {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fmt_mac(&self.0, f)
+    }
+}
+
+macro_rules! impl_net_serde {
+    ($($ty:ident => $ext:literal;)+) => {
+        $(
+            impl Debug for $ty {
+                fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                    write!(f, concat!(stringify!($ty), "({})"), self)
+                }
+            }
+
+            impl serde::Serialize for $ty {
+                fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                where
+                    S: Serializer,
+                {
+                    serializer.serialize_newtype_struct($ext, &self.to_string())
+                }
+            }
+
+            /// decodes from the text form, or from raw bytes (such as a MySQL `VARBINARY`)
+            impl<'de> serde::Deserialize<'de> for $ty {
+                fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                where
+                    D: Deserializer<'de>,
+                {
+                    use serde::de::Error;
+                    match Value::deserialize(deserializer)? {
+                        Value::String(s) => $ty::from_str(&s).map_err(D::Error::custom),
+                        Value::Binary(b) => $ty::from_bytes(&b).map_err(D::Error::custom),
+                        v => Err(D::Error::custom(format!(
+                            concat!("warn type decode ", stringify!($ty), ": {}"),
+                            v
+                        ))),
+                    }
+                }
+            }
+
+            impl From<$ty> for Value {
+                fn from(arg: $ty) -> Self {
+                    Value::Ext($ext, Box::new(Value::String(arg.to_string())))
+                }
+            }
+        )+
+    };
+}
+
+impl_net_serde! {
+    Inet => "Inet";
+    Cidr => "Cidr";
+    MacAddress => "Macaddr";
+    MacAddress8 => "Macaddr8";
+}
+
+#[cfg(test)]
+mod test {
+    use crate::network::{Cidr, Inet, MacAddress, MacAddress8};
+    use rbs::Value;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    #[test]
+    fn test_inet() {
+        let inet: Inet = "192.168.0.1".parse().unwrap();
+        assert_eq!(inet.prefix(), 32);
+        assert_eq!(inet.to_string(), "192.168.0.1");
+        let inet: Inet = "192.168.0.1/24".parse().unwrap();
+        assert_eq!(inet.to_string(), "192.168.0.1/24");
+        assert_eq!(inet.to_bytes(), vec![192, 168, 0, 1]);
+        let inet = Inet::from_bytes(&[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]).unwrap();
+        assert_eq!(inet.to_string(), "::1");
+        assert!(Inet::from_bytes(&[1, 2, 3]).is_err());
+    }
+
+    #[test]
+    fn test_cidr() {
+        let cidr: Cidr = "10.0.0.0/8".parse().unwrap();
+        assert_eq!(cidr.to_string(), "10.0.0.0/8");
+        assert!("10.0.0.1/8".parse::<Cidr>().is_err());
+    }
+
+    #[test]
+    fn test_mac() {
+        let mac: MacAddress = "08-00-2B-01-02-03".parse().unwrap();
+        assert_eq!(mac.to_string(), "08:00:2b:01:02:03");
+        assert_eq!("0800.2b01.0203".parse::<MacAddress>().unwrap(), mac);
+        let mac8: MacAddress8 = "08:00:2b:01:02:03".parse().unwrap();
+        assert_eq!(mac8.to_string(), "08:00:2b:ff:fe:01:02:03");
+        assert!("08:00:2b".parse::<MacAddress>().is_err());
+    }
+
+    #[test]
+    fn test_value() {
+        let inet = Inet::from(IpAddr::V4(Ipv4Addr::new(10, 1, 2, 3)));
+        let v = rbs::value(inet).unwrap();
+        assert_eq!(
+            v,
+            Value::Ext("Inet", Box::new(Value::String("10.1.2.3".to_string())))
+        );
+        assert_eq!(rbs::from_value::<Inet>(v).unwrap(), inet);
+        let from_binary: Inet = rbs::from_value(Value::Binary(vec![10, 1, 2, 3])).unwrap();
+        assert_eq!(from_binary, inet);
+    }
+}